@@ -0,0 +1,72 @@
+//! `--on-this-day`: works made on today's day of the year come first, with a
+//! badge saying when. Dates come from the sidecar's `year`, which can be
+//! anything from `1889` to `c. 1890`; only a full date in it has a day.
+
+use crate::scan::{ArtworkInfo, ArtworkMetadata};
+use chrono::{Datelike, NaiveDate};
+
+/// The first `YYYY-MM-DD` in `year`, wherever it is, not a part of a longer number.
+pub fn full_date(year: &str) -> Option<NaiveDate> {
+    let digit_at = |i: Option<usize>| i.and_then(|i| year.as_bytes().get(i)).is_some_and(u8::is_ascii_digit);
+    year.char_indices().find_map(|(start, _)| {
+        let candidate = year.get(start..start + 10)?;
+        let shaped = candidate.bytes().enumerate().all(|(i, byte)| match i {
+            4 | 7 => byte == b'-',
+            _ => byte.is_ascii_digit(),
+        }) && !digit_at(start.checked_sub(1))
+            && !digit_at(Some(start + 10));
+        if shaped { NaiveDate::parse_from_str(candidate, "%Y-%m-%d").ok() } else { None }
+    })
+}
+
+/// The year of the work, when it was made on the day and month of `today`.
+pub fn on_this_day(metadata: &ArtworkMetadata, today: NaiveDate) -> Option<i32> {
+    let date = full_date(&metadata.year)?;
+    (date.month() == today.month() && date.day() == today.day()).then_some(date.year())
+}
+
+/// Moves the works made on the day of `today` to the front, each part in
+/// the order it was in. Returns how many there are.
+pub fn put_first(artworks: &mut Vec<ArtworkInfo>, today: NaiveDate) -> usize {
+    let (mut first, rest): (Vec<_>, Vec<_>) =
+        std::mem::take(artworks).into_iter().partition(|info| on_this_day(&info.metadata, today).is_some());
+    let count = first.len();
+    first.extend(rest);
+    *artworks = first;
+    count
+}
+
+pub fn badge(year: i32) -> String {
+    format!("On this day in {}", year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn dated(name: &str, year: &str) -> ArtworkInfo {
+        ArtworkInfo {
+            path: PathBuf::from(name),
+            metadata: ArtworkMetadata { year: year.to_string(), ..ArtworkMetadata::unknown(name) },
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn works_of_the_day_come_first() {
+        assert_eq!(full_date("1889-06-18"), NaiveDate::from_ymd_opt(1889, 6, 18));
+        assert_eq!(full_date("painted 1889-06-18, signed later"), NaiveDate::from_ymd_opt(1889, 6, 18));
+        for vague in ["1889", "1889-06", "c. 1890", "Unknown", "1889-13-01", "18890-06-18x", "", "ca. 1890–95"] {
+            assert_eq!(full_date(vague), None, "{:?}", vague);
+        }
+
+        let today = NaiveDate::from_ymd_opt(2026, 6, 18).unwrap();
+        let mut artworks =
+            vec![dated("a", "1890"), dated("b", "1889-06-18"), dated("c", "1889-06-17"), dated("d", "1901-06-18")];
+        assert_eq!(put_first(&mut artworks, today), 2);
+        let names: Vec<_> = artworks.iter().map(|info| info.metadata.title.as_str()).collect();
+        assert_eq!(names, ["b", "d", "a", "c"]);
+        assert_eq!(on_this_day(&artworks[1].metadata, today).map(badge), Some("On this day in 1901".to_string()));
+    }
+}
@@ -0,0 +1,3046 @@
+use crate::anniversary;
+use crate::builder::AppBuilder;
+use crate::burnin;
+use crate::chapters::{self, TitleCard};
+use crate::cli;
+use crate::chime::Chime;
+use crate::collections::{Collections, NamedCollection};
+use crate::commons::Downloader;
+use crate::config::Config;
+use crate::control::Command;
+use crate::dedupe;
+use crate::editor::{Edit, MetadataEditor};
+use crate::ending::{self, EndAction};
+use crate::favorites::Favorites;
+use crate::filmstrip::Filmstrip;
+use crate::http;
+use crate::idle::{self, Change, Screensaver};
+use crate::ipc;
+use crate::kiosk::{AdminAction, KioskLock};
+use crate::launch;
+use crate::layout::{self, CaptionPosition, Corner, Corners, Fit, SlideLayout, Zoom};
+use crate::mat::MatSettings;
+use crate::media::MediaKeys;
+use crate::mqtt::{Mqtt, MqttSettings};
+use crate::music::{self, Music};
+use crate::narrate::{self, Narrator};
+use crate::night::OffHours;
+use crate::overlay::{
+    self, CAPTION_DELAY, CAPTION_DELAY_MANUAL, Caption, CaptionPalette, CaptionReveal, CaptionStyle, ClockFormat,
+    OVERLAY_MODE_KEY, OverlayMode, OverlaySettings, Toast,
+};
+use crate::pipeline::{
+    self, Background, BackgroundMode, Cancel, CaptionSample, PipelineError, ProcessFn, ProcessedImage,
+    ProcessingOptions,
+};
+use crate::power::SleepInhibitor;
+use crate::preprocess::{self, Preprocess};
+use crate::ratings::{self, Ratings};
+use crate::remote;
+use crate::rotation::Rotation;
+use crate::scan::{self, ArtworkInfo, ArtworkMetadata};
+use crate::scheduler::{Loaded, Processor, Scheduler, SlideState, Tick};
+use crate::search::Search;
+use crate::settings::{LiveSettings, SettingsPanel};
+use crate::shuffle::Shuffle;
+use crate::signals::{Signal, SignalFlags};
+use crate::source::{FolderSource, ImageSource, PlaylistSource, SharedSource};
+use crate::spotlight;
+use crate::stats::StatsLog;
+use crate::systemd::Notifier;
+use crate::transition::{Look, Transition};
+use crate::trash;
+use crate::webhook::{self, Event, EventKind, Webhook};
+use crate::window;
+use eframe::egui;
+use image::DynamicImage;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+enum LoadedBackground {
+    Texture(egui::TextureHandle),
+    Color(egui::Color32),
+    Black,
+}
+
+/// A slide as uploaded to the GPU. The pixels themselves aren't kept once the textures exist.
+struct LoadedArtwork {
+    texture: egui::TextureHandle,
+    background: LoadedBackground,
+    caption_sample: CaptionSample,
+    display_size: egui::Vec2,
+    // of the artwork itself, as far as zooming in goes
+    source_size: (u32, u32),
+    fit: Fit,
+    // replaced by the full-quality version once the upgrader has it
+    is_preview: bool,
+    // decided on the first frame the slide is drawn, then kept for the whole slide
+    caption_palette: Option<CaptionPalette>,
+    // more than one on a collage, the caption takes turns
+    works: Vec<ArtworkMetadata>,
+    qr_code: Option<egui::TextureHandle>,
+    // drawn with hard edges at a whole multiple of its size
+    pixel_art: bool,
+}
+
+/// The slide a transition is leaving, drawn as it was until the transition is over.
+struct Outgoing {
+    slide: LoadedArtwork,
+    caption_layout: OverlaySettings,
+    caption_work: usize,
+    overlay_alpha: f32,
+}
+
+#[derive(Clone)]
+enum PreparedBackground {
+    Image(egui::ColorImage),
+    Color(egui::Color32),
+    Black,
+}
+
+/// A processed slide with its pixels already in egui's format. Built on the
+/// worker thread, so the UI thread only hands the buffers over to egui.
+#[derive(Clone)]
+struct PreparedSlide {
+    main_image: egui::ColorImage,
+    background: PreparedBackground,
+    caption_sample: CaptionSample,
+    display_size: egui::Vec2,
+    source_size: (u32, u32),
+    fit: Fit,
+    is_preview: bool,
+    works: Vec<ArtworkMetadata>,
+    qr_code: Option<egui::ColorImage>,
+    pixel_art: bool,
+    detail_duration: Option<Duration>,
+}
+
+impl From<ProcessedImage> for PreparedSlide {
+    fn from(processed: ProcessedImage) -> Self {
+        Self {
+            main_image: color_image(&processed.main_image),
+            background: match &processed.background {
+                Background::Blurred(image) => PreparedBackground::Image(color_image(image)),
+                Background::Color([r, g, b]) => PreparedBackground::Color(egui::Color32::from_rgb(*r, *g, *b)),
+                Background::Black => PreparedBackground::Black,
+            },
+            caption_sample: processed.caption_sample,
+            display_size: egui::vec2(processed.display_size.0 as f32, processed.display_size.1 as f32),
+            source_size: processed.source_size,
+            fit: processed.fit,
+            is_preview: processed.is_preview,
+            works: processed.works,
+            qr_code: processed.qr_code.map(|code| {
+                egui::ColorImage::from_gray([code.width() as usize, code.height() as usize], code.as_raw())
+            }),
+            pixel_art: processed.pixel_art,
+            detail_duration: processed.detail_duration,
+        }
+    }
+}
+
+impl PreparedSlide {
+    /// Memory the pixels take.
+    fn bytes(&self) -> usize {
+        let background = match &self.background {
+            PreparedBackground::Image(image) => image.pixels.len(),
+            PreparedBackground::Color(_) | PreparedBackground::Black => 0,
+        };
+        let qr_code = self.qr_code.as_ref().map_or(0, |code| code.pixels.len());
+        (self.main_image.pixels.len() + background + qr_code) * std::mem::size_of::<egui::Color32>()
+    }
+}
+
+/// Pixel art keeps hard edges between its pixels, anything else is smoothed.
+fn texture_options(pixel_art: bool) -> egui::TextureOptions {
+    if pixel_art { egui::TextureOptions::NEAREST } else { egui::TextureOptions::LINEAR }
+}
+
+/// Input to the window that puts the screensaver away.
+fn is_touch(event: &egui::Event) -> bool {
+    matches!(
+        event,
+        egui::Event::Key { pressed: true, .. }
+            | egui::Event::PointerButton { pressed: true, .. }
+            | egui::Event::MouseWheel { .. }
+    )
+}
+
+/// What C puts on the clipboard: `"Title" — Artist (Year) — /full/path/to/file.jpg`.
+fn clipboard_text(metadata: &ArtworkMetadata, path: &Path) -> String {
+    format!("\"{}\" — {} ({}) — {}", metadata.title, metadata.artist, metadata.year, path.display())
+}
+
+fn color_image(image: &DynamicImage) -> egui::ColorImage {
+    let size = [image.width() as usize, image.height() as usize];
+    egui::ColorImage::from_rgba_unmultiplied(size, image.to_rgba8().as_flat_samples().as_slice())
+}
+
+/// What of `slide` goes on one slide. Two entries on a slide of their own
+/// layout might be a portrait pair: they are downloaded and measured, and
+/// stay together only when both are taller than wide, else the first goes
+/// alone. Returns the entries and which of those measured are portraits.
+pub fn settle_pair(
+    slide: &[ArtworkInfo],
+    source: &(dyn ImageSource + Sync),
+    options: &ProcessingOptions,
+) -> Result<(Vec<ArtworkInfo>, Vec<bool>), PipelineError> {
+    let pair = match slide {
+        [_, _] if options.layout == SlideLayout::Single => slide,
+        _ => return Ok((slide.to_vec(), Vec::new())),
+    };
+    let mut fetched = Vec::new();
+    let mut portrait = Vec::new();
+    for info in pair {
+        // downloaded once, processing doesn't fetch it again
+        let info = ArtworkInfo { remote: None, ..remote::fetch(info).map_err(PipelineError::Download)? };
+        portrait.push(pipeline::is_portrait(&source.read(&info)?, &info.path, options)?);
+        fetched.push(info);
+        if portrait == [false] {
+            break;
+        }
+    }
+    if portrait != [true, true] {
+        fetched.truncate(1);
+    }
+    Ok((fetched, portrait))
+}
+
+/// The slide showing `slide`, read from `source`: a single artwork, a portrait pair side by side or a
+/// collage. A playlist's URL entries are downloaded first.
+pub fn process_slide(
+    slide: &[ArtworkInfo],
+    source: &(dyn ImageSource + Sync),
+    options: &ProcessingOptions,
+    process: ProcessFn,
+) -> Result<ProcessedImage, PipelineError> {
+    let slide = slide.iter().map(remote::fetch).collect::<Result<Vec<_>, _>>().map_err(PipelineError::Download)?;
+    match (slide.as_slice(), options.layout) {
+        ([info], SlideLayout::Single) => process(&info.path, &source.read(info)?, info.metadata.clone(), options),
+        (pair, SlideLayout::Single) => pipeline::process_collage(pair, (pair.len(), 1), options, source, process),
+        (collage, layout) => pipeline::process_collage(collage, layout.grid(), options, source, process),
+    }
+}
+
+struct LoadRequest {
+    index: usize,
+    slide: Vec<ArtworkInfo>,
+    generation: u64,
+    preview: bool,
+    options: ProcessingOptions,
+    source: SharedSource,
+    // woken once the answer is ready, the UI doesn't poll for it
+    wake: Option<egui::Context>,
+}
+
+struct LoadResponse {
+    index: usize,
+    path: PathBuf,
+    generation: u64,
+    // the slide and which of its entries turned out portraits
+    result: Result<(PreparedSlide, Vec<bool>), PipelineError>,
+}
+
+/// Owns the long-lived worker thread that processes artworks off the UI thread.
+///
+/// Every request carries a generation number; answers to anything but the
+/// latest request are stale and get dropped. A panic while processing fails
+/// that request only, the thread keeps serving the next ones.
+struct BackgroundLoader {
+    options: ProcessingOptions,
+    // what the show's artworks are read from
+    source: SharedSource,
+    requests: Sender<LoadRequest>,
+    responses: Receiver<LoadResponse>,
+    generation: u64,
+    // the generation shared with the worker, which abandons the work for any older one
+    latest: Arc<AtomicU64>,
+    ready: Option<PreparedSlide>,
+    wake: Option<egui::Context>,
+    // artworks processed ahead by `--preprocess-all`, handed out without going to the worker
+    prepared: HashMap<PathBuf, PreparedSlide>,
+    prepared_bytes: usize,
+    // a slide processed without the worker, answered on the next poll
+    at_hand: Option<Loaded>,
+    // the files that failed since the show last took them, and why
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl BackgroundLoader {
+    fn new(options: ProcessingOptions, source: SharedSource) -> Self {
+        Self::with_processing(options, source, pipeline::process_image, pipeline::process_preview)
+    }
+
+    fn with_processing(options: ProcessingOptions, source: SharedSource, full: ProcessFn, preview: ProcessFn) -> Self {
+        let (requests, request_rx) = mpsc::channel::<LoadRequest>();
+        let (response_tx, responses) = mpsc::channel();
+
+        thread::spawn(move || {
+            while let Ok(mut request) = request_rx.recv() {
+                // only the newest request matters, skip anything queued before it
+                while let Ok(newer) = request_rx.try_recv() {
+                    request = newer;
+                }
+
+                let process = if request.preview { preview } else { full };
+                let result = pipeline::catch_panic(|| {
+                    let (slide, portrait) = settle_pair(&request.slide, &*request.source, &request.options)?;
+                    let processed = process_slide(&slide, &*request.source, &request.options, process)?;
+                    Ok((PreparedSlide::from(processed), portrait))
+                });
+                let response = LoadResponse {
+                    index: request.index,
+                    path: request.slide.first().map(|info| info.path.clone()).unwrap_or_default(),
+                    generation: request.generation,
+                    result,
+                };
+                if response_tx.send(response).is_err() {
+                    break;
+                }
+                if let Some(ctx) = &request.wake {
+                    ctx.request_repaint();
+                }
+            }
+        });
+
+        Self {
+            options,
+            source,
+            requests,
+            responses,
+            generation: 0,
+            latest: Arc::new(AtomicU64::new(0)),
+            ready: None,
+            wake: None,
+            prepared: HashMap::new(),
+            prepared_bytes: 0,
+            at_hand: None,
+            failed: Vec::new(),
+        }
+    }
+
+    /// Keeps `slide`, the artwork at `path` alone, to be handed out as it is.
+    /// Returns false when it's left out for going over the memory budget.
+    fn keep(&mut self, path: PathBuf, slide: PreparedSlide) -> bool {
+        let bytes = slide.bytes();
+        if self.prepared_bytes + bytes > preprocess::MEMORY_BUDGET {
+            return false;
+        }
+        self.prepared_bytes += bytes;
+        if let Some(replaced) = self.prepared.insert(path, slide) {
+            self.prepared_bytes -= replaced.bytes();
+        }
+        true
+    }
+
+    /// The kept slide of `slide`, with its sidecar as it is now.
+    fn prepared(&self, slide: &[ArtworkInfo]) -> Option<PreparedSlide> {
+        let [info] = slide else {
+            return None;
+        };
+        let prepared = self.prepared.get(&info.path)?;
+        Some(PreparedSlide { works: vec![info.metadata.clone()], ..prepared.clone() })
+    }
+
+    /// Drops the kept slide of `path`, it doesn't look like that anymore.
+    fn forget(&mut self, path: &Path) {
+        if let Some(forgotten) = self.prepared.remove(path) {
+            self.prepared_bytes -= forgotten.bytes();
+        }
+    }
+
+    fn take_failures(&mut self) -> Vec<(PathBuf, String)> {
+        std::mem::take(&mut self.failed)
+    }
+
+    /// Drops every kept slide, after the options all were processed with changed.
+    fn forget_all(&mut self) {
+        self.prepared.clear();
+        self.prepared_bytes = 0;
+    }
+
+    fn take(&mut self) -> Option<PreparedSlide> {
+        self.ready.take()
+    }
+
+    /// Moves on to a new generation, what is in flight stops at its next stage.
+    fn next_generation(&mut self) {
+        self.generation += 1;
+        self.latest.store(self.generation, Ordering::Relaxed);
+    }
+
+    /// Drops whatever is in flight or ready, its answer will be stale.
+    fn cancel(&mut self) {
+        self.next_generation();
+        self.ready = None;
+        self.at_hand = None;
+    }
+
+    /// Has the next poll answer for the slide at `index` with `result`, processed
+    /// on the spot instead of by the worker. `path` is its first file.
+    fn hand_over(&mut self, index: usize, path: PathBuf, result: Result<(PreparedSlide, Vec<bool>), PipelineError>) {
+        self.cancel();
+        self.at_hand = self.answer(LoadResponse { index, path, generation: self.generation, result });
+    }
+
+    /// What the show is told of a slide processed, keeping it until it is taken.
+    /// `None` for one that was cancelled, nothing waits for it.
+    fn answer(&mut self, response: LoadResponse) -> Option<Loaded> {
+        match response.result {
+            Ok((processed, portrait)) => {
+                self.ready = Some(processed);
+                Some(Loaded::Ready { index: response.index, portrait })
+            }
+            Err(PipelineError::Cancelled) => None,
+            Err(err) if err.is_transient() => {
+                tracing::warn!("skipping {} for now: {}", response.path.display(), err);
+                self.failed.push((response.path, err.to_string()));
+                Some(Loaded::Unavailable(response.index))
+            }
+            Err(err) => {
+                tracing::error!("skipping {}: {}", response.path.display(), err);
+                self.failed.push((response.path, err.to_string()));
+                Some(Loaded::Failed(response.index))
+            }
+        }
+    }
+}
+
+impl Processor for BackgroundLoader {
+    fn start(&mut self, index: usize, slide: &[ArtworkInfo], urgent: bool) {
+        self.next_generation();
+        self.ready = None;
+        self.at_hand = None;
+        if let Some(prepared) = self.prepared(slide) {
+            self.ready = Some(prepared);
+            self.at_hand = Some(Loaded::Ready { index, portrait: Vec::new() });
+            return;
+        }
+        let request = LoadRequest {
+            index,
+            slide: slide.to_vec(),
+            generation: self.generation,
+            preview: urgent,
+            options: ProcessingOptions {
+                cancel: Cancel::unless_latest(Arc::clone(&self.latest), self.generation),
+                ..self.options.clone()
+            },
+            source: Arc::clone(&self.source),
+            wake: self.wake.clone(),
+        };
+        if self.requests.send(request).is_err() {
+            tracing::error!("image loader thread is gone, cannot load slide {}", index + 1);
+        }
+    }
+
+    fn poll(&mut self) -> Option<Loaded> {
+        if let Some(loaded) = self.at_hand.take() {
+            return Some(loaded);
+        }
+        while let Ok(response) = self.responses.try_recv() {
+            if response.generation != self.generation {
+                continue;
+            }
+            // only a request no longer wanted is cancelled, nothing waits for it
+            if let Some(loaded) = self.answer(response) {
+                return Some(loaded);
+            }
+        }
+        None
+    }
+}
+
+/// Idle time after which the mouse cursor is hidden.
+const CURSOR_HIDE_AFTER: Duration = Duration::from_secs(3);
+/// How far the mouse moves before `--exit-on-input` quits, a nudged desk shouldn't.
+const EXIT_POINTER_MOVE: f32 = 10.0;
+
+/// Repaint interval while the screen is dark for the night.
+const NIGHT_REPAINT: Duration = Duration::from_secs(30);
+
+/// Longest sleep between frames, in case a wakeup was missed.
+const MAX_IDLE: Duration = Duration::from_secs(60);
+
+/// How late the next slide is before the screen says it is loading, a moment isn't worth a word.
+const LATE_NOTICE: Duration = Duration::from_secs(1);
+
+/// How long the window keeps its new shape before the slides are fitted to it,
+/// a window dragged to size shouldn't have them processed on every frame.
+const RESIZE_SETTLE: Duration = Duration::from_millis(400);
+/// Changes of the screen's aspect ratio by less than this are let be.
+const RESIZE_ASPECT_TOLERANCE: f32 = 0.03;
+/// `--force-fullscreen` asks again this long after a request the window didn't follow.
+const FULLSCREEN_RETRY: Duration = Duration::from_secs(2);
+
+/// Whether `screen` is shaped differently enough from `fitted` to fit the slides
+/// to it again. A minimized window has no shape to fit them to.
+fn reshaped(fitted: (u32, u32), screen: (u32, u32)) -> bool {
+    if screen.0 == 0 || screen.1 == 0 {
+        return false;
+    }
+    let aspect = |(width, height): (u32, u32)| width.max(1) as f32 / height.max(1) as f32;
+    (aspect(screen) / aspect(fitted) - 1.0).abs() > RESIZE_ASPECT_TOLERANCE
+}
+
+/// The `max_dimension` a screen that grew from `fitted` to `screen` needs, if
+/// it outgrew the one the slides are processed at. Never past `limit`.
+fn outgrown(fitted: (u32, u32), screen: (u32, u32), max_dimension: u32, limit: u32) -> Option<u32> {
+    let side = screen.0.max(screen.1).min(limit);
+    (side > fitted.0.max(fitted.1) && side > max_dimension).then_some(side)
+}
+
+/// Zooming in on the slide on screen.
+struct ZoomState {
+    view: Zoom,
+    // as far as it goes, the source's own resolution
+    max_scale: f32,
+    // decoded beyond the processed size once the zoom needs more pixels than that
+    texture: Option<egui::TextureHandle>,
+    requested: bool,
+    // the show was running when zooming began, it goes on once zoomed out
+    resume: bool,
+}
+
+pub struct ArtSlideshowApp {
+    artworks: Vec<ArtworkInfo>,
+    // shown instead of the slideshow when `artworks` is empty
+    empty_message: String,
+    scheduler: Scheduler,
+    loader: BackgroundLoader,
+    // brings a preview on screen up to full quality
+    upgrader: BackgroundLoader,
+    // decodes the slide on screen at up to its native resolution for the zoom
+    zoom_loader: BackgroundLoader,
+    zoom: Option<ZoomState>,
+    // how wide the image on screen was drawn on the last frame before any zoom,
+    // counting what a cover fit crops off
+    shown_width: Option<f32>,
+    // no blur size was given, size it from the screen on the first frame and whenever it changes shape
+    blur_for_screen: bool,
+    // the physical screen size the slides were last processed for
+    fitted_screen: Option<(u32, u32)>,
+    // the size the screen changed shape or grew to, and since when it has kept it
+    resize: Option<((u32, u32), Instant)>,
+    force_fullscreen: bool,
+    // the last time `force_fullscreen` asked for it
+    fullscreen_asked: Option<Instant>,
+    windowed: bool,
+    // saved on quitting windowed, for the window to come back maximized
+    maximized: bool,
+    // `--preprocess-all` begins on the first frame, once the screen size is known
+    preprocess_pending: bool,
+    preprocess: Option<Preprocess<PreparedSlide>>,
+    rotation: Rotation,
+    current_textures: Option<LoadedArtwork>,
+    // bumped on every transition so texture names never repeat, even for duplicate entries
+    slide_serial: u64,
+    transition: Transition,
+    // the slide being left while a transition other than a cut is under way
+    outgoing: Option<Outgoing>,
+    // `--chapters`, unless the show is shuffled across its folders
+    chapters: bool,
+    chapter_duration: Duration,
+    chapter_card: Option<TitleCard>,
+    end_action: EndAction,
+    // when `--end-action black` began to fade the last slide out
+    faded_out_at: Option<Instant>,
+    pub overlay_mode: OverlayMode,
+    caption_style: CaptionStyle,
+    caption_scale: f32,
+    caption_layout: OverlaySettings,
+    // spots the caption takes turns at from slide to slide, just its own without OLED protection
+    caption_positions: Vec<CaptionPosition>,
+    // when the picture started drifting, `None` without OLED protection
+    orbit_started: Option<Instant>,
+    black_frame: bool,
+    // the screen stays black until then between two slides
+    black_until: Option<Instant>,
+    // a gradient behind the caption
+    scrim: bool,
+    mat: MatSettings,
+    // where the clock goes, `None` when it is off
+    clock_corner: Option<Corner>,
+    clock_format: ClockFormat,
+    clock_date: bool,
+    // where the QR code of an artwork's url goes, `None` if no corner is left
+    qr_corner: Option<Corner>,
+    // where "Loading next…" goes while the next slide is late
+    late_corner: Corner,
+    // `--on-this-day` says where a work of the day was made then, `--group-by-artist` whose run begins
+    badge_corner: Option<Corner>,
+    artist_runs: Option<usize>,
+    // last slide change or user input, the auto-hide countdown starts from here
+    overlay_woken_at: Instant,
+    // the caption of the slide on screen coming in
+    caption_reveal: CaptionReveal,
+    // the next slide change comes from navigation, its caption waits less
+    navigated: bool,
+    show_cursor: bool,
+    last_pointer_activity: Instant,
+    // `None` outside kiosk mode
+    kiosk: Option<KioskLock>,
+    allow_delete: bool,
+    // the artwork the confirmation to move it to the trash is up for
+    delete_prompt: Option<PathBuf>,
+    favorites: Favorites,
+    ratings: Ratings,
+    stats: Option<StatsLog>,
+    error_webhook: Option<Webhook>,
+    // the last file posted to the error webhook, one that fails over and over is posted once
+    last_error: Option<PathBuf>,
+    // the slide on screen stayed too long and that was posted, until it changes
+    stall_reported: bool,
+    heartbeat_webhook: Option<Webhook>,
+    last_heartbeat: Option<Instant>,
+    // readiness and the watchdog for a systemd service
+    notifier: Notifier,
+    music: Option<Music>,
+    chime: Option<Chime>,
+    narrator: Option<Narrator>,
+    // draw slides by rating instead of in order
+    weighted: bool,
+    // portrait pairs share a slide on a landscape screen
+    pairing: bool,
+    toast: Option<Toast>,
+    screenshot_dir: PathBuf,
+    // F12 or a remote asked for a screenshot, egui takes it with the next frame
+    screenshot_requested: bool,
+    folder_path: PathBuf,
+    // applied again to collections dropped on the window
+    shuffle: Shuffle,
+    collections: Collections,
+    // `None` for the command line's folder
+    active_collection: Option<String>,
+    // the day the works of the day were put first for, the show is put in order again on the next
+    ordered_on: Option<chrono::NaiveDate>,
+    home_folder: PathBuf,
+    // what the collections are read with as the clock moves from one to the next
+    collection_config: Config,
+    status: ipc::SharedSnapshot,
+    signals: Option<SignalFlags>,
+    // the command line SIGHUP parses again, config file and all
+    reload_args: Option<Vec<String>>,
+    commands: Receiver<Command>,
+    // cloned for every remote control that gets started
+    command_sender: Sender<Command>,
+    mqtt: Option<Mqtt>,
+    media_keys: Option<MediaKeys>,
+    off_hours: Option<OffHours>,
+    night_clock: bool,
+    // the slideshow was paused by the night schedule, not by the user
+    paused_for_night: bool,
+    screensaver: Option<Screensaver>,
+    // the files of the Commons category coming in, until they all have
+    commons: Option<Downloader>,
+    // how many of them the show was last scanned with
+    commons_scanned: usize,
+    // the slideshow was paused while out of sight as a screensaver
+    paused_for_screensaver: bool,
+    exit_on_input: bool,
+    // where the pointer was first seen, moving away from it quits with `exit_on_input`
+    pointer_origin: Option<egui::Pos2>,
+    inhibit_sleep: bool,
+    settings: SettingsPanel,
+    filmstrip: Filmstrip,
+    search: Search,
+    editor: MetadataEditor,
+    // where closing the panel saves to, the changes only last the session without it
+    settings_file: Option<PathBuf>,
+    // the slideshow was paused by opening the panel
+    paused_for_settings: bool,
+    // the slideshow was paused by opening the metadata editor
+    paused_for_editor: bool,
+    _query_server: Option<ipc::Server>,
+    _sleep_inhibitor: Option<SleepInhibitor>,
+}
+
+/// The artworks a show goes through, in order, and what they came with.
+pub struct Collection {
+    pub artworks: Vec<ArtworkInfo>,
+    /// Where favorites and ratings are kept.
+    pub folder_path: PathBuf,
+    /// Shown instead when nothing is left to show.
+    pub empty_message: String,
+    pub favorites: Favorites,
+    pub ratings: Ratings,
+    /// What the artworks are read from.
+    pub source: SharedSource,
+    /// The day whose works of other years come first, with `--on-this-day`.
+    pub ordered_on: Option<chrono::NaiveDate>,
+}
+
+/// Reads what `config` shows from `folder_path` and the folders given with
+/// it, or from its playlist, leaving out what it filters and in the order it
+/// asks for.
+pub fn collect(folder_path: PathBuf, config: &Config) -> Result<Collection, String> {
+    let (mut sources, folder_path, mut empty_message, source): (_, _, _, SharedSource) = match &config.playlist {
+        Some(playlist) => {
+            let source = PlaylistSource::new(playlist, config.downloads_dir());
+            let artworks =
+                source.list().map_err(|err| format!("cannot read the playlist {}: {}", playlist.display(), err))?;
+            tracing::info!("found {} artworks in {}", artworks.len(), playlist.display());
+            // favorites are kept next to the playlist
+            let folder = playlist.parent().map(PathBuf::from).unwrap_or_default();
+            (vec![artworks], folder, "No images found in playlist.".to_string(), Arc::new(source))
+        }
+        None => {
+            let (sources, folder_path) = scan_folders(folder_path, config)?;
+            // the other folders' files are read the same way
+            let source = Arc::new(FolderSource::new(&folder_path));
+            (sources, folder_path, "No images found in folder.".to_string(), source)
+        }
+    };
+    let mut retain = |keep: &dyn Fn(&ArtworkInfo) -> bool| {
+        for artworks in &mut sources {
+            artworks.retain(|info| keep(info));
+        }
+    };
+
+    let favorites = Favorites::load(&folder_path);
+    if config.favorites_only {
+        for missing in favorites.missing() {
+            tracing::warn!("favorite {} no longer exists", missing);
+        }
+        retain(&|info| favorites.contains(&info.path));
+        empty_message = "No favorites in folder. Press F during the slideshow to add some.".to_string();
+    }
+
+    let ratings = Ratings::load(&folder_path);
+    if let Some(min_rating) = config.min_rating() {
+        retain(&|info| ratings.stars(&info.path) >= min_rating);
+        empty_message = format!("No artworks rated {} stars or more.", min_rating);
+    }
+
+    if config.filter.is_active() {
+        retain(&|info| config.filter.matches(&info.metadata));
+        empty_message = format!("No artworks match {}.", config.filter);
+    }
+    // chapters shuffled within keep their order, the folders come as they are and only their works move
+    let within = config.keeps_chapters() && config.shuffle != Shuffle::Off;
+    let mut artworks = config.merge.combine(sources, if within { Shuffle::Off } else { config.shuffle });
+    if within {
+        chapters::shuffle_within(&mut artworks, config.shuffle);
+    }
+    let ordered_on = config.on_this_day.then(|| chrono::Local::now().date_naive());
+    let mut first = 0;
+    if let Some(today) = ordered_on {
+        first = anniversary::put_first(&mut artworks, today);
+        tracing::info!("{} works were made on {} of another year, they come first", first, today.format("%B %-d"));
+    }
+    // the works of the day and the rest are grouped each on their own, no run is split up to put one first
+    if let Some(run) = config.group_by_artist {
+        let mut rest = artworks.split_off(first);
+        spotlight::group_by_artist(&mut artworks, run, None);
+        spotlight::group_by_artist(&mut rest, run, artworks.last());
+        artworks.append(&mut rest);
+    }
+
+    Ok(Collection { artworks, folder_path, empty_message, favorites, ratings, source, ordered_on })
+}
+
+/// The artworks of each of `first` and `more` that can be read, without what
+/// its ignore file leaves out, and the first folder that could be, which
+/// keeps the favorites and ratings. Folders that can't be read are skipped
+/// unless none can. `config` says whether their subfolders are scanned too,
+/// and whether copies are left out.
+fn scan_folders(first: PathBuf, config: &Config) -> Result<(Vec<Vec<ArtworkInfo>>, PathBuf), String> {
+    let mut folders = Vec::new();
+    let mut sources = Vec::new();
+    let mut errors = Vec::new();
+    for folder in std::iter::once(first).chain(config.extra_folders.iter().cloned()) {
+        let source = if config.recursive { FolderSource::recursive(&folder) } else { FolderSource::new(&folder) };
+        let artworks = match source.list() {
+            Ok(artworks) => artworks,
+            Err(err) => {
+                errors.push(format!("cannot read the folder {}: {}", folder.display(), err));
+                continue;
+            }
+        };
+        folders.push(folder);
+        sources.push(artworks);
+    }
+    if folders.is_empty() {
+        return Err(errors.join("; "));
+    }
+    for error in &errors {
+        tracing::warn!("{}, skipping it", error);
+    }
+
+    if config.dedupe {
+        // a copy in a later folder goes, along with the one in the same folder
+        let mut all: Vec<ArtworkInfo> = sources.concat();
+        dedupe::dedupe(&mut all);
+        let kept: HashSet<PathBuf> = all.into_iter().map(|info| info.path).collect();
+        for artworks in &mut sources {
+            artworks.retain(|info| kept.contains(&info.path));
+        }
+    }
+    for (folder, artworks) in folders.iter().zip(&sources) {
+        tracing::info!("found {} artworks in {}", artworks.len(), folder.display());
+    }
+    if folders.len() > 1 {
+        let total: usize = sources.iter().map(Vec::len).sum();
+        tracing::info!("showing {} artworks from {} folders", total, folders.len());
+    }
+    Ok((sources, folders.swap_remove(0)))
+}
+
+/// Reads the `scheduled` collection, or what `config` shows from `folder_path` without one.
+fn collect_scheduled(
+    folder_path: &Path,
+    scheduled: Option<&NamedCollection>,
+    config: &Config,
+) -> Result<Collection, String> {
+    match scheduled {
+        // a collection is a folder, whatever the playlist
+        Some(collection) => {
+            collect(collection.folder.clone(), &Config { playlist: None, extra_folders: Vec::new(), ..config.clone() })
+        }
+        None => collect(folder_path.to_path_buf(), config),
+    }
+}
+
+/// What the log calls the collection named `name`.
+fn collection_label(name: Option<&str>) -> String {
+    name.map_or_else(|| "the command line's folder".to_string(), |name| format!("collection {:?}", name))
+}
+
+impl ArtSlideshowApp {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    /// The show of `folder_path` with the default options. Panics when
+    /// the folder can't be read.
+    pub fn new(folder_path: PathBuf) -> Self {
+        Self::builder().folder(folder_path).build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like [`new`](Self::new), with `config`.
+    pub fn with_config(folder_path: PathBuf, config: Config) -> Self {
+        Self::builder().folder(folder_path).config(config).build().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// The show `config` makes of `folder_path`, failing when there's nothing
+    /// to read it from. Only reached through [`AppBuilder::build`], which
+    /// checks the options first: kiosk mode has a PIN by then.
+    pub(crate) fn try_with_config(folder_path: PathBuf, config: Config) -> Result<Self, String> {
+        let scheduled = config.collections.active_now();
+        if !config.collections.is_empty() {
+            tracing::info!("starting with {}", collection_label(scheduled.map(|collection| collection.name.as_str())));
+        }
+        let active_collection = scheduled.map(|collection| collection.name.clone());
+        // the category's folder is there before it is read, its files come in while the show runs
+        let commons = config.wikimedia_category.clone().zip(config.commons_folder()).map(|(category, folder)| {
+            Downloader::start(category, folder, config.wikimedia_limit)
+        });
+        let folder_path = config.commons_folder().unwrap_or(folder_path);
+        let home_folder = folder_path.clone();
+        let Collection { artworks, folder_path, empty_message, favorites, ratings, source, ordered_on } =
+            collect_scheduled(&folder_path, scheduled, &config)?;
+
+        if config.chapters && !config.keeps_chapters() {
+            tracing::info!("the show is shuffled or merged across its folders, no chapters");
+        }
+        let mut scheduler = Scheduler::new(config.slide_duration(), Instant::now());
+        scheduler.set_step(config.layout.cells());
+        scheduler.give_up_after = (config.skip_late > 0.0).then_some(config.skip_late);
+        scheduler.loops = config.loops;
+        // a cut and the OLED black frame keep nothing to transition from
+        let keeps_outgoing = config.transition != Transition::Cut && !config.oled_black_frame;
+        scheduler.transition = if keeps_outgoing { config.transition_duration() } else { Duration::ZERO };
+        if config.weighted {
+            scheduler.set_weights(Some(ratings.weights(&artworks)));
+        }
+        // the first slide's time starts once everything is processed
+        if config.preprocess_all {
+            scheduler.pause(Instant::now());
+        }
+
+        let (command_sender, commands) = mpsc::channel();
+        let options = config.processing_options();
+        let filmstrip = Filmstrip::new(options.cache.clone());
+
+        // the caption goes first, the clock and then the QR code fit around it
+        let caption_positions = if config.oled_protection {
+            burnin::caption_positions(config.overlay.position)
+        } else {
+            vec![config.overlay.position]
+        };
+        let mut corners = Corners::default();
+        for &position in &caption_positions {
+            for corner in (OverlaySettings { position, ..config.overlay }).corners() {
+                corners.claim(corner);
+            }
+        }
+        let clock_corner = if config.show_clock { corners.claim(config.clock_corner) } else { None };
+        let qr_corner = corners.claim(config.overlay.position.opposite());
+        // on top of something else if it must, it is up for a moment
+        let late_corner = corners.claim(Corner::TopRight).unwrap_or(Corner::TopRight);
+        let badge_corner =
+            (config.on_this_day || config.group_by_artist.is_some())
+                .then(|| corners.claim(Corner::TopLeft).unwrap_or(Corner::TopLeft));
+        let night = config.off_hours.is_some_and(|off_hours| off_hours.contains_now());
+        let screensaver = config.screensaver().and_then(|after| match Screensaver::new(after) {
+            Ok(screensaver) => Some(screensaver),
+            Err(err) => {
+                tracing::warn!("cannot run as a screensaver, showing the slideshow right away: {}", err);
+                None
+            }
+        });
+
+        Ok(Self {
+            artworks,
+            empty_message,
+            scheduler,
+            loader: BackgroundLoader::new(options.clone(), Arc::clone(&source)),
+            upgrader: BackgroundLoader::new(options.clone(), Arc::clone(&source)),
+            zoom_loader: BackgroundLoader::new(options, source),
+            zoom: None,
+            shown_width: None,
+            blur_for_screen: config.blur_size.is_none(),
+            fitted_screen: None,
+            resize: None,
+            force_fullscreen: config.force_fullscreen && !config.windowed,
+            fullscreen_asked: None,
+            windowed: config.windowed,
+            maximized: false,
+            preprocess_pending: config.preprocess_all,
+            preprocess: None,
+            rotation: config.rotation,
+            current_textures: None,
+            slide_serial: 0,
+            transition: config.transition,
+            outgoing: None,
+            chapters: config.keeps_chapters(),
+            chapter_duration: config.chapter_card(),
+            chapter_card: None,
+            end_action: config.end_action,
+            faded_out_at: None,
+            overlay_mode: OverlayMode::default(),
+            caption_style: config.caption_style,
+            caption_scale: config.caption_scale(),
+            caption_layout: config.overlay,
+            caption_positions,
+            orbit_started: config.oled_protection.then(Instant::now),
+            black_frame: config.oled_black_frame,
+            black_until: None,
+            scrim: config.scrim,
+            mat: config.mat,
+            clock_corner,
+            clock_format: config.clock_format.clone(),
+            clock_date: config.clock_date,
+            qr_corner,
+            late_corner,
+            badge_corner,
+            artist_runs: config.group_by_artist,
+            overlay_woken_at: Instant::now(),
+            caption_reveal: CaptionReveal::new(CAPTION_DELAY),
+            navigated: false,
+            show_cursor: config.show_cursor,
+            last_pointer_activity: Instant::now(),
+            // the builder refuses kiosk mode without a PIN
+            kiosk: config.kiosk.then(|| KioskLock::new(config.pin.clone().expect("kiosk mode needs a PIN"))),
+            allow_delete: config.allow_delete,
+            delete_prompt: None,
+            favorites,
+            ratings,
+            weighted: config.weighted,
+            pairing: config.pairing,
+            stats: config.stats_file.clone().map(StatsLog::new),
+            error_webhook: config.error_webhook.clone().map(Webhook::start),
+            last_error: None,
+            stall_reported: false,
+            heartbeat_webhook: config.heartbeat_webhook.clone().map(Webhook::start),
+            last_heartbeat: None,
+            notifier: Notifier::from_env(),
+            music: music::from_config(&config),
+            chime: config.transition_sound.clone().map(|path| Chime::start(path, config.transition_sound_volume)),
+            narrator: config.narrate.then(|| Narrator::start(config.narrate_rate)),
+            toast: None,
+            screenshot_dir: config.screenshot_dir.clone(),
+            screenshot_requested: false,
+            folder_path,
+            shuffle: config.shuffle,
+            collections: config.collections.clone(),
+            active_collection,
+            ordered_on,
+            home_folder,
+            collection_config: config.clone(),
+            status: ipc::SharedSnapshot::default(),
+            signals: None,
+            reload_args: None,
+            commands,
+            command_sender,
+            mqtt: None,
+            media_keys: None,
+            off_hours: config.off_hours,
+            night_clock: config.night_clock,
+            paused_for_night: false,
+            paused_for_screensaver: false,
+            exit_on_input: config.exit_on_input,
+            pointer_origin: None,
+            inhibit_sleep: config.inhibit_sleep && !config.windowed,
+            settings: SettingsPanel::default(),
+            filmstrip,
+            search: Search::default(),
+            editor: MetadataEditor::default(),
+            settings_file: None,
+            paused_for_settings: false,
+            paused_for_editor: false,
+            _query_server: None,
+            // taken in the morning instead when starting during the off hours, or once the screensaver comes up
+            _sleep_inhibitor: if config.inhibit_sleep && !config.windowed && !night && screensaver.is_none() {
+                SleepInhibitor::acquire()
+            } else {
+                None
+            },
+            screensaver,
+            commons,
+            commons_scanned: 0,
+        })
+    }
+
+    /// Answers `art-slideshow query` on `endpoint`. Failing to bind only costs the query feature.
+    pub fn serve_queries(&mut self, endpoint: &str) {
+        match ipc::serve(endpoint, Arc::clone(&self.status)) {
+            Ok(server) => self._query_server = Some(server),
+            Err(err) => tracing::warn!("query endpoint {} unavailable: {}", endpoint, err),
+        }
+    }
+
+    /// Accepts remote commands over HTTP on `port`. Failing to bind only costs the remote control.
+    pub fn serve_http(&mut self, port: u16, ctx: egui::Context) {
+        if let Err(err) = http::serve(port, self.command_sender.clone(), Arc::clone(&self.status), ctx) {
+            tracing::warn!("control port {} unavailable: {}", port, err);
+        }
+    }
+
+    /// Publishes the current artwork to an MQTT broker and takes commands from it.
+    pub fn connect_mqtt(&mut self, settings: &MqttSettings, ctx: egui::Context) {
+        match Mqtt::connect(settings, self.command_sender.clone(), ctx) {
+            Ok(mqtt) => self.mqtt = Some(mqtt),
+            Err(err) => tracing::warn!("mqtt disabled: {}", err),
+        }
+    }
+
+    /// Lets a remote's media keys steer the show and shows the artwork on
+    /// screen in the desktop's media controls, see [`crate::media`].
+    pub fn connect_media_keys(&mut self, cc: &eframe::CreationContext<'_>) {
+        self.media_keys = MediaKeys::connect(cc, self.command_sender.clone());
+        self.publish_status();
+    }
+
+    /// Steers the show by Unix signals, see [`crate::signals`]. Failing to
+    /// install the handlers only costs that.
+    pub fn handle_signals(&mut self, ctx: egui::Context) {
+        match SignalFlags::install(ctx) {
+            Ok(signals) => self.signals = Some(signals),
+            Err(err) => tracing::warn!("signals won't steer the show: {}", err),
+        }
+    }
+
+    /// Lets SIGHUP parse `args`, the command line without the program name,
+    /// again, reading the config file it names along with it.
+    pub fn reload_with(&mut self, args: Vec<String>) {
+        self.reload_args = Some(args);
+    }
+
+    /// Lets the settings panel save its changes into `path` when it closes.
+    pub fn save_settings_to(&mut self, path: PathBuf) {
+        self.settings_file = Some(path);
+    }
+
+    /// Applies a command from the keyboard or a remote control. Returns whether the status changed.
+    fn apply(&mut self, command: Command) -> bool {
+        let len = self.artworks.len();
+        let now = Instant::now();
+        if matches!(command, Command::Next | Command::Prev | Command::GoTo(_)) {
+            self.chapter_card = None;
+            self.faded_out_at = None;
+        }
+        match command {
+            Command::Next => {
+                self.scheduler.go_next(&self.artworks);
+                self.navigated = true;
+            }
+            Command::Prev => {
+                self.scheduler.go_prev(&self.artworks);
+                self.navigated = true;
+            }
+            Command::Pause => self.scheduler.pause(now),
+            Command::Resume => self.scheduler.resume(now),
+            Command::TogglePause if self.scheduler.is_paused() => self.scheduler.resume(now),
+            Command::TogglePause => self.scheduler.pause(now),
+            Command::GoTo(index) if index < len => {
+                self.scheduler.go_to(index);
+                self.navigated = true;
+            }
+            Command::GoTo(index) => {
+                tracing::warn!("ignoring goto:{}, only {} artworks", index, len);
+                return false;
+            }
+            Command::Screenshot => {
+                self.screenshot_requested = true;
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Blanks the screen during the off hours. Returns whether it did.
+    ///
+    /// The slide on screen is paused rather than dropped, so the morning picks
+    /// up exactly where the night left off.
+    fn update_night(&mut self, ctx: &egui::Context) -> bool {
+        let night = self.off_hours.is_some_and(|off_hours| off_hours.contains_now());
+        let now = Instant::now();
+
+        if night && !self.paused_for_night && !self.scheduler.is_paused() {
+            self.scheduler.pause(now);
+            self.paused_for_night = true;
+            self._sleep_inhibitor = None;
+            self.publish_status();
+        } else if !night && self.paused_for_night {
+            self.scheduler.resume(now);
+            self.paused_for_night = false;
+            if self.inhibit_sleep {
+                self._sleep_inhibitor = SleepInhibitor::acquire();
+            }
+            self.publish_status();
+        }
+        // a black screen shows nothing, however long the slide stays paused
+        if let Some(stats) = &mut self.stats {
+            if night {
+                stats.hidden();
+            } else if !stats.is_showing() && self.current_textures.is_some() {
+                stats.shown(&self.artworks[self.scheduler.current_index]);
+            }
+        }
+        if !night {
+            return false;
+        }
+
+        let night_clock = self.night_clock;
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+            .show(ctx, |ui| {
+                if night_clock {
+                    let time = chrono::Local::now().format("%H:%M").to_string();
+                    ui.painter().text(
+                        ui.max_rect().right_bottom() - egui::vec2(20.0, 20.0),
+                        egui::Align2::RIGHT_BOTTOM,
+                        time,
+                        egui::FontId::proportional(18.0),
+                        egui::Color32::from_gray(40),
+                    );
+                }
+            });
+        ctx.set_cursor_icon(egui::CursorIcon::None);
+        ctx.request_repaint_after(NIGHT_REPAINT);
+        true
+    }
+
+    /// Keeps the window out of sight while the computer is in use and brings
+    /// the show up fullscreen once it has been idle long enough. Returns
+    /// whether the show is out of sight.
+    ///
+    /// A key or a click that puts the show away is taken by this frame, so it
+    /// doesn't also go to the next slide or open a panel.
+    fn update_screensaver(&mut self, ctx: &egui::Context) -> bool {
+        let Some(screensaver) = &mut self.screensaver else {
+            return false;
+        };
+        let touched = screensaver.is_active() && ctx.input(|i| i.events.iter().any(is_touch));
+        let change = if touched { screensaver.hide() } else { screensaver.poll() };
+        let active = screensaver.is_active();
+        let now = Instant::now();
+
+        match change {
+            Some(Change::Show) => {
+                tracing::info!("idle, showing the slideshow");
+                if self.paused_for_screensaver {
+                    self.scheduler.resume(now);
+                    self.paused_for_screensaver = false;
+                }
+                if self.inhibit_sleep {
+                    self._sleep_inhibitor = SleepInhibitor::acquire();
+                }
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+                self.publish_status();
+            }
+            Some(Change::Hide) => {
+                tracing::info!("input, hiding the slideshow");
+                self._sleep_inhibitor = None;
+            }
+            None => {}
+        }
+        // the idle time is asked for now and then even while up, input elsewhere wakes no frame
+        ctx.request_repaint_after(idle::POLL);
+        if active {
+            return false;
+        }
+
+        if !self.paused_for_screensaver && !self.scheduler.is_paused() {
+            self.scheduler.pause(now);
+            self.paused_for_screensaver = true;
+            self.publish_status();
+        }
+        if let Some(stats) = &mut self.stats {
+            stats.hidden();
+        }
+        self.notifier.ready();
+        // eframe shows the window after its first frame whatever it was built with, so it is hidden again on
+        // every poll, and straight after that first frame
+        ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        if ctx.cumulative_pass_nr() == 0 {
+            ctx.request_repaint();
+        }
+        true
+    }
+
+    /// `--exit-on-input`: closes the window at a key, a click or the mouse
+    /// moving away from where it first was. Returns whether it did.
+    fn exit_on_input(&mut self, ctx: &egui::Context) -> bool {
+        if !self.exit_on_input {
+            return false;
+        }
+        let (touched, pointer) = ctx.input(|i| (i.events.iter().any(is_touch), i.pointer.latest_pos()));
+        let moved = match (pointer, self.pointer_origin) {
+            (Some(pointer), Some(origin)) => pointer.distance(origin) > EXIT_POINTER_MOVE,
+            (Some(pointer), None) => {
+                self.pointer_origin = Some(pointer);
+                false
+            }
+            (None, _) => false,
+        };
+        if !touched && !moved {
+            return false;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        true
+    }
+
+    /// Escape or Q closes the window. In kiosk mode Escape asks for the PIN instead.
+    fn handle_exit_keys(&mut self, ctx: &egui::Context) {
+        let (escape, q) = ctx.input(|i| (i.key_pressed(egui::Key::Escape), i.key_pressed(egui::Key::Q)));
+
+        match &mut self.kiosk {
+            Some(lock) if escape => lock.open(),
+            Some(_) => {}
+            None if escape || q => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            None => {}
+        }
+    }
+
+    /// Draws the kiosk's PIN pad or admin menu and does what the menu asks.
+    fn show_kiosk_lock(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.kiosk.as_mut().and_then(|lock| lock.show(ctx)) else {
+            return;
+        };
+        match action {
+            AdminAction::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            AdminAction::Settings => {
+                self.open_settings();
+                self.publish_status();
+            }
+            AdminAction::Rescan => self.rescan(),
+        }
+    }
+
+    fn live_settings(&self) -> LiveSettings {
+        LiveSettings {
+            slide_duration: self.scheduler.slide_duration.as_secs_f32(),
+            shuffle: self.shuffle,
+            transition: self.transition,
+            fit: self.loader.options.fit,
+            overlay_mode: self.overlay_mode,
+            brightness: self.loader.options.brightness,
+            gamma: self.loader.options.gamma,
+        }
+    }
+
+    fn apply_settings(&mut self, settings: LiveSettings) {
+        let current = self.live_settings();
+        if settings.slide_duration != current.slide_duration {
+            self.scheduler.set_slide_duration(Duration::from_secs_f32(settings.slide_duration.max(0.0)));
+        }
+        if settings.fit != current.fit {
+            for loader in [&mut self.loader, &mut self.upgrader] {
+                loader.options.fit = settings.fit;
+            }
+            self.loader.forget_all();
+            self.reload_current();
+        }
+        if (settings.brightness, settings.gamma) != (current.brightness, current.gamma) {
+            for loader in [&mut self.loader, &mut self.upgrader] {
+                loader.options.brightness = settings.brightness;
+                loader.options.gamma = settings.gamma;
+            }
+            self.loader.forget_all();
+            self.reload_current();
+        }
+        self.overlay_mode = settings.overlay_mode;
+        if settings.transition != current.transition {
+            self.transition = settings.transition;
+            let keeps_outgoing = settings.transition != Transition::Cut && !self.black_frame;
+            let duration = self.collection_config.transition_duration();
+            self.scheduler.transition = if keeps_outgoing { duration } else { Duration::ZERO };
+        }
+        // the folders are read again for the new order, folder order isn't kept once shuffled
+        if settings.shuffle != current.shuffle {
+            self.shuffle = settings.shuffle;
+            self.collection_config.shuffle = settings.shuffle;
+            self.rescan();
+        }
+    }
+
+    /// Processes the slide on screen and the preloaded one again after the options changed.
+    /// The slide on screen is swapped like a preview once the new version is done.
+    fn reload_current(&mut self) {
+        let index = self.scheduler.current_index;
+        if let Some(loaded) = &mut self.current_textures {
+            loaded.is_preview = true;
+            self.upgrader.start(index, self.scheduler.slide(&self.artworks, index), false);
+        }
+        self.scheduler.discard_preload();
+    }
+
+    /// Opens the settings panel, holding the slide on screen while it is open.
+    fn open_settings(&mut self) {
+        if !self.scheduler.is_paused() {
+            self.scheduler.pause(Instant::now());
+            self.paused_for_settings = true;
+        }
+        self.settings.open();
+    }
+
+    fn close_settings(&mut self) {
+        self.settings.close();
+        if std::mem::take(&mut self.paused_for_settings) {
+            self.scheduler.resume(Instant::now());
+        }
+
+        let text = match &self.settings_file {
+            Some(path) => match self.live_settings().save(path) {
+                Ok(()) => "Settings saved".to_string(),
+                Err(err) => {
+                    tracing::warn!("cannot save settings to {}: {}", path.display(), err);
+                    format!("Cannot save settings: {}", err)
+                }
+            },
+            None => "Settings kept until restart, start with --config FILE to save them".to_string(),
+        };
+        self.toast = Some(Toast::new(text));
+    }
+
+    /// Opens the metadata editor on the artwork on screen, holding it there.
+    fn open_editor(&mut self) {
+        let Some(info) = self.artworks.get(self.scheduler.current_index) else {
+            return;
+        };
+        self.editor.open(&info.path, &info.metadata);
+        if !self.scheduler.is_paused() {
+            self.scheduler.pause(Instant::now());
+            self.paused_for_editor = true;
+        }
+    }
+
+    fn close_editor(&mut self, edit: Edit) {
+        self.editor.close();
+        if std::mem::take(&mut self.paused_for_editor) {
+            self.scheduler.resume(Instant::now());
+        }
+        let Edit::Saved(path, metadata) = edit else {
+            return;
+        };
+        // its fit or filter may have changed
+        self.loader.forget(&path);
+
+        let index = self.scheduler.current_index;
+        if let Some(loaded) = &mut self.current_textures {
+            let shown = self.artworks.iter().skip(index).take(loaded.works.len());
+            if let Some(offset) = shown.into_iter().position(|info| info.path == path) {
+                loaded.works[offset] = metadata.clone();
+            }
+        }
+        if let Some(info) = self.artworks.iter_mut().find(|info| info.path == path) {
+            info.metadata = metadata;
+        }
+        self.toast = Some(Toast::new("Metadata saved"));
+        self.publish_status();
+    }
+
+    /// Switches to the folder or images dropped on the window. Anything that
+    /// isn't an image leaves the current show as it is.
+    fn load_dropped(&mut self, paths: &[PathBuf]) {
+        let (folder, mut artworks) = match scan::scan_dropped(paths) {
+            Ok(dropped) => dropped,
+            Err(err) => {
+                tracing::warn!("ignoring drop: {}", err);
+                self.toast = Some(Toast::new(format!("Cannot show that: {}", err)));
+                return;
+            }
+        };
+        self.shuffle.apply(&mut artworks);
+        tracing::info!("switching to {} artworks from {}", artworks.len(), folder.display());
+        self.toast = Some(Toast::new(format!("Loaded {} images from {}", artworks.len(), folder.display())));
+        self.replace_show(Collection {
+            artworks,
+            empty_message: self.empty_message.clone(),
+            favorites: Favorites::load(&folder),
+            ratings: Ratings::load(&folder),
+            source: Arc::new(FolderSource::new(&folder)),
+            folder_path: folder,
+            ordered_on: None,
+        });
+    }
+
+    /// Whether the clock has moved on to another collection than the one showing.
+    fn collection_due(&self) -> bool {
+        !self.collections.is_empty()
+            && self.collections.active_now().map(|collection| &collection.name) != self.active_collection.as_ref()
+    }
+
+    /// Whether the works put first for `--on-this-day` were put first for another day than today.
+    fn day_changed(&self) -> bool {
+        self.ordered_on.is_some_and(|day| day != chrono::Local::now().date_naive())
+    }
+
+    /// Switches to the collection the clock is at.
+    fn switch_collection(&mut self) {
+        let scheduled = self.collections.active_now().cloned();
+        let name = scheduled.as_ref().map(|collection| collection.name.clone());
+        let from = collection_label(self.active_collection.as_deref());
+        let to = collection_label(name.as_deref());
+        // not tried again before the next change once it fails
+        self.active_collection = name;
+        self.ordered_on = None;
+        match collect_scheduled(&self.home_folder, scheduled.as_ref(), &self.collection_config) {
+            Ok(collection) => {
+                let count = collection.artworks.len();
+                if from == to {
+                    tracing::info!("a new day, putting {} in order again, {} artworks", to, count);
+                } else {
+                    tracing::info!("switching from {} to {}, {} artworks", from, to, count);
+                }
+                self.replace_show(collection);
+            }
+            Err(err) => tracing::warn!("cannot switch from {} to {}, staying on it: {}", from, to, err),
+        }
+    }
+
+    /// Reads the show's folders again, taking in what was added or removed
+    /// since, and starts it over.
+    fn rescan(&mut self) {
+        let scheduled = self.collections.active_now().cloned();
+        self.active_collection = scheduled.as_ref().map(|collection| collection.name.clone());
+        match collect_scheduled(&self.home_folder, scheduled.as_ref(), &self.collection_config) {
+            Ok(collection) => {
+                tracing::info!("rescanned, {} artworks", collection.artworks.len());
+                self.toast = Some(Toast::new(format!("Found {} artworks", collection.artworks.len())));
+                self.replace_show(collection);
+            }
+            Err(err) => {
+                tracing::warn!("cannot rescan: {}", err);
+                self.toast = Some(Toast::new(format!("Cannot rescan: {}", err)));
+            }
+        }
+    }
+
+    /// Reads the command line and its config file again, and with them the
+    /// folders. What makes up the show and the slide duration change, the
+    /// rest of the settings take a restart.
+    fn reload(&mut self) {
+        if let Some(args) = &self.reload_args {
+            match cli::parse(args.clone()) {
+                Ok(cli) => {
+                    tracing::info!("reloaded the settings");
+                    self.scheduler.set_slide_duration(cli.config.slide_duration());
+                    self.shuffle = cli.config.shuffle;
+                    self.collections = cli.config.collections.clone();
+                    self.home_folder = cli.config.commons_folder().unwrap_or(cli.folder);
+                    self.collection_config = cli.config;
+                }
+                Err(err) => tracing::warn!("cannot reload the settings, keeping them: {}", err),
+            }
+        }
+        self.rescan();
+    }
+
+    /// Puts `collection` up in place of the show, from its first slide.
+    fn replace_show(&mut self, collection: Collection) {
+        // the edit was for a work of the old show
+        self.close_editor(Edit::Cancelled);
+        // what was being processed ahead was for the old show, and so was the pause for it
+        let preprocessing = self.preprocess.take().is_some() || std::mem::take(&mut self.preprocess_pending);
+        self.loader.forget_all();
+        let was_paused = self.scheduler.is_paused() && !preprocessing;
+        let now = Instant::now();
+        let (give_up_after, loops, transition) =
+            (self.scheduler.give_up_after, self.scheduler.loops, self.scheduler.transition);
+        self.scheduler = Scheduler::new(self.scheduler.slide_duration, now);
+        self.scheduler.set_step(self.loader.options.layout.cells());
+        self.scheduler.give_up_after = give_up_after;
+        self.scheduler.loops = loops;
+        self.scheduler.transition = transition;
+        if was_paused {
+            self.scheduler.pause(now);
+        }
+        self.loader.cancel();
+        self.upgrader.cancel();
+        for loader in [&mut self.loader, &mut self.upgrader, &mut self.zoom_loader] {
+            loader.source = Arc::clone(&collection.source);
+        }
+        self.artworks = collection.artworks;
+        self.empty_message = collection.empty_message;
+        self.current_textures = None;
+        self.outgoing = None;
+        self.chapter_card = None;
+        self.faded_out_at = None;
+        self.slide_serial += 1;
+        self.navigated = false;
+        self.filmstrip.close();
+        self.search.close();
+        self.favorites = collection.favorites;
+        self.ratings = collection.ratings;
+        self.folder_path = collection.folder_path;
+        self.ordered_on = collection.ordered_on;
+        self.update_weights();
+        self.publish_status();
+    }
+
+    /// Asks whether to move the artwork in `delete_prompt` to the trash, and
+    /// does it on Enter or a click.
+    fn show_delete_prompt(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.delete_prompt.clone() else {
+            return;
+        };
+        let title = match self.artworks.iter().find(|info| info.path == path) {
+            Some(info) => info.metadata.title.clone(),
+            None => path.display().to_string(),
+        };
+
+        let mut confirmed = ctx.input(|i| i.key_pressed(egui::Key::Enter));
+        let mut cancelled = false;
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+        egui::Area::new(egui::Id::new("delete_prompt"))
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                frame.show(ui, |ui| {
+                    ui.label(format!("Move “{}” to {}?", title, trash::TRASH_DIR));
+                    ui.horizontal(|ui| {
+                        confirmed |= ui.button("Move (Enter)").clicked();
+                        cancelled |= ui.button("Cancel (Escape)").clicked();
+                    });
+                });
+            });
+
+        if cancelled {
+            self.delete_prompt = None;
+        } else if confirmed {
+            self.delete_prompt = None;
+            self.delete(&path);
+        }
+    }
+
+    /// Moves `path` into the trash and out of the show. If it was on screen,
+    /// the next artwork takes its place right away.
+    fn delete(&mut self, path: &Path) {
+        let Some(index) = self.artworks.iter().position(|info| info.path == path) else {
+            return;
+        };
+        match trash::move_to_trash(path) {
+            Ok(moved) => tracing::info!("moved {} to {}", path.display(), moved.display()),
+            Err(err) => {
+                tracing::warn!("cannot move {} to the trash: {}", path.display(), err);
+                self.toast = Some(Toast::new(format!("Cannot move to the trash: {}", err)));
+                return;
+            }
+        }
+
+        let on_screen = index == self.scheduler.current_index;
+        self.loader.forget(path);
+        self.artworks.remove(index);
+        self.scheduler.remove(index, self.artworks.len(), Instant::now());
+        // anything in flight was asked for by index, and those moved
+        self.loader.cancel();
+        self.upgrader.cancel();
+        if on_screen {
+            // the scheduler waits for the artwork now at this index, as for the first
+            self.current_textures = None;
+            self.outgoing = None;
+            self.slide_serial += 1;
+            if self.artworks.is_empty() {
+                if let Some(stats) = &mut self.stats {
+                    stats.hidden();
+                }
+            }
+        } else if self.current_textures.as_ref().is_some_and(|loaded| loaded.is_preview) {
+            // its full-quality version was cancelled along with the rest
+            let current = self.scheduler.current_index;
+            self.upgrader.start(current, self.scheduler.slide(&self.artworks, current), false);
+        }
+        self.toast = Some(Toast::new("Moved to the trash"));
+        self.update_weights();
+        self.publish_status();
+    }
+
+    /// Writes a frame captured from the screen to a timestamped PNG in the screenshot directory.
+    fn save_screenshot(&mut self, image: &egui::ColorImage) {
+        let name = chrono::Local::now().format("slideshow-%Y%m%d-%H%M%S-%3f.png").to_string();
+        let path = self.screenshot_dir.join(name);
+        let pixels = image.pixels.iter().flat_map(|pixel| pixel.to_srgba_unmultiplied()).collect();
+        let frame = image::RgbaImage::from_raw(image.size[0] as u32, image.size[1] as u32, pixels)
+            .expect("as many pixels as the screenshot is large");
+        let saved = std::fs::create_dir_all(&self.screenshot_dir)
+            .map_err(image::ImageError::IoError)
+            .and_then(|()| frame.save(&path));
+        match saved {
+            Ok(()) => {
+                tracing::info!("saved a screenshot to {}", path.display());
+                self.toast = Some(Toast::new(format!("Saved {}", path.display())));
+            }
+            Err(err) => {
+                tracing::warn!("cannot save a screenshot to {}: {}", path.display(), err);
+                self.toast = Some(Toast::new(format!("Cannot save the screenshot: {}", err)));
+            }
+        }
+    }
+
+    /// Copies the artwork on screen to the clipboard, only its path with `path_only`.
+    fn copy_current(&mut self, ctx: &egui::Context, path_only: bool) {
+        let Some(info) = self.artworks.get(self.scheduler.current_index) else {
+            return;
+        };
+        let path = std::fs::canonicalize(&info.path).unwrap_or_else(|_| info.path.clone());
+        let text = if path_only { path.display().to_string() } else { clipboard_text(&info.metadata, &path) };
+        ctx.output_mut(|o| o.copied_text = text);
+        self.toast = Some(Toast::new(if path_only { "Copied the path" } else { "Copied" }));
+    }
+
+    /// Opens the artwork on screen in the default viewer, or with `reveal` in the file manager.
+    fn open_current(&mut self, reveal: bool) {
+        let Some(info) = self.artworks.get(self.scheduler.current_index) else {
+            return;
+        };
+        if !info.path.exists() {
+            tracing::warn!("cannot open {}, it is gone", info.path.display());
+            self.toast = Some(Toast::new("The file is gone"));
+            return;
+        }
+        launch::open(&info.path, reveal);
+    }
+
+    /// Reads the sidecars of the slide on screen again, so a fixed typo shows
+    /// right away. A sidecar that doesn't parse leaves the caption as it was.
+    fn reload_metadata(&mut self) {
+        let index = self.scheduler.current_index;
+        let Some(loaded) = &mut self.current_textures else {
+            return;
+        };
+        let mut error = None;
+        for (work, info) in loaded.works.iter_mut().zip(self.artworks.iter_mut().skip(index)) {
+            match scan::try_read_metadata(&info.path) {
+                Ok(metadata) => {
+                    *work = metadata.clone();
+                    info.metadata = metadata;
+                }
+                Err(err) => {
+                    tracing::warn!("bad sidecar {}: {}", info.path.with_extension("json").display(), err);
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        let text = match error {
+            Some(err) => format!("Bad sidecar: {}", err),
+            None => "Metadata reloaded".to_string(),
+        };
+        self.toast = Some(Toast::new(text));
+        self.publish_status();
+    }
+
+    /// Hands the ratings to the scheduler in weighted playback.
+    fn update_weights(&mut self) {
+        if self.weighted {
+            self.scheduler.set_weights(Some(self.ratings.weights(&self.artworks)));
+        }
+    }
+
+    fn rate(&mut self, stars: u8) {
+        let Some(info) = self.artworks.get(self.scheduler.current_index) else {
+            return;
+        };
+
+        let text = match self.ratings.set(&info.path, stars) {
+            Ok(()) => ratings::star_text(stars),
+            Err(err) => {
+                tracing::warn!("cannot save ratings: {}", err);
+                format!("Cannot save ratings: {}", err)
+            }
+        };
+        self.toast = Some(Toast::new(text));
+        self.update_weights();
+    }
+
+    fn toggle_favorite(&mut self) {
+        let Some(info) = self.artworks.get(self.scheduler.current_index) else {
+            return;
+        };
+
+        let text = match self.favorites.toggle(&info.path) {
+            Ok(true) => "♥ Added to favorites".to_string(),
+            Ok(false) => "Removed from favorites".to_string(),
+            Err(err) => {
+                tracing::warn!("cannot save favorites: {}", err);
+                format!("Cannot save favorites: {}", err)
+            }
+        };
+        self.toast = Some(Toast::new(text));
+    }
+
+    fn publish_status(&self) {
+        let mut status = self.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let index = self.scheduler.current_index;
+        let metadata = self.current_textures.as_ref().and_then(|loaded| loaded.works.first());
+
+        status.index = index;
+        status.total = self.artworks.len();
+        status.path = self.artworks.get(index).map(|info| info.path.clone());
+        status.title = metadata.map(|m| m.title.clone());
+        status.artist = metadata.map(|m| m.artist.clone());
+        status.year = metadata.map(|m| m.year.clone());
+        status.collections = vec![self.folder_path.clone()];
+        status.slides_shown = self.slide_serial + 1;
+        status.paused = self.scheduler.is_paused();
+        status.remaining = self.scheduler.remaining(Instant::now());
+        status.taken_at = Some(Instant::now());
+
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.publish_state(&status);
+        }
+        if let Some(media_keys) = &self.media_keys {
+            media_keys.publish(&status);
+        }
+    }
+
+    /// Puts `processed` on screen, and has it replaced by full quality if it is only a preview.
+    /// Without an image the slide is processed again on the next frame.
+    fn show(&mut self, ctx: &egui::Context, slide: Option<PreparedSlide>) {
+        self.reset_zoom();
+        let delay = if std::mem::take(&mut self.navigated) { CAPTION_DELAY_MANUAL } else { CAPTION_DELAY };
+        self.caption_reveal = CaptionReveal::new(delay);
+        self.current_textures = slide.map(|slide| {
+            let index = self.scheduler.current_index;
+            tracing::info!("showing {}/{}: {}", index + 1, self.artworks.len(), self.artworks[index].path.display());
+            if slide.is_preview {
+                self.upgrader.start(index, self.scheduler.slide(&self.artworks, index), false);
+            }
+            if let Some(stats) = &mut self.stats {
+                stats.shown(&self.artworks[index]);
+            }
+            if slide.detail_duration.is_some() {
+                self.scheduler.set_detail_duration(slide.detail_duration);
+            }
+            Self::create_textures(ctx, slide, &format!("slide{}", self.slide_serial))
+        });
+        if self.current_textures.is_some() {
+            self.notifier.ready();
+        }
+        self.narrate(narrate::DELAY);
+    }
+
+    /// The caption's place on the slide on screen.
+    fn caption_settings(&self) -> OverlaySettings {
+        OverlaySettings {
+            position: self.caption_positions[self.slide_serial as usize % self.caption_positions.len()],
+            ..self.caption_layout
+        }
+    }
+
+    /// Keeps the slide on screen to transition out of as the next one comes up.
+    /// A cut and the OLED black frame keep nothing, the slide is dropped as before.
+    fn begin_transition(&mut self) {
+        self.outgoing = None;
+        if self.transition == Transition::Cut || self.black_frame {
+            return;
+        }
+        let overlay_alpha = self.overlay_mode.alpha(self.overlay_woken_at.elapsed()) * self.caption_reveal.alpha();
+        let (caption_layout, (caption_work, _)) = (self.caption_settings(), self.caption_turn());
+        self.outgoing = self.current_textures.take().map(|slide| Outgoing {
+            slide,
+            caption_layout,
+            caption_work,
+            overlay_alpha,
+        });
+    }
+
+    /// Posts a file that failed to the error webhook, unless it was the last one posted.
+    fn report_failure(&mut self, path: PathBuf, message: String) {
+        let Some(webhook) = &self.error_webhook else {
+            return;
+        };
+        if self.last_error.as_ref() != Some(&path) {
+            webhook.send(Event::new(EventKind::Error, Some(&path), message));
+            self.last_error = Some(path);
+        }
+    }
+
+    /// Posts the slide on screen to the error webhook once it has been up
+    /// [`webhook::STALL_FACTOR`] times as long as it should have.
+    fn check_stall(&mut self) {
+        let Some(webhook) = &self.error_webhook else {
+            return;
+        };
+        let expected = self.scheduler.current_duration().unwrap_or(self.scheduler.slide_duration);
+        let shown = self.scheduler.shown_for(Instant::now());
+        // a show that has ended stays on its last slide on purpose
+        let holding = self.scheduler.is_paused() || self.scheduler.has_ended();
+        let stalled = !holding && shown > expected * webhook::STALL_FACTOR;
+        if stalled && !self.stall_reported {
+            let path = self.artworks.get(self.scheduler.current_index).map(|info| info.path.as_path());
+            let message = format!("no slide change for {} s, {} s expected", shown.as_secs(), expected.as_secs());
+            tracing::warn!("the show looks stuck: {}", message);
+            webhook.send(Event::new(EventKind::Stall, path, message));
+        }
+        self.stall_reported = stalled;
+    }
+
+    /// Takes in the files of the Commons category downloaded since the show was
+    /// last scanned: the first one at once when there is nothing to show, the
+    /// rest once all are there, so the show doesn't start over with each.
+    fn update_commons(&mut self, ctx: &egui::Context) {
+        let Some(downloader) = &self.commons else {
+            return;
+        };
+        let progress = downloader.progress();
+        if progress.finished {
+            self.commons = None;
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+        if progress.downloaded > self.commons_scanned && (self.artworks.is_empty() || progress.finished) {
+            self.commons_scanned = progress.downloaded;
+            self.rescan();
+        }
+    }
+
+    /// Posts the slide on screen to the heartbeat webhook if the hour is up.
+    /// Returns how long until the next one.
+    fn heartbeat(&mut self) -> Option<Duration> {
+        let webhook = self.heartbeat_webhook.as_ref()?;
+        let now = Instant::now();
+        let since = self.last_heartbeat.map(|last| now.duration_since(last));
+        if since.is_some_and(|since| since < webhook::HEARTBEAT_INTERVAL) {
+            return since.map(|since| webhook::HEARTBEAT_INTERVAL - since);
+        }
+        let index = self.scheduler.current_index;
+        let (path, message) = match self.artworks.get(index) {
+            Some(info) => (
+                Some(info.path.as_path()),
+                format!("showing {}/{}: {}", index + 1, self.artworks.len(), info.metadata.title),
+            ),
+            None => (None, self.empty_message.clone()),
+        };
+        webhook.send(Event::new(EventKind::Heartbeat, path, message));
+        self.last_heartbeat = Some(now);
+        Some(webhook::HEARTBEAT_INTERVAL)
+    }
+
+    /// Plays the transition sound, if there is one.
+    fn ring(&self) {
+        if let Some(chime) = &self.chime {
+            chime.ring();
+        }
+    }
+
+    /// Reads the caption of the slide on screen out after `delay`, with `--narrate`.
+    fn narrate(&self, delay: Duration) {
+        let (Some(narrator), Some(loaded)) = (&self.narrator, &self.current_textures) else {
+            return;
+        };
+        let text = loaded.works.iter().map(narrate::narration).collect::<Vec<_>>().join(". ");
+        narrator.say(text, delay);
+    }
+
+    /// Scrolling zooms toward the pointer and dragging pans, while the show holds
+    /// on the slide. A double click or 0 zooms back out.
+    fn handle_zoom(&mut self, ctx: &egui::Context, enabled: bool) {
+        if let Some(Loaded::Ready { index, .. }) = self.zoom_loader.poll() {
+            let slide = self.zoom_loader.take();
+            if let (Some(zoom), Some(slide), true) = (&mut self.zoom, slide, index == self.scheduler.current_index) {
+                let name = format!("slide{}_zoom", self.slide_serial);
+                zoom.texture = Some(ctx.load_texture(name, slide.main_image, texture_options(slide.pixel_art)));
+            }
+        }
+
+        // the worker read the size from the artwork's header, or from what it decoded
+        let source_size = self
+            .current_textures
+            .as_ref()
+            .filter(|loaded| loaded.works.len() == 1)
+            .map(|loaded| loaded.source_size);
+        let (Some(drawn_width), Some((width, height)), true) = (self.shown_width, source_size, enabled) else {
+            return;
+        };
+        let physical = ctx.screen_rect().size();
+        let rotation = self.rotation;
+        let (factor, pointer, drag, reset, max_texture_side) = ctx.input(|i| {
+            let pointer = i.pointer.hover_pos();
+            let drag = match pointer {
+                Some(p) if i.pointer.primary_down() => {
+                    rotation.unmap(p, physical) - rotation.unmap(p - i.pointer.delta(), physical)
+                }
+                _ => egui::Vec2::ZERO,
+            };
+            (
+                (i.smooth_scroll_delta.y * 0.005).exp() * i.zoom_delta(),
+                pointer.map(|p| rotation.unmap(p, physical)),
+                drag,
+                i.pointer.button_double_clicked(egui::PointerButton::Primary) || i.key_pressed(egui::Key::Num0),
+                i.max_texture_side,
+            )
+        });
+        if reset {
+            self.reset_zoom();
+            return;
+        }
+
+        if factor != 1.0 && self.zoom.is_none() {
+            // a texture can only be so large, neither can the zoom then
+            let decoded_width = width as f32 * (max_texture_side as f32 / width.max(height) as f32).min(1.0);
+            let resume = !self.scheduler.is_paused();
+            if resume {
+                self.scheduler.pause(Instant::now());
+            }
+            self.zoom = Some(ZoomState {
+                view: Zoom::default(),
+                max_scale: decoded_width / (drawn_width * ctx.pixels_per_point()),
+                texture: None,
+                requested: false,
+                resume,
+            });
+        }
+        let Some(zoom) = &mut self.zoom else {
+            return;
+        };
+        if let (Some(anchor), true) = (pointer, factor != 1.0) {
+            zoom.view.zoom_at(factor, anchor, zoom.max_scale);
+        }
+        zoom.view.offset += drag;
+        if !zoom.view.is_zoomed() {
+            self.reset_zoom();
+            return;
+        }
+
+        let texture_width = self.current_textures.as_ref().map_or(0, |loaded| loaded.texture.size()[0]);
+        let wanted_width = drawn_width * zoom.view.scale * ctx.pixels_per_point();
+        if !zoom.requested && wanted_width > texture_width as f32 {
+            zoom.requested = true;
+            let index = self.scheduler.current_index;
+            self.zoom_loader.options = ProcessingOptions {
+                max_dimension: max_texture_side as u32,
+                background: BackgroundMode::Black,
+                // too large to be worth keeping
+                cache: None,
+                ..self.loader.options.clone()
+            };
+            self.zoom_loader.wake = Some(ctx.clone());
+            self.zoom_loader.start(index, &self.artworks[index..=index], false);
+        }
+    }
+
+    /// Back to the whole slide, and the show on from where it was.
+    fn reset_zoom(&mut self) {
+        if let Some(zoom) = self.zoom.take() {
+            if zoom.resume {
+                self.scheduler.resume(Instant::now());
+            }
+            self.zoom_loader.cancel();
+        }
+    }
+
+    /// Swaps the full-quality image in for the preview on screen once it's done.
+    fn finish_upgrade(&mut self, ctx: &egui::Context) {
+        let Some(Loaded::Ready { index, .. }) = self.upgrader.poll() else {
+            return;
+        };
+        let showing_preview = self.current_textures.as_ref().is_some_and(|loaded| loaded.is_preview);
+        if index != self.scheduler.current_index || !showing_preview {
+            return;
+        }
+        let Some(full) = self.upgrader.take() else {
+            return;
+        };
+
+        let mut loaded = Self::create_textures(ctx, full, &format!("slide{}_full", self.slide_serial));
+        // the caption keeps its colors for the whole slide
+        loaded.caption_palette = self.current_textures.as_ref().and_then(|current| current.caption_palette);
+        self.current_textures = Some(loaded);
+    }
+
+    /// The work of a collage the caption is on, and how long until it moves on.
+    /// A pair has a caption for each, so it doesn't take turns.
+    fn caption_turn(&self) -> (usize, Option<Duration>) {
+        let works = match &self.current_textures {
+            Some(loaded) if self.loader.options.layout != SlideLayout::Single => loaded.works.len(),
+            _ => 1,
+        };
+        match (self.scheduler.current_duration(), self.scheduler.remaining(Instant::now())) {
+            (Some(duration), Some(remaining)) => overlay::caption_turn(works, duration - remaining, duration),
+            _ => (0, None),
+        }
+    }
+
+    /// Time until something on screen is due to change without any input:
+    /// the next slide, the caption fading in, out or over to the next work of a collage, the clock,
+    /// the cursor hiding or the night starting. Finished loads and remote commands wake the UI themselves.
+    fn next_wakeup(&self) -> Duration {
+        let now = Instant::now();
+        let slide = self.scheduler.remaining(now).filter(|_| !self.scheduler.is_paused());
+        let caption = self.overlay_mode.until_fade(self.overlay_woken_at.elapsed());
+        let reveal = self.caption_reveal.until_start().filter(|_| self.overlay_mode != OverlayMode::Hidden);
+        let turn = self
+            .caption_turn()
+            .1
+            .filter(|_| !self.scheduler.is_paused() && self.overlay_mode != OverlayMode::Hidden);
+        let clock = self.clock_corner.map(|_| self.clock_format.until_change());
+        let toast = self.toast.as_ref().map(Toast::until_fade);
+        let idle = self.last_pointer_activity.elapsed();
+        let cursor = (!self.show_cursor).then(|| CURSOR_HIDE_AFTER.saturating_sub(idle));
+        let night = self.off_hours.and_then(|off_hours| off_hours.until_change_now());
+        let black = self.black_until.map(|until| until.saturating_duration_since(Instant::now()));
+        let drift = self.orbit_started.map(|started| burnin::until_shift(started.elapsed()));
+        let late = self.scheduler.late_by(now).map(|late| LATE_NOTICE.saturating_sub(late));
+        let give_up = self.scheduler.until_give_up(now);
+        let card = self.chapter_card.as_ref().map(|card| card.until_fade(self.chapter_duration));
+
+        [slide, caption, reveal, turn, clock, toast, cursor, night, black, drift, late, give_up, card]
+            .into_iter()
+            .flatten()
+            .filter(|wait| !wait.is_zero())
+            .fold(MAX_IDLE, Duration::min)
+    }
+
+    /// Sizes the slides for the screen, and the blur too unless it was given a size.
+    fn fit_to_screen(&mut self, ctx: &egui::Context) {
+        for loader in [&mut self.loader, &mut self.upgrader] {
+            loader.wake = Some(ctx.clone());
+        }
+        let screen = self.screen_pixels(ctx);
+        self.fitted_screen = Some(screen);
+        for loader in [&mut self.loader, &mut self.upgrader] {
+            loader.options.canvas = screen;
+            if self.blur_for_screen {
+                loader.options.blur_for_screen(screen);
+            }
+        }
+        // side by side, two portraits only make use of a screen wider than tall
+        self.scheduler
+            .set_pairing(self.pairing && self.loader.options.layout == SlideLayout::Single && screen.0 > screen.1);
+    }
+
+    /// The screen in physical pixels, as the slides are laid out on it.
+    fn screen_pixels(&self, ctx: &egui::Context) -> (u32, u32) {
+        let screen = self.rotation.logical_size(ctx.screen_rect().size()) * ctx.pixels_per_point();
+        (screen.x as u32, screen.y as u32)
+    }
+
+    /// Fits the slides to a window that changed shape, or to a screen that grew
+    /// past `max_dimension` as a TV switching to 4K does, once it has held its
+    /// new size for [`RESIZE_SETTLE`]. The slide on screen stays as it is until
+    /// its new version is ready.
+    fn follow_resize(&mut self, ctx: &egui::Context) {
+        let Some(fitted) = self.fitted_screen else {
+            return;
+        };
+        let screen = self.screen_pixels(ctx);
+        let limit = ctx.input(|i| i.max_texture_side as u32).min(*pipeline::MAX_DIMENSION_RANGE.end());
+        let grown = outgrown(fitted, screen, self.loader.options.max_dimension, limit);
+        if !reshaped(fitted, screen) && grown.is_none() {
+            self.resize = None;
+            return;
+        }
+        let since = match self.resize {
+            Some((size, since)) if size == screen => since,
+            _ => {
+                self.resize = Some((screen, Instant::now()));
+                Instant::now()
+            }
+        };
+        let settled = since.elapsed();
+        if settled < RESIZE_SETTLE {
+            ctx.request_repaint_after(RESIZE_SETTLE - settled);
+            return;
+        }
+        tracing::debug!("screen resized from {:?} to {:?}, processing again", fitted, screen);
+        self.resize = None;
+        if let Some(max_dimension) = grown {
+            for loader in [&mut self.loader, &mut self.upgrader] {
+                loader.options.max_dimension = max_dimension;
+            }
+        }
+        self.fit_to_screen(ctx);
+        self.loader.forget_all();
+        self.reload_current();
+    }
+
+    /// `--force-fullscreen`: asks for fullscreen again when the window has left
+    /// it, as a display that slept or was plugged back in can leave it windowed.
+    fn keep_fullscreen(&mut self, ctx: &egui::Context) {
+        if !self.force_fullscreen || ctx.input(|i| i.viewport().fullscreen) != Some(false) {
+            return;
+        }
+        if self.fullscreen_asked.is_some_and(|asked| asked.elapsed() < FULLSCREEN_RETRY) {
+            ctx.request_repaint_after(FULLSCREEN_RETRY);
+            return;
+        }
+        tracing::info!("the window left fullscreen, asking for it again");
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        self.fullscreen_asked = Some(Instant::now());
+    }
+
+    /// Runs `--preprocess-all`, with its progress on screen instead of the
+    /// show. Returns whether it is still going.
+    fn update_preprocess(&mut self, ctx: &egui::Context) -> bool {
+        if std::mem::take(&mut self.preprocess_pending) {
+            self.fit_to_screen(ctx);
+            let options = self.loader.options.clone();
+            let source = Arc::clone(&self.loader.source);
+            let process = move |info: &ArtworkInfo| {
+                let slide = std::slice::from_ref(info);
+                let processed =
+                    pipeline::catch_panic(|| process_slide(slide, &*source, &options, pipeline::process_image));
+                processed.map(PreparedSlide::from)
+            };
+            self.preprocess = Some(Preprocess::start(&self.artworks, process, Some(ctx.clone())));
+        }
+        let Some(preprocess) = &mut self.preprocess else {
+            return false;
+        };
+
+        let mut left_out = 0;
+        for (index, slide) in preprocess.poll() {
+            if !self.loader.keep(self.artworks[index].path.clone(), slide) {
+                left_out += 1;
+            }
+        }
+        if left_out > 0 {
+            tracing::debug!("{} processed artworks don't fit in memory, they come from the disk cache", left_out);
+        }
+        if !preprocess.is_done() {
+            preprocess.show(ctx);
+            return true;
+        }
+
+        let kept = self.loader.prepared.len();
+        if kept < self.artworks.len() && self.loader.options.cache.is_none() {
+            tracing::warn!("only {} processed artworks fit in memory and there is no disk cache for the rest", kept);
+        }
+        let failed = preprocess.failed();
+        if !failed.is_empty() {
+            self.toast = Some(Toast::new(preprocess::failure_summary(&preprocess.failed_names())));
+        }
+        self.preprocess = None;
+        let now = Instant::now();
+        for index in failed.into_iter().rev() {
+            self.artworks.remove(index);
+            self.scheduler.remove(index, self.artworks.len(), now);
+        }
+        self.update_weights();
+        self.scheduler.resume(now);
+        self.publish_status();
+        if self.artworks.is_empty() {
+            // the next frame says so
+            self.empty_message = "None of the images could be processed.".to_string();
+            ctx.request_repaint();
+            return true;
+        }
+        false
+    }
+
+    /// Hands the prepared pixels to egui, which uploads them with the next frame.
+    fn create_textures(ctx: &egui::Context, slide: PreparedSlide, prefix: &str) -> LoadedArtwork {
+        let started = Instant::now();
+        let options = texture_options(slide.pixel_art);
+        let texture = ctx.load_texture(format!("{}_main", prefix), slide.main_image, options);
+        let background = match slide.background {
+            PreparedBackground::Image(image) => LoadedBackground::Texture(ctx.load_texture(
+                format!("{}_blur", prefix),
+                image,
+                egui::TextureOptions::LINEAR,
+            )),
+            PreparedBackground::Color(color) => LoadedBackground::Color(color),
+            PreparedBackground::Black => LoadedBackground::Black,
+        };
+        // sharp edges between the modules, whatever the scale
+        let qr_code = slide
+            .qr_code
+            .map(|code| ctx.load_texture(format!("{}_qr", prefix), code, egui::TextureOptions::NEAREST));
+        tracing::debug!("{}: texture handover {:?}", prefix, started.elapsed());
+
+        LoadedArtwork {
+            texture,
+            background,
+            caption_sample: slide.caption_sample,
+            display_size: slide.display_size,
+            source_size: slide.source_size,
+            fit: slide.fit,
+            is_preview: slide.is_preview,
+            caption_palette: None,
+            works: slide.works,
+            qr_code,
+            pixel_art: slide.pixel_art,
+        }
+    }
+}
+
+/// What a slide is drawn with on this frame. Both slides of a transition share
+/// it but for the caption, which the outgoing one keeps as it was.
+struct SlideFrame {
+    screen_size: egui::Vec2,
+    backdrop_rect: egui::Rect,
+    pixels_per_point: f32,
+    mat: MatSettings,
+    layout: SlideLayout,
+    caption_style: CaptionStyle,
+    caption_scale: f32,
+    caption_layout: OverlaySettings,
+    caption_work: usize,
+    overlay_alpha: f32,
+    scrim: bool,
+    qr_corner: Option<Corner>,
+}
+
+/// Draws `loaded` with its background, caption and QR code as `look` has it.
+/// Returns how wide the image was drawn, counting what a cover fit crops off.
+fn draw_slide(
+    ui: &mut egui::Ui,
+    loaded: &mut LoadedArtwork,
+    frame: &SlideFrame,
+    look: Look,
+    zoom: Option<(&mut Zoom, Option<&egui::TextureHandle>)>,
+) -> f32 {
+    let (ctx, layer) = (ui.ctx().clone(), ui.layer_id());
+    let first_shape = ctx.graphics(|graphics| graphics.get(layer).map_or(0, |list| list.next_idx().0));
+    let screen_size = frame.screen_size;
+    let shown_width = ui
+        .scope(|ui| {
+            ui.set_opacity(look.opacity);
+            match &loaded.background {
+                // Background blur FILL
+                LoadedBackground::Texture(texture) => {
+                    let img = egui::Image::new(texture)
+                        .fit_to_exact_size(frame.backdrop_rect.size())
+                        .maintain_aspect_ratio(false);
+
+                    ui.put(frame.backdrop_rect, img);
+                }
+                LoadedBackground::Color(color) => {
+                    ui.painter().rect_filled(frame.backdrop_rect, 0.0, *color);
+                }
+                // the panel frame is already black
+                LoadedBackground::Black => {}
+            }
+
+            // image centred, on its mat if it has one; a collage would let the mat show through its gaps
+            let mat = &frame.mat;
+            let matted = mat.is_on() && loaded.fit == Fit::Contain && loaded.works.len() == 1;
+            let (image_rect, uv) = layout::place_artwork(
+                loaded.fit,
+                loaded.display_size,
+                screen_size,
+                matted.then_some(mat),
+                loaded.pixel_art,
+                frame.pixels_per_point,
+            );
+            match zoom {
+                Some((view, texture)) => {
+                    // drawn straight away, the zoomed rect reaches far off the screen
+                    view.clamp(image_rect, screen_size);
+                    let texture = texture.unwrap_or(&loaded.texture);
+                    ui.painter().image(texture.id(), view.apply(image_rect), uv, egui::Color32::WHITE);
+                }
+                None => {
+                    if matted {
+                        mat.draw(ui.painter(), image_rect, screen_size);
+                    }
+                    ui.put(image_rect, egui::Image::new(&loaded.texture).uv(uv).fit_to_exact_size(image_rect.size()));
+                }
+            }
+
+            // Text overlay
+            let overlay_alpha = frame.overlay_alpha;
+            if overlay_alpha > 0.0 {
+                let (caption_scale, caption_layout) = (frame.caption_scale, frame.caption_layout);
+                // each of a pair gets its own caption under its half
+                let captions: Vec<Caption> = if frame.layout == SlideLayout::Single {
+                    let half = egui::vec2(screen_size.x / loaded.works.len() as f32, screen_size.y);
+                    loaded
+                        .works
+                        .iter()
+                        .enumerate()
+                        .map(|(i, metadata)| {
+                            Caption::layout(ui.painter(), metadata, half, caption_scale, caption_layout)
+                                .translated(egui::vec2(half.x * i as f32, 0.0))
+                        })
+                        .collect()
+                } else {
+                    let metadata = &loaded.works[frame.caption_work.min(loaded.works.len() - 1)];
+                    vec![Caption::layout(ui.painter(), metadata, screen_size, caption_scale, caption_layout)]
+                };
+                let first = captions[0].frame.rect;
+                let palette = *loaded.caption_palette.get_or_insert_with(|| {
+                    CaptionPalette::choose(frame.caption_style, loaded.caption_sample, image_rect, first)
+                });
+                if frame.scrim {
+                    let behind =
+                        captions.iter().fold(egui::Rect::NOTHING, |rect, caption| rect.union(caption.frame.rect));
+                    let top = caption_layout.position.is_top();
+                    overlay::draw_scrim(ui.painter(), behind, screen_size, top, overlay_alpha);
+                }
+                for caption in &captions {
+                    caption.draw(ui.painter(), overlay_alpha, palette);
+                }
+            }
+
+            if let (Some(texture), Some(corner)) = (&loaded.qr_code, frame.qr_corner) {
+                overlay::draw_qr_code(ui.painter(), texture, corner, screen_size, overlay_alpha);
+            }
+            image_rect.width() / uv.width()
+        })
+        .inner;
+    burnin::shift(&ctx, layer, first_shape, egui::vec2(look.shift * screen_size.x, 0.0));
+    shown_width
+}
+
+impl eframe::App for ArtSlideshowApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // every frame comes through here, a hang anywhere below stops the pings
+        if let Some(next_ping) = self.notifier.ping(Instant::now()) {
+            ctx.request_repaint_after(next_ping);
+        }
+        if let Some(next_heartbeat) = self.heartbeat() {
+            ctx.request_repaint_after(next_heartbeat);
+        }
+        // before any key is looked at, the one that ends the screensaver only does that
+        if self.update_screensaver(ctx) || self.exit_on_input(ctx) {
+            return;
+        }
+        self.keep_fullscreen(ctx);
+        if self.windowed {
+            self.maximized = ctx.input(|i| i.viewport().maximized).unwrap_or(self.maximized);
+        }
+
+        // Escape closes the settings panel, the filmstrip, the search, the editor, the delete prompt or the PIN
+        // pad first, and Q can't quit while one is open
+        if self.settings.is_open() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.close_settings();
+                self.publish_status();
+            }
+        } else if self.filmstrip.is_open() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.filmstrip.close();
+            }
+        } else if self.search.is_open() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.search.close();
+            }
+        } else if self.editor.is_open() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.close_editor(Edit::Cancelled);
+            }
+        } else if self.delete_prompt.is_some() {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                self.delete_prompt = None;
+            }
+        } else if let Some(lock) = self.kiosk.as_mut().filter(|lock| lock.is_open()) {
+            if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape)) {
+                lock.close();
+            }
+        } else {
+            self.handle_exit_keys(ctx);
+        }
+        // over the empty show and the night too
+        self.show_kiosk_lock(ctx);
+
+        let dropped: Vec<PathBuf> =
+            ctx.input(|i| i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).collect());
+        if !dropped.is_empty() && self.kiosk.is_none() {
+            self.load_dropped(&dropped);
+        }
+
+        // the rest come in with the remote controls' commands, at night they wait for the morning too
+        for signal in self.signals.as_ref().map(SignalFlags::take).unwrap_or_default() {
+            let command = match signal {
+                Signal::Next => Command::Next,
+                Signal::TogglePause => Command::TogglePause,
+                Signal::Reload => {
+                    self.reload();
+                    continue;
+                }
+            };
+            let _ = self.command_sender.send(command);
+        }
+
+        self.update_commons(ctx);
+        // with no slide to finish first
+        if self.artworks.is_empty() && (self.collection_due() || self.day_changed()) {
+            self.switch_collection();
+        }
+        if self.artworks.is_empty() {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    match self.commons.as_ref().map(Downloader::progress) {
+                        Some(progress) => ui.heading(progress.to_string()),
+                        None => ui.heading(&self.empty_message),
+                    };
+                });
+            });
+            // up as much as it will be until something is added
+            self.notifier.ready();
+            if let Some(wait) = self.collections.until_change_now() {
+                ctx.request_repaint_after(wait.max(Duration::from_secs(1)));
+            }
+            return;
+        }
+
+        if self.update_night(ctx) {
+            return;
+        }
+        if self.update_preprocess(ctx) {
+            return;
+        }
+
+        let mut status_changed = false;
+
+        // nothing on screen yet: a quick preview is up long before full quality would be, the
+        // scheduler brings it up on this frame's tick
+        if let SlideState::Initial { .. } = self.scheduler.state() {
+            self.fit_to_screen(ctx);
+            let index = self.scheduler.current_index;
+            let current = self.scheduler.slide(&self.artworks, index);
+            // downloads are left for the scheduler to ask the worker for, the window doesn't wait on the network
+            if current.iter().all(|info| info.remote.is_none()) {
+                let loader = &self.loader;
+                let result = match loader.prepared(current) {
+                    Some(prepared) => Ok((prepared, Vec::new())),
+                    None => pipeline::catch_panic(|| {
+                        let (slide, portrait) = settle_pair(current, &*loader.source, &loader.options)?;
+                        let options = &loader.options;
+                        let processed = process_slide(&slide, &*loader.source, options, pipeline::process_preview)?;
+                        Ok((PreparedSlide::from(processed), portrait))
+                    }),
+                };
+                let path = self.artworks[index].path.clone();
+                self.loader.hand_over(index, path, result);
+            }
+        }
+        self.follow_resize(ctx);
+
+        let mut commands: Vec<Command> = self.commands.try_iter().collect();
+        // the panel's controls, the filmstrip, the search, the editor, the delete prompt and the PIN pad
+        // get the keys while they are open
+        let modal = self.search.is_open()
+            || self.editor.is_open()
+            || self.delete_prompt.is_some()
+            || self.kiosk.as_ref().is_some_and(KioskLock::is_open);
+        let slideshow_keys = !self.settings.is_open() && !self.filmstrip.is_open() && !modal;
+        let toggle_filmstrip = !self.settings.is_open() && !modal && ctx.input(|i| i.key_pressed(egui::Key::Tab));
+        let open_search = slideshow_keys
+            && ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Text(text) if text == "/")));
+        let reload_metadata = ctx.input(|i| slideshow_keys && i.key_pressed(egui::Key::R));
+        // Shift+C is the path alone
+        let copy = ctx.input(|i| (slideshow_keys && i.key_pressed(egui::Key::C)).then_some(i.modifiers.shift));
+        // kiosk mode locks away opening and deleting files
+        let file_keys = slideshow_keys && self.kiosk.is_none();
+        // O opens the file and Shift+O shows it in its folder
+        let open = ctx.input(|i| (file_keys && i.key_pressed(egui::Key::O)).then_some(i.modifiers.shift));
+        let edit_metadata = ctx.input(|i| slideshow_keys && i.key_pressed(egui::Key::E));
+        let ask_delete = file_keys && self.allow_delete && ctx.input(|i| i.key_pressed(egui::Key::Delete));
+        // in kiosk mode the panel opens from the admin menu, S only closes it again
+        let settings_key = self.kiosk.is_none() || self.settings.is_open();
+        let repeat_narration = slideshow_keys && self.narrator.is_some() && ctx.input(|i| i.key_pressed(egui::Key::N));
+        let music_keys = slideshow_keys && self.music.is_some();
+        let (louder, quieter, toggle_mute) = ctx.input(|i| {
+            (
+                music_keys && (i.key_pressed(egui::Key::Plus) || i.key_pressed(egui::Key::Equals)),
+                music_keys && i.key_pressed(egui::Key::Minus),
+                music_keys && i.key_pressed(egui::Key::M),
+            )
+        });
+        let (toggle_overlay, toggle_favorite, rating, toggle_settings, key_pressed, pointer_active) = ctx.input(|i| {
+            let any_key = i.events.iter().any(|e| matches!(e, egui::Event::Key { pressed: true, .. }));
+            let pointer_active = i.pointer.delta() != egui::Vec2::ZERO || i.pointer.any_pressed();
+            if slideshow_keys && i.key_pressed(egui::Key::ArrowRight) {
+                commands.push(Command::Next);
+            }
+            if slideshow_keys && i.key_pressed(egui::Key::ArrowLeft) {
+                commands.push(Command::Prev);
+            }
+            if slideshow_keys && i.key_pressed(egui::Key::Space) {
+                commands.push(Command::TogglePause);
+            }
+            if i.key_pressed(egui::Key::F12) {
+                commands.push(Command::Screenshot);
+            }
+            (
+                slideshow_keys && i.key_pressed(egui::Key::I),
+                slideshow_keys && i.key_pressed(egui::Key::F),
+                [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4, egui::Key::Num5]
+                    .into_iter()
+                    .position(|key| slideshow_keys && i.key_pressed(key))
+                    .map(|position| position as u8 + 1),
+                settings_key && !self.filmstrip.is_open() && !modal && i.key_pressed(egui::Key::S),
+                any_key,
+                pointer_active,
+            )
+        });
+        for command in commands {
+            status_changed |= self.apply(command);
+        }
+        if std::mem::take(&mut self.screenshot_requested) {
+            // the frame is taken as it is next drawn, without a toast over it
+            self.toast = None;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot);
+        }
+        let screenshot = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(Arc::clone(image)),
+                _ => None,
+            })
+        });
+        if let Some(image) = screenshot {
+            self.save_screenshot(&image);
+        }
+        if toggle_settings {
+            if self.settings.is_open() {
+                self.close_settings();
+            } else {
+                self.open_settings();
+            }
+            status_changed = true;
+        }
+        if toggle_filmstrip {
+            if self.filmstrip.is_open() {
+                self.filmstrip.close();
+            } else if self.scheduler.is_paused() {
+                self.filmstrip.open(self.scheduler.current_index);
+            }
+        }
+        if ask_delete {
+            self.delete_prompt = self.artworks.get(self.scheduler.current_index).map(|info| info.path.clone());
+        }
+        if open_search {
+            // the slash that opened the search isn't part of the query
+            ctx.input_mut(|i| i.events.retain(|e| !matches!(e, egui::Event::Text(text) if text == "/")));
+            self.search.open();
+        }
+        // the strip is for browsing a paused show
+        self.handle_zoom(ctx, slideshow_keys);
+        if self.filmstrip.is_open() && !self.scheduler.is_paused() {
+            self.filmstrip.close();
+        }
+        if toggle_overlay {
+            self.overlay_mode = self.overlay_mode.next();
+        }
+        if toggle_favorite {
+            self.toggle_favorite();
+        }
+        if reload_metadata {
+            self.reload_metadata();
+        }
+        if edit_metadata {
+            self.open_editor();
+        }
+        if let Some(path_only) = copy {
+            self.copy_current(ctx, path_only);
+        }
+        if let Some(reveal) = open {
+            self.open_current(reveal);
+        }
+        if let Some(stars) = rating {
+            self.rate(stars);
+        }
+        if repeat_narration {
+            self.narrate(Duration::ZERO);
+        }
+        if let Some(music) = &mut self.music {
+            if louder {
+                music.louder();
+            }
+            if quieter {
+                music.quieter();
+            }
+            if toggle_mute {
+                music.toggle_mute();
+            }
+            if louder || quieter || toggle_mute {
+                let text = if music.is_muted() {
+                    "Music muted".to_string()
+                } else {
+                    format!("Volume {:.0}%", music.volume() * 100.0)
+                };
+                self.toast = Some(Toast::new(text));
+            }
+        }
+        if self.toast.as_ref().is_some_and(Toast::is_expired) {
+            self.toast = None;
+        }
+        if key_pressed || pointer_active {
+            self.overlay_woken_at = Instant::now();
+        }
+        if pointer_active {
+            self.last_pointer_activity = Instant::now();
+        }
+        if !self.show_cursor && self.last_pointer_activity.elapsed() >= CURSOR_HIDE_AFTER {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        }
+
+        // Auto-advance slideshow only if the next slide is done
+        let late_since = match self.scheduler.state() {
+            SlideState::WaitingForNext { due, .. } => Some(due),
+            _ => None,
+        };
+        let previous = self.scheduler.current_index;
+        let jumped = self.scheduler.is_jumping();
+        let tick = self.scheduler.tick(&self.artworks, &mut self.loader, Instant::now());
+        if let (Some(due), Tick::Advance { .. }) = (late_since, &tick) {
+            tracing::info!("the next slide came up {:.1} s late", due.elapsed().as_secs_f32());
+        }
+        match tick {
+            Tick::Stay => {}
+            Tick::First { .. } => {
+                let slide = self.loader.take();
+                self.show(ctx, slide);
+                status_changed = true;
+            }
+            Tick::Ended => {
+                tracing::info!("the show has played through, {:?}", self.end_action);
+                match self.end_action {
+                    EndAction::HoldLast => {}
+                    EndAction::Black => self.faded_out_at = Some(Instant::now()),
+                    EndAction::Exit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                }
+                status_changed = true;
+            }
+            Tick::Skipped { index } => {
+                let late = late_since.map_or(Duration::ZERO, |due| due.elapsed());
+                tracing::warn!(
+                    "skipping {}, still not processed {} s after it was due",
+                    self.artworks[index].path.display(),
+                    late.as_secs()
+                );
+            }
+            // the slide on screen had its time, the next one comes from the collection the clock is at, or
+            // from the show put in order again for the new day
+            Tick::Advance { .. } if self.collection_due() || self.day_changed() => {
+                self.switch_collection();
+                status_changed = true;
+            }
+            Tick::Advance { reuse_current: true, .. } => {
+                // same artwork again, the textures on screen are already right
+                self.slide_serial += 1;
+                self.ring();
+                self.narrate(narrate::DELAY);
+                self.navigated = false;
+                if let Some(stats) = &mut self.stats {
+                    stats.shown(&self.artworks[self.scheduler.current_index]);
+                }
+                self.overlay_woken_at = Instant::now();
+                status_changed = true;
+            }
+            Tick::Advance { index, reuse_current: false } => {
+                self.begin_transition();
+                self.slide_serial += 1;
+                self.ring();
+                if self.black_frame {
+                    self.black_until = Some(Instant::now() + burnin::BLACK_FRAME);
+                }
+                self.overlay_woken_at = Instant::now();
+                let slide = self.loader.take();
+                self.show(ctx, slide);
+                // only the show playing on into another folder, not a slide gone to
+                if self.chapters && !jumped {
+                    self.chapter_card = chapters::card(&self.artworks, previous, index, Instant::now());
+                    if self.chapter_card.is_some() {
+                        self.scheduler.delay(self.chapter_duration);
+                    }
+                }
+                status_changed = true;
+            }
+        }
+        self.finish_upgrade(ctx);
+        if status_changed {
+            self.publish_status();
+        }
+        let loaders = [&mut self.loader, &mut self.upgrader, &mut self.zoom_loader];
+        for (path, message) in loaders.map(BackgroundLoader::take_failures).into_iter().flatten() {
+            self.report_failure(path, message);
+        }
+        self.check_stall();
+
+        let mut settings = self.live_settings();
+        self.settings.show(ctx, &mut settings);
+        if settings != self.live_settings() {
+            self.apply_settings(settings);
+        }
+        if let Some(index) = self.filmstrip.show(ctx, &self.artworks, &self.loader.source) {
+            self.apply(Command::GoTo(index));
+            self.publish_status();
+        }
+        if let Some(index) = self.search.show(ctx, &self.artworks) {
+            self.apply(Command::GoTo(index));
+            self.publish_status();
+        }
+        if let Some(edit) = self.editor.show(ctx) {
+            self.close_editor(edit);
+        }
+        self.show_delete_prompt(ctx);
+
+        let overlay_alpha = self.overlay_mode.alpha(self.overlay_woken_at.elapsed()) * self.caption_reveal.alpha();
+        let (caption_work, _) = self.caption_turn();
+        let layout = self.loader.options.layout;
+
+        let caption_style = self.caption_style;
+        let caption_scale = self.caption_scale;
+        let caption_layout = self.caption_settings();
+        let orbit = self.orbit_started.map(|started| burnin::orbit_offset(started.elapsed()));
+        let blacked_out = self.black_until.is_some_and(|until| Instant::now() < until);
+        let scrim = self.scrim;
+        let mat = self.mat;
+        let qr_corner = self.qr_corner;
+        let rotation = self.rotation;
+        let clock = self.clock_corner.map(|corner| (corner, &self.clock_format, self.clock_date));
+        let toast = self.toast.as_ref();
+        let loading = self.scheduler.is_jumping();
+        let late = !loading && self.scheduler.late_by(Instant::now()).is_some_and(|late| late >= LATE_NOTICE);
+        let late_corner = self.late_corner;
+        let badge = self.badge_corner.and_then(|corner| {
+            let index = self.scheduler.current_index;
+            let info = self.artworks.get(index)?;
+            let spotlight = self.artist_runs.and_then(|run| spotlight::starts_run(&self.artworks, index, run));
+            // the day the show was put in order for, until it is again
+            let year = self.ordered_on.and_then(|day| anniversary::on_this_day(&info.metadata, day));
+            let text = match (spotlight.map(spotlight::line), year.map(anniversary::badge)) {
+                (Some(spotlight), Some(year)) => format!("{} · {}", spotlight, year),
+                (spotlight, year) => spotlight.or(year)?,
+            };
+            Some((text, corner))
+        });
+        let zoom = self.zoom.as_mut().map(|zoom| (&mut zoom.view, zoom.texture.as_ref()));
+        let mut shown_width = None;
+        // the scheduler says when the transition is over, in its time or cut short
+        let progress = self.scheduler.transition_progress(Instant::now());
+        if progress.is_none() {
+            self.outgoing = None;
+        }
+        let transitioning = self.outgoing.is_some() && !self.scheduler.is_paused();
+        let [outgoing_look, incoming_look] = match (&self.outgoing, progress) {
+            (Some(_), Some(progress)) => self.transition.looks(progress),
+            _ => [None, Some(Look::PLAIN)],
+        };
+        let outgoing = self.outgoing.as_mut();
+        if self.chapter_card.as_ref().is_some_and(|card| card.alpha(self.chapter_duration) == 0.0) {
+            self.chapter_card = None;
+        }
+        let chapter_card = self.chapter_card.as_ref().map(|card| (card, card.alpha(self.chapter_duration)));
+        let faded_out = self.faded_out_at.map(|at| ending::black(at.elapsed()));
+
+        // Render
+        if blacked_out {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |_| {});
+        } else if let Some(loaded) = &mut self.current_textures {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |ui| {
+                    // laid out on the rotated screen, turned onto the real one at the end
+                    let physical_size = ui.available_size();
+                    let screen_size = rotation.logical_size(physical_size);
+                    let pixels_per_point = ctx.pixels_per_point();
+                    let layer = ui.layer_id();
+                    let first_shape = ctx.graphics(|graphics| graphics.get(layer).map_or(0, |list| list.next_idx().0));
+                    if rotation != Rotation::None {
+                        ui.set_clip_rect(egui::Rect::EVERYTHING);
+                    }
+
+                    let screen_rect = egui::Rect::from_min_size(egui::pos2(0.0, 0.0), screen_size);
+                    let frame = SlideFrame {
+                        screen_size,
+                        // reaching past the edges, so a drifting picture never uncovers them
+                        backdrop_rect: if orbit.is_some() {
+                            screen_rect.expand(burnin::ORBIT_RADIUS)
+                        } else {
+                            screen_rect
+                        },
+                        pixels_per_point,
+                        mat,
+                        layout,
+                        caption_style,
+                        caption_scale,
+                        caption_layout,
+                        caption_work,
+                        overlay_alpha,
+                        scrim,
+                        qr_corner,
+                    };
+                    if let (Some(outgoing), Some(look)) = (outgoing, outgoing_look) {
+                        let frame = SlideFrame {
+                            caption_layout: outgoing.caption_layout,
+                            caption_work: outgoing.caption_work,
+                            overlay_alpha: outgoing.overlay_alpha,
+                            ..frame
+                        };
+                        draw_slide(ui, &mut outgoing.slide, &frame, look, None);
+                    }
+                    if let Some(look) = incoming_look {
+                        shown_width = Some(draw_slide(ui, loaded, &frame, look, zoom));
+                    }
+                    if let Some((card, alpha)) = chapter_card {
+                        overlay::draw_title_card(ui, card, alpha, screen_size);
+                    }
+                    if let Some(black) = faded_out {
+                        ui.painter().rect_filled(screen_rect, 0.0, egui::Color32::BLACK.gamma_multiply(black));
+                    }
+
+                    if let Some((corner, format, with_date)) = clock {
+                        overlay::draw_clock(ui, format, with_date, corner, screen_size);
+                    }
+
+                    if let Some(toast) = toast {
+                        overlay::draw_toast(ui, toast, screen_size);
+                    }
+
+                    if let Some((text, corner)) = badge {
+                        overlay::draw_badge(ui, text, corner, overlay_alpha, screen_size);
+                    }
+
+                    if late {
+                        overlay::draw_loading_next(ui, late_corner, screen_size);
+                    }
+
+                    // a slide jumped to stays up until the next one is ready
+                    if loading {
+                        let rect = egui::Rect::from_center_size(screen_rect.center(), egui::Vec2::splat(48.0));
+                        egui::Spinner::new().size(48.0).color(egui::Color32::WHITE).paint_at(ui, rect);
+                    }
+
+                    if let Some(offset) = orbit {
+                        burnin::shift(ctx, layer, first_shape, offset);
+                    }
+                    rotation.apply(ctx, layer, first_shape, physical_size);
+                });
+        } else {
+            // the first slide is still on its way
+            egui::CentralPanel::default()
+                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
+                .show(ctx, |ui| {
+                    let rect = egui::Rect::from_center_size(ui.max_rect().center(), egui::Vec2::splat(48.0));
+                    egui::Spinner::new().size(48.0).color(egui::Color32::WHITE).paint_at(ui, rect);
+                });
+        }
+        self.shown_width = shown_width;
+
+        let toast_fading = self.toast.as_ref().is_some_and(|toast| toast.alpha() < 1.0);
+        let card_fading = chapter_card.is_some_and(|(_, alpha)| alpha < 1.0);
+        let fading_out = faded_out.is_some_and(|black| black < 1.0);
+        if (overlay_alpha > 0.0 && overlay_alpha < 1.0) || toast_fading || transitioning || card_fading || fading_out {
+            // keep the fade smooth, it's the only time frames are drawn back to back
+            ctx.request_repaint();
+        } else {
+            ctx.request_repaint_after(self.next_wakeup());
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, OVERLAY_MODE_KEY, &self.overlay_mode);
+        if self.windowed {
+            eframe::set_value(storage, window::MAXIMIZED_KEY, &self.maximized);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SourceError;
+    use std::path::Path;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Every file there is, empty.
+    struct Blank;
+
+    impl ImageSource for Blank {
+        fn list(&self) -> Result<Vec<ArtworkInfo>, SourceError> {
+            Ok(Vec::new())
+        }
+
+        fn read(&self, _: &ArtworkInfo) -> Result<Vec<u8>, SourceError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn crash(
+        path: &Path,
+        _: &[u8],
+        _: ArtworkMetadata,
+        _: &ProcessingOptions,
+    ) -> Result<ProcessedImage, PipelineError> {
+        if path.ends_with("zero-byte.jpg") {
+            panic!("pathological file");
+        }
+        Err(PipelineError::Io(std::io::ErrorKind::NotFound.into()))
+    }
+
+    fn wait_for(loader: &mut BackgroundLoader) -> Option<Loaded> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(loaded) = loader.poll() {
+                return Some(loaded);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    fn info(name: &str) -> ArtworkInfo {
+        ArtworkInfo {
+            path: PathBuf::from(name),
+            metadata: ArtworkMetadata::unknown(name),
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn loader_survives_a_panicking_file() {
+        let mut loader = BackgroundLoader::with_processing(ProcessingOptions::default(), Arc::new(Blank), crash, crash);
+        loader.start(0, &[info("zero-byte.jpg")], false);
+        assert_eq!(wait_for(&mut loader), Some(Loaded::Failed(0)));
+        loader.start(1, &[info("next.jpg")], true);
+        // a file that can't be read now isn't given up on
+        assert_eq!(wait_for(&mut loader), Some(Loaded::Unavailable(1)));
+    }
+
+    #[test]
+    fn a_pair_stays_together_only_when_both_are_portraits() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = |name: &str, (width, height)| {
+            let path = dir.path().join(name);
+            image::RgbImage::new(width, height).save(&path).unwrap();
+            info(path.to_str().unwrap())
+        };
+        let (tall, taller) = (save("tall.png", (30, 40)), save("taller.png", (10, 40)));
+        let wide = save("wide.png", (40, 30));
+        let source = FolderSource::new(dir.path());
+        let options = ProcessingOptions::default();
+        let settled = |slide: &[ArtworkInfo]| {
+            let (slide, portrait) = settle_pair(slide, &source, &options).unwrap();
+            (slide.len(), portrait)
+        };
+
+        assert_eq!(settled(&[tall.clone(), taller.clone()]), (2, vec![true, true]));
+        assert_eq!(settled(&[tall.clone(), wide.clone()]), (1, vec![true, false]));
+        // the second isn't looked at once the first is wide
+        assert_eq!(settled(&[wide.clone(), tall.clone()]), (1, vec![false]));
+        assert_eq!(settled(&[wide]), (1, Vec::new()));
+    }
+
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+    static ABANDONED: AtomicUsize = AtomicUsize::new(0);
+
+    /// Takes a tenth of a second in stages, giving up between them once cancelled.
+    fn slow(
+        _: &Path,
+        _: &[u8],
+        _: ArtworkMetadata,
+        options: &ProcessingOptions,
+    ) -> Result<ProcessedImage, PipelineError> {
+        for _ in 0..20 {
+            if options.cancel.is_cancelled() {
+                ABANDONED.fetch_add(1, Ordering::Relaxed);
+                return Err(PipelineError::Cancelled);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        FINISHED.fetch_add(1, Ordering::Relaxed);
+        Err(PipelineError::Io(std::io::ErrorKind::NotFound.into()))
+    }
+
+    #[test]
+    fn skipping_ahead_abandons_the_slides_skipped() {
+        let mut loader = BackgroundLoader::with_processing(ProcessingOptions::default(), Arc::new(Blank), slow, slow);
+        for index in 0..10 {
+            loader.start(index, &[info(&format!("{}.jpg", index))], false);
+            thread::sleep(Duration::from_millis(2));
+        }
+        // only the last one asked for is done, the one the worker was on when the skipping started stops early
+        assert_eq!(wait_for(&mut loader), Some(Loaded::Unavailable(9)));
+        assert_eq!(FINISHED.load(Ordering::Relaxed), 1);
+        assert!(ABANDONED.load(Ordering::Relaxed) >= 1);
+    }
+
+    #[test]
+    fn a_new_shape_or_a_larger_screen_fits_the_slides_again() {
+        assert!(!reshaped((1920, 1080), (1280, 720)));
+        assert!(!reshaped((1920, 1080), (1920, 1050)));
+        assert!(reshaped((1920, 1080), (1080, 1920)));
+        assert!(reshaped((1920, 1080), (1920, 800)));
+        assert!(!reshaped((1920, 1080), (0, 0)));
+
+        // a TV switching to 4K, the slides were processed at 2048 for 1080p
+        assert_eq!(outgrown((1920, 1080), (3840, 2160), 2048, 8192), Some(3840));
+        assert_eq!(outgrown((1920, 1080), (3840, 2160), 2048, 3000), Some(3000));
+        assert_eq!(outgrown((1920, 1080), (3840, 2160), 4096, 8192), None);
+        assert_eq!(outgrown((3840, 2160), (1920, 1080), 2048, 8192), None);
+    }
+
+    #[test]
+    fn clipboard_text_names_the_work_and_its_file() {
+        let metadata = ArtworkMetadata {
+            artist: "Albrecht Dürer".to_string(),
+            year: "1514".to_string(),
+            ..info("Melencolia I").metadata
+        };
+        assert_eq!(
+            clipboard_text(&metadata, Path::new("/art/melencolia.jpg")),
+            "\"Melencolia I\" — Albrecht Dürer (1514) — /art/melencolia.jpg"
+        );
+    }
+
+    #[test]
+    fn works_of_the_day_lead_without_splitting_a_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = chrono::Local::now().date_naive();
+        let works = [("a1", "Goya", "1800"), ("a2", "Goya", "1801"), ("a3", "Goya", "1802"), ("b1", "Ernst", "1920")];
+        let works = works.into_iter().chain([("b2", "Ernst", "1921"), ("b3", "Ernst", "1922"), ("c1", "Hals", "1620")]);
+        for (name, artist, year) in works.chain([("c2", "Hals", "1622")]) {
+            // one of Goya's and one of Ernst's were made on this day
+            let year = if name.ends_with('3') { today.format("%Y-%m-%d").to_string() } else { year.to_string() };
+            let sidecar = serde_json::json!({ "title": name, "artist": artist, "year": year });
+            std::fs::write(dir.path().join(format!("{}.jpg", name)), b"").unwrap();
+            std::fs::write(dir.path().join(format!("{}.json", name)), sidecar.to_string()).unwrap();
+        }
+        let config = Config { on_this_day: true, group_by_artist: Some(2), ..Config::default() };
+
+        let artworks = collect(dir.path().to_path_buf(), &config).unwrap().artworks;
+        let titles: Vec<&str> = artworks.iter().map(|info| info.metadata.title.as_str()).collect();
+        let (first, rest) = titles.split_at(2);
+        assert!(first.iter().all(|title| title.ends_with('3')), "{:?}", titles);
+        // each artist's other two works make up one whole run
+        assert!(rest.chunks(2).all(|run| run[0][..1] == run[1][..1]), "{:?}", titles);
+        for index in [0, 1, 2, 4, 6] {
+            assert!(spotlight::starts_run(&artworks, index, 2).is_some(), "{:?}", titles);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! [`ArtSlideshowApp::builder`]: the show put together option by option, or
+//! from a whole [`Config`] as the command line does, and checked before the
+//! folder is read.
+
+use crate::app::ArtSlideshowApp;
+use crate::config::Config;
+use crate::layout::Fit;
+use crate::overlay::OverlaySettings;
+use crate::shuffle::Shuffle;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct AppBuilder {
+    folder: Option<PathBuf>,
+    config: Config,
+}
+
+impl AppBuilder {
+    /// The folder shown, and the one favorites and ratings are kept in.
+    pub fn folder(mut self, folder: impl Into<PathBuf>) -> Self {
+        self.folder = Some(folder.into());
+        self
+    }
+
+    /// Every option at once, replacing those set so far.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn slide_duration(mut self, duration: Duration) -> Self {
+        self.config.slide_duration = duration.as_secs_f32();
+        self
+    }
+
+    /// Plain random order, or the folder's.
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.config.shuffle = if shuffle { Shuffle::Plain } else { Shuffle::Off };
+        self
+    }
+
+    /// Longest side images are processed to.
+    pub fn max_dimension(mut self, max_dimension: u32) -> Self {
+        self.config.max_dimension = max_dimension;
+        self
+    }
+
+    pub fn fit(mut self, fit: Fit) -> Self {
+        self.config.fit = fit;
+        self
+    }
+
+    pub fn overlay(mut self, overlay: OverlaySettings) -> Self {
+        self.config.overlay = overlay;
+        self
+    }
+
+    /// The images listed in `playlist` instead of the folder.
+    pub fn playlist(mut self, playlist: impl Into<PathBuf>) -> Self {
+        self.config.playlist = Some(playlist.into());
+        self
+    }
+
+    /// Whether the options go together, without reading anything.
+    pub fn validate(&self) -> Result<(), String> {
+        let config = &self.config;
+        // nothing would ever let anyone out again
+        if config.kiosk && config.pin.is_none() {
+            return Err("kiosk mode needs a PIN to unlock the admin menu with".to_string());
+        }
+        if config.playlist.is_some() && config.wikimedia_category.is_some() {
+            return Err("a playlist and a Wikimedia Commons category can't both be shown".to_string());
+        }
+        if self.folder.is_none() && config.playlist.is_none() && config.wikimedia_category.is_none() {
+            return Err("nothing to show: no folder, playlist or Wikimedia Commons category".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks the options and reads what they show.
+    pub fn build(self) -> Result<ArtSlideshowApp, String> {
+        self.validate()?;
+        ArtSlideshowApp::try_with_config(self.folder.unwrap_or_default(), self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kiosk::Pin;
+
+    #[test]
+    fn the_defaults_are_the_command_line_defaults() {
+        let builder = ArtSlideshowApp::builder().folder("/art");
+        let defaults = serde_json::to_value(Config::default()).unwrap();
+        assert_eq!(serde_json::to_value(&builder.config).unwrap(), defaults);
+        assert_eq!(builder.validate(), Ok(()));
+
+        let builder = builder.slide_duration(Duration::from_secs(30)).shuffle(true).max_dimension(2048).fit(Fit::Cover);
+        assert_eq!(builder.config.slide_duration(), Duration::from_secs(30));
+        assert_eq!((builder.config.shuffle, builder.config.max_dimension), (Shuffle::Plain, 2048));
+        assert_eq!(builder.config.fit, Fit::Cover);
+    }
+
+    #[test]
+    fn options_that_dont_go_together_fail() {
+        assert!(ArtSlideshowApp::builder().validate().is_err());
+        assert_eq!(ArtSlideshowApp::builder().playlist("/art/show.txt").validate(), Ok(()));
+
+        let kiosk = Config { kiosk: true, ..Config::default() };
+        let builder = ArtSlideshowApp::builder().folder("/art").config(kiosk);
+        assert!(builder.validate().is_err());
+        let pin: Pin = "1234".parse().unwrap();
+        let builder = builder.config(Config { kiosk: true, pin: Some(pin), ..Config::default() });
+        assert_eq!(builder.validate(), Ok(()));
+    }
+}
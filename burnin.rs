@@ -0,0 +1,83 @@
+//! `--oled-protection`: keeping an OLED panel from burning in the parts of
+//! the picture that never move, like the caption backdrop.
+//!
+//! The whole picture drifts around a small circle, one step every few
+//! minutes, always measured from where it started so nothing adds up. The
+//! caption also moves between spots along its edge from one slide to the next.
+
+use crate::layout::CaptionPosition;
+use egui::Vec2;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+/// Farthest the picture ever is from its place, in points. Also how far the
+/// background reaches past the screen so no edge shows.
+pub const ORBIT_RADIUS: f32 = 3.0;
+/// Positions around the circle.
+const ORBIT_STEPS: u32 = 8;
+/// Time between two steps.
+pub const SHIFT_INTERVAL: Duration = Duration::from_secs(180);
+/// How long the screen goes black between slides with `--oled-black-frame`.
+pub const BLACK_FRAME: Duration = Duration::from_secs(2);
+
+/// Where the picture is moved to once it has been up for `elapsed`.
+pub fn orbit_offset(elapsed: Duration) -> Vec2 {
+    let step = (elapsed.as_secs() / SHIFT_INTERVAL.as_secs()) % ORBIT_STEPS as u64;
+    let angle = step as f32 / ORBIT_STEPS as f32 * TAU;
+    (Vec2::angled(angle) * ORBIT_RADIUS).round()
+}
+
+/// Time until the picture moves again.
+pub fn until_shift(elapsed: Duration) -> Duration {
+    let into_step = Duration::from_secs(elapsed.as_secs() % SHIFT_INTERVAL.as_secs())
+        + Duration::from_nanos(elapsed.subsec_nanos() as u64);
+    SHIFT_INTERVAL - into_step
+}
+
+/// The spots along its edge the caption takes turns at, `position` first.
+pub fn caption_positions(position: CaptionPosition) -> Vec<CaptionPosition> {
+    let edge: &[CaptionPosition] = if position.is_top() {
+        &[CaptionPosition::TopLeft, CaptionPosition::TopRight]
+    } else {
+        &[CaptionPosition::BottomLeft, CaptionPosition::BottomCenter, CaptionPosition::BottomRight]
+    };
+    let mut positions = vec![position];
+    positions.extend(edge.iter().copied().filter(|other| *other != position));
+    positions
+}
+
+/// Moves everything painted on `layer` from shape `start` on by `offset`.
+pub fn shift(ctx: &egui::Context, layer: egui::LayerId, start: usize, offset: Vec2) {
+    if offset == Vec2::ZERO {
+        return;
+    }
+    ctx.graphics_mut(|graphics| {
+        let list = graphics.entry(layer);
+        for idx in start..list.next_idx().0 {
+            list.mutate_shape(egui::layers::ShapeIdx(idx), |clipped| clipped.shape.translate(offset));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_picture_circles_its_place() {
+        assert_eq!(orbit_offset(Duration::ZERO), Vec2::new(3.0, 0.0));
+        assert_eq!(orbit_offset(SHIFT_INTERVAL - Duration::from_millis(1)), Vec2::new(3.0, 0.0));
+        assert_eq!(orbit_offset(SHIFT_INTERVAL * 2), Vec2::new(0.0, 3.0));
+        // back where it began after a full turn, however long it runs
+        for turns in [1, 10, 1000] {
+            assert_eq!(orbit_offset(SHIFT_INTERVAL * ORBIT_STEPS * turns), orbit_offset(Duration::ZERO));
+        }
+        for step in 0..ORBIT_STEPS {
+            assert!(orbit_offset(SHIFT_INTERVAL * step).length() <= ORBIT_RADIUS + 0.5);
+        }
+        assert_eq!(until_shift(SHIFT_INTERVAL + Duration::from_secs(30)), Duration::from_secs(150));
+
+        assert_eq!(caption_positions(CaptionPosition::TopRight), [CaptionPosition::TopRight, CaptionPosition::TopLeft]);
+        assert_eq!(caption_positions(CaptionPosition::BottomCenter).len(), 3);
+    }
+}
@@ -0,0 +1,115 @@
+//! `--chapters`: a title card whenever the show moves on into another folder,
+//! black with the folder's name and how many of its works are in the show.
+//! A chapter is a folder given on the command line or, with `--recursive`,
+//! any folder below one.
+
+use crate::scan::ArtworkInfo;
+use crate::shuffle::Shuffle;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long the card takes to give way to the slide, at the end of its time.
+pub const FADE: Duration = Duration::from_millis(600);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleCard {
+    pub title: String,
+    /// Works of the folder in the show.
+    pub count: usize,
+    pub shown_at: Instant,
+}
+
+/// The chapter `info` is in.
+fn folder(info: &ArtworkInfo) -> Option<&Path> {
+    info.path.parent()
+}
+
+/// The card to show before `index` when the show came to it from `previous`,
+/// in another folder.
+pub fn card(artworks: &[ArtworkInfo], previous: usize, index: usize, now: Instant) -> Option<TitleCard> {
+    let chapter = folder(artworks.get(index)?)?;
+    if folder(artworks.get(previous)?) == Some(chapter) {
+        return None;
+    }
+    let title = match chapter.file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => chapter.display().to_string(),
+    };
+    let count = artworks.iter().filter(|info| folder(info) == Some(chapter)).count();
+    Some(TitleCard { title, count, shown_at: now })
+}
+
+impl TitleCard {
+    /// "12 artworks", under the title.
+    pub fn subtitle(&self) -> String {
+        match self.count {
+            1 => "1 artwork".to_string(),
+            count => format!("{} artworks", count),
+        }
+    }
+
+    /// How much of the card still covers the slide, up `duration` in all:
+    /// fully until it begins to fade, nothing once it is over.
+    pub fn alpha(&self, duration: Duration) -> f32 {
+        let left = duration.saturating_sub(self.shown_at.elapsed());
+        (left.as_secs_f32() / FADE.as_secs_f32()).min(1.0)
+    }
+
+    /// Time until the card begins to fade, up `duration` in all.
+    pub fn until_fade(&self, duration: Duration) -> Duration {
+        duration.saturating_sub(FADE).saturating_sub(self.shown_at.elapsed())
+    }
+}
+
+/// `artworks` in `shuffle`'s order within each run of works from one folder,
+/// the folders staying in theirs.
+pub fn shuffle_within(artworks: &mut [ArtworkInfo], shuffle: Shuffle) {
+    let mut start = 0;
+    while start < artworks.len() {
+        let chapter = folder(&artworks[start]).map(Path::to_path_buf);
+        let len = artworks[start..].iter().take_while(|info| folder(info) == chapter.as_deref()).count();
+        let mut run = artworks[start..start + len].to_vec();
+        shuffle.apply(&mut run);
+        artworks[start..start + len].clone_from_slice(&run);
+        start += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+
+    fn artwork(path: &str) -> ArtworkInfo {
+        ArtworkInfo {
+            path: PathBuf::from(path),
+            metadata: ArtworkMetadata::unknown(path),
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn a_card_comes_up_between_folders() {
+        let paths = ["/art/Impressionism/a.jpg", "/art/Impressionism/b.jpg", "/art/Expressionism/c.jpg"];
+        let mut artworks: Vec<_> = paths.into_iter().map(artwork).collect();
+        let now = Instant::now();
+
+        assert_eq!(card(&artworks, 0, 1, now), None);
+        let expressionism = card(&artworks, 1, 2, now).unwrap();
+        assert_eq!(
+            (expressionism.title.as_str(), expressionism.subtitle()),
+            ("Expressionism", "1 artwork".to_string())
+        );
+        // round to the beginning again
+        assert_eq!(card(&artworks, 2, 0, now).unwrap().subtitle(), "2 artworks");
+
+        assert_eq!(expressionism.alpha(Duration::from_secs(60)), 1.0);
+        assert_eq!(expressionism.alpha(Duration::ZERO), 0.0);
+
+        artworks.extend((0..20).map(|n| artwork(&format!("/art/Expressionism/{}.jpg", n))));
+        shuffle_within(&mut artworks, Shuffle::Plain);
+        assert!(artworks[..2].iter().all(|info| info.path.starts_with("/art/Impressionism")));
+        assert!(artworks[2..].iter().all(|info| info.path.starts_with("/art/Expressionism")));
+    }
+}
@@ -0,0 +1,116 @@
+//! `--transition-sound`: a short sample, a bell say, played as each slide
+//! comes up. The sample is decoded once when the show starts and played from
+//! memory on a thread of its own, so ringing it never holds up a frame.
+
+use rodio::source::Buffered;
+use rodio::{Decoder, OutputStream, Source};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Rings closer together than this are left out, so skipping through slides
+/// overlaps a few chimes instead of piling them up.
+pub const MIN_GAP: Duration = Duration::from_millis(250);
+
+type Sample = Buffered<Decoder<BufReader<File>>>;
+
+/// Lets a ring through when the one before is at least [`MIN_GAP`] back.
+#[derive(Default)]
+struct Throttle {
+    last: Option<Instant>,
+}
+
+impl Throttle {
+    fn allows(&mut self, now: Instant) -> bool {
+        if self.last.is_some_and(|last| now.duration_since(last) < MIN_GAP) {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+}
+
+pub struct Chime {
+    rings: Option<Sender<Instant>>,
+    player: Option<JoinHandle<()>>,
+}
+
+impl Chime {
+    /// Decodes the sample at `path` and waits to play it at `volume`, 1 being
+    /// as loud as the file. A sample that doesn't decode or a missing audio
+    /// device are logged and leave the show silent.
+    pub fn start(path: PathBuf, volume: f32) -> Self {
+        let volume = volume.max(0.0);
+        let (rings, receiver) = mpsc::channel::<Instant>();
+        let player = thread::spawn(move || {
+            let sample: Sample = match File::open(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| Decoder::new(BufReader::new(file)).map_err(|err| err.to_string()))
+            {
+                Ok(decoder) => decoder.buffered(),
+                Err(err) => {
+                    tracing::warn!("cannot play the transition sound {}: {}", path.display(), err);
+                    return;
+                }
+            };
+            // running through a copy decodes it all, the ones played share what it kept
+            sample.clone().for_each(drop);
+            let (_stream, output) = match OutputStream::try_default() {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::warn!("no audio output, the slides change without a sound: {}", err);
+                    return;
+                }
+            };
+
+            let mut throttle = Throttle::default();
+            for rung_at in receiver {
+                // a ring that waited too long is late for its slide
+                if rung_at.elapsed() > MIN_GAP || !throttle.allows(rung_at) {
+                    continue;
+                }
+                // mixed in with whatever is still ringing
+                if let Err(err) = output.play_raw(sample.clone().amplify(volume).convert_samples()) {
+                    tracing::warn!("cannot play the transition sound: {}", err);
+                }
+            }
+        });
+        Self { rings: Some(rings), player: Some(player) }
+    }
+
+    /// Plays the sample, returning at once.
+    pub fn ring(&self) {
+        if let Some(rings) = &self.rings {
+            // gone when the sample or the device couldn't be had, the show goes on silent
+            let _ = rings.send(Instant::now());
+        }
+    }
+}
+
+impl Drop for Chime {
+    fn drop(&mut self) {
+        self.rings = None;
+        if let Some(player) = self.player.take() {
+            let _ = player.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_rings_are_left_out() {
+        let start = Instant::now();
+        let mut throttle = Throttle::default();
+        assert!(throttle.allows(start));
+        assert!(!throttle.allows(start + MIN_GAP / 2));
+        assert!(throttle.allows(start + MIN_GAP));
+        assert!(!throttle.allows(start + MIN_GAP * 3 / 2));
+        assert!(throttle.allows(start + MIN_GAP * 2));
+    }
+}
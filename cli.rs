@@ -0,0 +1,167 @@
+use crate::config::Config;
+use crate::shuffle::Shuffle;
+use crate::ipc;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub struct Cli {
+    pub folder: PathBuf,
+    /// Endpoint the query server binds to.
+    pub socket: String,
+    /// Debug logging, including per-image timings.
+    pub verbose: bool,
+    pub log_file: Option<PathBuf>,
+    /// Check the folder's sidecars and exit instead of starting the slideshow.
+    pub validate: bool,
+    /// Print the totals of this stats file and exit.
+    pub stats_summary: Option<PathBuf>,
+    /// File the settings panel saves to.
+    pub config_file: Option<PathBuf>,
+    pub config: Config,
+}
+
+fn value<T: FromStr>(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    let raw = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+    raw.parse().map_err(|err| format!("invalid value for {}: {}", flag, err))
+}
+
+/// Parses the slideshow's arguments, without the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut cli = Cli {
+        folder: PathBuf::from(r"CHANGE FOR DIRECTORY"),
+        socket: ipc::default_endpoint(),
+        verbose: false,
+        log_file: None,
+        validate: false,
+        stats_summary: None,
+        config_file: None,
+        config: Config::default(),
+    };
+
+    // the file comes first wherever it is given, so flags override it
+    let args: Vec<String> = args.into_iter().collect();
+    if let Some(position) = args.iter().position(|arg| arg == "--config") {
+        let path = PathBuf::from(args.get(position + 1).ok_or("--config needs a value")?);
+        cli.config = Config::load(&path)?;
+        cli.config_file = Some(path);
+    }
+
+    let mut folders = Vec::new();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--socket" => cli.socket = value(&arg, &mut args)?,
+            "--verbose" | "-v" => cli.verbose = true,
+            "--log-file" => cli.log_file = Some(value(&arg, &mut args)?),
+            "--validate" => cli.validate = true,
+            "--stats-summary" => cli.stats_summary = Some(value(&arg, &mut args)?),
+            "--stats-file" => cli.config.stats_file = Some(value(&arg, &mut args)?),
+            "--screenshot-dir" => cli.config.screenshot_dir = value(&arg, &mut args)?,
+            "--music" => cli.config.music = Some(value(&arg, &mut args)?),
+            "--music-shuffle" => cli.config.music_shuffle = true,
+            "--music-volume" => cli.config.music_volume = value(&arg, &mut args)?,
+            "--music-crossfade" => cli.config.music_crossfade = value(&arg, &mut args)?,
+            "--transition-sound" => cli.config.transition_sound = Some(value(&arg, &mut args)?),
+            "--transition-sound-volume" => cli.config.transition_sound_volume = value(&arg, &mut args)?,
+            "--narrate" => cli.config.narrate = true,
+            "--narrate-rate" => cli.config.narrate_rate = value(&arg, &mut args)?,
+            "--config" => {
+                args.next();
+            }
+            // plain unless followed by a mode, anything else is left for the folder
+            "--shuffle" => {
+                cli.config.shuffle = args
+                    .next_if(|next| next.parse::<Shuffle>().is_ok())
+                    .and_then(|mode| mode.parse().ok())
+                    .unwrap_or(Shuffle::Plain)
+            }
+            "--playlist" => cli.config.playlist = Some(value(&arg, &mut args)?),
+            "--merge" => cli.config.merge = value(&arg, &mut args)?,
+            "--recursive" => cli.config.recursive = true,
+            "--chapters" => cli.config.chapters = true,
+            "--chapter-card" => cli.config.chapter_card = value(&arg, &mut args)?,
+            "--shuffle-within-chapters" => cli.config.shuffle_within_chapters = true,
+            "--adaptive-duration" => cli.config.adaptive_duration = Some(value(&arg, &mut args)?),
+            "--skip-late" => cli.config.skip_late = value(&arg, &mut args)?,
+            "--loops" => cli.config.loops = Some(value(&arg, &mut args)?),
+            "--end-action" => cli.config.end_action = value(&arg, &mut args)?,
+            "--background" => cli.config.background = value(&arg, &mut args)?,
+            "--rotate" => cli.config.rotation = value(&arg, &mut args)?,
+            "--fit" => cli.config.fit = value(&arg, &mut args)?,
+            "--transition" => cli.config.transition = value(&arg, &mut args)?,
+            "--transition-duration" => cli.config.transition_duration = value(&arg, &mut args)?,
+            "--layout" => cli.config.layout = value(&arg, &mut args)?,
+            "--no-pairing" => cli.config.pairing = false,
+            "--max-dimension" => cli.config.max_dimension = value(&arg, &mut args)?,
+            "--max-megapixels" => cli.config.max_megapixels = value(&arg, &mut args)?,
+            "--raw-use-preview" => cli.config.raw_use_preview = true,
+            "--blur-size" => cli.config.blur_size = Some(value(&arg, &mut args)?),
+            "--blur-quality" => cli.config.blur_quality = value(&arg, &mut args)?,
+            "--darken" => cli.config.darken = value(&arg, &mut args)?,
+            "--brightness" => cli.config.brightness = value(&arg, &mut args)?,
+            "--gamma" => cli.config.gamma = value(&arg, &mut args)?,
+            "--vignette" => cli.config.vignette = true,
+            "--scrim" => cli.config.scrim = true,
+            "--dither" => cli.config.dither = value(&arg, &mut args)?,
+            "--filter" => cli.config.color_filter = value(&arg, &mut args)?,
+            "--font" => cli.config.font = Some(value(&arg, &mut args)?),
+            "--font-size-scale" => cli.config.font_size_scale = value(&arg, &mut args)?,
+            "--overlay-position" => cli.config.overlay.position = value(&arg, &mut args)?,
+            "--overlay-style" => cli.config.overlay.style = value(&arg, &mut args)?,
+            "--mat" => cli.config.mat.width = value(&arg, &mut args)?,
+            "--mat-color" => cli.config.mat.color = value(&arg, &mut args)?,
+            "--frame-color" => cli.config.mat.frame_color = value(&arg, &mut args)?,
+            "--caption-style" => cli.config.caption_style = value(&arg, &mut args)?,
+            "--show-cursor" => cli.config.show_cursor = true,
+            "--windowed" => cli.config.windowed = true,
+            "--force-fullscreen" => cli.config.force_fullscreen = true,
+            "--no-inhibit-sleep" => cli.config.inhibit_sleep = false,
+            "--oled-protection" => cli.config.oled_protection = true,
+            "--oled-black-frame" => cli.config.oled_black_frame = true,
+            "--screensaver" => cli.config.screensaver = Some(value(&arg, &mut args)?),
+            "--exit-on-input" => cli.config.exit_on_input = true,
+            "--kiosk" => cli.config.kiosk = true,
+            "--pin" => cli.config.pin = Some(value(&arg, &mut args)?),
+            "--error-webhook" => cli.config.error_webhook = Some(value(&arg, &mut args)?),
+            "--heartbeat-webhook" => cli.config.heartbeat_webhook = Some(value(&arg, &mut args)?),
+            "--allow-delete" => cli.config.allow_delete = true,
+            "--dedupe" => cli.config.dedupe = true,
+            "--preprocess-all" => cli.config.preprocess_all = true,
+            "--favorites-only" => cli.config.favorites_only = true,
+            "--on-this-day" => cli.config.on_this_day = true,
+            "--group-by-artist" => cli.config.group_by_artist = Some(value(&arg, &mut args)?),
+            "--min-rating" => cli.config.min_rating = Some(value(&arg, &mut args)?),
+            "--weighted" => cli.config.weighted = true,
+            "--artist" => cli.config.filter.artists.push(value(&arg, &mut args)?),
+            "--years" => cli.config.filter.years = Some(value(&arg, &mut args)?),
+            "--control-port" => cli.config.control_port = Some(value(&arg, &mut args)?),
+            "--show-clock" => cli.config.show_clock = true,
+            "--clock-format" => cli.config.clock_format = value(&arg, &mut args)?,
+            "--clock-date" => cli.config.clock_date = true,
+            "--clock-corner" => cli.config.clock_corner = value(&arg, &mut args)?,
+            "--off-hours" => cli.config.off_hours = Some(value(&arg, &mut args)?),
+            "--night-clock" => cli.config.night_clock = true,
+            "--wikimedia-category" => cli.config.wikimedia_category = Some(value(&arg, &mut args)?),
+            "--limit" => cli.config.wikimedia_limit = value(&arg, &mut args)?,
+            "--cache-dir" => cli.config.cache_dir = Some(value(&arg, &mut args)?),
+            "--cache-size" => cli.config.cache_size_mb = value(&arg, &mut args)?,
+            "--no-cache" => cli.config.cache = false,
+            "--mqtt-url" => cli.config.mqtt.url = Some(value(&arg, &mut args)?),
+            "--mqtt-topic-prefix" => cli.config.mqtt.topic_prefix = value(&arg, &mut args)?,
+            "--mqtt-username" => cli.config.mqtt.username = Some(value(&arg, &mut args)?),
+            "--mqtt-password" => cli.config.mqtt.password = Some(value(&arg, &mut args)?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => folders.push(PathBuf::from(arg)),
+        }
+    }
+    let mut folders = folders.into_iter();
+    if let Some(first) = folders.next() {
+        cli.folder = first;
+        cli.config.extra_folders = folders.collect();
+    }
+
+    Ok(cli)
+}
@@ -0,0 +1,177 @@
+//! Folders shown at their own times of day, landscapes in the morning and
+//! nocturnes at night, named in the config file's `collections`. One without
+//! hours is shown whenever no other's are on, and without one of those the
+//! folder given on the command line is.
+
+use crate::night::OffHours;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Daily hours written `07:00-12:00`, as the config file has them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Hours(pub OffHours);
+
+impl FromStr for Hours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hours: OffHours = s.parse().map_err(|_| format!("expected hours like 07:00-12:00, got {:?}", s))?;
+        if hours.start == hours.end {
+            return Err(format!("{:?} is no time at all", s));
+        }
+        Ok(Hours(hours))
+    }
+}
+
+impl TryFrom<String> for Hours {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Hours> for String {
+    fn from(Hours(hours): Hours) -> Self {
+        hours.to_string()
+    }
+}
+
+impl fmt::Display for Hours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NamedCollection {
+    /// What the log calls it.
+    pub name: String,
+    pub folder: PathBuf,
+    /// When it is shown, whenever no other collection's hours are on if unset.
+    #[serde(default)]
+    pub hours: Option<Hours>,
+}
+
+/// The config's collections, no two of them on at the same time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(try_from = "Vec<NamedCollection>", into = "Vec<NamedCollection>")]
+pub struct Collections(Vec<NamedCollection>);
+
+impl TryFrom<Vec<NamedCollection>> for Collections {
+    type Error = String;
+
+    fn try_from(collections: Vec<NamedCollection>) -> Result<Self, Self::Error> {
+        for (i, a) in collections.iter().enumerate() {
+            for b in &collections[i + 1..] {
+                if a.name == b.name {
+                    return Err(format!("there are two collections named {:?}", a.name));
+                }
+                match (a.hours, b.hours) {
+                    (Some(Hours(first)), Some(Hours(second))) => {
+                        let shared =
+                            (0..MINUTES_PER_DAY).find(|&minute| first.contains(minute) && second.contains(minute));
+                        if let Some(minute) = shared {
+                            return Err(format!(
+                                "the hours of collections {:?} ({}) and {:?} ({}) overlap at {:02}:{:02}",
+                                a.name,
+                                first,
+                                b.name,
+                                second,
+                                minute / 60,
+                                minute % 60
+                            ));
+                        }
+                    }
+                    (None, None) => {
+                        return Err(format!(
+                            "collections {:?} and {:?} both have no hours, only one can be shown otherwise",
+                            a.name, b.name
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(Collections(collections))
+    }
+}
+
+impl From<Collections> for Vec<NamedCollection> {
+    fn from(Collections(collections): Collections) -> Self {
+        collections
+    }
+}
+
+impl Collections {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The collection shown at `minute` since midnight, `None` for the
+    /// command line's folder.
+    pub fn active_at(&self, minute: u16) -> Option<&NamedCollection> {
+        let scheduled =
+            self.0.iter().find(|collection| collection.hours.is_some_and(|Hours(hours)| hours.contains(minute)));
+        scheduled.or_else(|| self.0.iter().find(|collection| collection.hours.is_none()))
+    }
+
+    pub fn active_now(&self) -> Option<&NamedCollection> {
+        use chrono::Timelike;
+        let now = chrono::Local::now();
+        self.active_at((now.hour() * 60 + now.minute()) as u16)
+    }
+
+    /// Time until the next collection's hours start or end.
+    pub fn until_change_now(&self) -> Option<Duration> {
+        self.0.iter().filter_map(|collection| collection.hours?.0.until_change_now()).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collections(json: &str) -> Result<Collections, String> {
+        serde_json::from_str(json).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn scheduled_collections_take_turns_with_the_default() {
+        let shown = collections(
+            r#"[
+                {"name": "morning", "folder": "/art/landscapes", "hours": "07:00-12:00"},
+                {"name": "night", "folder": "/art/nocturnes", "hours": "20:00-02:00"},
+                {"name": "rest", "folder": "/art/all"}
+            ]"#,
+        )
+        .unwrap();
+        let name = |minute: u16| shown.active_at(minute).map(|collection| collection.name.as_str());
+        assert_eq!(name(8 * 60), Some("morning"));
+        assert_eq!(name(12 * 60), Some("rest"));
+        assert_eq!(name(60), Some("night"));
+        assert_eq!(serde_json::to_value(&shown).unwrap()[0]["hours"], "07:00-12:00");
+
+        let without_default = collections(r#"[{"name": "morning", "folder": "/a", "hours": "07:00-12:00"}]"#).unwrap();
+        assert!(without_default.active_at(13 * 60).is_none());
+
+        let overlapping = collections(
+            r#"[
+                {"name": "morning", "folder": "/a", "hours": "07:00-12:00"},
+                {"name": "late", "folder": "/b", "hours": "22:00-08:00"}
+            ]"#,
+        );
+        assert!(
+            overlapping.unwrap_err().contains(r#""morning" (07:00-12:00) and "late" (22:00-08:00) overlap at 07:00"#)
+        );
+        let two_defaults = collections(r#"[{"name": "a", "folder": "/a"}, {"name": "b", "folder": "/b"}]"#);
+        assert!(two_defaults.unwrap_err().contains("both have no hours"));
+        assert!(collections(r#"[{"name": "a", "folder": "/a", "hours": "09:00-09:00"}]"#).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! `--wikimedia-category NAME`: a show of the files in a Wikimedia Commons
+//! category. Each is downloaded once, as a rendition about
+//! [`RENDITION_WIDTH`] pixels wide rather than the original, with a sidecar
+//! made from what Commons extracts of its Artwork or Information template:
+//! the title, the artist and the year, and the file's page as the link.
+//! Both are kept in `commons/` in the cache folder, so the show runs offline
+//! once they are there; at startup the category is only asked for files
+//! that are new, and without a network the ones already downloaded are shown.
+//!
+//! Requests carry a user agent of their own and are spaced out, as the
+//! Wikimedia API asks of bots.
+
+use crate::persist;
+use crate::scan;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const API: &str = "https://commons.wikimedia.org/w/api.php";
+const USER_AGENT: &str = concat!("art-slideshow/", env!("CARGO_PKG_VERSION"), " (Commons category show)");
+/// Shortest time between two requests, to the API or for a file.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+/// Width asked for, the originals can be hundreds of megapixels.
+pub const RENDITION_WIDTH: u32 = 2560;
+/// Files asked for in one API call, the most the API hands out.
+const BATCH: usize = 50;
+/// Files shown of a category without `--limit`.
+pub const DEFAULT_LIMIT: usize = 100;
+/// Larger renditions are refused, none at this width comes near it.
+const MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// `Category:Paintings by Claude Monet` for `Paintings_by_Claude_Monet`,
+/// with or without the prefix.
+fn category_title(name: &str) -> String {
+    let name = name.trim();
+    let name = name.strip_prefix("Category:").unwrap_or(name);
+    format!("Category:{}", name.replace('_', " "))
+}
+
+/// A name with only what is safe in a file name, the rest `_`.
+fn file_name_safe(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' }).collect()
+}
+
+/// Where the files of `category` are kept under the cache folder `base`.
+pub fn folder(base: &Path, category: &str) -> PathBuf {
+    let title = category_title(category);
+    base.join("commons").join(file_name_safe(title.trim_start_matches("Category:")))
+}
+
+/// What ends up in the sidecar.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Sidecar {
+    title: String,
+    artist: String,
+    year: String,
+    url: Option<String>,
+}
+
+/// A file of the category, as the API describes it.
+#[derive(Debug, Clone, PartialEq)]
+struct Member {
+    /// Its name in the folder, with the rendition's extension.
+    name: String,
+    rendition: String,
+    sidecar: Sidecar,
+}
+
+/// The text of an extmetadata value, which comes as HTML.
+fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `1889` from a date like `June 1889`, `circa 1665` or `1889-06-00`.
+fn year(date: &str) -> Option<String> {
+    date.split(|c: char| !c.is_ascii_digit()).find(|digits| digits.len() == 4).map(str::to_string)
+}
+
+/// The files listed in an answer of the API, leaving out what isn't an image
+/// the show can read, like videos and PDFs.
+fn members(answer: &Value) -> Vec<Member> {
+    let mut members: Vec<Member> = answer["query"]["pages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|page| {
+            let info = &page["imageinfo"][0];
+            if !info["mime"].as_str()?.starts_with("image/") {
+                return None;
+            }
+            let rendition = info["thumburl"].as_str().or(info["url"].as_str())?;
+            let file = page["title"].as_str()?.trim_start_matches("File:");
+            let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+            let without_query = rendition.split(['?', '#']).next().unwrap_or(rendition);
+            let extension = without_query.rsplit_once('.').map(|(_, extension)| extension)?;
+            let name = format!("{}.{}", file_name_safe(stem), extension.to_lowercase());
+            if !scan::is_supported_image(Path::new(&name)) {
+                return None;
+            }
+
+            let metadata = &info["extmetadata"];
+            let field = |key: &str| metadata[key]["value"].as_str().map(plain_text).filter(|text| !text.is_empty());
+            let sidecar = Sidecar {
+                title: field("ObjectName").unwrap_or_else(|| stem.replace('_', " ")),
+                artist: field("Artist").unwrap_or_else(|| "Unknown".to_string()),
+                year: field("DateTimeOriginal").as_deref().and_then(year).unwrap_or_else(|| "Unknown".to_string()),
+                url: info["descriptionurl"].as_str().map(str::to_string),
+            };
+            Some(Member { name, rendition: rendition.to_string(), sidecar })
+        })
+        .collect();
+    // the API hands out a batch in no particular order
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    members
+}
+
+/// Asks Commons, spacing the requests out.
+struct Client {
+    agent: ureq::Agent,
+    last_request: Option<Instant>,
+}
+
+impl Client {
+    fn new() -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).user_agent(USER_AGENT).build(),
+            last_request: None,
+        }
+    }
+
+    fn get(&mut self, url: &str, query: &[(&str, &str)]) -> Result<ureq::Response, String> {
+        if let Some(last) = self.last_request {
+            thread::sleep(REQUEST_INTERVAL.saturating_sub(last.elapsed()));
+        }
+        self.last_request = Some(Instant::now());
+        let mut request = self.agent.get(url);
+        for (name, value) in query {
+            request = request.query(name, value);
+        }
+        request.call().map_err(|err| err.to_string())
+    }
+
+    /// Up to `limit` of the files in `category`, following the API's
+    /// continuation from one batch to the next.
+    fn members(&mut self, category: &str, limit: usize) -> Result<Vec<Member>, String> {
+        let title = category_title(category);
+        let width = RENDITION_WIDTH.to_string();
+        let mut all = Vec::new();
+        let mut next: Vec<(String, String)> = Vec::new();
+        while all.len() < limit {
+            let batch = BATCH.min(limit - all.len()).to_string();
+            let mut query = vec![
+                ("action", "query"),
+                ("format", "json"),
+                ("formatversion", "2"),
+                ("generator", "categorymembers"),
+                ("gcmtitle", title.as_str()),
+                ("gcmtype", "file"),
+                ("gcmlimit", batch.as_str()),
+                ("prop", "imageinfo"),
+                ("iiprop", "url|mime|extmetadata"),
+                ("iiurlwidth", width.as_str()),
+                ("iiextmetadatafilter", "ObjectName|Artist|DateTimeOriginal"),
+                ("iiextmetadatalanguage", "en"),
+            ];
+            query.extend(next.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+            let body = self.get(API, &query)?.into_string().map_err(|err| err.to_string())?;
+            let answer: Value =
+                serde_json::from_str(&body).map_err(|err| format!("unexpected answer from Commons: {}", err))?;
+            if let Some(error) = answer["error"]["info"].as_str() {
+                return Err(error.to_string());
+            }
+            all.extend(members(&answer));
+            let Some(more) = answer["continue"].as_object() else {
+                break;
+            };
+            next = more.iter().filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string()))).collect();
+        }
+        all.truncate(limit);
+        Ok(all)
+    }
+
+    fn download(&mut self, url: &str) -> Result<Vec<u8>, String> {
+        let mut body = Vec::new();
+        let reader = self.get(url, &[])?.into_reader();
+        reader.take(MAX_BYTES + 1).read_to_end(&mut body).map_err(|err| err.to_string())?;
+        if body.len() as u64 > MAX_BYTES {
+            return Err(format!("larger than {} MiB", MAX_BYTES / 1024 / 1024));
+        }
+        Ok(body)
+    }
+}
+
+/// How far the download at startup got.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Progress {
+    /// Files looked at, downloaded or already there.
+    pub done: usize,
+    /// Files in the category, up to the limit; 0 until the API answered.
+    pub total: usize,
+    /// Files new this time.
+    pub downloaded: usize,
+    pub finished: bool,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.total {
+            0 => write!(f, "Asking Wikimedia Commons..."),
+            total => write!(f, "Downloading from Wikimedia Commons: {} of {}", self.done, total),
+        }
+    }
+}
+
+/// Brings the folder of a category up to date on a thread of its own.
+pub struct Downloader {
+    progress: Arc<Mutex<Progress>>,
+}
+
+impl Downloader {
+    /// Starts on up to `limit` files of `category`, into `folder`, which is
+    /// there once this returns.
+    pub fn start(category: String, folder: PathBuf, limit: usize) -> Self {
+        if let Err(err) = fs::create_dir_all(&folder) {
+            tracing::warn!("cannot create {}: {}", folder.display(), err);
+        }
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let shared = Arc::clone(&progress);
+        thread::spawn(move || {
+            let update = |change: &dyn Fn(&mut Progress)| change(&mut shared.lock().unwrap_or_else(|p| p.into_inner()));
+            let mut client = Client::new();
+            match client.members(&category, limit) {
+                Ok(members) => {
+                    tracing::info!("{} has {} images to show", category_title(&category), members.len());
+                    update(&|progress| progress.total = members.len());
+                    for member in members {
+                        let new = download(&mut client, &folder, &member);
+                        update(&|progress| {
+                            progress.done += 1;
+                            progress.downloaded += usize::from(new);
+                        });
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!("cannot list {}, showing what was downloaded before: {}", category, err)
+                }
+            }
+            update(&|progress| progress.finished = true);
+        });
+        Self { progress }
+    }
+
+    pub fn progress(&self) -> Progress {
+        *self.progress.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Puts `member` in `folder` unless it is there, and its sidecar unless one
+/// is, returning whether the image is new. Failures are logged.
+fn download(client: &mut Client, folder: &Path, member: &Member) -> bool {
+    let path = folder.join(&member.name);
+    let sidecar = path.with_extension("json");
+    if !sidecar.exists() {
+        let written = serde_json::to_vec_pretty(&member.sidecar)
+            .map_err(std::io::Error::other)
+            .and_then(|json| persist::write_atomic(&sidecar, &json));
+        if let Err(err) = written {
+            tracing::warn!("cannot write {}: {}", sidecar.display(), err);
+        }
+    }
+    if path.exists() {
+        return false;
+    }
+    let written = client
+        .download(&member.rendition)
+        .and_then(|bytes| persist::write_atomic(&path, &bytes).map_err(|err| err.to_string()));
+    match written {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::warn!("cannot download {}: {}", member.rendition, err);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn the_artwork_template_makes_the_sidecar() {
+        assert_eq!(category_title("Paintings_by_Claude_Monet"), "Category:Paintings by Claude Monet");
+        assert_eq!(folder(Path::new("/cache"), "Category:Water Lilies"), Path::new("/cache/commons/Water_Lilies"));
+
+        let answer = json!({"query": {"pages": [
+            {"title": "File:Claude Monet - Water Lilies.tif", "imageinfo": [{
+                "mime": "image/tiff",
+                "thumburl": "https://upload/thumb/Claude_Monet_-_Water_Lilies.tif/2560px-Water_Lilies.tif.jpg",
+                "descriptionurl": "https://commons.wikimedia.org/wiki/File:Claude_Monet_-_Water_Lilies.tif",
+                "extmetadata": {
+                    "ObjectName": {"value": "Water Lilies"},
+                    "Artist": {"value": "<bdi><a href=\"https://www.wikidata.org/wiki/Q296\">Claude Monet</a>\n</bdi>"},
+                    "DateTimeOriginal": {"value": "circa <time>1906</time>"},
+                },
+            }]},
+            {"title": "File:Giverny.webm", "imageinfo": [{"mime": "video/webm", "thumburl": "https://x.jpg"}]},
+            {"title": "File:Haystacks & snow.jpg", "imageinfo": [{"mime": "image/jpeg", "url": "https://x.jpg"}]},
+        ]}});
+        let members = members(&answer);
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].name, "Claude_Monet_-_Water_Lilies.jpg");
+        assert_eq!(
+            members[0].sidecar,
+            Sidecar {
+                title: "Water Lilies".to_string(),
+                artist: "Claude Monet".to_string(),
+                year: "1906".to_string(),
+                url: Some("https://commons.wikimedia.org/wiki/File:Claude_Monet_-_Water_Lilies.tif".to_string()),
+            }
+        );
+        assert_eq!(members[1].name, "Haystacks___snow.jpg");
+        assert_eq!(members[1].sidecar.title, "Haystacks & snow");
+        assert_eq!(members[1].sidecar.artist, "Unknown");
+    }
+}
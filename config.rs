@@ -0,0 +1,405 @@
+use crate::collections::Collections;
+use crate::commons;
+use crate::filter::ArtworkFilter;
+use crate::kiosk::Pin;
+use crate::layout::{Corner, Fit, SlideLayout};
+use crate::mat::MatSettings;
+use crate::ending::EndAction;
+use crate::merge::Merge;
+use crate::mqtt::MqttSettings;
+use crate::night::OffHours;
+use crate::overlay::{CaptionStyle, ClockFormat, OverlaySettings};
+use crate::pipeline::cache::{self, DiskCache};
+use crate::pipeline::color::ColorFilter;
+use crate::pipeline::depth::Dither;
+use crate::pipeline::{BackgroundMode, BlurQuality, BlurSize, DurationRange, ProcessingOptions};
+use crate::ratings;
+use crate::rotation::Rotation;
+use crate::shuffle::Shuffle;
+use crate::transition::Transition;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FONT_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+const DARKEN_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+
+/// User-facing settings, read from the `--config` file and the command line.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// Seconds each slide stays on screen unless its sidecar says otherwise.
+    pub slide_duration: f32,
+    /// Seconds between which each slide stays by the detail in it, instead of `slide_duration`.
+    pub adaptive_duration: Option<DurationRange>,
+    /// Slide durations the next slide is waited for past its time before it is skipped, 0 to wait however long.
+    pub skip_late: f32,
+    /// Times the show is played through before it ends, `None` for no end.
+    pub loops: Option<u32>,
+    /// What the show does once it ends.
+    pub end_action: EndAction,
+    pub background: BackgroundMode,
+    pub shuffle: Shuffle,
+    /// Show the images listed in this file instead of the folder.
+    pub playlist: Option<PathBuf>,
+    /// Show the files of this Wikimedia Commons category instead of the folder, downloaded into the cache folder.
+    pub wikimedia_category: Option<String>,
+    /// The most files of `wikimedia_category` shown.
+    pub wikimedia_limit: usize,
+    /// Folders shown along with the one given first.
+    pub extra_folders: Vec<PathBuf>,
+    /// The folders below the ones given are shown too, but not the trash.
+    pub recursive: bool,
+    /// How the works of several folders are put together.
+    pub merge: Merge,
+    /// Folders shown at their own times of day instead, see [`Collections`].
+    pub collections: Collections,
+    /// A title card whenever the show moves on into another folder.
+    pub chapters: bool,
+    /// Seconds the title card stays up.
+    pub chapter_card: f32,
+    /// Shuffling keeps the chapters, shuffling the works within each one.
+    pub shuffle_within_chapters: bool,
+    /// Longest side of the artwork texture, in pixels.
+    pub max_dimension: u32,
+    /// Images with more pixels than this many million are skipped undecoded.
+    pub max_megapixels: u32,
+    /// Camera raw files are shown by the JPEG preview in them instead of being developed.
+    pub raw_use_preview: bool,
+    /// Resolution of the blurred background. A quarter of the screen when unset.
+    pub blur_size: Option<BlurSize>,
+    pub blur_quality: BlurQuality,
+    /// Brightness left in the background, 0 for black.
+    pub darken: f32,
+    /// Multiplier for the artwork's brightness, for dim projectors.
+    pub brightness: f32,
+    /// Above 1 lifts the artwork's shadows, below 1 deepens them.
+    pub gamma: f32,
+    /// Darker corners on the blurred background.
+    pub vignette: bool,
+    /// A gradient behind the caption so it reads on bright backgrounds.
+    pub scrim: bool,
+    /// How 16-bit images are brought down to the 8 bits of the display.
+    pub dither: Dither,
+    pub fit: Fit,
+    /// How each slide gives way to the next.
+    pub transition: Transition,
+    /// Seconds the transition takes, all of it for `fade-black`.
+    pub transition_duration: f32,
+    /// Grayscale, sepia or warm for the whole show, sidecars can pick their own.
+    pub color_filter: ColorFilter,
+    /// One artwork per slide, or a grid of them.
+    pub layout: SlideLayout,
+    /// Show two portrait artworks in a row side by side on a landscape screen.
+    pub pairing: bool,
+    /// Turns the picture for displays mounted sideways.
+    pub rotation: Rotation,
+    pub caption_style: CaptionStyle,
+    /// Where the caption goes and how it looks.
+    pub overlay: OverlaySettings,
+    /// A mat and frame line around the artwork, off unless given a width.
+    pub mat: MatSettings,
+    /// TTF or OTF file for the caption text.
+    pub font: Option<PathBuf>,
+    /// Size of the caption relative to the default.
+    pub font_size_scale: f32,
+    /// Keep the mouse cursor visible instead of hiding it when idle.
+    pub show_cursor: bool,
+    /// Run in a normal window instead of fullscreen.
+    pub windowed: bool,
+    /// Go back to fullscreen whenever the window leaves it, as after a display reconnects.
+    pub force_fullscreen: bool,
+    /// Keep the display from sleeping. Only applies in fullscreen.
+    pub inhibit_sleep: bool,
+    /// Keep the picture drifting and the caption moving so an OLED panel doesn't burn in.
+    pub oled_protection: bool,
+    /// Black out the screen for a moment between slides.
+    pub oled_black_frame: bool,
+    /// Minutes without input anywhere on the computer before the show comes up; out of sight until then.
+    pub screensaver: Option<f32>,
+    /// Quit at a key, a click or a move of the mouse, as a screensaver does.
+    pub exit_on_input: bool,
+    /// Public installation: quitting, the settings panel, deleting and opening files need `pin`.
+    pub kiosk: bool,
+    /// Unlocks the admin menu in kiosk mode.
+    pub pin: Option<Pin>,
+    /// Where failed images, a stuck show and panics are posted.
+    pub error_webhook: Option<String>,
+    /// Where the slide on screen is posted every hour.
+    pub heartbeat_webhook: Option<String>,
+    /// Delete moves the artwork on screen to `.slideshow-trash`, after asking.
+    pub allow_delete: bool,
+    /// Show an image saved under several names only once.
+    pub dedupe: bool,
+    /// Process every artwork before the show starts, so transitions never wait on the pipeline.
+    pub preprocess_all: bool,
+    /// Only show artworks flagged in `favorites.json`.
+    pub favorites_only: bool,
+    /// Only show artworks rated at least this many stars, unrated ones count as 3.
+    pub min_rating: Option<u8>,
+    /// Show higher-rated artworks more often instead of going in order.
+    pub weighted: bool,
+    /// Works dated to today's day of the year come first.
+    pub on_this_day: bool,
+    /// Runs of up to this many works by one artist at a time, `None` to mix everyone.
+    pub group_by_artist: Option<usize>,
+    pub filter: ArtworkFilter,
+    /// Port of the HTTP remote control, off when unset.
+    pub control_port: Option<u16>,
+    pub mqtt: MqttSettings,
+    /// Daily window during which the screen stays black.
+    pub off_hours: Option<OffHours>,
+    /// Show a small dim clock while the screen is black.
+    pub night_clock: bool,
+    pub show_clock: bool,
+    pub clock_format: ClockFormat,
+    /// Show the date under the clock.
+    pub clock_date: bool,
+    /// Preferred corner, the clock moves on clockwise if another overlay has it.
+    pub clock_corner: Corner,
+    /// Append a line for every slide shown to this file.
+    pub stats_file: Option<PathBuf>,
+    /// Where F12 saves what is on screen.
+    pub screenshot_dir: PathBuf,
+    /// Play the audio files in this folder, or listed in this playlist file, during the show.
+    pub music: Option<PathBuf>,
+    pub music_shuffle: bool,
+    /// Starting volume of the music, from 0 to 1.
+    pub music_volume: f32,
+    /// Seconds each track fades into the next, 0 to cut straight over.
+    pub music_crossfade: f32,
+    /// Short sample played as each slide comes up.
+    pub transition_sound: Option<PathBuf>,
+    /// 1 plays the sample as loud as it is.
+    pub transition_sound_volume: f32,
+    /// Read each caption out with the platform's speech engine.
+    pub narrate: bool,
+    /// Speaking speed of the narration, 1 being the engine's usual one, from 0.5 to 2.
+    pub narrate_rate: f32,
+    /// Keep resized and blurred images on disk between runs.
+    pub cache: bool,
+    /// Defaults to the platform's cache directory.
+    pub cache_dir: Option<PathBuf>,
+    pub cache_size_mb: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            slide_duration: 10.0,
+            adaptive_duration: None,
+            skip_late: 3.0,
+            loops: None,
+            end_action: EndAction::HoldLast,
+            background: BackgroundMode::Blur,
+            shuffle: Shuffle::Off,
+            playlist: None,
+            wikimedia_category: None,
+            wikimedia_limit: commons::DEFAULT_LIMIT,
+            extra_folders: Vec::new(),
+            recursive: false,
+            merge: Merge::Append,
+            collections: Collections::default(),
+            chapters: false,
+            chapter_card: 4.0,
+            shuffle_within_chapters: false,
+            max_dimension: ProcessingOptions::default().max_dimension,
+            max_megapixels: 100,
+            raw_use_preview: false,
+            blur_size: None,
+            blur_quality: BlurQuality::Fast,
+            darken: ProcessingOptions::default().darken,
+            brightness: 1.0,
+            gamma: 1.0,
+            vignette: false,
+            scrim: false,
+            dither: Dither::Ordered,
+            color_filter: ColorFilter::None,
+            fit: Fit::Contain,
+            transition: Transition::Cut,
+            transition_duration: 1.0,
+            layout: SlideLayout::Single,
+            pairing: true,
+            rotation: Rotation::None,
+            caption_style: CaptionStyle::Auto,
+            overlay: OverlaySettings::default(),
+            mat: MatSettings::default(),
+            font: None,
+            font_size_scale: 1.0,
+            show_cursor: false,
+            windowed: false,
+            force_fullscreen: false,
+            inhibit_sleep: true,
+            oled_protection: false,
+            oled_black_frame: false,
+            screensaver: None,
+            exit_on_input: false,
+            kiosk: false,
+            pin: None,
+            error_webhook: None,
+            heartbeat_webhook: None,
+            allow_delete: false,
+            dedupe: false,
+            preprocess_all: false,
+            favorites_only: false,
+            on_this_day: false,
+            group_by_artist: None,
+            min_rating: None,
+            weighted: false,
+            filter: ArtworkFilter::default(),
+            control_port: None,
+            mqtt: MqttSettings::default(),
+            off_hours: None,
+            night_clock: false,
+            show_clock: false,
+            clock_format: ClockFormat::default(),
+            clock_date: false,
+            clock_corner: Corner::TopRight,
+            stats_file: None,
+            screenshot_dir: PathBuf::from("."),
+            music: None,
+            music_shuffle: false,
+            music_volume: 0.5,
+            music_crossfade: 2.0,
+            transition_sound: None,
+            transition_sound_volume: 1.0,
+            narrate: false,
+            narrate_rate: 1.0,
+            cache: true,
+            cache_dir: None,
+            cache_size_mb: cache::DEFAULT_CACHE_SIZE_MB,
+        }
+    }
+}
+
+impl Config {
+    /// Reads a JSON config file, anything it leaves out keeps its default.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        serde_json::from_str(&text).map_err(|err| format!("invalid config {}: {}", path.display(), err))
+    }
+
+    pub fn slide_duration(&self) -> Duration {
+        Duration::try_from_secs_f32(self.slide_duration).unwrap_or(Duration::ZERO)
+    }
+
+    pub fn transition_duration(&self) -> Duration {
+        Duration::try_from_secs_f32(self.transition_duration).unwrap_or(Duration::ZERO)
+    }
+
+    pub fn chapter_card(&self) -> Duration {
+        Duration::try_from_secs_f32(self.chapter_card).unwrap_or(Duration::ZERO)
+    }
+
+    /// Whether the show goes through its folders one after the other, as
+    /// `--chapters` needs, shuffled within each at most.
+    pub fn keeps_chapters(&self) -> bool {
+        self.chapters && self.merge == Merge::Append && (self.shuffle == Shuffle::Off || self.shuffle_within_chapters)
+    }
+
+    pub fn music_crossfade(&self) -> Duration {
+        Duration::try_from_secs_f32(self.music_crossfade).unwrap_or(Duration::ZERO)
+    }
+
+    pub fn screensaver(&self) -> Option<Duration> {
+        self.screensaver.map(|minutes| Duration::try_from_secs_f32(minutes * 60.0).unwrap_or(Duration::ZERO))
+    }
+
+    /// The caption scale, within what still fits on a screen.
+    pub fn caption_scale(&self) -> f32 {
+        let scale = self.font_size_scale;
+        if FONT_SCALE_RANGE.contains(&scale) {
+            return scale;
+        }
+        let (min, max) = FONT_SCALE_RANGE.into_inner();
+        let clamped = if scale.is_nan() { 1.0 } else { scale.clamp(min, max) };
+        tracing::warn!("font size scale {} is out of range, using {}", scale, clamped);
+        clamped
+    }
+
+    /// The background darkening, from black to untouched.
+    pub fn darken(&self) -> f32 {
+        let darken = self.darken;
+        if DARKEN_RANGE.contains(&darken) {
+            return darken;
+        }
+        let clamped = if darken.is_nan() {
+            ProcessingOptions::default().darken
+        } else {
+            darken.clamp(*DARKEN_RANGE.start(), *DARKEN_RANGE.end())
+        };
+        tracing::warn!("darken {} is out of range, using {}", darken, clamped);
+        clamped
+    }
+
+    /// The `--min-rating` filter, within the stars that can be given.
+    pub fn min_rating(&self) -> Option<u8> {
+        let stars = self.min_rating?;
+        let clamped = stars.clamp(1, ratings::MAX_STARS);
+        if clamped != stars {
+            tracing::warn!("minimum rating {} is out of range, using {}", stars, clamped);
+        }
+        Some(clamped)
+    }
+
+    /// The cache folder, even with `--no-cache`, for what is downloaded.
+    fn download_base(&self) -> PathBuf {
+        let dir = self.cache_dir.clone().or_else(cache::default_dir);
+        dir.unwrap_or_else(|| std::env::temp_dir().join("art-slideshow"))
+    }
+
+    /// Where a playlist's URL entries are downloaded to, the cache folder's `remote`.
+    pub fn downloads_dir(&self) -> PathBuf {
+        self.download_base().join("remote")
+    }
+
+    /// Where the files of `wikimedia_category` are kept, if one is shown.
+    pub fn commons_folder(&self) -> Option<PathBuf> {
+        let category = self.wikimedia_category.as_deref()?;
+        Some(commons::folder(&self.download_base(), category))
+    }
+
+    fn disk_cache(&self) -> Option<DiskCache> {
+        if !self.cache {
+            return None;
+        }
+        let dir = self.cache_dir.clone().or_else(cache::default_dir)?;
+        Some(DiskCache::new(dir, self.cache_size_mb.saturating_mul(1024 * 1024)))
+    }
+
+    pub fn processing_options(&self) -> ProcessingOptions {
+        let defaults = ProcessingOptions::default();
+        let blur_size = self.blur_size.unwrap_or(BlurSize {
+            width: defaults.blur_width,
+            height: defaults.blur_height,
+        });
+        ProcessingOptions {
+            background: self.background,
+            fit: self.fit,
+            max_dimension: self.max_dimension,
+            max_pixels: u64::from(self.max_megapixels) * 1_000_000,
+            raw_use_preview: self.raw_use_preview,
+            blur_width: blur_size.width,
+            blur_height: blur_size.height,
+            blur_quality: self.blur_quality,
+            darken: self.darken(),
+            brightness: self.brightness,
+            gamma: self.gamma,
+            vignette: self.vignette,
+            cache: self.disk_cache(),
+            // the caption takes turns along its edge, so the whole edge is sampled
+            caption_region: if self.oled_protection {
+                self.overlay.position.region(true)
+            } else {
+                self.overlay.region()
+            },
+            dither: self.dither,
+            filter: self.color_filter,
+            adaptive_duration: self.adaptive_duration,
+            layout: self.layout,
+            ..defaults
+        }
+        .clamped()
+    }
+}
@@ -0,0 +1,52 @@
+//! Commands that steer the slideshow, whatever they come from.
+
+use serde::Deserialize;
+use std::str::FromStr;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Command {
+    Next,
+    Prev,
+    Pause,
+    Resume,
+    TogglePause,
+    GoTo(usize),
+    /// Save what is on screen to a PNG file.
+    Screenshot,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// Parses the textual form used by remote controls: `next`, `pause`, `goto:12`, ...
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "next" => Ok(Command::Next),
+            "prev" => Ok(Command::Prev),
+            "pause" => Ok(Command::Pause),
+            "resume" => Ok(Command::Resume),
+            "toggle-pause" => Ok(Command::TogglePause),
+            "screenshot" => Ok(Command::Screenshot),
+            other => match other.strip_prefix("goto:").map(str::parse) {
+                Some(Ok(index)) => Ok(Command::GoTo(index)),
+                _ => Err(format!("unknown command {:?}", other)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_commands() {
+        assert_eq!("next".parse(), Ok(Command::Next));
+        assert_eq!(" resume\n".parse(), Ok(Command::Resume));
+        assert_eq!("goto:12".parse(), Ok(Command::GoTo(12)));
+        assert_eq!("screenshot".parse(), Ok(Command::Screenshot));
+        assert!("goto:x".parse::<Command>().is_err());
+        assert!("stop".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! `--dedupe`: the same image saved under two names is shown once.
+//!
+//! Only files sharing a size with another can be copies, so just those are
+//! read and hashed, on the thread pool. The hash only has to hold for one
+//! scan, so the standard one does.
+
+use crate::scan::ArtworkInfo;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Instant;
+
+/// Hash of the bytes of the file at `path`.
+fn content_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; 256 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+/// Drops every artwork whose file has the same bytes as one before it. Of a
+/// set of copies the first with a sidecar is kept, in the place of the first
+/// copy, or the first one when none has a sidecar. Returns how many were dropped.
+pub fn dedupe(artworks: &mut Vec<ArtworkInfo>) -> usize {
+    let sizes: Vec<Option<u64>> = artworks
+        .iter()
+        .map(|info| fs::metadata(&info.path).map(|metadata| metadata.len()).ok())
+        .collect();
+    let mut per_size: HashMap<u64, usize> = HashMap::new();
+    for size in sizes.iter().flatten() {
+        *per_size.entry(*size).or_default() += 1;
+    }
+    let candidates: Vec<usize> = (0..artworks.len())
+        .filter(|&index| sizes[index].is_some_and(|size| per_size[&size] > 1))
+        .collect();
+    if candidates.is_empty() {
+        return 0;
+    }
+
+    let started = Instant::now();
+    tracing::info!("hashing {} images that may be copies", candidates.len());
+    let hashes: Vec<(usize, u64)> = candidates
+        .par_iter()
+        .filter_map(|&index| {
+            let path = &artworks[index].path;
+            content_hash(path)
+                .map_err(|err| tracing::warn!("cannot read {} to find copies: {}", path.display(), err))
+                .ok()
+                .map(|hash| (index, hash))
+        })
+        .collect();
+
+    // the copies of each file, in scan order
+    let mut copies: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    for (index, hash) in hashes {
+        let size = sizes[index].expect("only files with a size are hashed");
+        copies.entry((size, hash)).or_default().push(index);
+    }
+    let mut keep = vec![true; artworks.len()];
+    let mut moves = Vec::new();
+    for group in copies.values().filter(|group| group.len() > 1) {
+        let first = group[0];
+        let kept = group
+            .iter()
+            .copied()
+            .find(|&index| artworks[index].path.with_extension("json").is_file())
+            .unwrap_or(first);
+        for &index in &group[1..] {
+            tracing::debug!(
+                "{} is a copy of {}",
+                artworks[index].path.display(),
+                artworks[first].path.display()
+            );
+            keep[index] = false;
+        }
+        if kept != first {
+            moves.push((first, kept));
+        }
+    }
+    for (first, kept) in moves {
+        artworks.swap(first, kept);
+    }
+
+    let mut index = 0;
+    artworks.retain(|_| {
+        index += 1;
+        keep[index - 1]
+    });
+    let removed = keep.iter().filter(|kept| !**kept).count();
+    tracing::info!("{} copies left out, found in {:.1?}", removed, started.elapsed());
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan;
+
+    #[test]
+    fn copies_are_shown_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("monet_waterlilies.jpg", "lilies"),
+            ("starry_night.jpg", "starry"),
+            ("waterlilies (1).jpg", "lilies"),
+            // same size, other bytes
+            ("irises.jpg", "irises"),
+        ];
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        fs::write(
+            dir.path().join("waterlilies (1).json"),
+            r#"{"title": "Water Lilies", "artist": "Claude Monet", "year": "1906"}"#,
+        )
+        .unwrap();
+        let mut artworks: Vec<ArtworkInfo> = files
+            .iter()
+            .map(|(name, _)| {
+                let path = dir.path().join(name);
+                ArtworkInfo {
+                    metadata: scan::read_metadata(&path),
+                    path,
+                    remote: None,
+                }
+            })
+            .collect();
+
+        assert_eq!(dedupe(&mut artworks), 1);
+        let titles: Vec<&str> = artworks.iter().map(|info| info.metadata.title.as_str()).collect();
+        // the copy with a sidecar stays, where the first one was
+        assert_eq!(titles, ["Water Lilies", "starry_night", "irises"]);
+    }
+}
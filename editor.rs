@@ -0,0 +1,240 @@
+//! Editing the sidecar of the artwork on screen, opened with E.
+//!
+//! Saving writes the fields back into the `.json` next to the image, keeping
+//! any other keys it has, or creates it.
+
+use crate::layout::Fit;
+use crate::persist;
+use crate::pipeline::color::ColorFilter;
+use crate::scan::ArtworkMetadata;
+use eframe::egui;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes `metadata` into the sidecar of `image`. Keys the metadata doesn't
+/// know are left as they are, and unset optional fields are left out.
+pub fn save_sidecar(image: &Path, metadata: &ArtworkMetadata) -> io::Result<()> {
+    let path = image.with_extension("json");
+    let mut sidecar = match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).map_err(io::Error::other)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => serde_json::Value::Object(Default::default()),
+        Err(err) => return Err(err),
+    };
+    let fields = sidecar
+        .as_object_mut()
+        .ok_or_else(|| io::Error::other("the sidecar is not a JSON object"))?;
+    let serde_json::Value::Object(edited) = serde_json::to_value(metadata).map_err(io::Error::other)? else {
+        unreachable!("metadata serializes to an object");
+    };
+    for (key, value) in edited {
+        if value.is_null() {
+            fields.remove(&key);
+        } else {
+            fields.insert(key, value);
+        }
+    }
+
+    let text = serde_json::to_string_pretty(&sidecar).map_err(io::Error::other)?;
+    persist::write_atomic(&path, text.as_bytes())
+}
+
+/// The fields being edited, as typed.
+struct Draft {
+    image: PathBuf,
+    title: String,
+    artist: String,
+    year: String,
+    duration: String,
+    fit: Option<Fit>,
+    url: String,
+    // not edited here, kept as they were
+    pixel_art: Option<bool>,
+    filter: Option<ColorFilter>,
+    // focus the first field on the frame the editor opens
+    focus_first: bool,
+}
+
+impl Draft {
+    /// The metadata typed in, or what is wrong with it.
+    fn metadata(&self) -> Result<ArtworkMetadata, String> {
+        let duration = match self.duration.trim() {
+            "" => None,
+            text => Some(
+                text.parse::<f32>()
+                    .map_err(|_| format!("{:?} is not a number of seconds", text))?,
+            ),
+        };
+        let url = self.url.trim();
+        Ok(ArtworkMetadata {
+            title: self.title.trim().to_string(),
+            artist: self.artist.trim().to_string(),
+            year: self.year.trim().to_string(),
+            duration,
+            fit: self.fit,
+            url: (!url.is_empty()).then(|| url.to_string()),
+            pixel_art: self.pixel_art,
+            filter: self.filter,
+        })
+    }
+}
+
+/// What became of an edit.
+pub enum Edit {
+    /// The sidecar of this image was saved with this metadata.
+    Saved(PathBuf, ArtworkMetadata),
+    Cancelled,
+}
+
+#[derive(Default)]
+pub struct MetadataEditor {
+    draft: Option<Draft>,
+    // why the last save didn't go through
+    error: Option<String>,
+}
+
+impl MetadataEditor {
+    pub fn is_open(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    /// Starts editing the metadata of `image`.
+    pub fn open(&mut self, image: &Path, metadata: &ArtworkMetadata) {
+        self.error = None;
+        self.draft = Some(Draft {
+            image: image.to_path_buf(),
+            title: metadata.title.clone(),
+            artist: metadata.artist.clone(),
+            year: metadata.year.clone(),
+            duration: metadata.duration.map(|secs| secs.to_string()).unwrap_or_default(),
+            fit: metadata.fit,
+            url: metadata.url.clone().unwrap_or_default(),
+            pixel_art: metadata.pixel_art,
+            filter: metadata.filter,
+            focus_first: true,
+        });
+    }
+
+    /// Drops the changes.
+    pub fn close(&mut self) {
+        self.draft = None;
+    }
+
+    /// Draws the editor. Returns what happened once it is saved or cancelled,
+    /// either of which closes it.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Edit> {
+        let draft = self.draft.as_mut()?;
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+        let (mut save, mut cancel) = (false, false);
+
+        egui::Window::new("Edit metadata")
+            .frame(frame)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("metadata_fields")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Title");
+                        let title = ui.text_edit_singleline(&mut draft.title);
+                        if std::mem::take(&mut draft.focus_first) {
+                            title.request_focus();
+                        }
+                        ui.end_row();
+                        ui.label("Artist");
+                        ui.text_edit_singleline(&mut draft.artist);
+                        ui.end_row();
+                        ui.label("Year");
+                        ui.text_edit_singleline(&mut draft.year);
+                        ui.end_row();
+                        ui.label("Duration (s)");
+                        ui.add(egui::TextEdit::singleline(&mut draft.duration).hint_text("slide default"));
+                        ui.end_row();
+                        ui.label("Fit");
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut draft.fit, None, "Default");
+                            ui.selectable_value(&mut draft.fit, Some(Fit::Contain), "Contain");
+                            ui.selectable_value(&mut draft.fit, Some(Fit::Cover), "Cover");
+                        });
+                        ui.end_row();
+                        ui.label("URL");
+                        ui.text_edit_singleline(&mut draft.url);
+                        ui.end_row();
+                    });
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+                ui.horizontal(|ui| {
+                    save = ui.button("Save").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+                ui.weak("Esc: cancel");
+            });
+
+        if cancel {
+            self.close();
+            return Some(Edit::Cancelled);
+        }
+        if !save {
+            return None;
+        }
+        let saved = draft.metadata().and_then(|metadata| {
+            save_sidecar(&draft.image, &metadata)
+                .map(|()| metadata)
+                .map_err(|err| err.to_string())
+        });
+        match saved {
+            Ok(metadata) => {
+                let image = draft.image.clone();
+                self.close();
+                Some(Edit::Saved(image, metadata))
+            }
+            Err(err) => {
+                tracing::warn!("cannot save the sidecar of {}: {}", draft.image.display(), err);
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_keeps_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("goya.jpg");
+        fs::write(
+            image.with_extension("json"),
+            r#"{"title": "Portrat", "artist": "Goya", "year": "1823", "fit": "cover", "inventory": "P-123"}"#,
+        )
+        .unwrap();
+
+        let metadata = ArtworkMetadata {
+            artist: "Goya".to_string(),
+            year: "1823".to_string(),
+            duration: Some(20.0),
+            ..ArtworkMetadata::unknown("Portrait")
+        };
+        save_sidecar(&image, &metadata).unwrap();
+
+        let text = fs::read_to_string(image.with_extension("json")).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(saved["title"], "Portrait");
+        assert_eq!(saved["inventory"], "P-123");
+        assert_eq!(saved["duration"], 20.0);
+        assert!(saved.get("fit").is_none() && saved.get("url").is_none());
+        assert_eq!(serde_json::from_str::<ArtworkMetadata>(&text).unwrap(), metadata);
+
+        // an image without a sidecar gets one
+        let bare = dir.path().join("bare.jpg");
+        save_sidecar(&bare, &metadata).unwrap();
+        assert!(bare.with_extension("json").exists());
+    }
+}
@@ -0,0 +1,53 @@
+//! `--loops` and `--end-action`: what the show does once it has played
+//! through as many times as it was asked to.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How long `black` takes to fade the last slide out.
+pub const FADE_OUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum EndAction {
+    /// The last slide stays up, the show can still be gone through by hand.
+    #[default]
+    HoldLast,
+    /// The last slide fades out to a black screen.
+    Black,
+    /// The app quits, for a script that plays something else next.
+    Exit,
+}
+
+impl FromStr for EndAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hold-last" => Ok(EndAction::HoldLast),
+            "black" => Ok(EndAction::Black),
+            "exit" => Ok(EndAction::Exit),
+            other => Err(format!("expected hold-last, black or exit, got {:?}", other)),
+        }
+    }
+}
+
+/// How much of the screen is black `elapsed` into the fade out, from 0 to 1.
+pub fn black(elapsed: Duration) -> f32 {
+    (elapsed.as_secs_f32() / FADE_OUT.as_secs_f32()).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_actions_parse() {
+        assert_eq!("hold-last".parse(), Ok(EndAction::HoldLast));
+        assert_eq!("exit".parse(), Ok(EndAction::Exit));
+        assert!("loop".parse::<EndAction>().is_err());
+        assert_eq!(black(Duration::from_secs(1)), 0.5);
+        assert_eq!(black(Duration::from_secs(5)), 1.0);
+    }
+}
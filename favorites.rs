@@ -0,0 +1,93 @@
+use crate::persist;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FAVORITES_FILE: &str = "favorites.json";
+
+/// Artworks flagged with the F key, stored as paths relative to the image folder.
+pub struct Favorites {
+    root: PathBuf,
+    entries: BTreeSet<String>,
+}
+
+impl Favorites {
+    /// Reads `favorites.json` from `root`. A missing file is an empty list, a
+    /// malformed one is reported and treated as empty.
+    pub fn load(root: &Path) -> Self {
+        let path = root.join(FAVORITES_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+                tracing::warn!("ignoring malformed {}: {}", path.display(), err);
+                BTreeSet::new()
+            }),
+            Err(_) => BTreeSet::new(),
+        };
+
+        Self {
+            root: root.to_path_buf(),
+            entries,
+        }
+    }
+
+    pub fn contains(&self, image: &Path) -> bool {
+        self.entries.contains(&persist::relative_key(&self.root, image))
+    }
+
+    /// Favorites whose file is gone from the folder.
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(|key| !self.root.join(key).exists())
+            .map(String::as_str)
+    }
+
+    /// Flips `image` in or out of the list and saves it. Returns whether it is now a favorite.
+    /// The list stays as it was when it can't be saved.
+    pub fn toggle(&mut self, image: &Path) -> io::Result<bool> {
+        let key = persist::relative_key(&self.root, image);
+        let mut entries = self.entries.clone();
+        let added = if entries.remove(&key) {
+            false
+        } else {
+            entries.insert(key);
+            true
+        };
+
+        let json = serde_json::to_vec_pretty(&entries)?;
+        persist::write_atomic(&self.root.join(FAVORITES_FILE), &json)?;
+        self.entries = entries;
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_round_trips_through_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("goya.jpg");
+
+        let mut favorites = Favorites::load(dir.path());
+        assert!(favorites.toggle(&image).unwrap());
+        assert!(Favorites::load(dir.path()).contains(&image));
+
+        assert!(!favorites.toggle(&image).unwrap());
+        assert!(!Favorites::load(dir.path()).contains(&image));
+    }
+
+    #[test]
+    fn a_toggle_that_cant_be_saved_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        // a file where the folder should be, nothing can be written under it
+        let root = dir.path().join("not-a-folder");
+        fs::write(&root, b"").unwrap();
+
+        let mut favorites = Favorites::load(&root);
+        assert!(favorites.toggle(&root.join("goya.jpg")).is_err());
+        assert!(!favorites.contains(&root.join("goya.jpg")));
+    }
+}
@@ -0,0 +1,293 @@
+//! Strip of thumbnails along the bottom for jumping anywhere in the
+//! collection, opened with Tab while the slideshow is paused.
+//!
+//! Thumbnails are made on a worker thread for the part of the strip on
+//! screen only, so a folder of thousands of images costs nothing until it is
+//! scrolled through. Like the settings panel, the strip is not turned with
+//! `--rotate`.
+
+use crate::layout;
+use crate::pipeline::cache::DiskCache;
+use crate::pipeline::{self, PipelineError, THUMBNAIL_SIZE};
+use crate::remote;
+use crate::scan::ArtworkInfo;
+use crate::source::SharedSource;
+use eframe::egui;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const SPACING: f32 = 8.0;
+
+/// Thumbnails kept beyond this many are dropped, except for the ones on screen.
+const MAX_THUMBNAILS: usize = 600;
+
+struct ThumbnailRequest {
+    index: usize,
+    info: ArtworkInfo,
+    source: SharedSource,
+    wake: egui::Context,
+}
+
+struct ThumbnailResponse {
+    path: PathBuf,
+    // `None` when the strip scrolled away before the worker got to it
+    result: Option<Result<egui::ColorImage, PipelineError>>,
+}
+
+enum Thumbnail {
+    Pending,
+    Ready(egui::TextureHandle),
+    Failed,
+}
+
+pub struct Filmstrip {
+    open: bool,
+    selected: usize,
+    // scroll the selection into view on the next frame
+    scroll_to_selected: bool,
+    thumbnails: HashMap<PathBuf, Thumbnail>,
+    requests: Sender<ThumbnailRequest>,
+    responses: Receiver<ThumbnailResponse>,
+    // indices on screen, the worker skips requests that scrolled out of it
+    visible: Arc<Mutex<Range<usize>>>,
+}
+
+impl Filmstrip {
+    pub fn new(cache: Option<DiskCache>) -> Self {
+        let (requests, request_rx) = mpsc::channel::<ThumbnailRequest>();
+        let (response_tx, responses) = mpsc::channel();
+        let visible = Arc::new(Mutex::new(0..0));
+
+        let worker_visible = Arc::clone(&visible);
+        thread::spawn(move || {
+            while let Ok(request) = request_rx.recv() {
+                let on_screen = worker_visible
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .contains(&request.index);
+                let result = on_screen.then(|| {
+                    pipeline::catch_panic(|| {
+                        // a URL entry not downloaded yet is fetched as it would be for its slide
+                        let info = if request.info.path.is_file() {
+                            request.info.clone()
+                        } else {
+                            remote::fetch(&request.info).map_err(PipelineError::Download)?
+                        };
+                        pipeline::thumbnail(&info, request.source.as_ref(), cache.as_ref())
+                    })
+                    .map(|image| color_image(&image))
+                });
+                if response_tx
+                    .send(ThumbnailResponse {
+                        path: request.info.path,
+                        result,
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+                request.wake.request_repaint();
+            }
+        });
+
+        Self {
+            open: false,
+            selected: 0,
+            scroll_to_selected: false,
+            thumbnails: HashMap::new(),
+            requests,
+            responses,
+            visible,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the strip with `current` selected.
+    pub fn open(&mut self, current: usize) {
+        self.open = true;
+        self.selected = current;
+        self.scroll_to_selected = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        *self.visible.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = 0..0;
+    }
+
+    /// Draws the strip of `artworks`, read from `source`, and handles its keys.
+    /// Returns the index to jump to once one is clicked or picked with Enter,
+    /// which also closes the strip.
+    pub fn show(&mut self, ctx: &egui::Context, artworks: &[ArtworkInfo], source: &SharedSource) -> Option<usize> {
+        self.receive(ctx);
+        if !self.open || artworks.is_empty() {
+            return None;
+        }
+
+        let (left, right, enter) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowLeft),
+                i.key_pressed(egui::Key::ArrowRight),
+                i.key_pressed(egui::Key::Enter),
+            )
+        });
+        self.selected = self.selected.min(artworks.len() - 1);
+        if left && self.selected > 0 {
+            self.selected -= 1;
+            self.scroll_to_selected = true;
+        }
+        if right && self.selected + 1 < artworks.len() {
+            self.selected += 1;
+            self.scroll_to_selected = true;
+        }
+        let mut picked = enter.then_some(self.selected);
+
+        let step = THUMBNAIL_SIZE as f32 + SPACING;
+        let thumbnail_size = egui::Vec2::splat(THUMBNAIL_SIZE as f32);
+        let screen = ctx.screen_rect();
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+
+        egui::Area::new(egui::Id::new("filmstrip"))
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -20.0))
+            .show(ctx, |ui| {
+                frame.show(ui, |ui| {
+                    ui.set_max_width(screen.width() - 60.0);
+                    egui::ScrollArea::horizontal().show_viewport(ui, |ui, viewport| {
+                        let origin = ui.min_rect().min;
+                        let width = step * artworks.len() as f32 - SPACING;
+                        ui.set_min_size(egui::vec2(width, THUMBNAIL_SIZE as f32));
+                        let slot = |index: usize| {
+                            egui::Rect::from_min_size(origin + egui::vec2(step * index as f32, 0.0), thumbnail_size)
+                        };
+
+                        if std::mem::take(&mut self.scroll_to_selected) {
+                            ui.scroll_to_rect(slot(self.selected), Some(egui::Align::Center));
+                        }
+
+                        let first = (viewport.min.x / step).floor().max(0.0) as usize;
+                        let end = ((viewport.max.x / step).ceil() as usize).min(artworks.len());
+                        *self.visible.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = first..end;
+
+                        for (index, info) in artworks.iter().enumerate().take(end).skip(first) {
+                            let rect = slot(index);
+                            let response = ui.allocate_rect(rect, egui::Sense::click());
+                            if response.clicked() {
+                                picked = Some(index);
+                            }
+                            self.draw_thumbnail(ui, ctx, index, info, source, rect);
+                            if index == self.selected || response.hovered() {
+                                ui.painter().rect_stroke(
+                                    rect.expand(2.0),
+                                    4.0,
+                                    egui::Stroke::new(3.0, egui::Color32::WHITE),
+                                );
+                            }
+                        }
+                    });
+                });
+            });
+
+        self.evict(artworks);
+        if picked.is_some() {
+            self.close();
+        }
+        picked
+    }
+
+    fn draw_thumbnail(
+        &mut self,
+        ui: &egui::Ui,
+        ctx: &egui::Context,
+        index: usize,
+        info: &ArtworkInfo,
+        source: &SharedSource,
+        rect: egui::Rect,
+    ) {
+        let thumbnail = self.thumbnails.entry(info.path.clone()).or_insert_with(|| {
+            let request = ThumbnailRequest {
+                index,
+                info: info.clone(),
+                source: Arc::clone(source),
+                wake: ctx.clone(),
+            };
+            if self.requests.send(request).is_err() {
+                tracing::error!("thumbnail thread is gone, cannot load {}", info.path.display());
+            }
+            Thumbnail::Pending
+        });
+
+        match thumbnail {
+            Thumbnail::Ready(texture) => {
+                let image_rect = layout::contain(texture.size_vec2(), rect.size()).translate(rect.min.to_vec2());
+                let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                ui.painter().image(texture.id(), image_rect, uv, egui::Color32::WHITE);
+            }
+            Thumbnail::Pending => {
+                ui.painter().rect_filled(rect, 4.0, egui::Color32::from_gray(40));
+            }
+            Thumbnail::Failed => {
+                ui.painter().rect_filled(rect, 4.0, egui::Color32::from_gray(25));
+                ui.painter().text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "?",
+                    egui::FontId::proportional(32.0),
+                    egui::Color32::GRAY,
+                );
+            }
+        }
+    }
+
+    /// Turns finished thumbnails into textures.
+    fn receive(&mut self, ctx: &egui::Context) {
+        while let Ok(response) = self.responses.try_recv() {
+            match response.result {
+                Some(Ok(image)) => {
+                    let name = format!("thumbnail_{}", response.path.display());
+                    let texture = ctx.load_texture(name, image, egui::TextureOptions::LINEAR);
+                    self.thumbnails.insert(response.path, Thumbnail::Ready(texture));
+                }
+                Some(Err(err)) => {
+                    tracing::warn!("no thumbnail for {}: {}", response.path.display(), err);
+                    self.thumbnails.insert(response.path, Thumbnail::Failed);
+                }
+                // asked for again if it comes back into view
+                None => {
+                    self.thumbnails.remove(&response.path);
+                }
+            }
+        }
+    }
+
+    /// Keeps the memory use of a huge folder bounded, dropping what is off screen.
+    fn evict(&mut self, artworks: &[ArtworkInfo]) {
+        if self.thumbnails.len() <= MAX_THUMBNAILS {
+            return;
+        }
+        let visible = self
+            .visible
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        let keep: Vec<&PathBuf> = artworks
+            .get(visible)
+            .unwrap_or_default()
+            .iter()
+            .map(|info| &info.path)
+            .collect();
+        // pending entries stay too, their answer is on its way
+        self.thumbnails
+            .retain(|path, thumbnail| matches!(thumbnail, Thumbnail::Pending) || keep.contains(&path));
+    }
+}
+
+fn color_image(image: &image::DynamicImage) -> egui::ColorImage {
+    let size = [image.width() as usize, image.height() as usize];
+    egui::ColorImage::from_rgba_unmultiplied(size, image.to_rgba8().as_flat_samples().as_slice())
+}
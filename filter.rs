@@ -0,0 +1,154 @@
+//! Narrowing the scanned artworks down by artist or year.
+
+use crate::scan::ArtworkMetadata;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Inclusive range of years, written `1860-1890` or just `1889`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct YearRange {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl YearRange {
+    fn overlaps(&self, other: &YearRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+}
+
+impl FromStr for YearRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |part: &str| {
+            part.trim()
+                .parse::<i32>()
+                .map_err(|_| format!("expected a year range like 1860-1890, got {:?}", s))
+        };
+
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let year = parse(s)?;
+                (year, year)
+            }
+        };
+        Ok(YearRange {
+            start: start.min(end),
+            end: start.max(end),
+        })
+    }
+}
+
+impl fmt::Display for YearRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// Years mentioned in a free-form `year` field: "1889", "c. 1890", "1870–1875".
+///
+/// Only runs of three or four digits count, so month and day numbers in
+/// dates like "1889-06-18" are ignored.
+pub fn parse_years(text: &str) -> Option<YearRange> {
+    let mut years = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|run| (3..=4).contains(&run.len()))
+        .filter_map(|run| run.parse::<i32>().ok());
+
+    let start = years.next()?;
+    let end = years.next().unwrap_or(start);
+    Some(YearRange {
+        start: start.min(end),
+        end: start.max(end),
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ArtworkFilter {
+    /// Case-insensitive substrings, an artwork matches if any of them is in its artist.
+    pub artists: Vec<String>,
+    pub years: Option<YearRange>,
+}
+
+impl ArtworkFilter {
+    pub fn is_active(&self) -> bool {
+        !self.artists.is_empty() || self.years.is_some()
+    }
+
+    pub fn matches(&self, metadata: &ArtworkMetadata) -> bool {
+        if !self.artists.is_empty() {
+            let artist = metadata.artist.to_lowercase();
+            if !self.artists.iter().any(|wanted| artist.contains(&wanted.to_lowercase())) {
+                return false;
+            }
+        }
+
+        match self.years {
+            // unknown years can't be placed in a range
+            Some(range) => parse_years(&metadata.year).is_some_and(|years| range.overlaps(&years)),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for ArtworkFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        for artist in &self.artists {
+            parts.push(format!("--artist {:?}", artist));
+        }
+        if let Some(years) = self.years {
+            parts.push(format!("--years {}", years));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(artist: &str, year: &str) -> ArtworkMetadata {
+        ArtworkMetadata { artist: artist.to_string(), year: year.to_string(), ..ArtworkMetadata::unknown("Untitled") }
+    }
+
+    #[test]
+    fn years_in_messy_fields() {
+        assert_eq!(parse_years("1889"), Some(YearRange { start: 1889, end: 1889 }));
+        assert_eq!(parse_years("c. 1890"), Some(YearRange { start: 1890, end: 1890 }));
+        assert_eq!(parse_years("1870–1875"), Some(YearRange { start: 1870, end: 1875 }));
+        assert_eq!(parse_years("1889-06-18"), Some(YearRange { start: 1889, end: 1889 }));
+        assert_eq!(parse_years("Unknown"), None);
+    }
+
+    #[test]
+    fn artist_and_year_filters() {
+        let filter = ArtworkFilter {
+            artists: vec!["monet".to_string(), "Renoir".to_string()],
+            years: Some("1860-1890".parse().unwrap()),
+        };
+
+        assert!(filter.matches(&metadata("Claude Monet", "1872")));
+        assert!(filter.matches(&metadata("Pierre-Auguste Renoir", "c. 1855–1862")));
+        assert!(!filter.matches(&metadata("Claude Monet", "1905")));
+        assert!(!filter.matches(&metadata("Claude Monet", "Unknown")));
+        assert!(!filter.matches(&metadata("Francisco de Goya", "1823")));
+    }
+
+    #[test]
+    fn unknown_years_pass_without_a_year_filter() {
+        let filter = ArtworkFilter {
+            artists: vec!["goya".to_string()],
+            years: None,
+        };
+        assert!(filter.matches(&metadata("Francisco de Goya", "Unknown")));
+    }
+}
@@ -0,0 +1,41 @@
+//! Font used for the caption, optionally loaded from a TTF/OTF file.
+
+use eframe::egui;
+use egui::{FontData, FontDefinitions, FontFamily};
+use std::fs;
+use std::path::Path;
+
+const CAPTION_FONT: &str = "caption";
+
+/// Family of the title, artist and year text.
+pub fn caption_family() -> FontFamily {
+    FontFamily::Name(CAPTION_FONT.into())
+}
+
+/// Font bytes from `path`, checked up front since egui panics on data it can't parse.
+pub fn load(path: &Path) -> Result<Vec<u8>, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    ab_glyph::FontRef::try_from_slice(&bytes).map_err(|_| "not a TrueType or OpenType font".to_string())?;
+    Ok(bytes)
+}
+
+/// Sets up the caption family: the font at `path` first, then egui's own
+/// fonts for any glyph it lacks, so an accent it doesn't cover still renders.
+/// Without a usable font the caption uses the default proportional font.
+pub fn install(ctx: &egui::Context, path: Option<&Path>) {
+    let mut fonts = FontDefinitions::default();
+    let mut family = fonts.families.get(&FontFamily::Proportional).cloned().unwrap_or_default();
+
+    if let Some(path) = path {
+        match load(path) {
+            Ok(bytes) => {
+                fonts.font_data.insert(CAPTION_FONT.to_string(), FontData::from_owned(bytes));
+                family.insert(0, CAPTION_FONT.to_string());
+            }
+            Err(err) => tracing::warn!("cannot use font {}, keeping the default: {}", path.display(), err),
+        }
+    }
+
+    fonts.families.insert(caption_family(), family);
+    ctx.set_fonts(fonts);
+}
@@ -0,0 +1,50 @@
+//! Optional HTTP remote control: `POST /next|/prev|/pause|/resume`, `GET /status|/screenshot`.
+
+use crate::control::Command;
+use crate::ipc::SharedSnapshot;
+use eframe::egui;
+use std::sync::mpsc::Sender;
+use std::thread;
+use tiny_http::{Header, Method, Response, Server};
+
+fn json_header() -> Header {
+    Header::from_bytes("Content-Type", "application/json").unwrap()
+}
+
+/// Starts the control server on all interfaces. Commands are handed to the UI
+/// thread through `commands`, and `ctx` is woken so they apply right away.
+pub fn serve(port: u16, commands: Sender<Command>, status: SharedSnapshot, ctx: egui::Context) -> std::io::Result<()> {
+    let server = Server::http(("0.0.0.0", port)).map_err(std::io::Error::other)?;
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let command = match (request.method(), request.url()) {
+                (Method::Post, "/next") => Command::Next,
+                (Method::Post, "/prev") => Command::Prev,
+                (Method::Post, "/pause") => Command::Pause,
+                (Method::Post, "/resume") => Command::Resume,
+                (Method::Get, "/screenshot") => Command::Screenshot,
+                (Method::Get, "/status") => {
+                    let body = {
+                        let status = status.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                        status.http_status().to_string()
+                    };
+                    let _ = request.respond(Response::from_string(body).with_header(json_header()));
+                    continue;
+                }
+                _ => {
+                    let _ = request.respond(Response::from_string("not found").with_status_code(404));
+                    continue;
+                }
+            };
+
+            if commands.send(command).is_err() {
+                break;
+            }
+            ctx.request_repaint();
+            let _ = request.respond(Response::from_string(r#"{"ok":true}"#).with_header(json_header()));
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! `--screensaver MINUTES`: the show as a screensaver. The window stays out
+//! of sight while the computer is in use and comes up fullscreen once nobody
+//! has touched the keyboard or the mouse, anywhere, for that long; the first
+//! touch puts it away again.
+//!
+//! How long the computer has been idle is asked of the system:
+//! `GetLastInputInfo` on Windows, CoreGraphics' event source on macOS and the
+//! X server's screensaver extension elsewhere, which needs `libXss`. A Wayland
+//! session only tells it through Xwayland, which sees just the input that goes
+//! to X windows.
+
+use std::time::Duration;
+
+/// How often the idle time is looked at.
+pub const POLL: Duration = Duration::from_secs(1);
+
+/// What the window should do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Show,
+    Hide,
+}
+
+/// Whether the show is up, and when it should come up or go away.
+pub struct Screensaver {
+    clock: platform::Clock,
+    after: Duration,
+    active: bool,
+}
+
+/// The change due for a show that is `active` or not, after `idle` without
+/// input. Once up the show was idle for at least `after`, less means someone
+/// touched something.
+fn change(active: bool, idle: Duration, after: Duration) -> Option<Change> {
+    match active {
+        false if idle >= after => Some(Change::Show),
+        true if idle < after => Some(Change::Hide),
+        _ => None,
+    }
+}
+
+impl Screensaver {
+    /// Starts out of sight, to come up after `after` without input. Fails
+    /// where the system won't tell how long it has been idle.
+    pub fn new(after: Duration) -> Result<Self, String> {
+        Ok(Self { clock: platform::Clock::open()?, after, active: false })
+    }
+
+    /// Whether the show is up.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Puts the show away, for input the window itself took.
+    pub fn hide(&mut self) -> Option<Change> {
+        std::mem::replace(&mut self.active, false).then_some(Change::Hide)
+    }
+
+    /// Looks at the idle time, returning the change it calls for.
+    pub fn poll(&mut self) -> Option<Change> {
+        let idle = self.clock.idle_time()?;
+        let change = change(self.active, idle, self.after);
+        if let Some(change) = change {
+            self.active = change == Change::Show;
+        }
+        change
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+    use windows_sys::Win32::System::SystemInformation::GetTickCount;
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    pub struct Clock;
+
+    impl Clock {
+        pub fn open() -> Result<Self, String> {
+            Ok(Clock)
+        }
+
+        pub fn idle_time(&self) -> Option<Duration> {
+            let mut info = LASTINPUTINFO { cbSize: size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+            // SAFETY: `info` is a LASTINPUTINFO with its size filled in, as asked
+            if unsafe { GetLastInputInfo(&mut info) } == 0 {
+                return None;
+            }
+            // SAFETY: no arguments; both tick counts wrap after 49 days, alike
+            let now = unsafe { GetTickCount() };
+            Some(Duration::from_millis(now.wrapping_sub(info.dwTime).into()))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::time::Duration;
+
+    const COMBINED_SESSION_STATE: i32 = 0;
+    const ANY_INPUT_EVENT: u32 = !0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    unsafe extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(source: i32, event_type: u32) -> f64;
+    }
+
+    pub struct Clock;
+
+    impl Clock {
+        pub fn open() -> Result<Self, String> {
+            Ok(Clock)
+        }
+
+        pub fn idle_time(&self) -> Option<Duration> {
+            // SAFETY: takes and returns plain values
+            let seconds = unsafe { CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT) };
+            Duration::try_from_secs_f64(seconds).ok()
+        }
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::ptr;
+    use std::time::Duration;
+    use x11_dl::{xlib, xss};
+
+    /// A connection to the X server of its own, kept open for the polls.
+    pub struct Clock {
+        xlib: xlib::Xlib,
+        xss: xss::Xss,
+        display: *mut xlib::Display,
+        info: *mut xss::XScreenSaverInfo,
+    }
+
+    impl Clock {
+        pub fn open() -> Result<Self, String> {
+            let xlib = xlib::Xlib::open().map_err(|err| err.to_string())?;
+            let xss = xss::Xss::open().map_err(|err| err.to_string())?;
+            // SAFETY: a null name opens $DISPLAY, the result is checked for null
+            let display = unsafe { (xlib.XOpenDisplay)(ptr::null()) };
+            if display.is_null() {
+                return Err("cannot open the X display".to_string());
+            }
+            let (mut event_base, mut error_base) = (0, 0);
+            // SAFETY: `display` is open, the out-parameters live on the stack
+            let info = if unsafe { (xss.XScreenSaverQueryExtension)(display, &mut event_base, &mut error_base) } != 0 {
+                // SAFETY: no arguments, null when out of memory
+                unsafe { (xss.XScreenSaverAllocInfo)() }
+            } else {
+                ptr::null_mut()
+            };
+            if info.is_null() {
+                // SAFETY: opened above and not used after
+                unsafe { (xlib.XCloseDisplay)(display) };
+                return Err("the X server has no screensaver extension".to_string());
+            }
+            Ok(Self { xlib, xss, display, info })
+        }
+
+        pub fn idle_time(&self) -> Option<Duration> {
+            // SAFETY: `display` and `info` stay valid until the drop
+            let idle = unsafe {
+                let root = (self.xlib.XDefaultRootWindow)(self.display);
+                ((self.xss.XScreenSaverQueryInfo)(self.display, root, self.info) != 0).then(|| (*self.info).idle)
+            };
+            // a c_ulong, 32 bits on some systems
+            #[allow(clippy::unnecessary_cast)]
+            idle.map(|millis| Duration::from_millis(millis as u64))
+        }
+    }
+
+    impl Drop for Clock {
+        fn drop(&mut self) {
+            // SAFETY: both were made in `open` and are used no more
+            unsafe {
+                (self.xlib.XFree)(self.info.cast());
+                (self.xlib.XCloseDisplay)(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod platform {
+    use std::time::Duration;
+
+    pub struct Clock;
+
+    impl Clock {
+        pub fn open() -> Result<Self, String> {
+            Err("the idle time is unknown on this system".to_string())
+        }
+
+        pub fn idle_time(&self) -> Option<Duration> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_show_comes_up_when_idle_and_goes_at_a_touch() {
+        let after = Duration::from_secs(300);
+        assert_eq!(change(false, Duration::from_secs(10), after), None);
+        assert_eq!(change(false, after, after), Some(Change::Show));
+        assert_eq!(change(true, Duration::from_secs(400), after), None);
+        assert_eq!(change(true, Duration::from_millis(20), after), Some(Change::Hide));
+    }
+}
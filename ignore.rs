@@ -0,0 +1,115 @@
+//! `.slideshowignore`: files in the folder that are never shown, like
+//! reference shots and color charts that live with the artworks.
+//!
+//! Each line is a glob pattern, `#` starting a comment. A pattern with a `/`
+//! is matched against the path from the scanned folder (`refs/*.jpg`,
+//! `**/charts/*`), one without against the file name wherever it is
+//! (`*_chart.png`). Matching ignores case.
+
+use crate::persist;
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const IGNORE_FILE: &str = ".slideshowignore";
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    // `*` stays within one folder, only `**` crosses into others
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Default)]
+pub struct IgnoreList {
+    // patterns matched against the whole relative path
+    paths: Vec<Pattern>,
+    // patterns matched against the file name alone
+    names: Vec<Pattern>,
+}
+
+impl IgnoreList {
+    /// Reads the ignore file at the top of `root`. Without one nothing is ignored.
+    pub fn load(root: &Path) -> Self {
+        let path = root.join(IGNORE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, &path),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!("cannot read {}: {}", path.display(), err);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// The patterns in `text`. Bad ones are reported against `source` and skipped.
+    pub fn parse(text: &str, source: &Path) -> Self {
+        let mut list = Self::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // a leading slash anchors the pattern at the root, as it is anyway with one inside
+            let (anchored, pattern) = match line.strip_prefix('/') {
+                Some(rest) => (true, rest),
+                None => (line.contains('/'), line),
+            };
+            match Pattern::new(pattern) {
+                Ok(pattern) if anchored => list.paths.push(pattern),
+                Ok(pattern) => list.names.push(pattern),
+                Err(err) => tracing::warn!(
+                    "{}:{}: skipping bad pattern {:?}: {}",
+                    source.display(),
+                    number + 1,
+                    line,
+                    err
+                ),
+            }
+        }
+        list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.names.is_empty()
+    }
+
+    /// Whether `image`, somewhere under `root`, is left out of the show.
+    pub fn is_ignored(&self, root: &Path, image: &Path) -> bool {
+        let relative = persist::relative_key(root, image);
+        let name = relative.rsplit('/').next().unwrap_or(&relative);
+        self.paths
+            .iter()
+            .any(|pattern| pattern.matches_with(&relative, OPTIONS))
+            || self.names.iter().any(|pattern| pattern.matches_with(name, OPTIONS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_match_relative_paths_without_case() {
+        let root = Path::new("/art");
+        let list = IgnoreList::parse(
+            "# calibration\n*_chart.png\n\nrefs/*.jpg\n/top.jpg\n**/sketches/**\n[unclosed\n",
+            Path::new("test"),
+        );
+        let ignored = |path: &str| list.is_ignored(root, &root.join(path));
+
+        assert!(ignored("color_chart.png"));
+        assert!(ignored("2024/spring/Gray_CHART.PNG"));
+        assert!(ignored("refs/shot.jpg"));
+        assert!(ignored("Refs/Shot.JPG"));
+        // `*` doesn't reach into subfolders, and anchored patterns only match from the root
+        assert!(!ignored("refs/old/shot.jpg"));
+        assert!(!ignored("2024/refs/shot.jpg"));
+        assert!(ignored("top.jpg"));
+        assert!(!ignored("sub/top.jpg"));
+        assert!(ignored("a/b/sketches/c/d.jpg"));
+        assert!(!ignored("starry_night.jpg"));
+    }
+}
@@ -0,0 +1,325 @@
+//! Query endpoint of a running slideshow.
+//!
+//! The protocol is one JSON request line answered by one JSON response line.
+//! On Unix the endpoint is a socket file, elsewhere a localhost TCP address.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Bumped whenever `Request` or `Response` change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueryCommand {
+    Status,
+    Current,
+    ListCollections,
+    Stats,
+}
+
+impl QueryCommand {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Request {
+    pub version: u32,
+    pub command: QueryCommand,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Response {
+    pub version: u32,
+    pub ok: bool,
+    #[serde(default)]
+    pub data: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What the running instance is showing, kept up to date by the app.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Snapshot {
+    pub index: usize,
+    pub total: usize,
+    pub path: Option<PathBuf>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub year: Option<String>,
+    pub collections: Vec<PathBuf>,
+    pub slides_shown: u64,
+    pub paused: bool,
+    /// Time left on the slide when the snapshot was taken.
+    #[serde(skip)]
+    pub remaining: Option<Duration>,
+    #[serde(skip)]
+    pub taken_at: Option<Instant>,
+}
+
+impl Snapshot {
+    /// Time left on the current slide right now.
+    pub fn remaining_now(&self) -> Option<Duration> {
+        let remaining = self.remaining?;
+        match self.taken_at {
+            Some(taken_at) if !self.paused => Some(remaining.saturating_sub(taken_at.elapsed())),
+            _ => Some(remaining),
+        }
+    }
+
+    /// Body of `GET /status` on the HTTP control endpoint.
+    pub fn http_status(&self) -> serde_json::Value {
+        serde_json::json!({
+            "index": self.index,
+            "total": self.total,
+            "path": self.path,
+            "title": self.title,
+            "artist": self.artist,
+            "year": self.year,
+            "paused": self.paused,
+            "remaining_secs": self.remaining_now().map(|remaining| remaining.as_secs_f32()),
+        })
+    }
+}
+
+pub type SharedSnapshot = Arc<Mutex<Snapshot>>;
+
+fn answer(command: QueryCommand, snapshot: &Snapshot, uptime: Duration) -> serde_json::Value {
+    match command {
+        QueryCommand::Status => serde_json::json!({
+            "index": snapshot.index,
+            "total": snapshot.total,
+            "title": snapshot.title,
+            "paused": snapshot.paused,
+            "uptime_secs": uptime.as_secs(),
+        }),
+        QueryCommand::Current => serde_json::json!({
+            "index": snapshot.index,
+            "path": snapshot.path,
+            "title": snapshot.title,
+            "artist": snapshot.artist,
+            "year": snapshot.year,
+        }),
+        QueryCommand::ListCollections => serde_json::json!({
+            "collections": snapshot.collections,
+        }),
+        QueryCommand::Stats => serde_json::json!({
+            "slides_shown": snapshot.slides_shown,
+            "total": snapshot.total,
+            "uptime_secs": uptime.as_secs(),
+        }),
+    }
+}
+
+fn handle_line(line: &str, snapshot: &SharedSnapshot, started: Instant) -> Response {
+    let failed = |error: String| Response {
+        version: PROTOCOL_VERSION,
+        ok: false,
+        data: serde_json::Value::Null,
+        error: Some(error),
+    };
+
+    // look at the version first so an old or new client gets a clean mismatch
+    let raw: serde_json::Value = match serde_json::from_str(line) {
+        Ok(raw) => raw,
+        Err(err) => return failed(format!("malformed request: {}", err)),
+    };
+    if raw.get("version").and_then(|v| v.as_u64()) != Some(PROTOCOL_VERSION as u64) {
+        return failed("protocol version mismatch".to_string());
+    }
+
+    match serde_json::from_value::<Request>(raw) {
+        Ok(request) => {
+            let snapshot = snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            Response {
+                version: PROTOCOL_VERSION,
+                ok: true,
+                data: answer(request.command, &snapshot, started.elapsed()),
+                error: None,
+            }
+        }
+        Err(err) => failed(format!("unknown command: {}", err)),
+    }
+}
+
+fn serve_connection<S: io::Read + Write>(stream: S, snapshot: &SharedSnapshot, started: Instant) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let response = handle_line(line.trim(), snapshot, started);
+    let mut stream = reader.into_inner();
+    serde_json::to_writer(&mut stream, &response)?;
+    stream.write_all(b"\n")?;
+    stream.flush()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::{env, fs, io};
+
+    pub type Stream = UnixStream;
+
+    pub fn default_endpoint() -> String {
+        let dir = env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(env::temp_dir);
+        dir.join("art-slideshow.sock").to_string_lossy().into_owned()
+    }
+
+    pub struct Listener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        pub fn bind(endpoint: &str) -> io::Result<Self> {
+            let path = PathBuf::from(endpoint);
+            if path.exists() {
+                // a socket nobody answers on is left over from a crashed instance
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(io::Error::new(io::ErrorKind::AddrInUse, "another instance is running"));
+                }
+                fs::remove_file(&path)?;
+            }
+            Ok(Self { listener: UnixListener::bind(&path)?, path })
+        }
+
+        pub fn accept(&self) -> io::Result<Stream> {
+            self.listener.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    pub fn connect(endpoint: &str) -> io::Result<Stream> {
+        UnixStream::connect(endpoint)
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::io;
+    use std::net::{TcpListener, TcpStream};
+
+    pub type Stream = TcpStream;
+
+    pub fn default_endpoint() -> String {
+        "127.0.0.1:47631".to_string()
+    }
+
+    pub struct Listener {
+        listener: TcpListener,
+    }
+
+    impl Listener {
+        pub fn bind(endpoint: &str) -> io::Result<Self> {
+            Ok(Self { listener: TcpListener::bind(endpoint)? })
+        }
+
+        pub fn accept(&self) -> io::Result<Stream> {
+            self.listener.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    pub fn connect(endpoint: &str) -> io::Result<Stream> {
+        TcpStream::connect(endpoint)
+    }
+}
+
+pub use platform::default_endpoint;
+
+/// Keeps the endpoint bound for as long as it lives.
+pub struct Server {
+    _listener: Arc<platform::Listener>,
+}
+
+/// Starts answering queries on `endpoint` from a background thread.
+pub fn serve(endpoint: &str, snapshot: SharedSnapshot) -> io::Result<Server> {
+    let listener = Arc::new(platform::Listener::bind(endpoint)?);
+    let started = Instant::now();
+
+    let accepting = Arc::clone(&listener);
+    thread::spawn(move || {
+        while let Ok(stream) = accepting.accept() {
+            let _ = serve_connection(stream, &snapshot, started);
+        }
+    });
+
+    Ok(Server { _listener: listener })
+}
+
+#[derive(Debug)]
+pub enum QueryError {
+    /// Nothing is listening on the endpoint.
+    NoInstance(io::Error),
+    /// The instance speaks a different protocol version.
+    ProtocolMismatch,
+    /// The instance answered with an error, or the answer was unreadable.
+    Failed(String),
+}
+
+impl QueryError {
+    /// Process exit code for the `query` subcommand.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            QueryError::NoInstance(_) => 2,
+            QueryError::ProtocolMismatch => 3,
+            QueryError::Failed(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::NoInstance(err) => write!(f, "no running instance: {}", err),
+            QueryError::ProtocolMismatch => write!(f, "running instance uses a different protocol version"),
+            QueryError::Failed(message) => write!(f, "query failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Sends `command` to the instance on `endpoint` and returns its `data`.
+pub fn query(endpoint: &str, command: QueryCommand) -> Result<serde_json::Value, QueryError> {
+    let mut stream = platform::connect(endpoint).map_err(QueryError::NoInstance)?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .map_err(|err| QueryError::Failed(err.to_string()))?;
+
+    let request = Request { version: PROTOCOL_VERSION, command };
+    let mut line = serde_json::to_string(&request).map_err(|err| QueryError::Failed(err.to_string()))?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|err| QueryError::Failed(err.to_string()))?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|err| QueryError::Failed(err.to_string()))?;
+
+    let response: Response = serde_json::from_str(&reply).map_err(|_| QueryError::ProtocolMismatch)?;
+    if response.version != PROTOCOL_VERSION {
+        return Err(QueryError::ProtocolMismatch);
+    }
+    if !response.ok {
+        return Err(QueryError::Failed(response.error.unwrap_or_default()));
+    }
+    Ok(response.data)
+}
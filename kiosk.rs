@@ -0,0 +1,248 @@
+//! `--kiosk`: for a show left running where anyone can get at the keyboard.
+//! Quitting, the settings panel, deleting and opening files are locked away;
+//! Escape brings up a PIN pad instead, and the right PIN opens a small admin
+//! menu. Too many wrong ones in a row lock the pad for a while.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Wrong PINs in a row before the pad locks.
+pub const MAX_ATTEMPTS: u32 = 3;
+/// How long the pad stays locked after them.
+pub const LOCKOUT: Duration = Duration::from_secs(30);
+const PIN_LENGTH: RangeInclusive<usize> = 4..=8;
+
+/// The digits that unlock the admin menu. Never shown or logged, not even
+/// when it is given wrong.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pin(String);
+
+impl FromStr for Pin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !PIN_LENGTH.contains(&s.len()) || !s.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("a PIN is {} to {} digits", PIN_LENGTH.start(), PIN_LENGTH.end()));
+        }
+        Ok(Pin(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for Pin {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Pin> for String {
+    fn from(Pin(pin): Pin) -> Self {
+        pin
+    }
+}
+
+impl fmt::Debug for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Pin(****)")
+    }
+}
+
+/// What the admin menu was asked to do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdminAction {
+    Quit,
+    Settings,
+    Rescan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    Closed,
+    Pad,
+    Menu,
+}
+
+pub struct KioskLock {
+    pin: Pin,
+    stage: Stage,
+    entered: String,
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl KioskLock {
+    pub fn new(pin: Pin) -> Self {
+        Self { pin, stage: Stage::Closed, entered: String::new(), failures: 0, locked_until: None }
+    }
+
+    /// Whether the pad or the menu is up, taking the keys.
+    pub fn is_open(&self) -> bool {
+        self.stage != Stage::Closed
+    }
+
+    /// Brings up the PIN pad, empty.
+    pub fn open(&mut self) {
+        self.stage = Stage::Pad;
+        self.entered.clear();
+    }
+
+    /// Puts the pad or the menu away, locking the menu again.
+    pub fn close(&mut self) {
+        self.stage = Stage::Closed;
+        self.entered.clear();
+    }
+
+    /// How long until the pad takes PINs again.
+    fn lockout_left(&self, now: Instant) -> Option<Duration> {
+        self.locked_until.map(|until| until.saturating_duration_since(now)).filter(|left| !left.is_zero())
+    }
+
+    fn press(&mut self, digit: char, now: Instant) {
+        if self.lockout_left(now).is_none() && self.entered.len() < *PIN_LENGTH.end() {
+            self.entered.push(digit);
+        }
+    }
+
+    /// Checks what was entered, opening the menu if it is the PIN.
+    fn submit(&mut self, now: Instant) {
+        if self.lockout_left(now).is_some() || self.entered.is_empty() {
+            return;
+        }
+        let entered = std::mem::take(&mut self.entered);
+        if entered == self.pin.0 {
+            tracing::info!("kiosk unlocked");
+            self.failures = 0;
+            self.stage = Stage::Menu;
+            return;
+        }
+        self.failures += 1;
+        if self.failures >= MAX_ATTEMPTS {
+            tracing::warn!(
+                "wrong kiosk PIN {} times in a row, locking the pad for {} s",
+                self.failures,
+                LOCKOUT.as_secs()
+            );
+            self.failures = 0;
+            self.locked_until = Some(now + LOCKOUT);
+        } else {
+            tracing::warn!("wrong kiosk PIN, {} attempt(s) left", MAX_ATTEMPTS - self.failures);
+        }
+    }
+
+    /// Draws the pad or the menu and takes their keys: digits, Backspace and
+    /// Enter on the pad. Returns what the menu was asked to do, which also
+    /// closes it.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<AdminAction> {
+        if self.stage == Stage::Closed {
+            return None;
+        }
+        let now = Instant::now();
+        if self.stage == Stage::Pad {
+            let (digits, backspace, enter) = ctx.input(|i| {
+                let digits: String = i
+                    .events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Text(text) => Some(text.chars().filter(char::is_ascii_digit).collect::<String>()),
+                        _ => None,
+                    })
+                    .collect();
+                (digits, i.key_pressed(egui::Key::Backspace), i.key_pressed(egui::Key::Enter))
+            });
+            for digit in digits.chars() {
+                self.press(digit, now);
+            }
+            if backspace {
+                self.entered.pop();
+            }
+            if enter {
+                self.submit(now);
+            }
+        }
+
+        let mut action = None;
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+        let area = egui::Area::new(egui::Id::new("kiosk_lock")).anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO);
+        area.show(ctx, |ui| {
+            frame.show(ui, |ui| match self.stage {
+                Stage::Pad => {
+                    ui.label("Enter the PIN");
+                    match self.lockout_left(now) {
+                        Some(left) => {
+                            ui.label(format!("Too many wrong PINs, try again in {} s", left.as_secs_f32().ceil()));
+                            ctx.request_repaint_after(Duration::from_secs(1));
+                        }
+                        None => {
+                            let dots: String = self.entered.chars().map(|_| '●').collect();
+                            ui.heading(if dots.is_empty() { " ".to_string() } else { dots });
+                            if self.failures > 0 {
+                                ui.label(format!("Wrong PIN, {} attempt(s) left", MAX_ATTEMPTS - self.failures));
+                            }
+                        }
+                    }
+                    ui.weak("Enter: unlock · Esc: cancel");
+                }
+                Stage::Menu => {
+                    ui.horizontal(|ui| {
+                        if ui.button("Quit").clicked() {
+                            action = Some(AdminAction::Quit);
+                        }
+                        if ui.button("Settings").clicked() {
+                            action = Some(AdminAction::Settings);
+                        }
+                        if ui.button("Rescan").clicked() {
+                            action = Some(AdminAction::Rescan);
+                        }
+                    });
+                    ui.weak("Esc: lock again");
+                }
+                Stage::Closed => {}
+            });
+        });
+        if action.is_some() {
+            self.close();
+        }
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enter(lock: &mut KioskLock, pin: &str, now: Instant) {
+        for digit in pin.chars() {
+            lock.press(digit, now);
+        }
+        lock.submit(now);
+    }
+
+    #[test]
+    fn wrong_pins_lock_the_pad_for_a_while() {
+        assert!("12a4".parse::<Pin>().is_err());
+        assert!("123".parse::<Pin>().is_err());
+        let pin: Pin = "1234".parse().unwrap();
+        assert_eq!(format!("{:?}", pin), "Pin(****)");
+
+        let start = Instant::now();
+        let mut lock = KioskLock::new(pin);
+        lock.open();
+        for _ in 0..MAX_ATTEMPTS {
+            enter(&mut lock, "9999", start);
+        }
+        // even the right one is refused while locked
+        enter(&mut lock, "1234", start + LOCKOUT / 2);
+        assert_eq!(lock.stage, Stage::Pad);
+
+        enter(&mut lock, "1234", start + LOCKOUT);
+        assert_eq!(lock.stage, Stage::Menu);
+        lock.close();
+        assert!(!lock.is_open());
+    }
+}
@@ -0,0 +1,70 @@
+//! Handing the artwork on screen to the desktop: O opens it in the default
+//! viewer, Shift+O shows it in the file manager. Both are off in kiosk mode,
+//! a public installation shouldn't lead into the file system.
+
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+/// The command doing it on one platform.
+#[derive(Debug, PartialEq)]
+struct Handler {
+    program: &'static str,
+    args: Vec<OsString>,
+    // explorer exits with 1 even when it worked
+    check_status: bool,
+}
+
+fn handler(os: &str, path: &Path, reveal: bool) -> Handler {
+    let (program, args, check_status) = match (os, reveal) {
+        ("macos", false) => ("open", vec![path.into()], true),
+        ("macos", true) => ("open", vec!["-R".into(), path.into()], true),
+        ("windows", false) => ("explorer", vec![path.into()], false),
+        ("windows", true) => {
+            let mut select = OsString::from("/select,");
+            select.push(path);
+            ("explorer", vec![select], false)
+        }
+        (_, false) => ("xdg-open", vec![path.into()], true),
+        // xdg-open can't select a file, the folder it is in is as close as it gets
+        (_, true) => ("xdg-open", vec![path.parent().unwrap_or(path).into()], true),
+    };
+    Handler {
+        program,
+        args,
+        check_status,
+    }
+}
+
+/// Opens `path` with the platform's default handler, or with `reveal` shows
+/// it in the file manager. Runs on a thread of its own, failures are logged.
+pub fn open(path: &Path, reveal: bool) {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let Handler {
+        program,
+        args,
+        check_status,
+    } = handler(std::env::consts::OS, &path, reveal);
+    thread::spawn(move || match Command::new(program).args(&args).status() {
+        Ok(status) if status.success() || !check_status => {}
+        Ok(status) => tracing::warn!("{} could not open {}: {}", program, path.display(), status),
+        Err(err) => tracing::warn!("cannot run {} to open {}: {}", program, path.display(), err),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_platform_has_its_handler() {
+        let path = Path::new("/art/melencolia.jpg");
+        assert_eq!(handler("macos", path, true).args, ["-R", "/art/melencolia.jpg"]);
+        assert_eq!(handler("windows", path, true).args, ["/select,/art/melencolia.jpg"]);
+        assert!(!handler("windows", path, false).check_status);
+
+        let linux = handler("linux", path, true);
+        assert_eq!((linux.program, linux.args), ("xdg-open", vec![OsString::from("/art")]));
+    }
+}
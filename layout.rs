@@ -0,0 +1,462 @@
+//! Screen geometry shared by everything that draws a slide.
+
+use crate::mat::MatSettings;
+use egui::{Pos2, Rect, Vec2, pos2, vec2};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How the artwork is scaled to the screen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Fit {
+    /// Whole image visible, the background shows around it.
+    #[default]
+    Contain,
+    /// Fills the screen, the overflowing edges are cropped.
+    Cover,
+}
+
+impl FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            other => Err(format!("expected contain or cover, got {:?}", other)),
+        }
+    }
+}
+
+/// Largest rect with the image's aspect ratio that fits the screen, centred.
+pub fn contain(image_size: Vec2, screen_size: Vec2) -> Rect {
+    let scale_x = screen_size.x / image_size.x;
+    let scale_y = screen_size.y / image_size.y;
+    let scale = scale_x.min(scale_y);
+
+    let display_width = image_size.x * scale;
+    let display_height = image_size.y * scale;
+
+    let x_offset = (screen_size.x - display_width) / 2.0;
+    let y_offset = (screen_size.y - display_height) / 2.0;
+
+    Rect::from_min_size(pos2(x_offset, y_offset), vec2(display_width, display_height))
+}
+
+/// `rect`, from [`contain`] for an image of `image_size`, shrunk to the largest
+/// whole multiple of the image's pixels that fits on screen pixels, so pixel
+/// art stays crisp. Left as it is when the image has to be scaled down.
+pub fn whole_pixels(rect: Rect, image_size: Vec2, pixels_per_point: f32) -> Rect {
+    let scale = (rect.width() * pixels_per_point / image_size.x).floor();
+    if scale < 1.0 {
+        return rect;
+    }
+    let size = image_size * scale / pixels_per_point;
+    let min = ((rect.center() - size / 2.0) * pixels_per_point).round() / pixels_per_point;
+    Rect::from_min_size(min, size)
+}
+
+/// Part of the image, in texture coordinates, that fills the screen in cover mode.
+/// The crop is symmetric on whichever axis overflows.
+pub fn cover_uv(image_size: Vec2, screen_size: Vec2) -> Rect {
+    let scale_x = screen_size.x / image_size.x;
+    let scale_y = screen_size.y / image_size.y;
+    let scale = scale_x.max(scale_y);
+
+    let visible = vec2(screen_size.x / (image_size.x * scale), screen_size.y / (image_size.y * scale));
+    Rect::from_center_size(pos2(0.5, 0.5), visible)
+}
+
+/// Texture coordinates of the whole image.
+pub const FULL_UV: Rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+
+/// Where an artwork of `image_size` goes on the screen and the part of it that
+/// shows there: the whole of it contained, on `mat` if it has one, or the part
+/// that covers the screen. Contained pixel art keeps whole multiples of its pixels.
+pub fn place_artwork(
+    fit: Fit,
+    image_size: Vec2,
+    screen_size: Vec2,
+    mat: Option<&MatSettings>,
+    pixel_art: bool,
+    pixels_per_point: f32,
+) -> (Rect, Rect) {
+    let (rect, uv) = match (fit, mat) {
+        (Fit::Contain, Some(mat)) => (mat.image_rect(image_size, screen_size), FULL_UV),
+        (Fit::Contain, None) => (contain(image_size, screen_size), FULL_UV),
+        (Fit::Cover, _) => (Rect::from_min_size(Pos2::ZERO, screen_size), cover_uv(image_size, screen_size)),
+    };
+    if pixel_art && fit == Fit::Contain { (whole_pixels(rect, image_size, pixels_per_point), uv) } else { (rect, uv) }
+}
+
+/// Magnification of the slide on screen: a point `p` of the unzoomed layout
+/// is drawn at `p * scale + offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zoom {
+    pub scale: f32,
+    pub offset: Vec2,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self { scale: 1.0, offset: Vec2::ZERO }
+    }
+}
+
+impl Zoom {
+    pub fn is_zoomed(&self) -> bool {
+        self.scale > 1.0
+    }
+
+    pub fn apply(&self, rect: Rect) -> Rect {
+        let map = |p: Pos2| (p.to_vec2() * self.scale + self.offset).to_pos2();
+        Rect::from_min_max(map(rect.min), map(rect.max))
+    }
+
+    /// Scales by `factor`, staying within 1 and `max_scale`, with the point under `anchor` kept there.
+    pub fn zoom_at(&mut self, factor: f32, anchor: Pos2, max_scale: f32) {
+        let scale = (self.scale * factor).clamp(1.0, max_scale.max(1.0));
+        let unzoomed = (anchor.to_vec2() - self.offset) / self.scale;
+        self.offset = anchor.to_vec2() - unzoomed * scale;
+        self.scale = scale;
+    }
+
+    /// Keeps the zoomed `image` on a screen of `screen_size`: along an axis it
+    /// overflows none of its edges come into view, along one it doesn't it is centred.
+    pub fn clamp(&mut self, image: Rect, screen_size: Vec2) {
+        let zoomed = self.apply(image);
+        for axis in 0..2 {
+            let (min, max, size) = (zoomed.min[axis], zoomed.max[axis], screen_size[axis]);
+            self.offset[axis] += if max - min <= size {
+                (size - min - max) / 2.0
+            } else if min > 0.0 {
+                -min
+            } else if max < size {
+                size - max
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+/// How many artworks a slide shows.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SlideLayout {
+    /// One artwork per slide.
+    #[default]
+    Single,
+    /// Four artworks in two rows of two.
+    Grid2x2,
+    /// Nine artworks in three rows of three.
+    Grid3x3,
+}
+
+impl FromStr for SlideLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(SlideLayout::Single),
+            "grid2x2" => Ok(SlideLayout::Grid2x2),
+            "grid3x3" => Ok(SlideLayout::Grid3x3),
+            other => Err(format!("expected single, grid2x2 or grid3x3, got {:?}", other)),
+        }
+    }
+}
+
+impl SlideLayout {
+    /// Cells across and down.
+    pub fn grid(self) -> (usize, usize) {
+        match self {
+            SlideLayout::Single => (1, 1),
+            SlideLayout::Grid2x2 => (2, 2),
+            SlideLayout::Grid3x3 => (3, 3),
+        }
+    }
+
+    /// Artworks on a full slide, and so how far playback moves per slide.
+    pub fn cells(self) -> usize {
+        let (columns, rows) = self.grid();
+        columns * rows
+    }
+}
+
+/// The cells of a `columns` × `rows` grid covering `size`, row by row, with
+/// `gap` between them and around the edge.
+pub fn grid_cells(size: Vec2, (columns, rows): (usize, usize), gap: f32) -> Vec<Rect> {
+    let cell = vec2(
+        (size.x - gap * (columns + 1) as f32) / columns as f32,
+        (size.y - gap * (rows + 1) as f32) / rows as f32,
+    );
+    (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let min = pos2(gap + column as f32 * (cell.x + gap), gap + row as f32 * (cell.y + gap));
+            Rect::from_min_size(min, cell.max(Vec2::ZERO))
+        })
+        .collect()
+}
+
+/// A screen corner an overlay can sit in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    /// Clockwise from the top left.
+    const CLOCKWISE: [Corner; 4] = [Corner::TopLeft, Corner::TopRight, Corner::BottomRight, Corner::BottomLeft];
+}
+
+impl FromStr for Corner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-right" => Ok(Corner::BottomRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            other => Err(format!("expected top-left, top-right, bottom-right or bottom-left, got {:?}", other)),
+        }
+    }
+}
+
+/// Hands out corners so overlays never end up on top of each other.
+#[derive(Default)]
+pub struct Corners {
+    taken: Vec<Corner>,
+}
+
+impl Corners {
+    /// `wanted` if it is free, otherwise the next free corner clockwise.
+    /// `None` once all four are taken.
+    pub fn claim(&mut self, wanted: Corner) -> Option<Corner> {
+        let start = Corner::CLOCKWISE.iter().position(|corner| *corner == wanted).unwrap_or(0);
+        let corner = (0..4)
+            .map(|offset| Corner::CLOCKWISE[(start + offset) % 4])
+            .find(|corner| !self.taken.contains(corner))?;
+        self.taken.push(corner);
+        Some(corner)
+    }
+}
+
+/// Rect of `size` tucked into `corner`, `margin` away from both edges.
+pub fn corner_rect(corner: Corner, size: Vec2, screen_size: Vec2, margin: f32) -> Rect {
+    let x = match corner {
+        Corner::TopLeft | Corner::BottomLeft => margin,
+        Corner::TopRight | Corner::BottomRight => screen_size.x - margin - size.x,
+    };
+    let y = match corner {
+        Corner::TopLeft | Corner::TopRight => margin,
+        Corner::BottomLeft | Corner::BottomRight => screen_size.y - margin - size.y,
+    };
+    Rect::from_min_size(pos2(x, y), size)
+}
+
+/// Where the caption sits on screen.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionPosition {
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+    BottomCenter,
+}
+
+impl FromStr for CaptionPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bottom-left" => Ok(CaptionPosition::BottomLeft),
+            "bottom-right" => Ok(CaptionPosition::BottomRight),
+            "top-left" => Ok(CaptionPosition::TopLeft),
+            "top-right" => Ok(CaptionPosition::TopRight),
+            "bottom-center" => Ok(CaptionPosition::BottomCenter),
+            other => Err(format!(
+                "expected bottom-left, bottom-right, top-left, top-right or bottom-center, got {:?}",
+                other
+            )),
+        }
+    }
+}
+
+impl CaptionPosition {
+    pub fn is_top(self) -> bool {
+        matches!(self, CaptionPosition::TopLeft | CaptionPosition::TopRight)
+    }
+
+    /// Horizontal anchor: 0 for the left edge, 0.5 centred, 1 for the right edge.
+    pub fn anchor(self) -> f32 {
+        match self {
+            CaptionPosition::BottomLeft | CaptionPosition::TopLeft => 0.0,
+            CaptionPosition::BottomCenter => 0.5,
+            CaptionPosition::BottomRight | CaptionPosition::TopRight => 1.0,
+        }
+    }
+
+    /// Screen area the caption roughly covers, as `[x0, y0, x1, y1]` fractions.
+    pub fn region(self, full_width: bool) -> [f32; 4] {
+        let (y0, y1) = if self.is_top() { (0.0, 0.2) } else { (0.8, 1.0) };
+        if full_width {
+            return [0.0, y0, 1.0, y1];
+        }
+        let x0 = 0.6 * self.anchor();
+        [x0, y0, x0 + 0.4, y1]
+    }
+
+    /// The corner across the screen from the caption.
+    pub fn opposite(self) -> Corner {
+        match self {
+            CaptionPosition::BottomLeft | CaptionPosition::BottomCenter => Corner::TopRight,
+            CaptionPosition::BottomRight => Corner::TopLeft,
+            CaptionPosition::TopLeft => Corner::BottomRight,
+            CaptionPosition::TopRight => Corner::BottomLeft,
+        }
+    }
+
+    /// Corners the caption keeps other overlays out of. A `full_width` strip
+    /// takes both corners of its edge, a centred box neither.
+    pub fn corners(self, full_width: bool) -> Vec<Corner> {
+        match (self, full_width) {
+            (position, true) if position.is_top() => vec![Corner::TopLeft, Corner::TopRight],
+            (_, true) => vec![Corner::BottomLeft, Corner::BottomRight],
+            (CaptionPosition::BottomLeft, false) => vec![Corner::BottomLeft],
+            (CaptionPosition::BottomRight, false) => vec![Corner::BottomRight],
+            (CaptionPosition::TopLeft, false) => vec![Corner::TopLeft],
+            (CaptionPosition::TopRight, false) => vec![Corner::TopRight],
+            (CaptionPosition::BottomCenter, false) => vec![],
+        }
+    }
+}
+
+/// Backdrop of a caption of `size` at `position`, `margin` away from the
+/// edges. `full_width` makes it a strip across the screen along its edge.
+pub fn caption_rect(position: CaptionPosition, size: Vec2, screen_size: Vec2, margin: f32, full_width: bool) -> Rect {
+    let y = if position.is_top() { 0.0 } else { screen_size.y - size.y };
+    if full_width {
+        return Rect::from_min_size(pos2(0.0, y), vec2(screen_size.x, size.y));
+    }
+    let y = if position.is_top() { margin } else { y - margin };
+    let x = margin + (screen_size.x - 2.0 * margin - size.x) * position.anchor();
+    Rect::from_min_size(pos2(x, y), size)
+}
+
+/// Fraction of `rect` covered by `other`.
+pub fn coverage(rect: Rect, other: Rect) -> f32 {
+    let area = rect.area();
+    if area <= 0.0 {
+        return 0.0;
+    }
+    let overlap = rect.intersect(other);
+    if overlap.is_positive() {
+        overlap.area() / area
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_keeps_the_pointer_on_its_spot() {
+        let screen = vec2(1920.0, 1080.0);
+        let image = contain(vec2(1000.0, 1000.0), screen);
+        let mut zoom = Zoom::default();
+        let anchor = pos2(1200.0, 300.0);
+        let before = (anchor - zoom.apply(image).min) / zoom.apply(image).size();
+
+        zoom.zoom_at(3.0, anchor, 2.0);
+        assert_eq!(zoom.scale, 2.0);
+        let after = (anchor - zoom.apply(image).min) / zoom.apply(image).size();
+        assert!((before - after).length() < 1e-5);
+
+        // panned far off, the image comes back to the edge it left
+        zoom.offset.x += 5000.0;
+        zoom.clamp(image, screen);
+        let zoomed = zoom.apply(image);
+        assert_eq!((zoomed.min.x, zoomed.max.x), (0.0, 2160.0));
+        assert!(zoomed.min.y <= 0.0 && zoomed.max.y >= 1080.0);
+
+        zoom.zoom_at(0.1, anchor, 2.0);
+        assert!(!zoom.is_zoomed());
+    }
+
+    #[test]
+    fn corners_are_never_shared() {
+        let mut corners = Corners::default();
+        assert_eq!(corners.claim(Corner::BottomLeft), Some(Corner::BottomLeft));
+        assert_eq!(corners.claim(Corner::TopRight), Some(Corner::TopRight));
+        // taken, so the next one clockwise
+        assert_eq!(corners.claim(Corner::BottomLeft), Some(Corner::TopLeft));
+        assert_eq!(corners.claim(Corner::TopRight), Some(Corner::BottomRight));
+        assert_eq!(corners.claim(Corner::TopLeft), None);
+    }
+
+    #[test]
+    fn caption_positions() {
+        let screen = vec2(1920.0, 1080.0);
+        let size = vec2(600.0, 100.0);
+
+        let rect = caption_rect(CaptionPosition::TopRight, size, screen, 15.0, false);
+        assert_eq!(rect, Rect::from_min_size(pos2(1305.0, 15.0), size));
+        let rect = caption_rect(CaptionPosition::BottomCenter, size, screen, 15.0, false);
+        assert_eq!(rect, Rect::from_min_size(pos2(660.0, 965.0), size));
+        let rect = caption_rect(CaptionPosition::BottomLeft, size, screen, 15.0, true);
+        assert_eq!(rect, Rect::from_min_max(pos2(0.0, 980.0), pos2(1920.0, 1080.0)));
+
+        assert_eq!(CaptionPosition::TopLeft.corners(true), [Corner::TopLeft, Corner::TopRight]);
+        assert!(CaptionPosition::BottomCenter.corners(false).is_empty());
+    }
+
+    #[test]
+    fn grid_cells_leave_even_gaps() {
+        let cells = grid_cells(vec2(1000.0, 500.0), SlideLayout::Grid2x2.grid(), 10.0);
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells[0], Rect::from_min_size(pos2(10.0, 10.0), vec2(485.0, 235.0)));
+        assert_eq!(cells[3].max, pos2(990.0, 490.0));
+        assert_eq!("grid3x3".parse::<SlideLayout>().map(SlideLayout::cells), Ok(9));
+    }
+
+    #[test]
+    fn pixel_art_scales_by_whole_multiples() {
+        let screen = vec2(1920.0, 1080.0);
+        let sprite = vec2(64.0, 48.0);
+        let rect = whole_pixels(contain(sprite, screen), sprite, 1.0);
+        // 22.5 times would fit, 22 do
+        assert_eq!(rect.size(), vec2(1408.0, 1056.0));
+        assert_eq!(rect.min, pos2(256.0, 12.0));
+        // on a 2x screen it's whole screen pixels that count, 45 of them to an image pixel
+        assert_eq!(whole_pixels(contain(sprite, screen), sprite, 2.0).size(), vec2(1440.0, 1080.0));
+
+        let large = vec2(4000.0, 3000.0);
+        assert_eq!(whole_pixels(contain(large, screen), large, 1.0), contain(large, screen));
+    }
+
+    #[test]
+    fn cover_crops_the_overflowing_axis() {
+        let screen = vec2(1920.0, 1080.0);
+
+        // wider than the screen: the sides go
+        let uv = cover_uv(vec2(3000.0, 1000.0), screen);
+        assert!((uv.height() - 1.0).abs() < 1e-6);
+        assert!((uv.width() - 1920.0 / 3240.0).abs() < 1e-6);
+        assert!((uv.center().x - 0.5).abs() < 1e-6);
+
+        // taller: top and bottom go
+        let uv = cover_uv(vec2(1000.0, 2000.0), screen);
+        assert!((uv.width() - 1.0).abs() < 1e-6);
+        assert!(uv.height() < 1.0);
+    }
+}
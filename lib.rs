@@ -0,0 +1,58 @@
+pub mod anniversary;
+pub mod app;
+pub mod builder;
+pub mod burnin;
+pub mod chapters;
+pub mod chime;
+pub mod cli;
+pub mod collections;
+pub mod commons;
+pub mod config;
+pub mod control;
+pub mod dedupe;
+pub mod editor;
+pub mod ending;
+pub mod favorites;
+pub mod filmstrip;
+pub mod filter;
+pub mod fonts;
+pub mod http;
+pub mod idle;
+pub mod ignore;
+pub mod ipc;
+pub mod kiosk;
+pub mod launch;
+pub mod layout;
+pub mod logging;
+pub mod mat;
+pub mod media;
+pub mod merge;
+pub mod mqtt;
+pub mod music;
+pub mod narrate;
+pub mod night;
+pub mod overlay;
+pub mod persist;
+pub mod pipeline;
+pub mod power;
+pub mod preprocess;
+pub mod ratings;
+pub mod remote;
+pub mod render;
+pub mod rotation;
+pub mod scan;
+pub mod scheduler;
+pub mod scr;
+pub mod search;
+pub mod settings;
+pub mod shuffle;
+pub mod signals;
+pub mod source;
+pub mod spotlight;
+pub mod stats;
+pub mod systemd;
+pub mod transition;
+pub mod trash;
+pub mod webhook;
+pub mod wikidata;
+pub mod window;
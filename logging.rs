@@ -0,0 +1,34 @@
+//! Log output on stderr, and optionally in a file rotated every day.
+
+use std::path::Path;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Installs the global logger. `verbose` adds debug output such as per-image
+/// timings; `RUST_LOG` overrides both levels when set.
+///
+/// The returned guard flushes the log file when dropped, keep it alive until exit.
+pub fn init(verbose: bool, log_file: Option<&Path>) -> Option<WorkerGuard> {
+    let default = if verbose { "warn,art_slideshow=debug" } else { "warn,art_slideshow=info" };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+
+    let (file_layer, guard) = match log_file {
+        Some(path) => {
+            let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let name = path.file_name().unwrap_or("art-slideshow.log".as_ref());
+            // files are named `<name>.<date>`
+            let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(dir, name));
+            (Some(fmt::layer().with_writer(writer).with_ansi(false)), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .init();
+    guard
+}
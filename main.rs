@@ -1,410 +1,382 @@
-use eframe::egui;
-use image::{DynamicImage, GenericImageView, imageops};
-use serde::Deserialize;
-use std::fs;
-use std::path::PathBuf;
-use std::time::{Duration, Instant};
-use std::thread;
-use std::sync::{Arc, Mutex};
-
-#[derive(Deserialize, Debug, Clone)]
-struct ArtworkMetadata {
-    title: String,
-    artist: String,
-    year: String,
-}
-
-#[derive(Clone)]
-struct ArtworkInfo {
-    path: PathBuf,
-    metadata: ArtworkMetadata,
-}
-
-#[derive(Clone)]
-struct ProcessedImage {
-    main_image: DynamicImage,
-    blurred_image: DynamicImage,
-    metadata: ArtworkMetadata,
-}
-
-struct LoadedArtwork {
-    texture: egui::TextureHandle,
-    blurred_texture: egui::TextureHandle,
-    metadata: ArtworkMetadata,
-}
-
-struct ArtSlideshowApp {
-    artworks: Vec<ArtworkInfo>,
-    current_index: usize,
-    current_processed: Option<ProcessedImage>,
-    next_processed: Arc<Mutex<Option<ProcessedImage>>>,
-    current_textures: Option<LoadedArtwork>,
-    last_change: Instant,
-    slide_duration: Duration,
-    loading_next: bool,
-}
-
-impl ArtSlideshowApp {
-    fn new(folder_path: PathBuf) -> Self {
-        let mut artworks = Vec::new();
-        let entries = fs::read_dir(&folder_path).expect("Directory cannot be read");
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                let ext = ext.to_string_lossy().to_lowercase();
-                if matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "bmp" | "gif") {
-                    let json_path = path.with_extension("json");
-                    
-                    let metadata = if json_path.exists() {
-                        fs::read_to_string(&json_path)
-                            .ok()
-                            .and_then(|content| serde_json::from_str(&content).ok())
-                            .unwrap_or_else(|| ArtworkMetadata {
-                                title: "Unknown".to_string(),
-                                artist: "Unknown".to_string(),
-                                year: "Unknown".to_string(),
-                            })
-                    } else {
-                        ArtworkMetadata {
-                            title: path.file_stem()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string(),
-                            artist: "Unknown".to_string(),
-                            year: "Unknown".to_string(),
+use art_slideshow::app::ArtSlideshowApp;
+use art_slideshow::cli;
+use art_slideshow::fonts;
+use art_slideshow::ipc::{self, QueryCommand};
+use art_slideshow::logging;
+use art_slideshow::overlay::OVERLAY_MODE_KEY;
+use art_slideshow::render;
+use art_slideshow::scan;
+#[cfg(windows)]
+use art_slideshow::scr;
+use art_slideshow::stats;
+use art_slideshow::webhook;
+use art_slideshow::wikidata::{self, Fetcher};
+use art_slideshow::window;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const FETCH_USAGE: &str = "usage: art-slideshow fetch FOLDER [--dry-run]";
+/// Failed lookups in a row after which Wikidata is taken to be unreachable.
+const FETCH_GIVE_UP_AFTER: u32 = 3;
+const RENDER_USAGE: &str = "usage: art-slideshow render FOLDER --out DIR [--size 1920x1080] [slideshow options]";
+const QUERY_USAGE: &str = "usage: art-slideshow query <status|current|list-collections|stats> [--json] [--socket PATH]";
+
+/// Prints a response object as `key: value` lines for humans.
+fn print_plain(data: &serde_json::Value) {
+    match data.as_object() {
+        Some(fields) => {
+            for (key, value) in fields {
+                match value {
+                    serde_json::Value::String(text) => println!("{}: {}", key, text),
+                    serde_json::Value::Null => println!("{}: -", key),
+                    serde_json::Value::Array(items) => {
+                        println!("{}:", key);
+                        for item in items {
+                            println!("  {}", item.as_str().map(str::to_string).unwrap_or_else(|| item.to_string()));
                         }
-                    };
-
-                    artworks.push(ArtworkInfo { path, metadata });
+                    }
+                    other => println!("{}: {}", key, other),
                 }
             }
         }
+        None => println!("{}", data),
+    }
+}
 
-        Self {
-            artworks,
-            current_index: 0,
-            current_processed: None,
-            next_processed: Arc::new(Mutex::new(None)),
-            current_textures: None,
-            last_change: Instant::now(),
-            slide_duration: Duration::from_secs(10), // CHANGE TIME VALUE
-            loading_next: false,
+fn run_query(args: &[String]) -> ExitCode {
+    let mut command = None;
+    let mut json = false;
+    let mut endpoint = ipc::default_endpoint();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--socket" => match args.next() {
+                Some(path) => endpoint = path.clone(),
+                None => {
+                    eprintln!("{}", QUERY_USAGE);
+                    return ExitCode::from(1);
+                }
+            },
+            name => match QueryCommand::parse(name) {
+                Some(parsed) if command.is_none() => command = Some(parsed),
+                _ => {
+                    eprintln!("{}", QUERY_USAGE);
+                    return ExitCode::from(1);
+                }
+            },
         }
     }
 
-    fn process_image(path: &PathBuf, metadata: ArtworkMetadata) -> Option<ProcessedImage> {
-        if let Ok(img) = image::open(path) {
-            // image processing
-            let (img_width, img_height) = img.dimensions();
-            let max_dimension = 2048;
-            let scale = if img_width.max(img_height) > max_dimension {
-                max_dimension as f32 / img_width.max(img_height) as f32
+    let Some(command) = command else {
+        eprintln!("{}", QUERY_USAGE);
+        return ExitCode::from(1);
+    };
+
+    match ipc::query(&endpoint, command) {
+        Ok(data) => {
+            if json {
+                println!("{}", data);
             } else {
-                1.0
-            };
-            
-            let new_width = (img_width as f32 * scale) as u32;
-            let new_height = (img_height as f32 * scale) as u32;
-            let main_image = img.resize_exact(new_width, new_height, imageops::FilterType::Lanczos3);
-            
-            // background blur
-            let blur_width = 640;
-            let blur_height = 360;
-            
-            let blurred_small = img.resize_to_fill(blur_width, blur_height, imageops::FilterType::Lanczos3);
-            let mut blurred = blurred_small.to_rgba8();
-            
-            // Multi-pass blur 
-            for _ in 0..3 {
-                blurred = Self::fast_box_blur(&blurred, 10);
-            }
-            
-            // darken
-            for pixel in blurred.pixels_mut() {
-                pixel[0] = (pixel[0] as f32 * 0.6) as u8;
-                pixel[1] = (pixel[1] as f32 * 0.6) as u8;
-                pixel[2] = (pixel[2] as f32 * 0.6) as u8;
+                print_plain(&data);
             }
-            
-            let blurred_image = DynamicImage::ImageRgba8(blurred);
-            
-            return Some(ProcessedImage {
-                main_image,
-                blurred_image,
-                metadata,
-            });
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(err.exit_code() as u8)
         }
-        None
     }
+}
 
-    fn fast_box_blur(img: &image::ImageBuffer<image::Rgba<u8>, Vec<u8>>, radius: i32) -> image::ImageBuffer<image::Rgba<u8>, Vec<u8>> {
-        let (width, height) = img.dimensions();
-        let mut output = img.clone();
-        
-        // Horizontal pass
-        for y in 0..height {
-            for x in 0..width {
-                let mut r = 0u32;
-                let mut g = 0u32;
-                let mut b = 0u32;
-                let mut count = 0u32;
-                
-                for dx in -radius..=radius {
-                    let nx = (x as i32 + dx).clamp(0, width as i32 - 1) as u32;
-                    let pixel = img.get_pixel(nx, y);
-                    r += pixel[0] as u32;
-                    g += pixel[1] as u32;
-                    b += pixel[2] as u32;
-                    count += 1;
-                }
-                
-                let pixel = output.get_pixel_mut(x, y);
-                pixel[0] = (r / count) as u8;
-                pixel[1] = (g / count) as u8;
-                pixel[2] = (b / count) as u8;
-            }
+/// `--validate`: lists every sidecar problem in `folder`, failing if there are any.
+fn run_validate(folder: &Path) -> ExitCode {
+    match scan::validate_folder(folder) {
+        Ok(problems) if problems.is_empty() => {
+            println!("{}: all sidecars ok", folder.display());
+            ExitCode::SUCCESS
         }
-        
-        let temp = output.clone();
-        
-        // Vertical pass
-        for y in 0..height {
-            for x in 0..width {
-                let mut r = 0u32;
-                let mut g = 0u32;
-                let mut b = 0u32;
-                let mut count = 0u32;
-                
-                for dy in -radius..=radius {
-                    let ny = (y as i32 + dy).clamp(0, height as i32 - 1) as u32;
-                    let pixel = temp.get_pixel(x, ny);
-                    r += pixel[0] as u32;
-                    g += pixel[1] as u32;
-                    b += pixel[2] as u32;
-                    count += 1;
-                }
-                
-                let pixel = output.get_pixel_mut(x, y);
-                pixel[0] = (r / count) as u8;
-                pixel[1] = (g / count) as u8;
-                pixel[2] = (b / count) as u8;
+        Ok(problems) => {
+            for problem in &problems {
+                println!("{}", problem);
             }
+            println!("{} problem(s) found", problems.len());
+            ExitCode::from(1)
         }
-        
-        output
-    }
-
-    fn load_next_in_background(&mut self) {
-        if self.loading_next || self.artworks.len() <= 1 {
-            return;
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", folder.display(), err);
+            ExitCode::from(1)
         }
-
-        let next_index = (self.current_index + 1) % self.artworks.len();
-        let next_info = self.artworks[next_index].clone();
-        let next_processed = Arc::clone(&self.next_processed);
-        
-        self.loading_next = true;
-        
-        thread::spawn(move || {
-            if let Some(processed) = Self::process_image(&next_info.path, next_info.metadata) {
-                let mut next = next_processed.lock().unwrap();
-                *next = Some(processed);
-            }
-        });
     }
+}
 
-    fn create_textures(ctx: &egui::Context, processed: &ProcessedImage, prefix: &str) -> LoadedArtwork {
-        let texture = Self::image_to_texture(ctx, &processed.main_image, &format!("{}_main", prefix));
-        let blurred_texture = Self::image_to_texture(ctx, &processed.blurred_image, &format!("{}_blur", prefix));
-        
-        LoadedArtwork {
-            texture,
-            blurred_texture,
-            metadata: processed.metadata.clone(),
+/// `--stats-summary`: screen time per artwork over a whole stats file.
+fn run_stats_summary(path: &Path) -> ExitCode {
+    let summary = File::open(path).and_then(|file| stats::summarize(BufReader::new(file)));
+    let (totals, skipped) = match summary {
+        Ok(summary) => summary,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", path.display(), err);
+            return ExitCode::from(1);
         }
-    }
-
-    fn image_to_texture(
-        ctx: &egui::Context,
-        image: &DynamicImage,
-        name: &str,
-    ) -> egui::TextureHandle {
-        let size = [image.width() as usize, image.height() as usize];
-        let pixels = image.to_rgba8();
-        let pixels_flat: Vec<_> = pixels.as_flat_samples().as_slice().to_vec();
-
-        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &pixels_flat);
+    };
 
-        ctx.load_texture(name, color_image, egui::TextureOptions::LINEAR)
+    println!("{:>6}  {:>9}  artwork", "shown", "time");
+    for total in &totals {
+        println!(
+            "{:>6}  {:>9}  {} - {} ({})",
+            total.times_shown,
+            stats::format_seconds(total.seconds),
+            total.artist,
+            total.title,
+            total.path.display()
+        );
+    }
+    let seconds = totals.iter().map(|total| total.seconds).sum();
+    println!("{} artwork(s), {} in total", totals.len(), stats::format_seconds(seconds));
+    if skipped > 0 {
+        eprintln!("skipped {} line(s) that don't parse", skipped);
     }
+    ExitCode::SUCCESS
 }
 
-impl eframe::App for ArtSlideshowApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.artworks.is_empty() {
-            egui::CentralPanel::default().show(ctx, |ui| {
-                ui.centered_and_justified(|ui| {
-                    ui.heading("No images found in folder.");
-                });
-            });
-            return;
-        }
-
-        // load first image
-        if self.current_processed.is_none() {
-            let current_info = &self.artworks[self.current_index];
-            self.current_processed = Self::process_image(&current_info.path, current_info.metadata.clone());
-            
-            if let Some(processed) = &self.current_processed {
-                self.current_textures = Some(Self::create_textures(ctx, processed, "current"));
+/// `fetch`: writes sidecars from Wikidata for the images in a folder that have none.
+fn run_fetch(args: &[String]) -> ExitCode {
+    let mut folder = None;
+    let mut dry_run = false;
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            path if !path.starts_with("--") && folder.is_none() => folder = Some(Path::new(path)),
+            _ => {
+                eprintln!("{}", FETCH_USAGE);
+                return ExitCode::from(1);
             }
-            
-            // Start loading next in background
-            self.load_next_in_background();
         }
+    }
+    let Some(folder) = folder else {
+        eprintln!("{}", FETCH_USAGE);
+        return ExitCode::from(1);
+    };
 
-        // verify if the next image had beed loaded
-        if self.loading_next {
-            let next_lock = self.next_processed.lock().unwrap();
-            if next_lock.is_some() {
-                self.loading_next = false;
-            }
+    let images = match wikidata::missing_sidecars(folder) {
+        Ok(images) => images,
+        Err(err) => {
+            eprintln!("error: cannot read {}: {}", folder.display(), err);
+            return ExitCode::from(1);
         }
+    };
 
-        // Auto-advance slideshow only if it s done
-        if self.last_change.elapsed() >= self.slide_duration {
-            let next_lock = self.next_processed.lock().unwrap();
-            
-            if next_lock.is_some() {
-                // index ++
-                self.current_index = (self.current_index + 1) % self.artworks.len();
-                self.last_change = Instant::now();
-                
-                self.current_processed = next_lock.clone();
-                drop(next_lock);
-                
-                if let Some(processed) = &self.current_processed {
-                    self.current_textures = Some(Self::create_textures(ctx, processed, "current"));
+    let mut fetcher = Fetcher::new();
+    let mut written = 0;
+    let mut failures_in_a_row = 0;
+    for image in &images {
+        let name = image.file_name().unwrap_or_default().to_string_lossy();
+        let stem = image.file_stem().unwrap_or_default().to_string_lossy();
+        match fetcher.lookup(&stem) {
+            Ok(Some(found)) => {
+                failures_in_a_row = 0;
+                let saved = if dry_run { Ok(()) } else { wikidata::write_sidecar(image, &found) };
+                match saved {
+                    Ok(()) => {
+                        println!("{}: {}, {}, {}", name, found.title, found.artist, found.year);
+                        written += 1;
+                    }
+                    Err(err) => println!("{}: cannot write sidecar: {}", name, err),
                 }
-                
-                // remove next and load after
-                {
-                    let mut next = self.next_processed.lock().unwrap();
-                    *next = None;
+            }
+            Ok(None) => {
+                failures_in_a_row = 0;
+                println!("{}: no confident match", name);
+            }
+            Err(err) => {
+                println!("{}: lookup failed: {}", name, err);
+                failures_in_a_row += 1;
+                if failures_in_a_row == FETCH_GIVE_UP_AFTER {
+                    eprintln!("giving up, Wikidata looks unreachable");
+                    break;
                 }
-                
-                self.loading_next = false;
-                self.load_next_in_background();
             }
         }
+    }
 
-        // Render
-        if let Some(loaded) = &self.current_textures {
-            egui::CentralPanel::default()
-                .frame(egui::Frame::none().fill(egui::Color32::BLACK))
-                .show(ctx, |ui| {
-                    let screen_size = ui.available_size();
-
-                    // Background blur FILL
-                    let img = egui::Image::new(&loaded.blurred_texture)
-                        .fit_to_exact_size(screen_size)
-                        .maintain_aspect_ratio(false);
-                    
-                    ui.put(
-                        egui::Rect::from_min_size(egui::pos2(0.0, 0.0), screen_size),
-                        img,
-                    );
-
-                    // image centred
-                    let texture_size = loaded.texture.size();
-                    let img_width = texture_size[0] as f32;
-                    let img_height = texture_size[1] as f32;
-                    
-                    let scale_x = screen_size.x / img_width;
-                    let scale_y = screen_size.y / img_height;
-                    let scale = scale_x.min(scale_y);
-
-                    let display_width = img_width * scale;
-                    let display_height = img_height * scale;
-
-                    let x_offset = (screen_size.x - display_width) / 2.0;
-                    let y_offset = (screen_size.y - display_height) / 2.0;
-
-                    ui.put(
-                        egui::Rect::from_min_size(
-                            egui::pos2(x_offset, y_offset),
-                            egui::vec2(display_width, display_height),
-                        ),
-                        egui::Image::new(&loaded.texture)
-                            .fit_to_exact_size(egui::vec2(display_width, display_height)),
-                    );
-
-                    // Text overlay
-                    let text_margin = 30.0;
-                    let text_y_base = screen_size.y - 120.0;
-
-                    ui.painter().rect_filled(
-                        egui::Rect::from_min_size(
-                            egui::pos2(text_margin - 15.0, text_y_base - 15.0),
-                            egui::vec2(700.0, 110.0),
-                        ),
-                        8.0,
-                        egui::Color32::from_black_alpha(200),
-                    );
+    let verb = if dry_run { "found" } else { "wrote" };
+    println!("{} {} sidecar(s) for {} image(s) without one", verb, written, images.len());
+    ExitCode::SUCCESS
+}
 
-                    let line1 = format!("{} - {}", loaded.metadata.title, loaded.metadata.artist);
-                    
-                    ui.put(
-                        egui::Rect::from_min_size(
-                            egui::pos2(text_margin, text_y_base),
-                            egui::vec2(650.0, 40.0),
-                        ),
-                        egui::Label::new(
-                            egui::RichText::new(&line1)
-                                .size(26.0)
-                                .color(egui::Color32::WHITE)
-                                .family(egui::FontFamily::Proportional),
-                        ),
-                    );
+/// `render`: writes the slides as PNG files, without a window. Fails if any slide does.
+fn run_render(args: &[String]) -> ExitCode {
+    let mut out = None;
+    let mut size = render::DEFAULT_SIZE;
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(path) => out = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("{}", RENDER_USAGE);
+                    return ExitCode::from(1);
+                }
+            },
+            "--size" => match args.next().map(|value| render::parse_size(value)) {
+                Some(Ok(parsed)) => size = parsed,
+                Some(Err(err)) => {
+                    eprintln!("error: invalid value for --size: {}", err);
+                    return ExitCode::from(1);
+                }
+                None => {
+                    eprintln!("{}", RENDER_USAGE);
+                    return ExitCode::from(1);
+                }
+            },
+            _ => rest.push(arg.clone()),
+        }
+    }
+    let Some(out) = out else {
+        eprintln!("{}", RENDER_USAGE);
+        return ExitCode::from(1);
+    };
+    // the slideshow's own options choose and style the slides
+    let cli = match cli::parse(rest) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return ExitCode::from(1);
+        }
+    };
+    if cli.config.playlist.is_none() && !cli.folder.is_dir() {
+        eprintln!("{}", RENDER_USAGE);
+        return ExitCode::from(1);
+    }
 
-                    ui.put(
-                        egui::Rect::from_min_size(
-                            egui::pos2(text_margin, text_y_base + 45.0),
-                            egui::vec2(650.0, 35.0),
-                        ),
-                        egui::Label::new(
-                            egui::RichText::new(&loaded.metadata.year)
-                                .size(22.0)
-                                .color(egui::Color32::from_rgb(220, 220, 220))
-                                .family(egui::FontFamily::Proportional),
-                        ),
-                    );
-                });
+    let _log_guard = logging::init(cli.verbose, cli.log_file.as_deref());
+    match render::render_all(cli.folder, &cli.config, &out, size) {
+        Ok(report) => {
+            for (path, err) in &report.failed {
+                eprintln!("{}: {}", path.display(), err);
+            }
+            println!("wrote {} slide(s) to {}", report.written, out.display());
+            if report.failed.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(1) }
+        }
+        Err(err) => {
+            eprintln!("error: cannot write to {}: {}", out.display(), err);
+            ExitCode::from(1)
         }
+    }
+}
 
-        ctx.request_repaint_after(Duration::from_millis(100));
+/// Windows' screensaver arguments: the settings dialog and the preview are
+/// run here, `/s` turns into the slideshow's own arguments. `Err` is the code
+/// to exit with.
+#[cfg(windows)]
+fn screensaver_args(args: Vec<String>) -> Result<Vec<String>, ExitCode> {
+    let Some(mode) = scr::mode(&args[1..], scr::launched_as_scr()) else {
+        return Ok(args);
+    };
+    scr::detach_console();
+    let path = scr::settings_path();
+    let result = match mode {
+        scr::Mode::Run => scr::run_args(path.as_deref()).map(Some),
+        scr::Mode::Configure => match path {
+            Some(path) => scr::configure(path).map(|()| None),
+            None => Err("APPDATA is not set, there is nowhere to keep the settings".to_string()),
+        },
+        scr::Mode::Preview(parent) => scr::preview(parent, path.as_deref()).map(|()| None),
+    };
+    match result {
+        Ok(Some(args)) => Ok(args),
+        Ok(None) => Err(ExitCode::SUCCESS),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            Err(ExitCode::from(1))
+        }
     }
 }
 
-fn main() -> eframe::Result<()> {
+fn main() -> ExitCode {
     let args: Vec<String> = std::env::args().collect();
-    let folder_path = if args.len() > 1 {
-        PathBuf::from(&args[1])
-    } else {
-        PathBuf::from(r"CHANGE FOR DIRECTORY")
+    #[cfg(windows)]
+    let args = match screensaver_args(args) {
+        Ok(args) => args,
+        Err(code) => return code,
     };
+    if args.get(1).map(String::as_str) == Some("query") {
+        return run_query(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("fetch") {
+        return run_fetch(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("render") {
+        return run_render(&args[2..]);
+    }
 
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_fullscreen(true)
-            .with_title("Art Slideshow"),
-        ..Default::default()
+    let cli = match cli::parse(args.iter().skip(1).cloned()) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return ExitCode::from(1);
+        }
     };
 
-    eframe::run_native(
+    if cli.validate {
+        return run_validate(&cli.folder);
+    }
+    if let Some(path) = &cli.stats_summary {
+        return run_stats_summary(path);
+    }
+
+    let builder = ArtSlideshowApp::builder().folder(cli.folder).config(cli.config.clone());
+    if let Err(err) = builder.validate() {
+        eprintln!("error: {}", err);
+        return ExitCode::from(1);
+    }
+
+    let _log_guard = logging::init(cli.verbose, cli.log_file.as_deref());
+    if let Some(url) = &cli.config.error_webhook {
+        webhook::report_panics(url.clone());
+    }
+
+    let options = window::native_options(cli.config.windowed);
+
+    let result = eframe::run_native(
         "Art Slideshow",
         options,
-        Box::new(|_cc| Ok(Box::new(ArtSlideshowApp::new(folder_path)))),
-    )
+        Box::new(move |cc| {
+            let control_port = cli.config.control_port;
+            let mqtt = cli.config.mqtt.clone();
+            fonts::install(&cc.egui_ctx, cli.config.font.as_deref());
+            let mut app = builder.build()?;
+            if let Some(storage) = cc.storage {
+                app.overlay_mode = eframe::get_value(storage, OVERLAY_MODE_KEY).unwrap_or_default();
+            }
+            window::restore_maximized(cc, cli.config.windowed);
+            if let Some(path) = cli.config_file {
+                app.save_settings_to(path);
+            }
+            app.reload_with(args[1..].to_vec());
+            app.handle_signals(cc.egui_ctx.clone());
+            app.connect_media_keys(cc);
+            app.serve_queries(&cli.socket);
+            if let Some(port) = control_port {
+                app.serve_http(port, cc.egui_ctx.clone());
+            }
+            if mqtt.url.is_some() {
+                app.connect_mqtt(&mqtt, cc.egui_ctx.clone());
+            }
+            Ok(Box::new(app))
+        }),
+    );
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            tracing::error!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
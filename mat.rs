@@ -0,0 +1,127 @@
+//! The mat and frame line drawn around the artwork for a gallery look.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// An sRGB color written `#rrggbb`, the `#` being optional.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('#').unwrap_or(s);
+        if digits.len() != 6 || !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(format!("expected a color like #f2efe8, got {:?}", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).expect("hex digits");
+        Ok(HexColor([channel(0), channel(2), channel(4)]))
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(HexColor([r, g, b]): HexColor) -> Self {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+impl From<HexColor> for egui::Color32 {
+    fn from(HexColor([r, g, b]): HexColor) -> Self {
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+
+/// The frame line's width as a fraction of the mat's, never under a point.
+const FRAME_FRACTION: f32 = 0.06;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct MatSettings {
+    /// Width of the mat as a fraction of the screen's shorter side, 0 for none.
+    pub width: f32,
+    pub color: HexColor,
+    /// The thin line around the outside of the mat.
+    pub frame_color: HexColor,
+}
+
+impl Default for MatSettings {
+    fn default() -> Self {
+        Self {
+            width: 0.0,
+            color: HexColor([0xf2, 0xef, 0xe8]),
+            frame_color: HexColor([0x2b, 0x28, 0x25]),
+        }
+    }
+}
+
+impl MatSettings {
+    pub fn is_on(&self) -> bool {
+        self.width > 0.0
+    }
+
+    /// Mat and frame line widths on a screen of `screen_size`.
+    fn widths(&self, screen_size: egui::Vec2) -> (f32, f32) {
+        let mat = screen_size.min_elem() * self.width.clamp(0.0, 0.25);
+        (mat, (mat * FRAME_FRACTION).max(1.0))
+    }
+
+    /// Where an image of `image_size` goes so that it, its mat and its frame
+    /// together are as large as fits on the screen, centred.
+    pub fn image_rect(&self, image_size: egui::Vec2, screen_size: egui::Vec2) -> egui::Rect {
+        let (mat, frame) = self.widths(screen_size);
+        let border = egui::Vec2::splat(mat + frame);
+        let inner = crate::layout::contain(image_size, (screen_size - border * 2.0).max(egui::Vec2::splat(1.0)));
+        inner.translate(border)
+    }
+
+    /// The frame's and then the mat's rect around `image_rect`, each to be
+    /// filled with its color over the one before.
+    pub fn rings(&self, image_rect: egui::Rect, screen_size: egui::Vec2) -> [(egui::Rect, HexColor); 2] {
+        let (mat, frame) = self.widths(screen_size);
+        let mat_rect = image_rect.expand(mat);
+        [(mat_rect.expand(frame), self.frame_color), (mat_rect, self.color)]
+    }
+
+    /// Paints the frame and the mat around `image_rect`, for the image to go on top.
+    pub fn draw(&self, painter: &egui::Painter, image_rect: egui::Rect, screen_size: egui::Vec2) {
+        for (rect, color) in self.rings(image_rect, screen_size) {
+            painter.rect_filled(rect, 0.0, egui::Color32::from(color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui::vec2;
+
+    #[test]
+    fn the_framed_artwork_fits_the_screen() {
+        let screen = vec2(1920.0, 1080.0);
+        let mat = MatSettings {
+            width: 0.05,
+            ..MatSettings::default()
+        };
+        let image = mat.image_rect(vec2(1000.0, 1000.0), screen);
+        let (width, frame) = mat.widths(screen);
+        let outer = image.expand(width + frame);
+
+        assert_eq!(width, 54.0);
+        assert!((outer.height() - 1080.0).abs() < 1e-3);
+        assert!((outer.center().x - 960.0).abs() < 1e-3);
+
+        assert_eq!("#F2efe8".parse(), Ok(HexColor([0xf2, 0xef, 0xe8])));
+        assert!("f2efe".parse::<HexColor>().is_err());
+        assert_eq!(String::from(HexColor([1, 2, 255])), "#0102ff");
+    }
+}
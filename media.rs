@@ -0,0 +1,141 @@
+//! A remote's media keys, with the `media-keys` feature: Play/Pause pauses
+//! the show and Next and Previous go through it, by way of the same commands
+//! as the keyboard. On Linux the show is an MPRIS player whose track is the
+//! artwork on screen, so the desktop's media controls show its title and
+//! artist; Windows and macOS hand over the keys through their own controls.
+
+use crate::control::Command;
+use crate::ipc::Snapshot;
+use std::sync::mpsc::Sender;
+
+/// D-Bus name of the MPRIS player, under `org.mpris.MediaPlayer2`.
+#[cfg_attr(not(feature = "media-keys"), allow(dead_code))]
+const DBUS_NAME: &str = "art_slideshow";
+
+#[cfg(feature = "media-keys")]
+struct Controls {
+    controls: souvlaki::MediaControls,
+    // title, artist and whether paused as last told, the same isn't sent again
+    shown: Option<(Option<String>, Option<String>, bool)>,
+}
+
+#[cfg(feature = "media-keys")]
+pub struct MediaKeys {
+    controls: std::sync::Mutex<Controls>,
+}
+
+#[cfg(not(feature = "media-keys"))]
+pub struct MediaKeys;
+
+/// The command a press on the remote stands for.
+#[cfg(feature = "media-keys")]
+fn command(event: souvlaki::MediaControlEvent) -> Option<Command> {
+    use souvlaki::MediaControlEvent;
+    match event {
+        MediaControlEvent::Play => Some(Command::Resume),
+        MediaControlEvent::Pause | MediaControlEvent::Stop => Some(Command::Pause),
+        MediaControlEvent::Toggle => Some(Command::TogglePause),
+        MediaControlEvent::Next => Some(Command::Next),
+        MediaControlEvent::Previous => Some(Command::Prev),
+        _ => None,
+    }
+}
+
+/// The window Windows' media controls belong to.
+#[cfg(all(feature = "media-keys", windows))]
+fn hwnd(cc: &eframe::CreationContext<'_>) -> Option<*mut std::ffi::c_void> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    match cc.window_handle().ok()?.as_raw() {
+        RawWindowHandle::Win32(handle) => Some(handle.hwnd.get() as *mut std::ffi::c_void),
+        _ => None,
+    }
+}
+
+#[cfg(all(feature = "media-keys", not(windows)))]
+fn hwnd(_cc: &eframe::CreationContext<'_>) -> Option<*mut std::ffi::c_void> {
+    None
+}
+
+impl MediaKeys {
+    /// Takes the media keys for the window of `cc`, sending what they ask
+    /// for to `commands`. Without the platform's media controls the warning
+    /// is logged and the keys do nothing.
+    #[cfg(feature = "media-keys")]
+    pub fn connect(cc: &eframe::CreationContext<'_>, commands: Sender<Command>) -> Option<Self> {
+        use souvlaki::{MediaControls, PlatformConfig};
+
+        let hwnd = hwnd(cc);
+        if cfg!(windows) && hwnd.is_none() {
+            tracing::warn!("media keys unavailable: no window to take them for");
+            return None;
+        }
+        let config = PlatformConfig { display_name: "Art Slideshow", dbus_name: DBUS_NAME, hwnd };
+        let ctx = cc.egui_ctx.clone();
+        let controls = MediaControls::new(config).and_then(|mut controls| {
+            controls.attach(move |event| {
+                if let Some(command) = command(event) {
+                    if commands.send(command).is_ok() {
+                        ctx.request_repaint();
+                    }
+                }
+            })?;
+            Ok(controls)
+        });
+        match controls {
+            Ok(controls) => Some(Self { controls: std::sync::Mutex::new(Controls { controls, shown: None }) }),
+            Err(err) => {
+                tracing::warn!("media keys unavailable: {:?}", err);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "media-keys"))]
+    pub fn connect(_cc: &eframe::CreationContext<'_>, _commands: Sender<Command>) -> Option<Self> {
+        None
+    }
+
+    /// Shows the artwork in `snapshot` as the track playing, or paused.
+    #[cfg(feature = "media-keys")]
+    pub fn publish(&self, snapshot: &Snapshot) {
+        use souvlaki::{MediaMetadata, MediaPlayback};
+
+        let mut guard = self.controls.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Controls { controls, shown: latest } = &mut *guard;
+        let shown = (snapshot.title.clone(), snapshot.artist.clone(), snapshot.paused);
+        if latest.as_ref() == Some(&shown) {
+            return;
+        }
+        let metadata = MediaMetadata {
+            title: snapshot.title.as_deref(),
+            artist: snapshot.artist.as_deref(),
+            ..Default::default()
+        };
+        let playback = if snapshot.paused {
+            MediaPlayback::Paused { progress: None }
+        } else {
+            MediaPlayback::Playing { progress: None }
+        };
+        if let Err(err) = controls.set_metadata(metadata).and_then(|()| controls.set_playback(playback)) {
+            tracing::debug!("cannot update the media controls: {:?}", err);
+        }
+        *latest = Some(shown);
+    }
+
+    #[cfg(not(feature = "media-keys"))]
+    pub fn publish(&self, _snapshot: &Snapshot) {}
+}
+
+#[cfg(all(test, feature = "media-keys"))]
+mod tests {
+    use super::*;
+    use souvlaki::MediaControlEvent;
+
+    #[test]
+    fn the_remote_steers_the_show() {
+        assert_eq!(command(MediaControlEvent::Toggle), Some(Command::TogglePause));
+        assert_eq!(command(MediaControlEvent::Play), Some(Command::Resume));
+        assert_eq!(command(MediaControlEvent::Previous), Some(Command::Prev));
+        assert_eq!(command(MediaControlEvent::SetVolume(0.5)), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! How the artworks of several folders given together make up one show.
+
+use crate::scan::ArtworkInfo;
+use crate::shuffle::Shuffle;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Merge {
+    /// One folder after the other.
+    #[default]
+    Append,
+    /// A work from each folder in turn, the smaller ones starting over until
+    /// the largest is through, so a folder of a few still comes up regularly.
+    Interleave,
+    /// All of them in a random order.
+    Shuffle,
+}
+
+impl FromStr for Merge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "append" => Ok(Merge::Append),
+            "interleave" => Ok(Merge::Interleave),
+            "shuffle" => Ok(Merge::Shuffle),
+            other => Err(format!("expected append, interleave or shuffle, got {:?}", other)),
+        }
+    }
+}
+
+impl Merge {
+    /// The show made of `sources`, one list of artworks per folder, in the
+    /// order `shuffle` asks for. When interleaving, each folder is shuffled
+    /// on its own so the turns between them stay.
+    pub fn combine(self, mut sources: Vec<Vec<ArtworkInfo>>, shuffle: Shuffle) -> Vec<ArtworkInfo> {
+        sources.retain(|artworks| !artworks.is_empty());
+        match self {
+            Merge::Append | Merge::Shuffle => {
+                let mut artworks: Vec<ArtworkInfo> = sources.into_iter().flatten().collect();
+                let shuffle = if self == Merge::Shuffle && shuffle == Shuffle::Off { Shuffle::Plain } else { shuffle };
+                shuffle.apply(&mut artworks);
+                artworks
+            }
+            Merge::Interleave => {
+                for artworks in &mut sources {
+                    shuffle.apply(artworks);
+                }
+                let rounds = sources.iter().map(Vec::len).max().unwrap_or(0);
+                (0..rounds)
+                    .flat_map(|round| sources.iter().map(move |artworks| artworks[round % artworks.len()].clone()))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+
+    fn folder(name: &str, count: usize) -> Vec<ArtworkInfo> {
+        (1..=count)
+            .map(|n| ArtworkInfo {
+                path: PathBuf::from(format!("/{}/{}.jpg", name, n)),
+                metadata: ArtworkMetadata::unknown(&format!("{}{}", name, n)),
+                remote: None,
+            })
+            .collect()
+    }
+
+    fn titles(artworks: &[ArtworkInfo]) -> Vec<&str> {
+        artworks.iter().map(|info| info.metadata.title.as_str()).collect()
+    }
+
+    #[test]
+    fn folders_take_turns_or_follow_each_other() {
+        let sources = || vec![folder("a", 4), folder("b", 2), Vec::new()];
+        assert_eq!(titles(&Merge::Append.combine(sources(), Shuffle::Off)), ["a1", "a2", "a3", "a4", "b1", "b2"]);
+        assert_eq!(
+            titles(&Merge::Interleave.combine(sources(), Shuffle::Off)),
+            ["a1", "b1", "a2", "b2", "a3", "b1", "a4", "b2"]
+        );
+
+        let mut shuffled = Merge::Shuffle.combine(sources(), Shuffle::Off);
+        shuffled.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(titles(&shuffled), ["a1", "a2", "a3", "a4", "b1", "b2"]);
+        assert_eq!("interleave".parse(), Ok(Merge::Interleave));
+        assert!("zip".parse::<Merge>().is_err());
+    }
+}
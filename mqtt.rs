@@ -0,0 +1,157 @@
+//! Optional MQTT bridge for home-automation dashboards.
+//!
+//! The current artwork is published retained on `<prefix>/state` and commands
+//! are read from `<prefix>/command`. The broker going away never blocks the
+//! slideshow: commands sent meanwhile are lost and the latest state is
+//! published again once the connection is back.
+
+use crate::control::Command;
+use crate::ipc::Snapshot;
+use eframe::egui;
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 1883;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct MqttSettings {
+    /// Broker as `mqtt://host[:port]`, the bridge is off when unset.
+    pub url: Option<String>,
+    pub topic_prefix: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            topic_prefix: "art-slideshow".to_string(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+/// Splits `mqtt://host[:port]` into host and port.
+fn parse_broker(url: &str) -> Result<(String, u16), String> {
+    let address = url.strip_prefix("mqtt://").unwrap_or(url).trim_end_matches('/');
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| format!("invalid port in {:?}", url))?),
+        None => (address, DEFAULT_PORT),
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in {:?}", url));
+    }
+    Ok((host.to_string(), port))
+}
+
+fn state_payload(snapshot: &Snapshot) -> String {
+    serde_json::json!({
+        "index": snapshot.index,
+        "title": snapshot.title,
+        "artist": snapshot.artist,
+        "year": snapshot.year,
+        "file": snapshot.path.as_ref().and_then(|path| path.file_name()).map(|name| name.to_string_lossy()),
+    })
+    .to_string()
+}
+
+/// Publishes through the background connection for as long as it lives.
+pub struct Mqtt {
+    client: Client,
+    state_topic: String,
+    // last state sent, published again after every reconnect
+    latest: Arc<Mutex<Option<String>>>,
+}
+
+impl Mqtt {
+    /// Starts the connection thread. Commands are handed to the UI thread
+    /// through `commands`, and `ctx` is woken so they apply right away.
+    pub fn connect(settings: &MqttSettings, commands: Sender<Command>, ctx: egui::Context) -> Result<Self, String> {
+        let url = settings.url.as_deref().ok_or("no broker configured")?;
+        let (host, port) = parse_broker(url)?;
+
+        let mut options = MqttOptions::new(format!("art-slideshow-{}", std::process::id()), host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &settings.username {
+            options.set_credentials(username.clone(), settings.password.clone().unwrap_or_default());
+        }
+
+        let (client, mut connection) = Client::new(options, 16);
+        let state_topic = format!("{}/state", settings.topic_prefix);
+        let command_topic = format!("{}/command", settings.topic_prefix);
+        let latest = Arc::new(Mutex::new(None::<String>));
+
+        let subscriber = client.clone();
+        let republish = Arc::clone(&latest);
+        let republish_topic = state_topic.clone();
+        thread::spawn(move || {
+            // the iterator reconnects by itself on the next poll after an error
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        let _ = subscriber.try_subscribe(command_topic.as_str(), QoS::AtMostOnce);
+                        let latest = republish.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+                        if let Some(payload) = latest {
+                            let _ = subscriber.try_publish(republish_topic.as_str(), QoS::AtLeastOnce, true, payload);
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == command_topic => {
+                        let text = String::from_utf8_lossy(&publish.payload);
+                        match text.parse::<Command>() {
+                            Ok(command) => {
+                                if commands.send(command).is_err() {
+                                    break;
+                                }
+                                ctx.request_repaint();
+                            }
+                            Err(err) => tracing::warn!("mqtt: {}", err),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::warn!("mqtt broker unreachable: {}", err);
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+
+        Ok(Self { client, state_topic, latest })
+    }
+
+    /// Publishes `snapshot` retained, unless it is what the broker already has.
+    pub fn publish_state(&self, snapshot: &Snapshot) {
+        let payload = state_payload(snapshot);
+        let mut latest = self.latest.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if latest.as_deref() == Some(payload.as_str()) {
+            return;
+        }
+        // a full queue means we're offline, the reconnect publishes `latest` anyway
+        let _ = self
+            .client
+            .try_publish(self.state_topic.as_str(), QoS::AtLeastOnce, true, payload.clone());
+        *latest = Some(payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broker_urls() {
+        assert_eq!(parse_broker("mqtt://broker.lan:1884"), Ok(("broker.lan".to_string(), 1884)));
+        assert_eq!(parse_broker("mqtt://broker.lan/"), Ok(("broker.lan".to_string(), 1883)));
+        assert_eq!(parse_broker("10.0.0.2"), Ok(("10.0.0.2".to_string(), 1883)));
+        assert!(parse_broker("mqtt://broker.lan:abc").is_err());
+        assert!(parse_broker("mqtt://").is_err());
+    }
+}
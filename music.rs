@@ -0,0 +1,280 @@
+//! `--music`: audio played along with the show, from a folder or a playlist
+//! file. It plays on a thread of its own and has no say in the slides: without
+//! an audio device, or with files that don't decode, the show goes on silent.
+
+use crate::config::Config;
+use rand::seq::SliceRandom;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "wav"];
+
+/// How much `+` and `-` change the volume.
+pub const VOLUME_STEP: f32 = 0.1;
+
+/// How often the player looks at its tracks and for a new volume.
+const TICK: Duration = Duration::from_millis(50);
+
+pub fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| AUDIO_EXTENSIONS.iter().any(|audio| ext.eq_ignore_ascii_case(audio)))
+}
+
+/// The tracks in `source`: the audio files in a folder, by name, or those
+/// listed in a playlist file, one path per line with `#` starting a comment
+/// line, so `.m3u` files do. Relative paths are resolved against the
+/// playlist's directory, missing and unsupported files are skipped.
+pub fn tracks(source: &Path) -> io::Result<Vec<PathBuf>> {
+    if source.is_dir() {
+        let mut tracks: Vec<PathBuf> = fs::read_dir(source)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && is_audio(path))
+            .collect();
+        tracks.sort();
+        return Ok(tracks);
+    }
+
+    let content = fs::read_to_string(source)?;
+    let base = source.parent().unwrap_or(Path::new(""));
+    let mut tracks = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = base.join(line);
+        if !path.is_file() {
+            tracing::warn!("{}:{}: skipping missing {}", source.display(), number + 1, path.display());
+        } else if !is_audio(&path) {
+            tracing::warn!("{}:{}: skipping unsupported {}", source.display(), number + 1, path.display());
+        } else {
+            tracks.push(path);
+        }
+    }
+    Ok(tracks)
+}
+
+/// The music `config` asks for, `None` when it asks for none or none can be found.
+pub fn from_config(config: &Config) -> Option<Music> {
+    let source = config.music.as_ref()?;
+    match tracks(source) {
+        Ok(tracks) if tracks.is_empty() => {
+            tracing::warn!("no music found in {}", source.display());
+            None
+        }
+        Ok(tracks) => Some(Music::start(tracks, config.music_shuffle, config.music_volume, config.music_crossfade())),
+        Err(err) => {
+            tracing::warn!("cannot read the music in {}: {}", source.display(), err);
+            None
+        }
+    }
+}
+
+/// How loud a track is at `position` during a crossfade of `crossfade`, from
+/// 0 to 1: rising from its start if it came in over the track before, falling
+/// from `fade_out_from` once the next one comes in over it.
+fn fade_gain(position: Duration, fade_in: bool, fade_out_from: Option<Duration>, crossfade: Duration) -> f32 {
+    if crossfade.is_zero() {
+        return 1.0;
+    }
+    let ramp = |time: Duration| (time.as_secs_f32() / crossfade.as_secs_f32()).min(1.0);
+    let rising = if fade_in { ramp(position) } else { 1.0 };
+    let falling = fade_out_from.map_or(1.0, |from| 1.0 - ramp(position.saturating_sub(from)));
+    rising * falling
+}
+
+/// A track being played.
+struct Track {
+    sink: Sink,
+    // unknown for some files, those and the very short end without a crossfade
+    length: Option<Duration>,
+    fade_in: bool,
+    fade_out_from: Option<Duration>,
+}
+
+impl Track {
+    fn start(path: &Path, output: &OutputStreamHandle, fade_in: bool) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let source = Decoder::new(BufReader::new(file)).map_err(|err| err.to_string())?;
+        let length = source.total_duration();
+        let sink = Sink::try_new(output).map_err(|err| err.to_string())?;
+        if fade_in {
+            sink.set_volume(0.0);
+        }
+        sink.append(source);
+        tracing::debug!("playing {}", path.display());
+        Ok(Self { sink, length, fade_in, fade_out_from: None })
+    }
+
+    /// Whether the next track should come in over this one.
+    fn is_ending(&self, crossfade: Duration) -> bool {
+        !crossfade.is_zero()
+            && self.fade_out_from.is_none()
+            && self.length.is_some_and(|length| length > crossfade * 2 && self.sink.get_pos() + crossfade >= length)
+    }
+
+    fn set_volume(&self, volume: f32, crossfade: Duration) {
+        let gain = fade_gain(self.sink.get_pos(), self.fade_in, self.fade_out_from, crossfade);
+        self.sink.set_volume(volume * gain);
+    }
+}
+
+/// The music thread, playing until it is dropped.
+pub struct Music {
+    volume: f32,
+    muted: bool,
+    levels: Option<Sender<f32>>,
+    player: Option<JoinHandle<()>>,
+}
+
+impl Music {
+    /// Starts playing `tracks` over and over, reshuffled on every round when
+    /// `shuffle` is set, each fading into the next over `crossfade`.
+    pub fn start(mut tracks: Vec<PathBuf>, shuffle: bool, volume: f32, crossfade: Duration) -> Self {
+        let volume = volume.clamp(0.0, 1.0);
+        let (levels, receiver) = mpsc::channel::<f32>();
+        let player = thread::spawn(move || {
+            // the stream has to stay on the thread it was opened on
+            let (_stream, output) = match OutputStream::try_default() {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::warn!("no audio output, the show goes on without music: {}", err);
+                    return;
+                }
+            };
+            tracing::info!("playing {} tracks", tracks.len());
+            let mut level = volume;
+            let mut queue: Vec<PathBuf> = Vec::new();
+            let mut current: Option<Track> = None;
+            let mut fading: Option<Track> = None;
+            loop {
+                match receiver.recv_timeout(TICK) {
+                    Ok(new_level) => level = new_level,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                let due = current.as_ref().is_none_or(|track| track.sink.empty() || track.is_ending(crossfade));
+                if due {
+                    if let Some(mut ending) = current.take().filter(|track| !track.sink.empty()) {
+                        ending.fade_out_from = Some(ending.sink.get_pos());
+                        fading = Some(ending);
+                    }
+                    while current.is_none() {
+                        if tracks.is_empty() {
+                            tracing::warn!("none of the music can be played, the show goes on without it");
+                            return;
+                        }
+                        if queue.is_empty() {
+                            queue = tracks.iter().rev().cloned().collect();
+                            if shuffle {
+                                queue.shuffle(&mut rand::thread_rng());
+                            }
+                        }
+                        let path = queue.pop().expect("refilled when empty");
+                        match Track::start(&path, &output, fading.is_some()) {
+                            Ok(track) => current = Some(track),
+                            Err(err) => {
+                                tracing::warn!("cannot play {}, leaving it out: {}", path.display(), err);
+                                tracks.retain(|track| *track != path);
+                                queue.retain(|track| *track != path);
+                            }
+                        }
+                    }
+                }
+
+                if fading.as_ref().is_some_and(|track| track.sink.empty()) {
+                    fading = None;
+                }
+                for track in current.iter().chain(&fading) {
+                    track.set_volume(level, crossfade);
+                }
+            }
+        });
+        Self { volume, muted: false, levels: Some(levels), player: Some(player) }
+    }
+
+    /// The volume between 0 and 1, as set with [`Music::louder`] and [`Music::quieter`].
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// A step louder, unmuting.
+    pub fn louder(&mut self) {
+        self.set_volume(self.volume + VOLUME_STEP);
+    }
+
+    /// A step quieter, unmuting.
+    pub fn quieter(&mut self) {
+        self.set_volume(self.volume - VOLUME_STEP);
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.send_level();
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        // rounded so steps land on whole percents
+        self.volume = (volume.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        self.muted = false;
+        self.send_level();
+    }
+
+    fn send_level(&self) {
+        let level = if self.muted { 0.0 } else { self.volume };
+        if let Some(levels) = &self.levels {
+            // gone once the player gave up, nothing to set then
+            let _ = levels.send(level);
+        }
+    }
+}
+
+impl Drop for Music {
+    /// Stops the music and waits for the thread to let go of the audio device.
+    fn drop(&mut self) {
+        self.levels = None;
+        if let Some(player) = self.player.take() {
+            let _ = player.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_come_from_a_folder_or_a_playlist() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.flac", "a.MP3", "cover.jpg", "c.ogg"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let names = |tracks: Vec<PathBuf>| -> Vec<String> {
+            tracks.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect()
+        };
+        assert_eq!(names(tracks(dir.path()).unwrap()), ["a.MP3", "b.flac", "c.ogg"]);
+
+        let playlist = dir.path().join("evening.m3u");
+        fs::write(&playlist, "#EXTM3U\nc.ogg\n\ncover.jpg\nmissing.mp3\na.MP3\n").unwrap();
+        assert_eq!(names(tracks(&playlist).unwrap()), ["c.ogg", "a.MP3"]);
+
+        let second = Duration::from_secs(1);
+        let crossfade = 2 * second;
+        assert_eq!(fade_gain(second, false, None, crossfade), 1.0);
+        assert_eq!(fade_gain(second, true, None, crossfade), 0.5);
+        assert_eq!(fade_gain(10 * second, false, Some(9 * second), crossfade), 0.5);
+        assert_eq!(fade_gain(12 * second, false, Some(9 * second), crossfade), 0.0);
+        assert_eq!(fade_gain(second, true, None, Duration::ZERO), 1.0);
+    }
+}
@@ -0,0 +1,157 @@
+//! `--narrate`: the caption read out a moment after each slide comes up, for
+//! whoever can't make it out from across the room. The platform's speech
+//! engine is driven from a thread of its own; skipping ahead cuts off what was
+//! being said about the slide before.
+
+use crate::scan::ArtworkMetadata;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long a new slide is up before it is read out.
+pub const DELAY: Duration = Duration::from_secs(1);
+
+/// Speaking speeds that can be asked for, 1 being the engine's usual one.
+#[cfg_attr(not(feature = "narration"), allow(dead_code))]
+const SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
+
+/// Whether a sidecar field says something, rather than holding the placeholder.
+fn is_known(field: &str) -> bool {
+    let field = field.trim();
+    !field.is_empty() && !field.eq_ignore_ascii_case("unknown")
+}
+
+/// What is said for an artwork: "Title, by Artist, Year", leaving out what
+/// its sidecar doesn't know.
+pub fn narration(metadata: &ArtworkMetadata) -> String {
+    let mut parts = vec![metadata.title.trim().to_string()];
+    if is_known(&metadata.artist) {
+        parts.push(format!("by {}", metadata.artist.trim()));
+    }
+    if is_known(&metadata.year) {
+        parts.push(metadata.year.trim().to_string());
+    }
+    parts.join(", ")
+}
+
+/// The engine's rate for `speed`, between its `min`, `normal` and `max`
+/// rates: half speed is its slowest and double speed its fastest, whatever
+/// scale the engine counts in.
+#[cfg_attr(not(feature = "narration"), allow(dead_code))]
+fn engine_rate(speed: f32, min: f32, normal: f32, max: f32) -> f32 {
+    let speed = if speed.is_nan() { 1.0 } else { speed.clamp(*SPEED_RANGE.start(), *SPEED_RANGE.end()) };
+    if speed >= 1.0 { normal + (max - normal) * (speed - 1.0) } else { normal - (normal - min) * (1.0 - speed) * 2.0 }
+}
+
+/// Something to say, and when.
+#[cfg_attr(not(feature = "narration"), allow(dead_code))]
+struct Utterance {
+    text: String,
+    at: Instant,
+}
+
+pub struct Narrator {
+    utterances: Option<Sender<Utterance>>,
+    speaker: Option<JoinHandle<()>>,
+}
+
+impl Narrator {
+    /// Starts the speech engine at `speed` times its usual pace. Without one,
+    /// the warning is logged and the slides go by unread.
+    pub fn start(speed: f32) -> Self {
+        let (utterances, receiver) = mpsc::channel::<Utterance>();
+        let speaker = thread::spawn(move || speak(receiver, speed));
+        Self { utterances: Some(utterances), speaker: Some(speaker) }
+    }
+
+    /// Says `text` after `delay`, cutting off anything still being said and
+    /// dropping what was waiting to be.
+    pub fn say(&self, text: String, delay: Duration) {
+        if let Some(utterances) = &self.utterances {
+            // gone when there is no speech engine
+            let _ = utterances.send(Utterance { text, at: Instant::now() + delay });
+        }
+    }
+}
+
+impl Drop for Narrator {
+    /// Stops mid-sentence and waits for the engine to be let go.
+    fn drop(&mut self) {
+        self.utterances = None;
+        if let Some(speaker) = self.speaker.take() {
+            let _ = speaker.join();
+        }
+    }
+}
+
+#[cfg(feature = "narration")]
+fn speak(receiver: mpsc::Receiver<Utterance>, speed: f32) {
+    use std::sync::mpsc::RecvTimeoutError;
+
+    // the engine has to stay on the thread it was made on
+    let mut tts = match tts::Tts::default() {
+        Ok(tts) => tts,
+        Err(err) => {
+            tracing::warn!("no speech engine, the captions won't be read out: {}", err);
+            return;
+        }
+    };
+    let rate = engine_rate(speed, tts.min_rate(), tts.normal_rate(), tts.max_rate());
+    if let Err(err) = tts.set_rate(rate) {
+        tracing::debug!("cannot set the speaking rate, using the engine's own: {}", err);
+    }
+
+    let mut waiting: Option<Utterance> = None;
+    loop {
+        let received = match &waiting {
+            Some(utterance) => receiver.recv_timeout(utterance.at.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(utterance) => {
+                // whatever is being said is about the slide before
+                let _ = tts.stop();
+                waiting = Some(utterance);
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if let Some(utterance) = waiting.take() {
+                    if let Err(err) = tts.speak(utterance.text, true) {
+                        tracing::warn!("cannot read out the caption: {}", err);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = tts.stop();
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "narration"))]
+fn speak(_receiver: mpsc::Receiver<Utterance>, _speed: f32) {
+    tracing::warn!("built without the narration feature, the captions won't be read out");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captions_read_as_sentences() {
+        let metadata = |artist: &str, year: &str| ArtworkMetadata {
+            artist: artist.to_string(),
+            year: year.to_string(),
+            ..ArtworkMetadata::unknown("The Starry Night")
+        };
+        assert_eq!(narration(&metadata("Vincent van Gogh", "1889")), "The Starry Night, by Vincent van Gogh, 1889");
+        assert_eq!(narration(&metadata("Unknown", "1889")), "The Starry Night, 1889");
+        assert_eq!(narration(&metadata("Vincent van Gogh", "Unknown")), "The Starry Night, by Vincent van Gogh");
+
+        // Speech Dispatcher counts from -100 to 100
+        assert_eq!(engine_rate(1.0, -100.0, 0.0, 100.0), 0.0);
+        assert_eq!(engine_rate(2.0, -100.0, 0.0, 100.0), 100.0);
+        assert_eq!(engine_rate(0.75, -100.0, 0.0, 100.0), -50.0);
+        assert_eq!(engine_rate(5.0, 0.1, 1.0, 10.0), 10.0);
+    }
+}
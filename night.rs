@@ -0,0 +1,139 @@
+//! Hours during which the screen stays dark.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Daily window written `23:00-07:00`, stored as minutes since local midnight.
+///
+/// Only the wall-clock time is compared, so the window follows DST shifts: on
+/// the night the clocks change it is simply an hour longer or shorter.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct OffHours {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl OffHours {
+    /// Whether `minute` (since midnight) falls in the window. Equal start and end is never off.
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            // crosses midnight
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    pub fn contains_now(&self) -> bool {
+        use chrono::Timelike;
+        let now = chrono::Local::now();
+        self.contains((now.hour() * 60 + now.minute()) as u16)
+    }
+
+    /// Minutes from `minute` until the screen goes dark or lights up again, `None` if it never does.
+    pub fn minutes_until_change(&self, minute: u16) -> Option<u16> {
+        if self.start == self.end {
+            return None;
+        }
+        let boundary = if self.contains(minute) { self.end } else { self.start };
+        Some((boundary + MINUTES_PER_DAY - minute) % MINUTES_PER_DAY)
+    }
+
+    /// Time until the window next opens or closes.
+    pub fn until_change_now(&self) -> Option<std::time::Duration> {
+        use chrono::Timelike;
+        let now = chrono::Local::now();
+        let minutes = self.minutes_until_change((now.hour() * 60 + now.minute()) as u16)?;
+        let seconds = (minutes as u64 * 60).saturating_sub(now.second() as u64);
+        Some(std::time::Duration::from_secs(seconds))
+    }
+}
+
+fn parse_time(s: &str) -> Option<u16> {
+    let (hours, minutes) = s.trim().split_once(':')?;
+    let (hours, minutes): (u16, u16) = (hours.parse().ok()?, minutes.parse().ok()?);
+    let minute = hours * 60 + minutes;
+    // 24:00 is accepted as the end of the day
+    (minutes < 60 && minute <= MINUTES_PER_DAY).then_some(minute % MINUTES_PER_DAY)
+}
+
+impl FromStr for OffHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("expected hours like 23:00-07:00, got {:?}", s);
+        let (start, end) = s.split_once('-').ok_or_else(error)?;
+        Ok(OffHours {
+            start: parse_time(start).ok_or_else(error)?,
+            end: parse_time(end).ok_or_else(error)?,
+        })
+    }
+}
+
+impl fmt::Display for OffHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> u16 {
+        parse_time(time).unwrap()
+    }
+
+    #[test]
+    fn window_across_midnight() {
+        let night: OffHours = "23:00-07:00".parse().unwrap();
+        assert!(night.contains(at("23:00")));
+        assert!(night.contains(at("00:00")));
+        assert!(night.contains(at("06:59")));
+        assert!(!night.contains(at("07:00")));
+        assert!(!night.contains(at("22:59")));
+        assert_eq!(night.to_string(), "23:00-07:00");
+    }
+
+    #[test]
+    fn window_within_a_day() {
+        let siesta: OffHours = "13:30-15:00".parse().unwrap();
+        assert!(siesta.contains(at("14:00")));
+        assert!(!siesta.contains(at("15:00")));
+        assert!(!siesta.contains(at("08:00")));
+    }
+
+    #[test]
+    fn equal_start_and_end_is_never_off() {
+        let never: OffHours = "07:00-07:00".parse().unwrap();
+        assert!((0..MINUTES_PER_DAY).all(|minute| !never.contains(minute)));
+        assert_eq!(never.minutes_until_change(at("12:00")), None);
+    }
+
+    #[test]
+    fn time_until_the_next_boundary() {
+        let night: OffHours = "23:00-07:00".parse().unwrap();
+        assert_eq!(night.minutes_until_change(at("22:30")), Some(30));
+        assert_eq!(night.minutes_until_change(at("23:00")), Some(8 * 60));
+        assert_eq!(night.minutes_until_change(at("06:59")), Some(1));
+        assert_eq!(night.minutes_until_change(at("07:00")), Some(16 * 60));
+    }
+
+    #[test]
+    fn rejects_bad_times() {
+        assert!("23:00".parse::<OffHours>().is_err());
+        assert!("25:00-07:00".parse::<OffHours>().is_err());
+        assert!("23:60-07:00".parse::<OffHours>().is_err());
+        assert_eq!("22:00-24:00".parse::<OffHours>().unwrap().end, 0);
+    }
+}
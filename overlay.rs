@@ -0,0 +1,671 @@
+use crate::chapters::TitleCard;
+use crate::fonts;
+use crate::layout::{self, CaptionPosition, Corner};
+use crate::pipeline::CaptionSample;
+use crate::scan::ArtworkMetadata;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long the caption stays up in auto-hide mode before fading.
+const OVERLAY_AUTO_HIDE_AFTER: Duration = Duration::from_secs(5);
+const OVERLAY_FADE: Duration = Duration::from_millis(600);
+/// How long a new slide is on screen before its caption starts fading in.
+pub const CAPTION_DELAY: Duration = Duration::from_millis(500);
+/// Shorter hold after the arrow keys or a remote, so skipping through doesn't drag.
+pub const CAPTION_DELAY_MANUAL: Duration = Duration::from_millis(150);
+const CAPTION_FADE_IN: Duration = Duration::from_millis(700);
+pub const OVERLAY_MODE_KEY: &str = "overlay_mode";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum OverlayMode {
+    #[default]
+    Always,
+    AutoHide,
+    Hidden,
+}
+
+impl OverlayMode {
+    pub fn next(self) -> Self {
+        match self {
+            OverlayMode::Always => OverlayMode::AutoHide,
+            OverlayMode::AutoHide => OverlayMode::Hidden,
+            OverlayMode::Hidden => OverlayMode::Always,
+        }
+    }
+
+    /// Caption opacity given the time since the overlay was last woken up.
+    pub fn alpha(self, since_wake: Duration) -> f32 {
+        match self {
+            OverlayMode::Always => 1.0,
+            OverlayMode::Hidden => 0.0,
+            OverlayMode::AutoHide => {
+                let fading = since_wake.saturating_sub(OVERLAY_AUTO_HIDE_AFTER);
+                1.0 - (fading.as_secs_f32() / OVERLAY_FADE.as_secs_f32()).min(1.0)
+            }
+        }
+    }
+
+    /// Time until the caption starts fading, `None` if it never does or already is.
+    pub fn until_fade(self, since_wake: Duration) -> Option<Duration> {
+        (self == OverlayMode::AutoHide && since_wake < OVERLAY_AUTO_HIDE_AFTER)
+            .then(|| OVERLAY_AUTO_HIDE_AFTER - since_wake)
+    }
+}
+
+/// The caption coming in after a slide change: invisible for `delay`, then
+/// fading in. Multiplies whatever [`OverlayMode::alpha`] allows.
+#[derive(Debug, Clone, Copy)]
+pub struct CaptionReveal {
+    started: Instant,
+    delay: Duration,
+}
+
+impl CaptionReveal {
+    pub fn new(delay: Duration) -> Self {
+        Self { started: Instant::now(), delay }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        Self::alpha_at(self.started.elapsed(), self.delay)
+    }
+
+    fn alpha_at(elapsed: Duration, delay: Duration) -> f32 {
+        let fading = elapsed.saturating_sub(delay);
+        (fading.as_secs_f32() / CAPTION_FADE_IN.as_secs_f32()).min(1.0)
+    }
+
+    /// Time until the fade starts, `None` once it has.
+    pub fn until_start(&self) -> Option<Duration> {
+        self.delay.checked_sub(self.started.elapsed()).filter(|wait| !wait.is_zero())
+    }
+}
+
+/// Which of a collage's `works` the caption is on `shown` into a slide of
+/// `duration`, each getting an equal turn, and how long until the next one's.
+pub fn caption_turn(works: usize, shown: Duration, duration: Duration) -> (usize, Option<Duration>) {
+    if works < 2 || duration.is_zero() {
+        return (0, None);
+    }
+    let turn = duration / works as u32;
+    let index = ((shown.as_secs_f64() / turn.as_secs_f64()) as usize).min(works - 1);
+    let next = (index + 1 < works).then(|| (turn * (index as u32 + 1)).saturating_sub(shown));
+    (index, next)
+}
+
+/// Which caption colors to use. `Auto` decides per slide from what is behind the caption.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionStyle {
+    Auto,
+    /// White text on a dark backdrop.
+    Light,
+    /// Dark text on a light backdrop.
+    Dark,
+}
+
+impl FromStr for CaptionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(CaptionStyle::Auto),
+            "light" => Ok(CaptionStyle::Light),
+            "dark" => Ok(CaptionStyle::Dark),
+            other => Err(format!("expected auto, light or dark, got {:?}", other)),
+        }
+    }
+}
+
+/// Above this luminance white text stops being readable.
+const LIGHT_BACKGROUND_LUMINANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionPalette {
+    pub backdrop: egui::Color32,
+    pub title: egui::Color32,
+    pub year: egui::Color32,
+}
+
+impl CaptionPalette {
+    pub const LIGHT_TEXT: Self = Self {
+        backdrop: egui::Color32::from_black_alpha(200),
+        title: egui::Color32::WHITE,
+        year: egui::Color32::from_rgb(220, 220, 220),
+    };
+
+    pub const DARK_TEXT: Self = Self {
+        backdrop: egui::Color32::from_rgba_premultiplied(200, 200, 200, 200),
+        title: egui::Color32::from_rgb(20, 20, 20),
+        year: egui::Color32::from_rgb(60, 60, 60),
+    };
+
+    /// Picks the palette for a slide whose artwork is drawn at `image_rect`, under a caption at `caption_rect`.
+    pub fn choose(
+        style: CaptionStyle,
+        sample: CaptionSample,
+        image_rect: egui::Rect,
+        caption_rect: egui::Rect,
+    ) -> Self {
+        match style {
+            CaptionStyle::Light => Self::LIGHT_TEXT,
+            CaptionStyle::Dark => Self::DARK_TEXT,
+            CaptionStyle::Auto => {
+                let covered = layout::coverage(caption_rect, image_rect);
+                let luminance = sample.image * covered + sample.background * (1.0 - covered);
+                if luminance > LIGHT_BACKGROUND_LUMINANCE {
+                    Self::DARK_TEXT
+                } else {
+                    Self::LIGHT_TEXT
+                }
+            }
+        }
+    }
+}
+
+/// How the caption is set off from the artwork.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionLook {
+    /// Rounded backdrop behind the text.
+    #[default]
+    Box,
+    /// Text with a soft drop shadow and no backdrop.
+    Shadow,
+    /// Translucent strip across the whole screen.
+    Bar,
+}
+
+impl FromStr for CaptionLook {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "box" => Ok(CaptionLook::Box),
+            "shadow" => Ok(CaptionLook::Shadow),
+            "bar" => Ok(CaptionLook::Bar),
+            other => Err(format!("expected box, shadow or bar, got {:?}", other)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub position: CaptionPosition,
+    pub style: CaptionLook,
+}
+
+impl OverlaySettings {
+    /// Corners the caption takes, kept free of the clock.
+    pub fn corners(&self) -> Vec<Corner> {
+        self.position.corners(self.style == CaptionLook::Bar)
+    }
+
+    pub fn region(&self) -> [f32; 4] {
+        self.position.region(self.style == CaptionLook::Bar)
+    }
+}
+
+const CAPTION_MARGIN: f32 = 15.0;
+const CAPTION_PADDING: egui::Vec2 = egui::vec2(15.0, 12.0);
+
+/// Darkest the scrim gets, at the screen edge behind the caption.
+const SCRIM_ALPHA: f32 = 0.55;
+/// Steps of the scrim's gradient, enough for the curve not to show its corners.
+const SCRIM_STEPS: u32 = 8;
+
+/// Where the scrim behind `caption` is clear and the edge it darkens toward, as heights.
+pub fn scrim_span(caption: egui::Rect, screen_size: egui::Vec2, top: bool) -> (f32, f32) {
+    if top { (caption.max.y + caption.height(), 0.0) } else { (caption.min.y - caption.height(), screen_size.y) }
+}
+
+/// Opacity of the scrim `t` of the way from where it is clear to the edge.
+pub fn scrim_alpha(t: f32) -> f32 {
+    SCRIM_ALPHA * t * t * (3.0 - 2.0 * t)
+}
+
+/// A gradient across the screen behind `caption`, darkest at the edge the
+/// caption sits on and gone a caption's height past it, so the text reads
+/// without dimming the rest of the frame.
+pub fn draw_scrim(painter: &egui::Painter, caption: egui::Rect, screen_size: egui::Vec2, top: bool, alpha: f32) {
+    let (clear, edge) = scrim_span(caption, screen_size, top);
+    let mut mesh = egui::Mesh::default();
+    for step in 0..=SCRIM_STEPS {
+        let t = step as f32 / SCRIM_STEPS as f32;
+        let y = clear + (edge - clear) * t;
+        let color = egui::Color32::from_black_alpha((scrim_alpha(t) * alpha * 255.0) as u8);
+        mesh.colored_vertex(egui::pos2(0.0, y), color);
+        mesh.colored_vertex(egui::pos2(screen_size.x, y), color);
+        if step > 0 {
+            let i = step * 2;
+            mesh.add_triangle(i - 2, i - 1, i);
+            mesh.add_triangle(i - 1, i, i + 1);
+        }
+    }
+    painter.add(mesh);
+}
+
+/// Sizes of the caption's two lines at the usual scale: title and artist, then the year.
+pub const CAPTION_FONT_SIZES: [f32; 2] = [26.0, 22.0];
+/// Offsets of the faint copies that make the soft shadow of [`CaptionLook::Shadow`].
+pub const SHADOW_OFFSETS: [egui::Vec2; 4] =
+    [egui::vec2(1.0, 1.0), egui::vec2(2.0, 2.0), egui::vec2(1.0, 2.5), egui::vec2(2.5, 1.0)];
+
+/// The text of the caption's two lines.
+pub fn caption_text(metadata: &ArtworkMetadata) -> [String; 2] {
+    [format!("{} - {}", metadata.title, metadata.artist), metadata.year.clone()]
+}
+
+/// Width the caption's lines wrap at on a screen of `screen_size`.
+pub fn caption_wrap_width(screen_size: egui::Vec2, settings: OverlaySettings) -> f32 {
+    let inset = 2.0 * (CAPTION_MARGIN + CAPTION_PADDING.x);
+    // a box or shadow takes at most 60% of the width, longer titles wrap
+    let width = if settings.style == CaptionLook::Bar {
+        screen_size.x - inset
+    } else {
+        (screen_size.x * 0.6).min(screen_size.x - inset)
+    };
+    width.max(1.0)
+}
+
+/// Where the caption's backdrop and lines go once the lines are measured,
+/// whatever draws them: the screen or `render`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionFrame {
+    /// Backdrop, or just the area the text covers for [`CaptionLook::Shadow`].
+    pub rect: egui::Rect,
+    text_origin: egui::Pos2,
+    text_width: f32,
+    gap: f32,
+    anchor: f32,
+    pub look: CaptionLook,
+}
+
+impl CaptionFrame {
+    /// Places lines measuring `line_sizes`, set at `scale` times the usual size.
+    pub fn new(line_sizes: [egui::Vec2; 2], screen_size: egui::Vec2, scale: f32, settings: OverlaySettings) -> Self {
+        let full_width = settings.style == CaptionLook::Bar;
+        let inset = 2.0 * (CAPTION_MARGIN + CAPTION_PADDING.x);
+        let gap = 8.0 * scale;
+        let text_size =
+            egui::vec2(line_sizes[0].x.max(line_sizes[1].x), line_sizes[0].y + gap + line_sizes[1].y);
+        let rect = layout::caption_rect(
+            settings.position,
+            text_size + CAPTION_PADDING * 2.0,
+            screen_size,
+            CAPTION_MARGIN,
+            full_width,
+        );
+
+        let anchor = settings.position.anchor();
+        let text_x = if full_width {
+            CAPTION_MARGIN + CAPTION_PADDING.x + (screen_size.x - inset - text_size.x).max(0.0) * anchor
+        } else {
+            rect.min.x + CAPTION_PADDING.x
+        };
+
+        Self {
+            rect,
+            text_origin: egui::pos2(text_x, rect.min.y + CAPTION_PADDING.y),
+            text_width: text_size.x,
+            gap,
+            anchor,
+            look: settings.style,
+        }
+    }
+
+    /// The same frame moved by `offset`, for a caption laid out on part of the screen.
+    pub fn translated(mut self, offset: egui::Vec2) -> Self {
+        self.rect = self.rect.translate(offset);
+        self.text_origin += offset;
+        self
+    }
+
+    /// Corner radius of the backdrop, `None` when there is none.
+    pub fn rounding(&self) -> Option<f32> {
+        match self.look {
+            CaptionLook::Box => Some(8.0),
+            CaptionLook::Bar => Some(0.0),
+            CaptionLook::Shadow => None,
+        }
+    }
+
+    /// Top left corners of the lines measuring `line_sizes`, each lined up
+    /// with the side the caption sits on.
+    pub fn line_positions(&self, line_sizes: [egui::Vec2; 2]) -> [egui::Pos2; 2] {
+        let x = |size: egui::Vec2| self.text_origin.x + (self.text_width - size.x) * self.anchor;
+        let second = self.text_origin.y + line_sizes[0].y + self.gap;
+        [egui::pos2(x(line_sizes[0]), self.text_origin.y), egui::pos2(x(line_sizes[1]), second)]
+    }
+}
+
+/// Title, artist and year laid out for one screen, wrapped so they stay on it.
+pub struct Caption {
+    lines: [Arc<egui::Galley>; 2],
+    pub frame: CaptionFrame,
+}
+
+impl Caption {
+    /// Measures the caption at `scale` times the usual size.
+    pub fn layout(
+        painter: &egui::Painter,
+        metadata: &ArtworkMetadata,
+        screen_size: egui::Vec2,
+        scale: f32,
+        settings: OverlaySettings,
+    ) -> Self {
+        let wrap_width = caption_wrap_width(screen_size, settings);
+        let [title, year] = caption_text(metadata);
+        let line = |text: String, size: f32| {
+            let font = egui::FontId::new(size * scale, fonts::caption_family());
+            painter.layout(text, font, egui::Color32::PLACEHOLDER, wrap_width)
+        };
+        let lines = [line(title, CAPTION_FONT_SIZES[0]), line(year, CAPTION_FONT_SIZES[1])];
+        let frame = CaptionFrame::new([lines[0].size(), lines[1].size()], screen_size, scale, settings);
+        Self { lines, frame }
+    }
+
+    /// The same caption moved by `offset`, for one laid out on part of the screen.
+    pub fn translated(mut self, offset: egui::Vec2) -> Self {
+        self.frame = self.frame.translated(offset);
+        self
+    }
+
+    pub fn draw(&self, painter: &egui::Painter, alpha: f32, palette: CaptionPalette) {
+        if let Some(rounding) = self.frame.rounding() {
+            painter.rect_filled(self.frame.rect, rounding, palette.backdrop.gamma_multiply(alpha));
+        }
+
+        let colors = [palette.title, palette.year];
+        let positions = self.frame.line_positions([self.lines[0].size(), self.lines[1].size()]);
+        for ((line, color), pos) in self.lines.iter().zip(colors).zip(positions) {
+            if self.frame.look == CaptionLook::Shadow {
+                let shadow = palette.backdrop.gamma_multiply(alpha * 0.5);
+                for offset in SHADOW_OFFSETS {
+                    painter.galley(pos + offset, Arc::clone(line), shadow);
+                }
+            }
+            painter.galley(pos, Arc::clone(line), color.gamma_multiply(alpha));
+        }
+    }
+}
+
+/// strftime pattern for the clock, checked up front because formatting a bad one panics.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct ClockFormat(String);
+
+impl Default for ClockFormat {
+    fn default() -> Self {
+        ClockFormat("%H:%M".to_string())
+    }
+}
+
+impl TryFrom<String> for ClockFormat {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        use chrono::format::{Item, StrftimeItems};
+        if StrftimeItems::new(&pattern).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("invalid time format {:?}", pattern));
+        }
+        Ok(ClockFormat(pattern))
+    }
+}
+
+impl ClockFormat {
+    fn shows_seconds(&self) -> bool {
+        use chrono::format::{Item, Numeric, StrftimeItems};
+        StrftimeItems::new(&self.0)
+            .any(|item| matches!(item, Item::Numeric(Numeric::Second | Numeric::Timestamp, _)))
+    }
+
+    /// Time until the clock reads differently, the next second or the next minute.
+    pub fn until_change(&self) -> Duration {
+        use chrono::Timelike;
+        let now = chrono::Local::now();
+        // the nanoseconds go past a second during a leap second
+        let into_second = Duration::from_nanos((now.nanosecond() % 1_000_000_000) as u64);
+        if self.shows_seconds() {
+            Duration::from_secs(1) - into_second
+        } else {
+            Duration::from_secs(60) - Duration::from_secs(now.second() as u64) - into_second
+        }
+    }
+}
+
+impl From<ClockFormat> for String {
+    fn from(format: ClockFormat) -> Self {
+        format.0
+    }
+}
+
+impl FromStr for ClockFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ClockFormat::try_from(s.to_string())
+    }
+}
+
+/// Current time, and the date below it when `with_date`, in `corner`.
+pub fn draw_clock(ui: &mut egui::Ui, format: &ClockFormat, with_date: bool, corner: Corner, screen_size: egui::Vec2) {
+    let now = chrono::Local::now();
+    let palette = CaptionPalette::LIGHT_TEXT;
+    let painter = ui.painter();
+
+    let time = painter.layout_no_wrap(
+        now.format(&format.0).to_string(),
+        egui::FontId::proportional(26.0),
+        palette.title,
+    );
+    let date = with_date.then(|| {
+        painter.layout_no_wrap(
+            now.format("%A %-d %B").to_string(),
+            egui::FontId::proportional(18.0),
+            palette.year,
+        )
+    });
+
+    let padding = egui::vec2(15.0, 10.0);
+    let line_gap = 4.0;
+    let text_size = match &date {
+        Some(date) => egui::vec2(time.size().x.max(date.size().x), time.size().y + line_gap + date.size().y),
+        None => time.size(),
+    };
+    let rect = layout::corner_rect(corner, text_size + padding * 2.0, screen_size, 15.0);
+
+    painter.rect_filled(rect, 8.0, palette.backdrop);
+    let time_height = time.size().y;
+    painter.galley(rect.min + padding, time, palette.title);
+    if let Some(date) = date {
+        painter.galley(rect.min + padding + egui::vec2(0.0, time_height + line_gap), date, palette.year);
+    }
+}
+
+/// Side of a QR code on screen, rounded down to a whole number of pixels per module.
+const QR_SIZE: f32 = 120.0;
+
+/// A QR code texture in `corner`, on white with a label under it.
+pub fn draw_qr_code(
+    painter: &egui::Painter,
+    texture: &egui::TextureHandle,
+    corner: Corner,
+    screen_size: egui::Vec2,
+    alpha: f32,
+) {
+    let modules = texture.size()[0] as f32;
+    let side = (QR_SIZE / modules).floor().max(1.0) * modules;
+    let label = painter.layout_no_wrap(
+        "scan for details".to_string(),
+        egui::FontId::proportional(14.0),
+        egui::Color32::from_gray(40).gamma_multiply(alpha),
+    );
+    let label_height = label.size().y + 6.0;
+    let rect = layout::corner_rect(corner, egui::vec2(side, side + label_height), screen_size, CAPTION_MARGIN);
+
+    // the code's own quiet zone is the white around it
+    painter.rect_filled(rect, 6.0, egui::Color32::WHITE.gamma_multiply(alpha));
+    let code_rect = egui::Rect::from_min_size(rect.min, egui::Vec2::splat(side));
+    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    painter.image(texture.id(), code_rect, uv, egui::Color32::WHITE.gamma_multiply(alpha));
+    let label_pos = egui::pos2(rect.center().x - label.size().x / 2.0, code_rect.max.y);
+    painter.galley(label_pos, label, egui::Color32::from_gray(40));
+}
+
+/// Short notification drawn at the top of the screen, fading after a moment.
+pub struct Toast {
+    pub text: String,
+    pub shown_at: Instant,
+}
+
+const TOAST_VISIBLE: Duration = Duration::from_millis(1800);
+const TOAST_FADE: Duration = Duration::from_millis(400);
+
+impl Toast {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            shown_at: Instant::now(),
+        }
+    }
+
+    /// Opacity right now, zero once the toast has run its course.
+    pub fn alpha(&self) -> f32 {
+        let fading = self.shown_at.elapsed().saturating_sub(TOAST_VISIBLE);
+        1.0 - (fading.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0)
+    }
+
+    /// Time until the toast starts fading, zero once it is.
+    pub fn until_fade(&self) -> Duration {
+        TOAST_VISIBLE.saturating_sub(self.shown_at.elapsed())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.shown_at.elapsed() >= TOAST_VISIBLE + TOAST_FADE
+    }
+}
+
+pub fn draw_toast(ui: &mut egui::Ui, toast: &Toast, screen_size: egui::Vec2) {
+    let alpha = toast.alpha();
+    let galley = ui.painter().layout_no_wrap(
+        toast.text.clone(),
+        egui::FontId::proportional(24.0),
+        egui::Color32::WHITE.gamma_multiply(alpha),
+    );
+
+    let padding = egui::vec2(20.0, 12.0);
+    let rect = egui::Rect::from_center_size(
+        egui::pos2(screen_size.x / 2.0, 60.0),
+        galley.size() + padding * 2.0,
+    );
+
+    ui.painter().rect_filled(rect, 8.0, egui::Color32::from_black_alpha((200.0 * alpha) as u8));
+    ui.painter().galley(rect.min + padding, galley, egui::Color32::WHITE);
+}
+
+/// "Loading next…" with a spinner in `corner`, while the next slide is late.
+pub fn draw_loading_next(ui: &mut egui::Ui, corner: Corner, screen_size: egui::Vec2) {
+    let galley = ui.painter().layout_no_wrap(
+        "Loading next…".to_string(),
+        egui::FontId::proportional(18.0),
+        egui::Color32::WHITE,
+    );
+    let padding = egui::vec2(14.0, 8.0);
+    let spinner = galley.size().y;
+    let size = egui::vec2(spinner + padding.x + galley.size().x, galley.size().y) + padding * 2.0;
+    let rect = layout::corner_rect(corner, size, screen_size, 20.0);
+
+    ui.painter().rect_filled(rect, size.y / 2.0, egui::Color32::from_black_alpha(160));
+    let spinner_rect = egui::Rect::from_min_size(rect.min + padding, egui::Vec2::splat(spinner));
+    egui::Spinner::new().size(spinner).color(egui::Color32::WHITE).paint_at(ui, spinner_rect);
+    let text_pos = egui::pos2(spinner_rect.max.x + padding.x, rect.min.y + padding.y);
+    ui.painter().galley(text_pos, galley, egui::Color32::WHITE);
+}
+
+/// A little pill of `text` in `corner`, `alpha` of it as the caption fades.
+pub fn draw_badge(ui: &mut egui::Ui, text: String, corner: Corner, alpha: f32, screen_size: egui::Vec2) {
+    let galley =
+        ui.painter().layout_no_wrap(text, egui::FontId::proportional(18.0), egui::Color32::WHITE.gamma_multiply(alpha));
+    let padding = egui::vec2(14.0, 8.0);
+    let size = galley.size() + padding * 2.0;
+    let rect = layout::corner_rect(corner, size, screen_size, 20.0);
+    ui.painter().rect_filled(rect, size.y / 2.0, egui::Color32::from_black_alpha((160.0 * alpha) as u8));
+    ui.painter().galley(rect.min + padding, galley, egui::Color32::WHITE);
+}
+
+/// A chapter's title card over the whole screen, `alpha` of it as it fades away.
+pub fn draw_title_card(ui: &mut egui::Ui, card: &TitleCard, alpha: f32, screen_size: egui::Vec2) {
+    let painter = ui.painter();
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, screen_size);
+    painter.rect_filled(screen, 0.0, egui::Color32::BLACK.gamma_multiply(alpha));
+    let center = screen.center();
+    painter.text(
+        center,
+        egui::Align2::CENTER_BOTTOM,
+        &card.title,
+        egui::FontId::proportional(screen_size.y / 12.0),
+        egui::Color32::WHITE.gamma_multiply(alpha),
+    );
+    painter.text(
+        center + egui::vec2(0.0, screen_size.y / 40.0),
+        egui::Align2::CENTER_TOP,
+        card.subtitle(),
+        egui::FontId::proportional(screen_size.y / 30.0),
+        egui::Color32::from_gray(180).gamma_multiply(alpha),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_style_follows_what_is_behind_the_caption() {
+        let sample = CaptionSample { background: 0.1, image: 0.9 };
+        let narrow_portrait = egui::Rect::from_min_max(egui::pos2(800.0, 0.0), egui::pos2(1120.0, 1080.0));
+        let panorama = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1920.0, 1080.0));
+        let caption = egui::Rect::from_min_size(egui::pos2(15.0, 945.0), egui::vec2(700.0, 110.0));
+
+        let behind_portrait = CaptionPalette::choose(CaptionStyle::Auto, sample, narrow_portrait, caption);
+        assert_eq!(behind_portrait, CaptionPalette::LIGHT_TEXT);
+        assert_eq!(CaptionPalette::choose(CaptionStyle::Auto, sample, panorama, caption), CaptionPalette::DARK_TEXT);
+        assert_eq!(CaptionPalette::choose(CaptionStyle::Light, sample, panorama, caption), CaptionPalette::LIGHT_TEXT);
+    }
+
+    #[test]
+    fn caption_fades_in_after_the_delay() {
+        assert_eq!(CaptionReveal::alpha_at(Duration::from_millis(300), CAPTION_DELAY), 0.0);
+        assert_eq!(CaptionReveal::alpha_at(CAPTION_DELAY, CAPTION_DELAY), 0.0);
+        let halfway = CaptionReveal::alpha_at(CAPTION_DELAY + CAPTION_FADE_IN / 2, CAPTION_DELAY);
+        assert!((halfway - 0.5).abs() < 1e-3);
+        assert_eq!(CaptionReveal::alpha_at(Duration::from_secs(5), CAPTION_DELAY), 1.0);
+
+        assert!(CaptionReveal::new(CAPTION_DELAY).until_start().is_some());
+        assert_eq!(CaptionReveal::new(Duration::ZERO).until_start(), None);
+    }
+
+    #[test]
+    fn collage_captions_take_turns() {
+        let slide = Duration::from_secs(12);
+        assert_eq!(caption_turn(1, Duration::from_secs(5), slide), (0, None));
+        assert_eq!(caption_turn(4, Duration::from_secs(1), slide), (0, Some(Duration::from_secs(2))));
+        assert_eq!(caption_turn(4, Duration::from_secs(7), slide), (2, Some(Duration::from_secs(2))));
+        assert_eq!(caption_turn(4, Duration::from_secs(11), slide), (3, None));
+    }
+
+    #[test]
+    fn wakeups_for_the_caption_and_clock() {
+        assert_eq!(OverlayMode::AutoHide.until_fade(Duration::from_secs(2)), Some(Duration::from_secs(3)));
+        assert_eq!(OverlayMode::AutoHide.until_fade(Duration::from_secs(5)), None);
+        assert_eq!(OverlayMode::Always.until_fade(Duration::ZERO), None);
+
+        assert!(!ClockFormat::default().shows_seconds());
+        assert!("%T".parse::<ClockFormat>().unwrap().shows_seconds());
+        assert!("%I:%M:%S %p".parse::<ClockFormat>().unwrap().shows_seconds());
+        assert!(ClockFormat::default().until_change() <= Duration::from_secs(60));
+    }
+}
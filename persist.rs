@@ -0,0 +1,30 @@
+//! Small helpers for files the slideshow writes next to the artworks.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Writes `contents` to a temporary file beside `path` and renames it into
+/// place, so a crash or power cut leaves either the old or the new file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+}
+
+/// `image` relative to `root` with forward slashes, or the full path if it lies outside.
+pub fn relative_key(root: &Path, image: &Path) -> String {
+    let relative = image.strip_prefix(root).unwrap_or(image);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,1017 @@
+pub mod blur;
+pub mod cache;
+pub mod color;
+pub mod depth;
+pub mod icc;
+pub mod qr;
+#[cfg(feature = "raw")]
+pub mod raw;
+#[cfg(feature = "svg")]
+pub mod svg;
+
+use crate::layout::{self, CaptionPosition, Fit, SlideLayout};
+use crate::scan::{ArtworkInfo, ArtworkMetadata};
+use crate::source::{ImageSource, SourceError};
+use cache::{Cached, DiskCache};
+use color::ColorFilter;
+use depth::Dither;
+use image::{DynamicImage, GenericImageView, GrayImage, ImageReader, ImageResult, RgbaImage, imageops};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, Cursor};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// What fills the screen around the artwork.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundMode {
+    /// Darkened, blurred copy of the artwork.
+    Blur,
+    /// Darkened dominant color of the artwork.
+    Color,
+    /// Plain black, skips background generation entirely.
+    Black,
+}
+
+impl FromStr for BackgroundMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blur" => Ok(BackgroundMode::Blur),
+            "color" => Ok(BackgroundMode::Color),
+            "black" => Ok(BackgroundMode::Black),
+            other => Err(format!("expected blur, color or black, got {:?}", other)),
+        }
+    }
+}
+
+/// How the blurred background is made.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BlurQuality {
+    /// Scaled far down and back up, a heavy blur for little work.
+    #[default]
+    Fast,
+    /// Several box blur passes, smoother but much slower on weak hardware.
+    Nice,
+}
+
+impl FromStr for BlurQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fast" => Ok(BlurQuality::Fast),
+            "nice" => Ok(BlurQuality::Nice),
+            other => Err(format!("expected fast or nice, got {:?}", other)),
+        }
+    }
+}
+
+/// How much [`ProcessingOptions::vignette`] takes off in the corners, on top of the darkening.
+const VIGNETTE_STRENGTH: f32 = 0.5;
+
+/// How far [`BlurQuality::Fast`] scales the background down before scaling it back up.
+const FAST_BLUR_FACTOR: u32 = 16;
+
+/// Resolution the background is blurred at, written `640x360`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BlurSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for BlurSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("expected a size like 640x360, got {:?}", s);
+        let (width, height) = s.split_once(['x', 'X']).ok_or_else(error)?;
+        Ok(BlurSize {
+            width: width.trim().parse().map_err(|_| error())?,
+            height: height.trim().parse().map_err(|_| error())?,
+        })
+    }
+}
+
+/// Slide durations in seconds for `--adaptive-duration`, written `5..30`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DurationRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl FromStr for DurationRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("expected seconds like 5..30, got {:?}", s);
+        let (min, max) = s.split_once("..").ok_or_else(error)?;
+        let range = DurationRange {
+            min: min.trim().parse().map_err(|_| error())?,
+            max: max.trim().parse().map_err(|_| error())?,
+        };
+        if !(range.min > 0.0 && range.min <= range.max) {
+            return Err(format!("{:?} should go from a positive duration up to a longer one", s));
+        }
+        Ok(range)
+    }
+}
+
+/// Detail values mapped onto the ends of a [`DurationRange`], from
+/// [`color::detail`]: a plain field to a crowded scene.
+const DETAIL_SPAN: RangeInclusive<f32> = 0.01..=0.12;
+
+impl DurationRange {
+    /// The duration for an image of this `detail`, longer for busier ones.
+    pub fn for_detail(&self, detail: f32) -> Duration {
+        let t = ((detail - DETAIL_SPAN.start()) / (DETAIL_SPAN.end() - DETAIL_SPAN.start())).clamp(0.0, 1.0);
+        Duration::from_secs_f32(self.min + t * (self.max - self.min))
+    }
+}
+
+/// Sane values for [`ProcessingOptions::max_dimension`].
+pub const MAX_DIMENSION_RANGE: RangeInclusive<u32> = 256..=8192;
+/// Sane values for either side of the blur size.
+pub const BLUR_SIDE_RANGE: RangeInclusive<u32> = 16..=2048;
+/// Sane values for [`ProcessingOptions::brightness`] and [`ProcessingOptions::gamma`].
+pub const BRIGHTNESS_RANGE: RangeInclusive<f32> = 0.25..=4.0;
+pub const GAMMA_RANGE: RangeInclusive<f32> = 0.25..=4.0;
+
+#[derive(Clone)]
+pub enum Background {
+    Blurred(DynamicImage),
+    Color([u8; 3]),
+    Black,
+}
+
+/// Brightness of what ends up behind the caption, measured once per image so
+/// the caption colors don't flicker between frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CaptionSample {
+    /// Mean luminance of the background where the caption goes.
+    pub background: f32,
+    /// Mean luminance of the same area of the artwork, for when it reaches under the caption.
+    pub image: f32,
+}
+
+/// Longest side of the quick first image from [`process_preview`].
+pub const PREVIEW_DIMENSION: u32 = 512;
+
+/// Longest side of the filmstrip thumbnails from [`thumbnail`].
+pub const THUMBNAIL_SIZE: u32 = 160;
+
+/// Images smaller than this both ways are taken for pixel art unless their
+/// sidecar says otherwise.
+pub const PIXEL_ART_SIZE: u32 = 512;
+
+#[derive(Clone)]
+pub struct ProcessedImage {
+    pub main_image: DynamicImage,
+    /// Size of the full-quality `main_image`. A preview is drawn at this size
+    /// too, so swapping in the full image doesn't move anything on screen.
+    pub display_size: (u32, u32),
+    /// Size of the artwork itself, which zooming in can go up to. A collage's
+    /// is its `display_size`.
+    pub source_size: (u32, u32),
+    /// Produced by [`process_preview`], to be replaced by the full image.
+    pub is_preview: bool,
+    pub fit: Fit,
+    pub background: Background,
+    pub caption_sample: CaptionSample,
+    /// The artworks on the slide, more than one for a collage.
+    pub works: Vec<ArtworkMetadata>,
+    /// Code for the `url` of a slide with a single work that has one.
+    pub qr_code: Option<GrayImage>,
+    /// `main_image` is pixel art kept at its own size, to be drawn with hard edges.
+    pub pixel_art: bool,
+    /// How long the slide stays for the detail in it, with `--adaptive-duration`.
+    pub detail_duration: Option<Duration>,
+}
+
+/// Processes one artwork from its encoded bytes, [`process_image`] or
+/// [`process_preview`]. The path names it in the cache and the log.
+pub type ProcessFn = fn(&Path, &[u8], ArtworkMetadata, &ProcessingOptions) -> Result<ProcessedImage, PipelineError>;
+
+/// Gap between the cells of a collage, as a fraction of its height.
+const COLLAGE_GAP: f32 = 0.01;
+
+/// Knobs for [`process_image`]. The defaults match what the slideshow has always used.
+#[derive(Clone, Debug)]
+pub struct ProcessingOptions {
+    /// Longest side of the foreground image, larger images are downscaled.
+    pub max_dimension: u32,
+    /// Images with more pixels are refused before they are decoded.
+    pub max_pixels: u64,
+    /// Camera raw files are shown by their embedded JPEG without being developed.
+    pub raw_use_preview: bool,
+    pub blur_width: u32,
+    pub blur_height: u32,
+    pub blur_quality: BlurQuality,
+    /// Box blur passes and radius, for [`BlurQuality::Nice`].
+    pub blur_passes: u32,
+    pub blur_radius: i32,
+    /// Multiplier applied to the background's color channels.
+    pub darken: f32,
+    /// Multiplier applied to the artwork's color channels, for a dim projector.
+    pub brightness: f32,
+    /// Above 1 lifts the artwork's shadows, below 1 deepens them.
+    pub gamma: f32,
+    /// Darkens the corners of the blurred background further.
+    pub vignette: bool,
+    pub background: BackgroundMode,
+    /// Default fit, artworks can override it in their sidecar.
+    pub fit: Fit,
+    /// Default look, artworks can override it in their sidecar too.
+    pub filter: ColorFilter,
+    /// Where resized and blurred images are kept between runs, if anywhere.
+    pub cache: Option<DiskCache>,
+    /// Area the caption covers, as `[x0, y0, x1, y1]` fractions, sampled for its colors.
+    pub caption_region: [f32; 4],
+    /// How 16-bit images are brought down to 8 bits for the texture.
+    pub dither: Dither,
+    /// How many artworks go on a slide.
+    pub layout: SlideLayout,
+    /// Size of a collage, the screen's in physical pixels once it is known.
+    pub canvas: (u32, u32),
+    /// Slide durations to pick from by the detail in each image, instead of one for all.
+    pub adaptive_duration: Option<DurationRange>,
+    /// Stops processing between its stages once the image isn't wanted anymore.
+    pub cancel: Cancel,
+}
+
+/// Whether the image being processed is still wanted: cancelled once a
+/// newer request comes along, so the work stops at the next stage instead
+/// of finishing an image nobody will see. The default is never cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct Cancel(Option<(Arc<AtomicU64>, u64)>);
+
+impl Cancel {
+    /// Cancelled once `latest` is no longer `generation`.
+    pub fn unless_latest(latest: Arc<AtomicU64>, generation: u64) -> Self {
+        Self(Some((latest, generation)))
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.as_ref().is_some_and(|(latest, generation)| latest.load(Ordering::Relaxed) != *generation)
+    }
+
+    fn check(&self) -> Result<(), PipelineError> {
+        if self.is_cancelled() { Err(PipelineError::Cancelled) } else { Ok(()) }
+    }
+}
+
+fn clamp_setting(name: &str, value: u32, range: RangeInclusive<u32>) -> u32 {
+    let clamped = value.clamp(*range.start(), *range.end());
+    if clamped != value {
+        tracing::warn!("{} {} is out of range, using {}", name, value, clamped);
+    }
+    clamped
+}
+
+/// Like [`clamp_setting`] for a factor, which is 1 when not a number.
+fn clamp_factor(name: &str, value: f32, range: RangeInclusive<f32>) -> f32 {
+    let clamped = if value.is_nan() { 1.0 } else { value.clamp(*range.start(), *range.end()) };
+    if clamped != value {
+        tracing::warn!("{} {} is out of range, using {}", name, value, clamped);
+    }
+    clamped
+}
+
+impl ProcessingOptions {
+    /// Pulls the sizes back into their sane ranges, warning about each one that was off.
+    pub fn clamped(self) -> Self {
+        Self {
+            max_dimension: clamp_setting("max dimension", self.max_dimension, MAX_DIMENSION_RANGE),
+            blur_width: clamp_setting("blur width", self.blur_width, BLUR_SIDE_RANGE),
+            blur_height: clamp_setting("blur height", self.blur_height, BLUR_SIDE_RANGE),
+            brightness: clamp_factor("brightness", self.brightness, BRIGHTNESS_RANGE),
+            gamma: clamp_factor("gamma", self.gamma, GAMMA_RANGE),
+            ..self
+        }
+    }
+
+    /// The options for one artwork: a covering image hides the background, so none is made.
+    fn for_artwork(&self, metadata: &ArtworkMetadata) -> ProcessingOptions {
+        let fit = metadata.fit.unwrap_or(self.fit);
+        ProcessingOptions {
+            fit,
+            filter: metadata.filter.unwrap_or(self.filter),
+            background: if fit == Fit::Cover { BackgroundMode::Black } else { self.background },
+            ..self.clone()
+        }
+    }
+
+    /// Blurs at a quarter of the screen resolution, `screen` being in physical pixels.
+    pub fn blur_for_screen(&mut self, screen: (u32, u32)) {
+        self.blur_width = (screen.0 / 4).clamp(*BLUR_SIDE_RANGE.start(), *BLUR_SIDE_RANGE.end());
+        self.blur_height = (screen.1 / 4).clamp(*BLUR_SIDE_RANGE.start(), *BLUR_SIDE_RANGE.end());
+    }
+}
+
+impl Default for ProcessingOptions {
+    fn default() -> Self {
+        Self {
+            max_dimension: 2048,
+            max_pixels: 100_000_000,
+            raw_use_preview: false,
+            blur_width: 640,
+            blur_height: 360,
+            blur_quality: BlurQuality::Fast,
+            blur_passes: 3,
+            blur_radius: 10,
+            darken: 0.6,
+            brightness: 1.0,
+            gamma: 1.0,
+            vignette: false,
+            background: BackgroundMode::Blur,
+            fit: Fit::Contain,
+            filter: ColorFilter::None,
+            cache: None,
+            caption_region: CaptionPosition::default().region(false),
+            dither: Dither::Ordered,
+            layout: SlideLayout::Single,
+            canvas: (1920, 1080),
+            adaptive_duration: None,
+            cancel: Cancel::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file was read but is not a decodable image.
+    Decode(image::ImageError),
+    /// The file is in a format, or a variant of one, there is no decoder for.
+    UnsupportedFormat(image::error::UnsupportedError),
+    /// Decoding the image would take more memory than it is allowed.
+    TooLarge(image::error::LimitError),
+    /// Processing panicked, with the panic message.
+    Panicked(String),
+    /// A playlist's URL entry could not be downloaded.
+    Download(String),
+    /// Another image was asked for before this one was done.
+    Cancelled,
+    /// A collage was asked for without any artworks in it.
+    NoArtworks,
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::Io(err) => write!(f, "cannot read image: {}", err),
+            PipelineError::Decode(err) => write!(f, "cannot decode image: {}", err),
+            PipelineError::UnsupportedFormat(err) => write!(f, "unsupported image: {}", err),
+            PipelineError::TooLarge(err) => write!(f, "image too large to decode: {}", err),
+            PipelineError::Panicked(message) => write!(f, "processing crashed: {}", message),
+            PipelineError::Download(message) => write!(f, "{}", message),
+            PipelineError::Cancelled => write!(f, "cancelled, another image was asked for"),
+            PipelineError::NoArtworks => write!(f, "no artworks to compose"),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PipelineError::Io(err) => Some(err),
+            PipelineError::Decode(err) => Some(err),
+            PipelineError::UnsupportedFormat(err) => Some(err),
+            PipelineError::TooLarge(err) => Some(err),
+            PipelineError::Panicked(_)
+            | PipelineError::Download(_)
+            | PipelineError::Cancelled
+            | PipelineError::NoArtworks => None,
+        }
+    }
+}
+
+impl PipelineError {
+    /// Whether the same file could work another time around: the disk or the
+    /// server may be back by then. A file that doesn't decode never will.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            PipelineError::Io(_) | PipelineError::Download(_) => true,
+            PipelineError::Decode(_)
+            | PipelineError::UnsupportedFormat(_)
+            | PipelineError::TooLarge(_)
+            | PipelineError::Panicked(_)
+            | PipelineError::Cancelled
+            | PipelineError::NoArtworks => false,
+        }
+    }
+}
+
+impl From<SourceError> for PipelineError {
+    fn from(err: SourceError) -> Self {
+        match err {
+            SourceError::Io(err) => PipelineError::Io(err),
+        }
+    }
+}
+
+impl From<image::ImageError> for PipelineError {
+    fn from(err: image::ImageError) -> Self {
+        match err {
+            image::ImageError::IoError(err) => PipelineError::Io(err),
+            image::ImageError::Unsupported(err) => PipelineError::UnsupportedFormat(err),
+            image::ImageError::Limits(err) => PipelineError::TooLarge(err),
+            err => PipelineError::Decode(err),
+        }
+    }
+}
+
+/// Runs `process`, turning a panic into an error so one pathological file
+/// fails like any unreadable one instead of ending the show.
+pub fn catch_panic<T>(process: impl FnOnce() -> Result<T, PipelineError>) -> Result<T, PipelineError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(process)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(PipelineError::Panicked(message))
+    })
+}
+
+pub fn process_image(
+    path: &Path,
+    bytes: &[u8],
+    metadata: ArtworkMetadata,
+    options: &ProcessingOptions,
+) -> Result<ProcessedImage, PipelineError> {
+    let options = &options.for_artwork(&metadata);
+    let with_blur = options.background == BackgroundMode::Blur;
+    // a JPEG decodes scaled down and a cached image may be downscaled, the header has the real size
+    let header = dimensions(bytes).ok();
+    let pixel_art = is_pixel_art(&metadata, header);
+    // the cache holds the smoothly downscaled image, wrong for large pixel art
+    let key = options
+        .cache
+        .as_ref()
+        .filter(|_| metadata.pixel_art != Some(true))
+        .and_then(|cache| cache.key(path, options));
+    let cached = match (&options.cache, &key) {
+        (Some(cache), Some(key)) => cache.load(key, with_blur),
+        _ => None,
+    };
+
+    let (cached, source_size) = match cached {
+        Some(cached) => {
+            tracing::debug!("{}: cache hit", path.display());
+            let size = header.unwrap_or(cached.main_image.dimensions());
+            (cached, size)
+        }
+        None => {
+            options.cancel.check()?;
+            let started = Instant::now();
+            let img = decode(bytes, path, options, (!pixel_art).then_some(options.max_dimension))?;
+            options.cancel.check()?;
+            let decoded = Instant::now();
+            let size = header.unwrap_or(img.dimensions());
+            // the foreground and the background only share the decoded image, both get the filter
+            let ((main_image, resize_time), (blurred, blur_time)) = rayon::join(
+                || timed(|| options.filter.apply(resize_main(&img, size, options, pixel_art))),
+                || timed(|| with_blur.then(|| options.filter.apply(blurred_background(&img, options)))),
+            );
+            // a blur cut short is no good for the cache either
+            options.cancel.check()?;
+            tracing::debug!(
+                "{}: decode ({}×{}) {:?}, resize {:?}, blur ({:?}) {:?}, total {:?}",
+                path.display(),
+                img.width(),
+                img.height(),
+                decoded - started,
+                resize_time,
+                options.blur_quality,
+                blur_time,
+                started.elapsed()
+            );
+
+            let cached = Cached { main_image, blurred };
+            if let (Some(cache), Some(key)) = (&options.cache, key) {
+                cache.store(key, cached.clone());
+            }
+            (cached, size)
+        }
+    };
+
+    let display_size = cached.main_image.dimensions();
+    let processed = assemble(cached, display_size, vec![metadata], options, false);
+    Ok(ProcessedImage { pixel_art, source_size, ..processed })
+}
+
+/// Like [`process_image`] but fast: the image is scaled with a cheap filter to
+/// at most [`PREVIEW_DIMENSION`] and the background is blurred from that, since
+/// the blur hides the difference anyway. A cache hit returns full quality.
+pub fn process_preview(
+    path: &Path,
+    bytes: &[u8],
+    metadata: ArtworkMetadata,
+    options: &ProcessingOptions,
+) -> Result<ProcessedImage, PipelineError> {
+    let options = &options.for_artwork(&metadata);
+    let with_blur = options.background == BackgroundMode::Blur;
+    let header = dimensions(bytes).ok();
+    let pixel_art = is_pixel_art(&metadata, header);
+    if let Some(cache) = options.cache.as_ref().filter(|_| metadata.pixel_art != Some(true)) {
+        if let Some(cached) = cache.key(path, options).and_then(|key| cache.load(&key, with_blur)) {
+            let display_size = cached.main_image.dimensions();
+            let source_size = header.unwrap_or(display_size);
+            let processed = assemble(cached, display_size, vec![metadata], options, false);
+            return Ok(ProcessedImage { pixel_art, source_size, ..processed });
+        }
+    }
+
+    options.cancel.check()?;
+    let started = Instant::now();
+    let img = decode(bytes, path, options, (!pixel_art).then_some(PREVIEW_DIMENSION))?;
+    options.cancel.check()?;
+    let decoded = Instant::now();
+    let source_size = header.unwrap_or(img.dimensions());
+    let display_size = target_size(source_size, options.max_dimension);
+    let main_image = options.filter.apply(if pixel_art {
+        pixel_art_within(&img, PREVIEW_DIMENSION)
+    } else {
+        img.thumbnail(PREVIEW_DIMENSION, PREVIEW_DIMENSION)
+    });
+    // blurred from the filtered image, so it needs no filter of its own
+    let blurred = with_blur.then(|| blurred_background(&main_image, options));
+    tracing::debug!(
+        "{}: preview decode {:?}, scale and blur {:?}",
+        path.display(),
+        decoded - started,
+        decoded.elapsed()
+    );
+    let processed = assemble(Cached { main_image, blurred }, display_size, vec![metadata], options, true);
+    Ok(ProcessedImage { pixel_art, source_size, ..processed })
+}
+
+/// Composes `artworks` into one slide the shape of [`ProcessingOptions::canvas`],
+/// each letterboxed in its cell of a `grid` of columns and rows, in order.
+/// `process` makes the cells, so a collage of previews is a preview too. An
+/// artwork that fails leaves its cell empty, the slide only fails when none
+/// are left.
+pub fn process_collage(
+    artworks: &[ArtworkInfo],
+    grid: (usize, usize),
+    options: &ProcessingOptions,
+    source: &(dyn ImageSource + Sync),
+    process: ProcessFn,
+) -> Result<ProcessedImage, PipelineError> {
+    // the cells are cached like single artworks, the background is made and the colors adjusted for the whole slide
+    let cell_options = ProcessingOptions {
+        background: BackgroundMode::Black,
+        fit: Fit::Contain,
+        brightness: 1.0,
+        gamma: 1.0,
+        ..options.clone()
+    };
+    let results: Vec<_> = artworks
+        .par_iter()
+        .map(|info| {
+            let metadata = ArtworkMetadata { fit: None, ..info.metadata.clone() };
+            catch_panic(|| process(&info.path, &source.read(info)?, metadata, &cell_options))
+        })
+        .collect();
+    options.cancel.check()?;
+
+    let (width, height) = target_size(options.canvas, options.max_dimension);
+    let (width, height) = (width.max(1), height.max(1));
+    let mut canvas = RgbaImage::new(width, height);
+    let size = egui::vec2(width as f32, height as f32);
+    let cells = layout::grid_cells(size, grid, height as f32 * COLLAGE_GAP);
+    let mut works = Vec::new();
+    let mut is_preview = false;
+    let mut first_error = None;
+    for ((info, result), cell) in artworks.iter().zip(results).zip(cells) {
+        let processed = match result {
+            Ok(processed) => processed,
+            Err(err) => {
+                tracing::error!("leaving {} out of the collage: {}", info.path.display(), err);
+                first_error.get_or_insert(err);
+                continue;
+            }
+        };
+        let (image_width, image_height) = processed.display_size;
+        let rect = layout::contain(egui::vec2(image_width as f32, image_height as f32), cell.size())
+            .translate(cell.min.to_vec2());
+        let image = processed.main_image.resize_exact(
+            (rect.width() as u32).max(1),
+            (rect.height() as u32).max(1),
+            imageops::FilterType::Lanczos3,
+        );
+        imageops::overlay(&mut canvas, &image.to_rgba8(), rect.min.x as i64, rect.min.y as i64);
+        is_preview |= processed.is_preview;
+        works.extend(processed.works);
+    }
+    if works.is_empty() {
+        return Err(first_error.unwrap_or(PipelineError::NoArtworks));
+    }
+
+    let options = &ProcessingOptions { fit: Fit::Contain, ..options.clone() };
+    let main_image = DynamicImage::ImageRgba8(canvas);
+    // blurred from the whole collage, the gaps between the cells only let it through darkened
+    let blurred = (options.background == BackgroundMode::Blur).then(|| blurred_background(&main_image, options));
+    Ok(assemble(Cached { main_image, blurred }, (width, height), works, options, is_preview))
+}
+
+/// A small copy of `info`'s image, read from `source`, for the filmstrip. From
+/// `cache` when it has one.
+pub fn thumbnail(
+    info: &ArtworkInfo,
+    source: &dyn ImageSource,
+    cache: Option<&DiskCache>,
+) -> Result<DynamicImage, PipelineError> {
+    let path = &info.path;
+    let key = cache.and_then(|cache| cache.thumbnail_key(path, THUMBNAIL_SIZE));
+    if let (Some(cache), Some(key)) = (cache, &key) {
+        if let Some(cached) = cache.load(key, false) {
+            return Ok(cached.main_image);
+        }
+    }
+
+    let bytes = source.read(info)?;
+    let options = ProcessingOptions { max_dimension: THUMBNAIL_SIZE, ..ProcessingOptions::default() };
+    let thumbnail = decode(&bytes, path, &options, Some(THUMBNAIL_SIZE))?.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.store(key, Cached { main_image: thumbnail.clone(), blurred: None });
+    }
+    Ok(thumbnail)
+}
+
+fn assemble(
+    cached: Cached,
+    display_size: (u32, u32),
+    works: Vec<ArtworkMetadata>,
+    options: &ProcessingOptions,
+    is_preview: bool,
+) -> ProcessedImage {
+    let Cached { main_image, blurred } = cached;
+    let background = match (options.background, blurred) {
+        (BackgroundMode::Blur, Some(blurred)) => Background::Blurred(blurred),
+        (BackgroundMode::Color, _) => {
+            Background::Color(color::dominant_color(&main_image).map(|c| (c as f32 * options.darken) as u8))
+        }
+        _ => Background::Black,
+    };
+
+    // 16-bit images stay that way through resizing and the cache, up to here
+    let main_image = depth::to_8_bit(main_image, options.dither);
+    // after the cache, so changing them doesn't need new entries
+    let main_image = color::adjust(main_image, options.brightness, options.gamma);
+    let caption_sample = CaptionSample {
+        background: match &background {
+            Background::Blurred(blurred) => color::mean_luminance(blurred, options.caption_region),
+            Background::Color(rgb) => color::luminance(*rgb),
+            Background::Black => 0.0,
+        },
+        image: color::mean_luminance(&main_image, options.caption_region),
+    };
+    let qr_code = match works.as_slice() {
+        [work] => work.url.as_deref().and_then(qr::code),
+        _ => None,
+    };
+    let detail_duration = options.adaptive_duration.map(|range| {
+        let detail = color::detail(&main_image);
+        let duration = range.for_detail(detail);
+        let title = works.first().map_or("", |work| work.title.as_str());
+        tracing::debug!("{:?}: detail {:.3}, on screen for {:.1?}", title, detail, duration);
+        duration
+    });
+
+    ProcessedImage {
+        main_image,
+        display_size,
+        source_size: display_size,
+        is_preview,
+        fit: options.fit,
+        background,
+        caption_sample,
+        works,
+        qr_code,
+        pixel_art: false,
+        detail_duration,
+    }
+}
+
+fn timed<T>(work: impl FnOnce() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let result = work();
+    (result, started.elapsed())
+}
+
+/// Size of the foreground image for a source of the given size, downscaled to fit `max_dimension`.
+fn target_size((img_width, img_height): (u32, u32), max_dimension: u32) -> (u32, u32) {
+    let scale = if img_width.max(img_height) > max_dimension {
+        max_dimension as f32 / img_width.max(img_height) as f32
+    } else {
+        1.0
+    };
+
+    ((img_width as f32 * scale) as u32, (img_height as f32 * scale) as u32)
+}
+
+/// `bytes`, the image at `path`, decoded in sRGB, float images tone-mapped to 16 bits.
+/// A drawing is drawn at `options.max_dimension`, there is nothing to gain from more.
+/// A JPEG may come out smaller than it is, though still over `scale_to`.
+fn decode(
+    bytes: &[u8],
+    path: &Path,
+    options: &ProcessingOptions,
+    scale_to: Option<u32>,
+) -> Result<DynamicImage, PipelineError> {
+    #[cfg(feature = "svg")]
+    if svg::is_svg(path) {
+        return Ok(svg::rasterize(bytes, path, options.max_dimension)?);
+    }
+    #[cfg(feature = "raw")]
+    if raw::is_raw(path) {
+        return raw::decode(bytes, path, options);
+    }
+    Ok(depth::tone_map(icc::decode(bytes, path, options.max_pixels, scale_to)?))
+}
+
+/// The size of the image in `bytes`, from its header.
+fn dimensions(bytes: &[u8]) -> ImageResult<(u32, u32)> {
+    ImageReader::new(Cursor::new(bytes)).with_guessed_format()?.into_dimensions()
+}
+
+/// Whether the artwork in `bytes` is taller than wide. The header says so
+/// where it can be read, anything else is decoded to find out.
+pub fn is_portrait(bytes: &[u8], path: &Path, options: &ProcessingOptions) -> Result<bool, PipelineError> {
+    let (width, height) = match dimensions(bytes) {
+        Ok(size) => size,
+        Err(_) => decode(bytes, path, options, None)?.dimensions(),
+    };
+    Ok(height > width)
+}
+
+/// Whether the artwork is pixel art: as its sidecar says, or when its source
+/// is small, of the `size` its header gives.
+fn is_pixel_art(metadata: &ArtworkMetadata, size: Option<(u32, u32)>) -> bool {
+    metadata.pixel_art.unwrap_or(size.is_some_and(|(width, height)| width < PIXEL_ART_SIZE && height < PIXEL_ART_SIZE))
+}
+
+/// Pixel art at its own size, or cut down without blending pixels if it is
+/// larger than `max_dimension`.
+fn pixel_art_within(img: &DynamicImage, max_dimension: u32) -> DynamicImage {
+    let (width, height) = target_size(img.dimensions(), max_dimension);
+    if (width, height) == img.dimensions() {
+        img.clone()
+    } else {
+        img.resize_exact(width, height, imageops::FilterType::Nearest)
+    }
+}
+
+/// `img`, decoded from an artwork of `size`, at the size that artwork is shown at.
+fn resize_main(img: &DynamicImage, size: (u32, u32), options: &ProcessingOptions, pixel_art: bool) -> DynamicImage {
+    if pixel_art {
+        return pixel_art_within(img, options.max_dimension);
+    }
+    let (new_width, new_height) = target_size(size, options.max_dimension);
+    img.resize_exact(new_width, new_height, imageops::FilterType::Lanczos3)
+}
+
+fn blurred_background(img: &DynamicImage, options: &ProcessingOptions) -> DynamicImage {
+    let (width, height) = (options.blur_width, options.blur_height);
+    let mut blurred = match options.blur_quality {
+        BlurQuality::Fast => {
+            // scaling back up from a tiny copy smears it as much as the box blur would
+            let (small_width, small_height) = ((width / FAST_BLUR_FACTOR).max(1), (height / FAST_BLUR_FACTOR).max(1));
+            img.resize_to_fill(small_width, small_height, imageops::FilterType::Triangle)
+                .resize_exact(width, height, imageops::FilterType::Triangle)
+                .to_rgba8()
+        }
+        BlurQuality::Nice => {
+            let mut blurred = img.resize_to_fill(width, height, imageops::FilterType::Lanczos3).to_rgba8();
+            // Multi-pass blur
+            for _ in 0..options.blur_passes {
+                // what is left is thrown away anyway
+                if options.cancel.is_cancelled() {
+                    break;
+                }
+                blurred = blur::fast_box_blur(&blurred, options.blur_radius);
+            }
+            blurred
+        }
+    };
+
+    blur::darken(&mut blurred, options.darken);
+    if options.vignette {
+        blur::vignette(&mut blurred, VIGNETTE_STRENGTH);
+    }
+
+    DynamicImage::ImageRgba8(blurred)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan;
+    use crate::source::FolderSource;
+    use image::RgbImage;
+    use std::fs;
+
+    #[test]
+    fn panics_become_errors() {
+        let result: Result<(), _> = catch_panic(|| panic!("absurd dimensions {}", 1 << 20));
+        assert!(matches!(result, Err(PipelineError::Panicked(message)) if message == "absurd dimensions 1048576"));
+        assert!(matches!(catch_panic(|| Ok(3)), Ok(3)));
+    }
+
+    #[test]
+    fn errors_say_whether_to_try_again() {
+        let garbage = icc::decode(b"not an image", Path::new("notes.png"), u64::MAX, None).unwrap_err();
+        let garbage = PipelineError::from(garbage);
+        assert!(matches!(garbage, PipelineError::UnsupportedFormat(_)), "{}", garbage);
+        assert!(!garbage.is_transient());
+        let limit = image::error::LimitError::from_kind(image::error::LimitErrorKind::InsufficientMemory);
+        assert!(matches!(image::ImageError::Limits(limit).into(), PipelineError::TooLarge(_)));
+        assert!(PipelineError::Io(std::io::ErrorKind::NotFound.into()).is_transient());
+        assert!(!PipelineError::Cancelled.is_transient());
+    }
+
+    #[test]
+    fn sizes_parse_and_clamp() {
+        assert_eq!("1280x720".parse(), Ok(BlurSize { width: 1280, height: 720 }));
+        assert!("1280".parse::<BlurSize>().is_err());
+
+        let options = ProcessingOptions {
+            max_dimension: 16384,
+            blur_width: 4,
+            ..ProcessingOptions::default()
+        }
+        .clamped();
+        assert_eq!((options.max_dimension, options.blur_width, options.blur_height), (8192, 16, 360));
+    }
+
+    #[test]
+    fn detail_picks_the_duration() {
+        let range: DurationRange = "5..30".parse().unwrap();
+        assert_eq!(range, DurationRange { min: 5.0, max: 30.0 });
+        assert!("30..5".parse::<DurationRange>().is_err() && "10".parse::<DurationRange>().is_err());
+
+        assert_eq!(range.for_detail(0.0), Duration::from_secs(5));
+        assert_eq!(range.for_detail(1.0), Duration::from_secs(30));
+        let middle = range.for_detail((DETAIL_SPAN.start() + DETAIL_SPAN.end()) / 2.0);
+        assert!((middle.as_secs_f32() - 17.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn preview_is_drawn_at_full_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wide.png");
+        RgbImage::from_pixel(900, 300, image::Rgb([90, 60, 30])).save(&path).unwrap();
+        let metadata = scan::read_metadata(&path);
+        let options = ProcessingOptions { max_dimension: 600, ..ProcessingOptions::default() };
+
+        let bytes = fs::read(&path).unwrap();
+        let preview = process_preview(&path, &bytes, metadata.clone(), &options).unwrap();
+        let full = process_image(&path, &bytes, metadata, &options).unwrap();
+
+        assert!(preview.is_preview && !full.is_preview);
+        assert!(preview.main_image.width() <= PREVIEW_DIMENSION);
+        assert_eq!(full.display_size, full.main_image.dimensions());
+        assert_eq!(preview.display_size, full.display_size);
+    }
+
+    #[test]
+    fn pixel_art_keeps_its_pixels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sprite.png");
+        let sprite = RgbImage::from_fn(24, 16, |x, y| image::Rgb([if (x + y) % 2 == 0 { 255 } else { 0 }; 3]));
+        sprite.save(&path).unwrap();
+        let metadata = scan::read_metadata(&path);
+        let bytes = fs::read(&path).unwrap();
+
+        for process in [process_image, process_preview] {
+            let processed = process(&path, &bytes, metadata.clone(), &ProcessingOptions::default()).unwrap();
+            assert!(processed.pixel_art);
+            assert_eq!(processed.main_image.to_rgb8(), sprite);
+        }
+        let photo = ArtworkMetadata { pixel_art: Some(false), ..metadata };
+        assert!(!process_image(&path, &bytes, photo, &ProcessingOptions::default()).unwrap().pixel_art);
+    }
+
+    #[test]
+    fn large_jpegs_are_decoded_scaled_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan.jpg");
+        RgbImage::from_fn(1600, 1000, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])).save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let options = ProcessingOptions { max_dimension: 300, ..ProcessingOptions::default() };
+
+        let decoded = decode(&bytes, &path, &options, Some(options.max_dimension)).unwrap();
+        assert_eq!(decoded.dimensions(), (400, 250));
+        assert_eq!(decode(&bytes, &path, &options, None).unwrap().dimensions(), (1600, 1000));
+        let processed = process_image(&path, &bytes, scan::read_metadata(&path), &options).unwrap();
+        assert_eq!(processed.main_image.dimensions(), (300, 187));
+        assert_eq!(processed.display_size, (300, 187));
+        assert_eq!(processed.source_size, (1600, 1000));
+    }
+
+    #[test]
+    fn both_blurs_agree_on_the_overall_color() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(1200, 800, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x / 40 + y / 40) % 2 * 255) as u8])
+        }));
+        let mean = |quality: BlurQuality| {
+            let options = ProcessingOptions { blur_quality: quality, ..ProcessingOptions::default() };
+            let blurred = blurred_background(&image, &options);
+            assert_eq!(blurred.dimensions(), (options.blur_width, options.blur_height));
+            color::mean_luminance(&blurred, [0.0, 0.0, 1.0, 1.0])
+        };
+        assert!((mean(BlurQuality::Fast) - mean(BlurQuality::Nice)).abs() < 0.02);
+    }
+
+    #[test]
+    fn collages_leave_out_what_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let artworks: Vec<ArtworkInfo> = ["tall.png", "missing.png", "wide.png"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                ArtworkInfo { metadata: scan::read_metadata(&path), path, remote: None }
+            })
+            .collect();
+        RgbImage::from_pixel(100, 300, image::Rgb([200, 0, 0])).save(&artworks[0].path).unwrap();
+        RgbImage::from_pixel(300, 100, image::Rgb([0, 0, 200])).save(&artworks[2].path).unwrap();
+        let options = ProcessingOptions {
+            layout: SlideLayout::Grid2x2,
+            canvas: (800, 400),
+            ..ProcessingOptions::default()
+        };
+
+        let source = FolderSource::new(dir.path());
+        let collage = process_collage(&artworks, options.layout.grid(), &options, &source, process_image).unwrap();
+        assert_eq!(collage.display_size, (800, 400));
+        assert_eq!(collage.works.len(), 2);
+        let canvas = collage.main_image.to_rgba8();
+        // the first cell is the top left one, the failed second stays empty and the third goes below
+        assert_eq!(canvas.get_pixel(200, 100).0, [200, 0, 0, 255]);
+        assert_eq!(canvas.get_pixel(600, 100).0[3], 0);
+        assert_eq!(canvas.get_pixel(200, 300).0, [0, 0, 200, 255]);
+        let empty = process_collage(&[], options.layout.grid(), &options, &source, process_image);
+        assert!(matches!(empty, Err(PipelineError::NoArtworks)));
+        assert!(!PipelineError::NoArtworks.is_transient());
+    }
+
+    #[test]
+    fn thumbnails_have_their_own_cache_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wide.png");
+        RgbImage::from_pixel(900, 300, image::Rgb([90, 60, 30])).save(&path).unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"), 1 << 20);
+
+        let info = ArtworkInfo { metadata: scan::read_metadata(&path), path: path.clone(), remote: None };
+        let thumbnail = thumbnail(&info, &FolderSource::new(dir.path()), Some(&cache)).unwrap();
+        assert_eq!(thumbnail.dimensions(), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 3));
+        assert_ne!(cache.thumbnail_key(&path, THUMBNAIL_SIZE), cache.key(&path, &ProcessingOptions::default()));
+    }
+
+    /// The sample `name` from `tests/fixtures`, processed as an artwork is.
+    #[cfg(any(feature = "avif", feature = "jxl"))]
+    fn process_fixture(name: &str) -> Result<ProcessedImage, PipelineError> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+        let bytes = fs::read(&path).unwrap();
+        process_image(&path, &bytes, scan::read_metadata(&path), &ProcessingOptions::default())
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn avif_artworks_are_shown() {
+        let still = process_fixture("still.avif").unwrap();
+        assert_eq!(still.display_size, (8, 8));
+        let [red, green, blue] = still.main_image.to_rgb8().get_pixel(0, 0).0;
+        assert!(red > green && green > blue);
+        // a white frame, then a black one
+        let animated = process_fixture("animated.avif").unwrap();
+        assert_eq!(animated.display_size, (16, 16));
+        assert!(animated.main_image.to_rgb8().pixels().all(|pixel| pixel.0.iter().all(|&value| value > 200)));
+        // cut off in its metadata
+        assert!(matches!(process_fixture("corrupt.avif"), Err(PipelineError::Decode(_))));
+    }
+
+    #[cfg(feature = "jxl")]
+    #[test]
+    fn jxl_artworks_are_shown() {
+        let still = process_fixture("still.jxl").unwrap();
+        assert_eq!(still.display_size, (8, 8));
+        assert!(still.main_image.to_rgb8().pixels().all(|pixel| pixel.0.iter().all(|&value| value.abs_diff(128) <= 2)));
+        // a white frame, then a black one
+        let animated = process_fixture("animated.jxl").unwrap();
+        assert!(animated.main_image.to_rgb8().pixels().all(|pixel| pixel.0 == [255; 3]));
+        // cut off in its header
+        assert!(matches!(process_fixture("corrupt.jxl"), Err(PipelineError::Decode(_))));
+    }
+}
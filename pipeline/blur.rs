@@ -0,0 +1,137 @@
+use image::RgbaImage;
+use rayon::prelude::*;
+
+/// Box-blurs the color channels of `img`, one horizontal and one vertical pass.
+/// Rows are spread across all cores; alpha is left alone.
+pub fn fast_box_blur(img: &RgbaImage, radius: i32) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let stride = w * 4;
+    let count = (2 * radius + 1).max(1) as u32;
+    let source = img.as_raw();
+    let mut horizontal = source.clone();
+
+    // Horizontal pass
+    horizontal.par_chunks_mut(stride).enumerate().for_each(|(y, out_row)| {
+        let row = &source[y * stride..(y + 1) * stride];
+        for x in 0..w {
+            let mut sum = [0u32; 3];
+            for dx in -radius..=radius {
+                let nx = (x as i32 + dx).clamp(0, w as i32 - 1) as usize;
+                for (channel, total) in sum.iter_mut().enumerate() {
+                    *total += row[nx * 4 + channel] as u32;
+                }
+            }
+            for (channel, total) in sum.iter().enumerate() {
+                out_row[x * 4 + channel] = (total / count) as u8;
+            }
+        }
+    });
+
+    let mut output = horizontal.clone();
+
+    // Vertical pass
+    output.par_chunks_mut(stride).enumerate().for_each(|(y, out_row)| {
+        for x in 0..w {
+            let mut sum = [0u32; 3];
+            for dy in -radius..=radius {
+                let ny = (y as i32 + dy).clamp(0, h as i32 - 1) as usize;
+                for (channel, total) in sum.iter_mut().enumerate() {
+                    *total += horizontal[ny * stride + x * 4 + channel] as u32;
+                }
+            }
+            for (channel, total) in sum.iter().enumerate() {
+                out_row[x * 4 + channel] = (total / count) as u8;
+            }
+        }
+    });
+
+    RgbaImage::from_raw(width, height, output).expect("buffer keeps the image's size")
+}
+
+/// Scales the color channels by `factor`, leaving alpha alone.
+pub fn darken(img: &mut RgbaImage, factor: f32) {
+    img.par_chunks_mut(4 * 1024).for_each(|chunk| {
+        for pixel in chunk.chunks_exact_mut(4) {
+            pixel[0] = (pixel[0] as f32 * factor) as u8;
+            pixel[1] = (pixel[1] as f32 * factor) as u8;
+            pixel[2] = (pixel[2] as f32 * factor) as u8;
+        }
+    });
+}
+
+/// Where the vignette starts, as a fraction of the way from the centre to a corner.
+const VIGNETTE_START: f32 = 0.35;
+
+/// Darkens towards the corners, leaving `1 - strength` of the brightness in
+/// them. The falloff is a smooth curve of the position, so it stays smooth
+/// when the image is stretched to the screen.
+pub fn vignette(img: &mut RgbaImage, strength: f32) {
+    let (width, height) = img.dimensions();
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+    img.par_chunks_mut(4 * width as usize).enumerate().for_each(|(y, row)| {
+        let dy = (y as f32 + 0.5 - center_y) / center_y;
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let dx = (x as f32 + 0.5 - center_x) / center_x;
+            let distance = ((dx * dx + dy * dy) / 2.0).sqrt();
+            let t = ((distance - VIGNETTE_START) / (1.0 - VIGNETTE_START)).clamp(0.0, 1.0);
+            let factor = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+            for channel in &mut pixel[..3] {
+                *channel = (*channel as f32 * factor) as u8;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn uniform_image_is_unchanged() {
+        let img = RgbaImage::from_pixel(16, 9, Rgba([120, 60, 30, 255]));
+        assert_eq!(fast_box_blur(&img, 3), img);
+    }
+
+    #[test]
+    fn single_bright_pixel_spreads_out() {
+        let mut img = RgbaImage::from_pixel(9, 9, Rgba([0, 0, 0, 255]));
+        img.put_pixel(4, 4, Rgba([255, 255, 255, 255]));
+
+        let blurred = fast_box_blur(&img, 1);
+
+        // 3x3 box: the energy is shared by the centre and its eight neighbours
+        assert_eq!(blurred.get_pixel(4, 4)[0], 255 / 3 / 3);
+        assert_eq!(blurred.get_pixel(3, 3)[0], 255 / 3 / 3);
+        assert_eq!(blurred.get_pixel(2, 2)[0], 0);
+    }
+
+    #[test]
+    fn edges_are_clamped() {
+        let mut img = RgbaImage::from_pixel(4, 1, Rgba([0, 0, 0, 255]));
+        img.put_pixel(0, 0, Rgba([90, 90, 90, 255]));
+
+        let blurred = fast_box_blur(&img, 1);
+
+        // the left neighbour of x=0 is x=0 itself
+        assert_eq!(blurred.get_pixel(0, 0)[0], 60);
+        assert_eq!(blurred.get_pixel(1, 0)[0], 30);
+    }
+
+    #[test]
+    fn vignette_darkens_the_corners_only() {
+        let mut img = RgbaImage::from_pixel(64, 36, Rgba([200, 200, 200, 255]));
+        vignette(&mut img, 0.5);
+        assert_eq!(img.get_pixel(32, 18)[0], 200);
+        assert!(img.get_pixel(0, 0)[0] < 110);
+        assert_eq!(img.get_pixel(0, 0)[3], 255);
+    }
+
+    #[test]
+    fn darken_keeps_alpha() {
+        let mut img = RgbaImage::from_pixel(2, 2, Rgba([100, 200, 50, 128]));
+        darken(&mut img, 0.5);
+        assert_eq!(*img.get_pixel(1, 1), Rgba([50, 100, 25, 128]));
+    }
+}
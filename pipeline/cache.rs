@@ -0,0 +1,276 @@
+//! On-disk cache of resized and blurred images.
+//!
+//! Entries are PNG files named after a hash of the source file (path, size,
+//! modification time) and every processing option that affects the pixels.
+//! Anything that fails to read is treated as a miss and reprocessed.
+
+use super::{BackgroundMode, ProcessingOptions};
+use crate::persist;
+use image::DynamicImage;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Bumped whenever decoding changes the pixels, so older entries are no longer hit.
+const KEY_VERSION: u8 = 2;
+
+/// Default size cap, in megabytes.
+pub const DEFAULT_CACHE_SIZE_MB: u64 = 1024;
+
+/// `$XDG_CACHE_HOME/art-slideshow`, `~/.cache/art-slideshow` or `%LOCALAPPDATA%\art-slideshow`.
+pub fn default_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("art-slideshow"))
+}
+
+/// FNV-1a, stable across Rust releases unlike `DefaultHasher`.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        self
+    }
+}
+
+/// FNV-1a of `bytes`, for file names that stay the same from run to run.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    Fnv::new().write(bytes).0
+}
+
+#[derive(Clone)]
+pub struct Cached {
+    pub main_image: DynamicImage,
+    /// Only present when the background mode is blur.
+    pub blurred: Option<DynamicImage>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Hash of what identifies the contents of `source`.
+    fn source_hash(source: &Path) -> Option<Fnv> {
+        let meta = fs::metadata(source).ok()?;
+        let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        let mut hash = Fnv::new();
+        hash.write(&[KEY_VERSION])
+            .write(source.to_string_lossy().as_bytes())
+            .write(&meta.len().to_le_bytes())
+            .write(&modified.as_nanos().to_le_bytes());
+        Some(hash)
+    }
+
+    /// Cache key for `source` processed with `options`, `None` if the file can't be stat'ed.
+    pub fn key(&self, source: &Path, options: &ProcessingOptions) -> Option<String> {
+        let mut hash = Self::source_hash(source)?;
+        hash.write(&options.max_dimension.to_le_bytes())
+            .write(&[options.background as u8])
+            .write(&[options.filter as u8]);
+        // only a camera raw file comes out differently
+        #[cfg(feature = "raw")]
+        if super::raw::is_raw(source) {
+            hash.write(&[options.raw_use_preview as u8]);
+        }
+        if options.background == BackgroundMode::Blur {
+            hash.write(&[options.blur_quality as u8])
+                .write(&options.blur_width.to_le_bytes())
+                .write(&options.blur_height.to_le_bytes())
+                .write(&options.blur_passes.to_le_bytes())
+                .write(&options.blur_radius.to_le_bytes())
+                .write(&options.darken.to_le_bytes())
+                .write(&[options.vignette as u8]);
+        }
+        Some(format!("{:016x}", hash.0))
+    }
+
+    /// Cache key for the filmstrip thumbnail of `source`, at most `size` pixels on a side.
+    pub fn thumbnail_key(&self, source: &Path, size: u32) -> Option<String> {
+        let mut hash = Self::source_hash(source)?;
+        hash.write(b"thumbnail").write(&size.to_le_bytes());
+        Some(format!("{:016x}", hash.0))
+    }
+
+    fn entry(&self, key: &str, kind: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}.png", key, kind))
+    }
+
+    /// The cached images for `key`. A missing or unreadable entry is a miss,
+    /// and broken files are removed so they get rewritten.
+    pub fn load(&self, key: &str, with_blur: bool) -> Option<Cached> {
+        let read = |kind: &str| {
+            let path = self.entry(key, kind);
+            match image::open(&path) {
+                Ok(image) => {
+                    // the modification time doubles as the last use for eviction
+                    let _ = fs::File::options()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|file| file.set_modified(SystemTime::now()));
+                    Some(image)
+                }
+                Err(image::ImageError::IoError(err)) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => {
+                    tracing::warn!("discarding cache entry {}: {}", path.display(), err);
+                    let _ = fs::remove_file(&path);
+                    None
+                }
+            }
+        };
+
+        let main_image = read("main")?;
+        let blurred = if with_blur { Some(read("blur")?) } else { None };
+        Some(Cached { main_image, blurred })
+    }
+
+    /// Writes the entry from a background thread so a cache miss costs no extra time.
+    pub fn store(&self, key: String, cached: Cached) {
+        let cache = self.clone();
+        thread::spawn(move || {
+            if let Err(err) = cache.write(&key, &cached) {
+                tracing::warn!("cannot write cache entry {}: {}", key, err);
+            }
+            cache.evict();
+        });
+    }
+
+    fn write(&self, key: &str, cached: &Cached) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.dir)?;
+        let images = [("main", Some(&cached.main_image)), ("blur", cached.blurred.as_ref())];
+        for (kind, image) in images {
+            if let Some(image) = image {
+                let mut bytes = Vec::new();
+                image.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)?;
+                persist::write_atomic(&self.entry(key, kind), &bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the least recently used entries until the cache fits its size cap.
+    fn evict(&self) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "png"))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        files.sort();
+        for (_, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::color::ColorFilter;
+    use image::RgbaImage;
+
+    fn cached() -> Cached {
+        Cached {
+            main_image: DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 3, image::Rgba([10, 20, 30, 255]))),
+            blurred: Some(DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, image::Rgba([1, 2, 3, 255])))),
+        }
+    }
+
+    #[test]
+    fn key_follows_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("a.png");
+        fs::write(&source, "pixels").unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"), u64::MAX);
+
+        let options = ProcessingOptions::default();
+        let key = cache.key(&source, &options).unwrap();
+        assert_eq!(cache.key(&source, &options), Some(key.clone()));
+
+        let smaller = ProcessingOptions { max_dimension: 1024, ..options.clone() };
+        assert_ne!(cache.key(&source, &smaller), Some(key.clone()));
+        let blurrier = ProcessingOptions { blur_passes: 4, ..options.clone() };
+        assert_ne!(cache.key(&source, &blurrier), Some(key.clone()));
+        let sepia = ProcessingOptions { filter: ColorFilter::Sepia, ..options.clone() };
+        assert_ne!(cache.key(&source, &sepia), Some(key.clone()));
+        let preview = ProcessingOptions { raw_use_preview: true, ..options.clone() };
+        assert_eq!(cache.key(&source, &preview), Some(key));
+        #[cfg(feature = "raw")]
+        {
+            let raw = dir.path().join("a.NEF");
+            fs::write(&raw, "sensor data").unwrap();
+            assert_ne!(cache.key(&raw, &preview), cache.key(&raw, &options));
+        }
+        assert_eq!(cache.key(&dir.path().join("missing.png"), &ProcessingOptions::default()), None);
+    }
+
+    #[test]
+    fn round_trip_and_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path().to_path_buf(), u64::MAX);
+
+        assert!(cache.load("k", true).is_none());
+        cache.write("k", &cached()).unwrap();
+        let hit = cache.load("k", true).unwrap();
+        assert_eq!(hit.main_image.to_rgba8(), cached().main_image.to_rgba8());
+        assert_eq!(hit.blurred.unwrap().to_rgba8(), cached().blurred.unwrap().to_rgba8());
+
+        fs::write(cache.entry("k", "blur"), "not a png").unwrap();
+        assert!(cache.load("k", true).is_none());
+        assert!(!cache.entry("k", "blur").exists());
+        assert!(cache.load("k", false).is_some());
+    }
+
+    #[test]
+    fn eviction_keeps_recent_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path().to_path_buf(), 0);
+        cache.write("old", &cached()).unwrap();
+        let size: u64 = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum();
+
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        for kind in ["main", "blur"] {
+            let file = fs::File::options().write(true).open(cache.entry("old", kind)).unwrap();
+            file.set_modified(old).unwrap();
+        }
+        cache.write("new", &cached()).unwrap();
+
+        DiskCache { max_bytes: size, ..cache.clone() }.evict();
+        assert!(!cache.entry("old", "main").exists());
+        assert!(cache.load("new", true).is_some());
+    }
+}
@@ -0,0 +1,256 @@
+use image::{DynamicImage, imageops};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// A look applied to the whole show, or to one artwork from its sidecar.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFilter {
+    #[default]
+    None,
+    Grayscale,
+    Sepia,
+    /// A slightly lower color temperature, like gallery spotlights.
+    Warm,
+}
+
+impl FromStr for ColorFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ColorFilter::None),
+            "grayscale" => Ok(ColorFilter::Grayscale),
+            "sepia" => Ok(ColorFilter::Sepia),
+            "warm" => Ok(ColorFilter::Warm),
+            other => Err(format!("expected none, grayscale, sepia or warm, got {:?}", other)),
+        }
+    }
+}
+
+impl ColorFilter {
+    /// Rows giving the red, green and blue out of the red, green and blue in.
+    fn matrix(self) -> Option<[[f32; 3]; 3]> {
+        const REC_709: [f32; 3] = [0.2126, 0.7152, 0.0722];
+        match self {
+            ColorFilter::None => None,
+            ColorFilter::Grayscale => Some([REC_709; 3]),
+            ColorFilter::Sepia => Some([[0.393, 0.769, 0.189], [0.349, 0.686, 0.168], [0.272, 0.534, 0.131]]),
+            ColorFilter::Warm => Some([[1.08, 0.0, 0.0], [0.0, 1.01, 0.0], [0.0, 0.0, 0.88]]),
+        }
+    }
+
+    /// `img` with the filter applied, 16-bit images staying 16-bit.
+    pub fn apply(self, img: DynamicImage) -> DynamicImage {
+        let Some(matrix) = self.matrix() else {
+            return img;
+        };
+        // every pixel is `channels` samples, the first three are mixed
+        fn mix<S: Copy + Into<f32>>(samples: &mut [S], channels: usize, matrix: &[[f32; 3]; 3], to: fn(f32) -> S) {
+            for pixel in samples.chunks_exact_mut(channels) {
+                let rgb: [f32; 3] = [pixel[0].into(), pixel[1].into(), pixel[2].into()];
+                for (sample, row) in pixel.iter_mut().zip(matrix) {
+                    *sample = to(row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+                }
+            }
+        }
+        let to_u8 = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        let to_u16 = |value: f32| value.round().clamp(0.0, 65535.0) as u16;
+
+        // more than a byte per sample
+        let wide = img.color().bytes_per_pixel() > img.color().channel_count();
+        match (wide, img.color().has_alpha()) {
+            (false, false) => {
+                let mut pixels = img.into_rgb8();
+                mix(&mut pixels, 3, &matrix, to_u8);
+                DynamicImage::ImageRgb8(pixels)
+            }
+            (false, true) => {
+                let mut pixels = img.into_rgba8();
+                mix(&mut pixels, 4, &matrix, to_u8);
+                DynamicImage::ImageRgba8(pixels)
+            }
+            (true, false) => {
+                let mut pixels = img.into_rgb16();
+                mix(&mut pixels, 3, &matrix, to_u16);
+                DynamicImage::ImageRgb16(pixels)
+            }
+            (true, true) => {
+                let mut pixels = img.into_rgba16();
+                mix(&mut pixels, 4, &matrix, to_u16);
+                DynamicImage::ImageRgba16(pixels)
+            }
+        }
+    }
+}
+
+/// Most common color of `img`, from a histogram quantized to 4 bits per channel.
+///
+/// The winning bin is averaged so the result is not snapped to the bin's corner.
+pub fn dominant_color(img: &DynamicImage) -> [u8; 3] {
+    let small = img.resize(64, 64, imageops::FilterType::Triangle).to_rgb8();
+
+    let mut counts = vec![0u32; 16 * 16 * 16];
+    let mut sums = vec![[0u32; 3]; 16 * 16 * 16];
+    for pixel in small.pixels() {
+        let bin = (pixel[0] as usize >> 4) << 8 | (pixel[1] as usize >> 4) << 4 | pixel[2] as usize >> 4;
+        counts[bin] += 1;
+        for channel in 0..3 {
+            sums[bin][channel] += pixel[channel] as u32;
+        }
+    }
+
+    let (bin, &count) = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, count)| **count)
+        .unwrap_or((0, &0));
+    if count == 0 {
+        return [0, 0, 0];
+    }
+    sums[bin].map(|sum| (sum / count) as u8)
+}
+
+/// `img` brightened by `brightness` after `gamma` lifts (above 1) or sinks
+/// (below 1) the shadows and midtones, through a table per 8-bit value.
+/// Alpha is left alone, and anything but 8 bits per channel comes out RGBA.
+pub fn adjust(img: DynamicImage, brightness: f32, gamma: f32) -> DynamicImage {
+    if brightness == 1.0 && gamma == 1.0 {
+        return img;
+    }
+    let table: [u8; 256] = std::array::from_fn(|value| {
+        let level = (value as f32 / 255.0).powf(1.0 / gamma) * brightness;
+        (level * 255.0).round().clamp(0.0, 255.0) as u8
+    });
+    // every pixel is `channels` samples, the first `colors` of them looked up
+    let map = |samples: &mut [u8], channels: usize, colors: usize| {
+        for pixel in samples.chunks_exact_mut(channels) {
+            for sample in &mut pixel[..colors] {
+                *sample = table[*sample as usize];
+            }
+        }
+    };
+    match img {
+        DynamicImage::ImageLuma8(mut pixels) => {
+            map(&mut pixels, 1, 1);
+            DynamicImage::ImageLuma8(pixels)
+        }
+        DynamicImage::ImageRgb8(mut pixels) => {
+            map(&mut pixels, 3, 3);
+            DynamicImage::ImageRgb8(pixels)
+        }
+        other => {
+            let mut pixels = other.into_rgba8();
+            map(&mut pixels, 4, 3);
+            DynamicImage::ImageRgba8(pixels)
+        }
+    }
+}
+
+/// How busy `img` is: the mean brightness step between neighbouring pixels,
+/// 0 for a flat image. Measured on a copy at most 256 pixels across, so a
+/// preview and the full image come out alike.
+pub fn detail(img: &DynamicImage) -> f32 {
+    let small = img.thumbnail(256, 256).to_luma8();
+    let (width, height) = small.dimensions();
+    if width < 2 || height < 2 {
+        return 0.0;
+    }
+    let mut total = 0u64;
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let here = small.get_pixel(x, y)[0] as i32;
+            let right = small.get_pixel(x + 1, y)[0] as i32;
+            let below = small.get_pixel(x, y + 1)[0] as i32;
+            total += ((right - here).abs() + (below - here).abs()) as u64;
+        }
+    }
+    total as f32 / ((width - 1) * (height - 1)) as f32 / 255.0
+}
+
+/// Relative luminance in `0.0..=1.0`, using Rec. 709 weights on the sRGB values.
+pub fn luminance([r, g, b]: [u8; 3]) -> f32 {
+    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+}
+
+/// Mean luminance of a region of `img`, given as `[x0, y0, x1, y1]` fractions of its size.
+pub fn mean_luminance(img: &DynamicImage, region: [f32; 4]) -> f32 {
+    let rgb = img.to_rgb8();
+    let (width, height) = rgb.dimensions();
+    let x0 = (region[0] * width as f32) as u32;
+    let y0 = (region[1] * height as f32) as u32;
+    let x1 = ((region[2] * width as f32) as u32).clamp(x0 + 1, width.max(1));
+    let y1 = ((region[3] * height as f32) as u32).clamp(y0 + 1, height.max(1));
+
+    let mut total = 0.0;
+    let mut count = 0u32;
+    for y in y0..y1.min(height) {
+        for x in x0..x1.min(width) {
+            total += luminance(rgb.get_pixel(x, y).0);
+            count += 1;
+        }
+    }
+
+    if count == 0 { 0.0 } else { total / count as f32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn majority_color_wins() {
+        let mut img = RgbImage::from_pixel(100, 100, Rgb([200, 40, 40]));
+        for x in 0..30 {
+            for y in 0..100 {
+                img.put_pixel(x, y, Rgb([20, 20, 220]));
+            }
+        }
+
+        let [r, g, b] = dominant_color(&DynamicImage::ImageRgb8(img));
+        assert!(r > 180 && g < 60 && b < 60, "got {:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn filters_mix_the_channels() {
+        let img = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([200, 100, 50])));
+        let gray = ColorFilter::Grayscale.apply(img.clone()).into_rgb8().get_pixel(0, 0).0;
+        assert_eq!(gray, [118; 3]);
+
+        let white = DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(1, 1, Rgb([65535u16; 3])));
+        let DynamicImage::ImageRgb16(sepia) = ColorFilter::Sepia.apply(white) else {
+            panic!("expected the depth to be kept");
+        };
+        assert_eq!(sepia.get_pixel(0, 0).0, [65535, 65535, 61406]);
+
+        let [r, g, b] = ColorFilter::Warm.apply(img.clone()).into_rgb8().get_pixel(0, 0).0;
+        assert!(r > 200 && g == 101 && b < 50);
+        assert_eq!("sepia".parse(), Ok(ColorFilter::Sepia));
+        assert!(matches!(ColorFilter::None.apply(img), DynamicImage::ImageRgb8(_)));
+    }
+
+    #[test]
+    fn busy_images_have_more_detail() {
+        let flat = DynamicImage::ImageRgb8(RgbImage::from_pixel(300, 200, Rgb([120, 80, 40])));
+        let stripes = DynamicImage::ImageRgb8(RgbImage::from_fn(300, 200, |x, _| Rgb([(x / 4 % 2 * 255) as u8; 3])));
+        assert_eq!(detail(&flat), 0.0);
+        assert!(detail(&stripes) > 0.1);
+    }
+
+    #[test]
+    fn gamma_lifts_the_shadows() {
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(3, 1, |x, _| {
+            image::Rgba([[0, 40, 255][x as usize], 128, 200, 77])
+        }));
+        let lifted = adjust(img.clone(), 1.0, 2.0).into_rgba8();
+        // black and white stay, the dark value comes up most, alpha is untouched
+        assert_eq!(lifted.get_pixel(0, 0).0, [0, 181, 226, 77]);
+        assert_eq!(lifted.get_pixel(2, 0)[0], 255);
+        assert_eq!(lifted.get_pixel(1, 0)[0], 101);
+
+        let brighter = adjust(img.clone(), 1.5, 1.0).into_rgba8();
+        assert_eq!(brighter.get_pixel(1, 0).0, [60, 192, 255, 77]);
+        assert_eq!(adjust(img.clone(), 1.0, 1.0).into_rgba8(), img.into_rgba8());
+    }
+}
@@ -0,0 +1,152 @@
+//! High bit depth sources. 16-bit images are resized at full depth and only
+//! brought down to 8 bits for the texture, dithered so smooth gradients like
+//! skies don't band. Float images (OpenEXR) are tone-mapped to 16 bits first.
+
+use image::{DynamicImage, ImageBuffer, Rgb, Rgba, RgbImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How 16-bit images are brought down to the 8 bits of the texture.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Dither {
+    /// Each value is rounded to the nearest 8-bit one.
+    None,
+    /// An 8×8 Bayer pattern spreads the rounding error.
+    #[default]
+    Ordered,
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Dither::None),
+            "ordered" => Ok(Dither::Ordered),
+            other => Err(format!("expected none or ordered, got {:?}", other)),
+        }
+    }
+}
+
+/// Thresholds of the 8×8 Bayer matrix, 0 to 63.
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+fn is_float(img: &DynamicImage) -> bool {
+    matches!(img, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+fn is_16_bit(img: &DynamicImage) -> bool {
+    matches!(
+        img,
+        DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_)
+    )
+}
+
+/// Linear light to a 16-bit sRGB value, with a Reinhard curve rolling off the highlights.
+fn tone_map_channel(linear: f32) -> u16 {
+    let linear = linear.max(0.0);
+    let mapped = linear / (1.0 + linear);
+    let encoded = if mapped <= 0.003_130_8 { mapped * 12.92 } else { 1.055 * mapped.powf(1.0 / 2.4) - 0.055 };
+    (encoded.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+/// Float images tone-mapped to 16 bits, anything else as it is.
+pub fn tone_map(img: DynamicImage) -> DynamicImage {
+    if !is_float(&img) {
+        return img;
+    }
+    if img.color().has_alpha() {
+        let float = img.into_rgba32f();
+        DynamicImage::ImageRgba16(ImageBuffer::from_fn(float.width(), float.height(), |x, y| {
+            let [r, g, b, a] = float.get_pixel(x, y).0;
+            Rgba([tone_map_channel(r), tone_map_channel(g), tone_map_channel(b), (a.clamp(0.0, 1.0) * 65535.0) as u16])
+        }))
+    } else {
+        let float = img.into_rgb32f();
+        DynamicImage::ImageRgb16(ImageBuffer::from_fn(float.width(), float.height(), |x, y| {
+            Rgb(float.get_pixel(x, y).0.map(tone_map_channel))
+        }))
+    }
+}
+
+/// `img` in 8 bits per channel. Only 16-bit images are dithered, and alpha never is.
+pub fn to_8_bit(img: DynamicImage, dither: Dither) -> DynamicImage {
+    if !is_16_bit(&img) {
+        return img;
+    }
+    let reduce = |value: u16, x: u32, y: u32| -> u8 {
+        match dither {
+            Dither::None => ((value as u32 + 128) / 257) as u8,
+            Dither::Ordered => {
+                // a threshold within one 8-bit step decides which way the value rounds
+                let threshold = (BAYER[y as usize % 8][x as usize % 8] as u32 * 2 + 1) * 257 / 128;
+                ((value as u32 + threshold) / 257).min(255) as u8
+            }
+        }
+    };
+
+    if img.color().has_alpha() {
+        let wide = img.into_rgba16();
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(wide.width(), wide.height(), |x, y| {
+            let [r, g, b, a] = wide.get_pixel(x, y).0;
+            Rgba([reduce(r, x, y), reduce(g, x, y), reduce(b, x, y), ((a as u32 + 128) / 257) as u8])
+        }))
+    } else {
+        let wide = img.into_rgb16();
+        DynamicImage::ImageRgb8(RgbImage::from_fn(wide.width(), wide.height(), |x, y| {
+            Rgb(wide.get_pixel(x, y).0.map(|value| reduce(value, x, y)))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb32FImage};
+
+    #[test]
+    fn dithering_follows_a_gradient_between_two_steps() {
+        // a slow ramp from 8-bit level 100 to 101, which plain rounding turns into two flat bands
+        let ramp = ImageBuffer::from_fn(256, 8, |x, _| Rgb([100 * 257 + x as u16; 3]));
+        let error = |dither: Dither| {
+            let reduced = to_8_bit(DynamicImage::ImageRgb16(ramp.clone()), dither).into_rgb8();
+            // how far every 8×8 block's average strays from the true mean
+            (0..32)
+                .map(|block| {
+                    let (sum, truth): (f32, f32) = (block * 8..block * 8 + 8)
+                        .flat_map(|x| (0..8).map(move |y| (x, y)))
+                        .map(|(x, y)| (reduced.get_pixel(x, y)[0] as f32, ramp.get_pixel(x, y)[0] as f32 / 257.0))
+                        .fold((0.0, 0.0), |(s, t), (value, exact)| (s + value, t + exact));
+                    ((sum - truth) / 64.0).abs()
+                })
+                .sum::<f32>()
+                / 32.0
+        };
+        assert!(error(Dither::Ordered) < error(Dither::None) / 4.0);
+        assert!(matches!(to_8_bit(DynamicImage::ImageRgb16(ramp), Dither::None), DynamicImage::ImageRgb8(_)));
+    }
+
+    #[test]
+    fn float_images_are_tone_mapped() {
+        let hdr = Rgb32FImage::from_fn(2, 1, |x, _| Rgb([if x == 0 { 0.0 } else { 50.0 }; 3]));
+        let DynamicImage::ImageRgb16(mapped) = tone_map(DynamicImage::ImageRgb32F(hdr)) else {
+            panic!("expected a 16-bit image");
+        };
+        assert_eq!(mapped.get_pixel(0, 0)[0], 0);
+        // bright highlights roll off instead of clipping
+        assert!(mapped.get_pixel(1, 0)[0] > 64000 && mapped.get_pixel(1, 0)[0] < 65535);
+    }
+}
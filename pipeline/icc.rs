@@ -0,0 +1,185 @@
+//! Embedded color profiles. Textures are shown as sRGB, so an Adobe RGB or
+//! ProPhoto scan would look washed out if its pixels went up as they are.
+
+use image::error::{LimitError, LimitErrorKind};
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageError, ImageFormat, ImageReader, ImageResult, Limits, RgbImage};
+use jpeg_decoder::PixelFormat;
+use qcms::{DataType, Intent, Profile, Transform};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Decodes `bytes`, the image at `path`, converted to sRGB when it carries
+/// another profile. Images without one, or with an sRGB one, are not touched.
+/// Like `image::load_from_memory`, with the decoder kept for the profile.
+/// Images of more than `max_pixels` are refused from their header, before
+/// anything is allocated for them. A JPEG larger than `max_dimension` comes
+/// out at the smallest of its 1/2, 1/4 and 1/8 scales still larger, which its
+/// decoder gets to skipping most of the work.
+pub fn decode(bytes: &[u8], path: &Path, max_pixels: u64, max_dimension: Option<u32>) -> ImageResult<DynamicImage> {
+    // the image crate doesn't know JPEG XL, not even to guess it
+    #[cfg(feature = "jxl")]
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jxl")) {
+        let decoder = jxl_oxide::integration::JxlDecoder::new(Cursor::new(bytes))
+            .map_err(|err| ImageError::Decoding(DecodingError::new(ImageFormatHint::Name("JPEG XL".into()), err)))?;
+        return from_decoder(decoder, path, max_pixels);
+    }
+    if let Some(max_dimension) = max_dimension.filter(|_| image::guess_format(bytes).ok() == Some(ImageFormat::Jpeg)) {
+        if let Some(image) = decode_jpeg_scaled(bytes, path, max_pixels, max_dimension) {
+            return image;
+        }
+    }
+
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    // the image crate only knows an AVIF by its avif brand, an animation's is avis
+    #[cfg(feature = "avif")]
+    if reader.format().is_none() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("avif")) {
+        reader.set_format(ImageFormat::Avif);
+    }
+    let mut limits = Limits::default();
+    // room for the largest image allowed in 32-bit float RGBA, 512 MiB alone would refuse a big 16-bit scan
+    limits.max_alloc = limits.max_alloc.map(|default| default.max(max_pixels.saturating_mul(16)));
+    reader.limits(limits);
+    from_decoder(reader.into_decoder()?, path, max_pixels)
+}
+
+/// The image `decoder` has, of the one at `path`, as [`decode`] returns it.
+/// Of an animation that is its first frame.
+fn from_decoder(mut decoder: impl ImageDecoder, path: &Path, max_pixels: u64) -> ImageResult<DynamicImage> {
+    let (width, height) = decoder.dimensions();
+    within_cap((width, height), path, max_pixels)?;
+    let profile = decoder.icc_profile().unwrap_or_else(|err| {
+        tracing::debug!("{}: cannot read the color profile: {}", path.display(), err);
+        None
+    });
+    let image = DynamicImage::from_decoder(decoder)?;
+    Ok(match profile {
+        Some(profile) => to_srgb(image, &profile, path),
+        None => image,
+    })
+}
+
+/// The JPEG in `bytes` decoded at the smallest scale still larger than
+/// `max_dimension`. None where there is no smaller scale to take, or the image is
+/// one the scaling decoder has no plain pixels for, CMYK or 16 bits, and the
+/// image crate decodes it whole instead.
+fn decode_jpeg_scaled(
+    bytes: &[u8],
+    path: &Path,
+    max_pixels: u64,
+    max_dimension: u32,
+) -> Option<ImageResult<DynamicImage>> {
+    let mut decoder = jpeg_decoder::Decoder::new(Cursor::new(bytes));
+    // anything it can't read, the image crate gets to say what is wrong with
+    decoder.read_info().ok()?;
+    let info = decoder.info()?;
+    let (width, height) = (u32::from(info.width), u32::from(info.height));
+    if !matches!(info.pixel_format, PixelFormat::L8 | PixelFormat::RGB24) {
+        return None;
+    }
+    if let Err(err) = within_cap((width, height), path, max_pixels) {
+        return Some(Err(err));
+    }
+    let (target_width, target_height) = super::target_size((width, height), max_dimension);
+    // at least one pixel across, however thin the artwork
+    let requested = (target_width.max(1) as u16, target_height.max(1) as u16);
+    let (scaled_width, scaled_height) = decoder.scale(requested.0, requested.1).ok()?;
+    if (u32::from(scaled_width), u32::from(scaled_height)) == (width, height) {
+        return None;
+    }
+    decoder.set_max_decoding_buffer_size(usize::try_from(max_pixels.saturating_mul(16)).unwrap_or(usize::MAX));
+
+    let pixels = match decoder.decode() {
+        Ok(pixels) => pixels,
+        Err(err) => {
+            let format = ImageFormatHint::Exact(ImageFormat::Jpeg);
+            return Some(Err(ImageError::Decoding(DecodingError::new(format, err))));
+        }
+    };
+    let (scaled_width, scaled_height) = (u32::from(scaled_width), u32::from(scaled_height));
+    let image = match info.pixel_format {
+        PixelFormat::L8 => GrayImage::from_raw(scaled_width, scaled_height, pixels).map(DynamicImage::ImageLuma8),
+        _ => RgbImage::from_raw(scaled_width, scaled_height, pixels).map(DynamicImage::ImageRgb8),
+    }?;
+    tracing::debug!("{}: decoded {}×{} at {}×{}", path.display(), width, height, scaled_width, scaled_height);
+    Some(Ok(match decoder.icc_profile() {
+        Some(profile) => to_srgb(image, &profile, path),
+        None => image,
+    }))
+}
+
+/// Refuses an image at `path` of more than `max_pixels` at its full `size`.
+fn within_cap((width, height): (u32, u32), path: &Path, max_pixels: u64) -> ImageResult<()> {
+    if u64::from(width) * u64::from(height) > max_pixels {
+        tracing::warn!(
+            "{}: {}×{} is over {} megapixels, not decoding it",
+            path.display(),
+            width,
+            height,
+            max_pixels / 1_000_000
+        );
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+    Ok(())
+}
+
+/// `image` converted from the ICC `profile` to sRGB. Anything that gets in the
+/// way is logged and leaves the colors as they were.
+fn to_srgb(image: DynamicImage, profile: &[u8], path: &Path) -> DynamicImage {
+    let Some(source) = Profile::new_from_slice(profile, false) else {
+        tracing::warn!("{}: unreadable color profile, showing the colors as they are", path.display());
+        return image;
+    };
+    // grayscale keeps its gray, whatever the profile says
+    if source.is_sRGB() || !image.color().has_color() {
+        return image;
+    }
+
+    let mut srgb = Profile::new_sRGB();
+    srgb.precache_output_transform();
+    let has_alpha = image.color().has_alpha();
+    let data_type = if has_alpha { DataType::RGBA8 } else { DataType::RGB8 };
+    let Some(transform) = Transform::new(&source, &srgb, data_type, Intent::Perceptual) else {
+        tracing::warn!("{}: cannot convert its color profile, showing the colors as they are", path.display());
+        return image;
+    };
+
+    tracing::debug!("{}: converting the embedded color profile to sRGB", path.display());
+    // qcms works in 8 bits, so a 16-bit image with a profile loses its extra depth here
+    if has_alpha {
+        let mut pixels = image.into_rgba8();
+        transform.apply(&mut pixels);
+        DynamicImage::ImageRgba8(pixels)
+    } else {
+        let mut pixels = image.into_rgb8();
+        transform.apply(&mut pixels);
+        DynamicImage::ImageRgb8(pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn unusable_profiles_leave_the_colors_alone() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, image::Rgb([200, 40, 10])));
+        let kept = to_srgb(image.clone(), b"not an icc profile", Path::new("scan.jpg"));
+        assert_eq!(kept.to_rgb8(), image.to_rgb8());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.png");
+        image.save(&path).unwrap();
+        assert_eq!(decode(&std::fs::read(&path).unwrap(), &path, 16, None).unwrap().to_rgb8(), image.to_rgb8());
+    }
+
+    #[test]
+    fn images_over_the_cap_are_not_decoded() {
+        let mut png = Vec::new();
+        let image = DynamicImage::ImageRgb8(RgbImage::new(5, 4));
+        image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png).unwrap();
+        assert!(matches!(decode(&png, Path::new("huge.png"), 19, None), Err(ImageError::Limits(_))));
+        assert!(decode(&png, Path::new("huge.png"), 20, None).is_ok());
+    }
+}
@@ -0,0 +1,59 @@
+//! QR codes for an artwork's `url`, for visitors to scan its museum page.
+
+use image::{GrayImage, Luma};
+use qrcode::{Color, QrCode};
+use std::sync::Mutex;
+
+/// Light modules around the code, as many as the standard asks for.
+const QUIET_ZONE: u32 = 4;
+
+/// The last code made, so the preview and the full image of a slide share it.
+static LAST: Mutex<Option<(String, GrayImage)>> = Mutex::new(None);
+
+/// `url` as a QR code, black on white at a pixel per module, quiet zone
+/// included. `None` for one too long to encode.
+pub fn code(url: &str) -> Option<GrayImage> {
+    let mut last = LAST.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((cached_url, image)) = &*last {
+        if cached_url == url {
+            return Some(image.clone());
+        }
+    }
+
+    let qr = match QrCode::new(url.as_bytes()) {
+        Ok(qr) => qr,
+        Err(err) => {
+            tracing::warn!("no QR code for {}: {}", url, err);
+            return None;
+        }
+    };
+    let width = qr.width() as u32;
+    let colors = qr.to_colors();
+    let module = |pixel: u32| pixel.checked_sub(QUIET_ZONE).filter(|module| *module < width);
+    let side = width + 2 * QUIET_ZONE;
+    let image = GrayImage::from_fn(side, side, |x, y| {
+        let dark = match (module(x), module(y)) {
+            (Some(x), Some(y)) => colors[(y * width + x) as usize] == Color::Dark,
+            _ => false,
+        };
+        Luma([if dark { 0 } else { 255 }])
+    });
+    *last = Some((url.to_string(), image.clone()));
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_have_a_quiet_zone() {
+        let image = code("https://x.org").unwrap();
+        // version 1 is 21 modules across
+        assert_eq!(image.dimensions(), (29, 29));
+        assert!((0..29).all(|i| image.get_pixel(i, 0)[0] == 255 && image.get_pixel(0, i)[0] == 255));
+        // the top left finder pattern starts right inside it
+        assert_eq!(image.get_pixel(QUIET_ZONE, QUIET_ZONE)[0], 0);
+        assert_eq!(code("https://x.org"), Some(image));
+    }
+}
@@ -0,0 +1,95 @@
+//! Camera raw files, with the `raw` feature: a card dumped straight into the
+//! folder. They are developed with a plain demosaic to sRGB, or shown by the
+//! JPEG the camera embedded when that fails or `--raw-use-preview` asks for it.
+
+use super::{PipelineError, ProcessingOptions, depth, icc};
+use image::error::{ImageFormatHint, UnsupportedError, UnsupportedErrorKind};
+use image::{DynamicImage, ImageFormat, ImageReader, RgbImage};
+use std::io::Cursor;
+use std::path::Path;
+
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "cr3", "nef", "arw", "dng"];
+
+pub fn is_raw(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| RAW_EXTENSIONS.iter().any(|raw| ext.eq_ignore_ascii_case(raw)))
+}
+
+/// The raw file in `bytes`, the one at `path`, developed to at most
+/// `options.max_dimension`, or its preview.
+pub fn decode(bytes: &[u8], path: &Path, options: &ProcessingOptions) -> Result<DynamicImage, PipelineError> {
+    if !options.raw_use_preview {
+        match develop(bytes, options.max_dimension) {
+            Ok(image) => return Ok(image),
+            Err(err) => tracing::warn!("{}: cannot develop it, showing its preview instead: {}", path.display(), err),
+        }
+    }
+    let Some(preview) = embedded_preview(bytes) else {
+        let format = ImageFormatHint::PathExtension(path.to_path_buf());
+        let kind = UnsupportedErrorKind::Format(ImageFormatHint::Name("camera raw without a preview".to_string()));
+        return Err(PipelineError::UnsupportedFormat(UnsupportedError::from_format_and_kind(format, kind)));
+    };
+    Ok(depth::tone_map(icc::decode(preview, path, options.max_pixels, None)?))
+}
+
+/// Demosaiced and brought to sRGB, scaled down on the way to `max_dimension`.
+/// Cameras rawloader doesn't know fail here, with their make and model.
+fn develop(bytes: &[u8], max_dimension: u32) -> Result<DynamicImage, String> {
+    let raw = rawloader::decode(&mut Cursor::new(bytes)).map_err(|err| err.to_string())?;
+    let mut pipeline = imagepipe::Pipeline::new_from_source(imagepipe::ImageSource::Raw(raw))?;
+    pipeline.globals.settings.maxwidth = max_dimension as usize;
+    pipeline.globals.settings.maxheight = max_dimension as usize;
+    let developed = pipeline.output_8bit(None)?;
+    RgbImage::from_raw(developed.width as u32, developed.height as u32, developed.data)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| "developed to fewer pixels than its size".to_string())
+}
+
+/// The largest JPEG in the raw file, the camera's own rendering of it. The
+/// slice runs on to the end of the file, the decoder stops where the JPEG does.
+pub fn embedded_preview(bytes: &[u8]) -> Option<&[u8]> {
+    bytes
+        .windows(3)
+        .enumerate()
+        .filter(|(_, marker)| *marker == [0xFF, 0xD8, 0xFF])
+        .filter_map(|(start, _)| {
+            let jpeg = &bytes[start..];
+            let (width, height) =
+                ImageReader::with_format(Cursor::new(jpeg), ImageFormat::Jpeg).into_dimensions().ok()?;
+            Some((u64::from(width) * u64::from(height), jpeg))
+        })
+        .max_by_key(|(pixels, _)| *pixels)
+        .map(|(_, jpeg)| jpeg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn the_largest_preview_is_shown() {
+        // a thumbnail first and the full preview after, with sensor data around them
+        let mut nef = b"MM\0*not really sensor data".to_vec();
+        nef.extend(jpeg(16, 8));
+        nef.extend([0xFF, 0xD8, 0xFF, 0x00, 0x13]);
+        nef.extend(jpeg(64, 32));
+        nef.extend(b"more sensor data");
+
+        let options = ProcessingOptions { raw_use_preview: true, ..ProcessingOptions::default() };
+        let preview = decode(&nef, Path::new("DSC_0042.NEF"), &options).unwrap();
+        assert_eq!(preview.dimensions(), (64, 32));
+        assert!(matches!(
+            decode(b"MM\0*", Path::new("DSC_0043.NEF"), &options),
+            Err(PipelineError::UnsupportedFormat(_))
+        ));
+        assert!(is_raw(Path::new("DSC_0042.NEF")) && !is_raw(Path::new("DSC_0042.jpg")));
+    }
+}
@@ -0,0 +1,75 @@
+//! SVG artworks, with the `svg` feature: vector engravings and museum plates
+//! drawn at the size they are shown at, then processed like any other image.
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, ImageError, ImageResult, RgbaImage};
+use resvg::{tiny_skia, usvg};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+pub fn is_svg(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
+/// The system's fonts, for the text in a drawing. Looking them up takes a
+/// while, so it is done once.
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+fn error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Name("SVG".to_string()), err))
+}
+
+/// The drawing in `bytes`, the SVG at `path`, with its longer side
+/// `max_dimension` pixels long. Images it links to are looked for next to it.
+pub fn rasterize(bytes: &[u8], path: &Path, max_dimension: u32) -> ImageResult<DynamicImage> {
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: fonts(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_data(bytes, &options).map_err(error)?;
+    let size = tree.size();
+    let scale = max_dimension as f32 / size.width().max(size.height());
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or_else(|| error("drawing too large"))?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia keeps its colors multiplied by their alpha
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Ok(DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, pixels).expect("four bytes a pixel")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drawings_come_out_at_the_size_asked_for() {
+        let plate = br##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+            <rect width="40" height="20" fill="#ff0000"/>
+        </svg>"##;
+        let image = rasterize(plate, Path::new("plate.svg"), 400).unwrap();
+        assert_eq!((image.width(), image.height()), (400, 200));
+        assert_eq!(image.to_rgba8().get_pixel(200, 100).0, [255, 0, 0, 255]);
+
+        assert!(matches!(rasterize(b"<svg", Path::new("broken.svg"), 400), Err(ImageError::Decoding(_))));
+        assert!(is_svg(Path::new("engraving.SVG")));
+    }
+}
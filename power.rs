@@ -0,0 +1,28 @@
+//! Keeps the display awake while the slideshow runs.
+
+/// Holds the platform's wake lock until dropped.
+pub struct SleepInhibitor {
+    _awake: keepawake::KeepAwake,
+}
+
+impl SleepInhibitor {
+    /// Asks the OS not to blank the display or sleep. Failure (for example no
+    /// D-Bus session) is logged and the slideshow carries on without it.
+    pub fn acquire() -> Option<Self> {
+        let result = keepawake::Builder::default()
+            .display(true)
+            .idle(true)
+            .reason("Art slideshow running")
+            .app_name("art-slideshow")
+            .app_reverse_domain("io.github.davidcroitoru.art-slideshow")
+            .create();
+
+        match result {
+            Ok(awake) => Some(Self { _awake: awake }),
+            Err(err) => {
+                tracing::warn!("cannot keep the display awake: {}", err);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! `--preprocess-all`: every artwork is processed once before the show starts,
+//! all cores at it, so slow hardware pays for the pipeline up front and not
+//! on every transition. The app shows the progress meanwhile and keeps what
+//! comes out, in memory when the collection is small enough and otherwise in
+//! the disk cache the processing fills anyway.
+
+use crate::pipeline::PipelineError;
+use crate::scan::ArtworkInfo;
+use eframe::egui;
+use rayon::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Instant;
+
+/// Most the processed images kept in memory may take together, in bytes.
+pub const MEMORY_BUDGET: usize = 1 << 30;
+
+/// Failed files named in the summary before the rest are only counted.
+const NAMED_FAILURES: usize = 3;
+
+/// The file name shown for `info`.
+fn file_name(info: &ArtworkInfo) -> String {
+    info.path.file_name().map_or_else(|| info.path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// What to tell about the files that could not be processed.
+pub fn failure_summary(names: &[String]) -> String {
+    let listed = names.iter().take(NAMED_FAILURES).map(String::as_str).collect::<Vec<_>>().join(", ");
+    let more = names.len().saturating_sub(NAMED_FAILURES);
+    let images = if names.len() == 1 { "image" } else { "images" };
+    if more > 0 {
+        format!("Left out {} {} that could not be processed: {} and {} more", names.len(), images, listed, more)
+    } else {
+        format!("Left out {} {} that could not be processed: {}", names.len(), images, listed)
+    }
+}
+
+/// Processing of the whole collection, running on the rayon pool.
+pub struct Preprocess<T> {
+    names: Vec<String>,
+    finished: usize,
+    // the file finished last, named on the progress screen
+    last: Option<usize>,
+    failed: Vec<usize>,
+    results: Receiver<(usize, Result<T, PipelineError>)>,
+    started: Instant,
+    // set once the results aren't wanted anymore, what is left is skipped
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> Preprocess<T> {
+    /// Starts `process` on every one of `artworks`. `wake` is repainted as
+    /// each one finishes.
+    pub fn start<F>(artworks: &[ArtworkInfo], process: F, wake: Option<egui::Context>) -> Self
+    where
+        F: Fn(&ArtworkInfo) -> Result<T, PipelineError> + Send + Sync + 'static,
+    {
+        let (sender, results) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let work = artworks.to_vec();
+        let skip = Arc::clone(&cancelled);
+        rayon::spawn(move || {
+            work.par_iter().enumerate().for_each_with(sender, |sender, (index, info)| {
+                if skip.load(Ordering::Relaxed) {
+                    return;
+                }
+                if sender.send((index, process(info))).is_ok() {
+                    if let Some(ctx) = &wake {
+                        ctx.request_repaint();
+                    }
+                }
+            });
+        });
+        tracing::info!("processing all {} artworks before the show starts", artworks.len());
+        Self {
+            names: artworks.iter().map(file_name).collect(),
+            finished: 0,
+            last: None,
+            failed: Vec::new(),
+            results,
+            started: Instant::now(),
+            cancelled,
+        }
+    }
+
+    /// The results that came in since the last call, by index into the
+    /// artworks. Failures are logged and kept for [`Preprocess::failed`].
+    pub fn poll(&mut self) -> Vec<(usize, T)> {
+        let before = self.finished;
+        let mut done = Vec::new();
+        for (index, result) in self.results.try_iter() {
+            self.finished += 1;
+            self.last = Some(index);
+            match result {
+                Ok(processed) => done.push((index, processed)),
+                Err(err) => {
+                    tracing::warn!("cannot process {}, leaving it out: {}", self.names[index], err);
+                    self.failed.push(index);
+                }
+            }
+        }
+        if self.is_done() && self.finished > before {
+            tracing::info!("processed {} artworks in {:.1?}", self.names.len(), self.started.elapsed());
+        }
+        done
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.finished == self.names.len()
+    }
+
+    /// Indices of the artworks that could not be processed, in order.
+    pub fn failed(&self) -> Vec<usize> {
+        let mut failed = self.failed.clone();
+        failed.sort_unstable();
+        failed
+    }
+
+    /// Names of the files that could not be processed, in order.
+    pub fn failed_names(&self) -> Vec<String> {
+        self.failed().into_iter().map(|index| self.names[index].clone()).collect()
+    }
+
+    /// The progress screen.
+    pub fn show(&self, ctx: &egui::Context) {
+        let total = self.names.len();
+        let text = match self.last {
+            Some(last) => format!("Processing {}/{}: {}", self.finished, total, self.names[last]),
+            None => format!("Processing {} images", total),
+        };
+        egui::CentralPanel::default().frame(egui::Frame::none().fill(egui::Color32::BLACK)).show(ctx, |ui| {
+            ui.centered_and_justified(|ui| {
+                ui.add(
+                    egui::ProgressBar::new(self.finished as f32 / total.max(1) as f32)
+                        .desired_width(ui.available_width().min(600.0))
+                        .text(text),
+                );
+            });
+        });
+    }
+}
+
+impl<T> Drop for Preprocess<T> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn every_artwork_is_processed_and_failures_are_named() {
+        let artworks: Vec<ArtworkInfo> = ["a.jpg", "broken.png", "c.jpg"]
+            .iter()
+            .map(|name| ArtworkInfo {
+                path: PathBuf::from("/art").join(name),
+                metadata: ArtworkMetadata::unknown(name),
+                remote: None,
+            })
+            .collect();
+        let mut preprocess = Preprocess::start(
+            &artworks,
+            |info| match info.metadata.title.as_str() {
+                "broken.png" => Err(PipelineError::Io(std::io::ErrorKind::InvalidData.into())),
+                title => Ok(title.len()),
+            },
+            None,
+        );
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut done = Vec::new();
+        while !preprocess.is_done() && Instant::now() < deadline {
+            done.extend(preprocess.poll());
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        done.sort_unstable();
+        assert_eq!(done, [(0, 5), (2, 5)]);
+        assert_eq!(preprocess.failed(), [1]);
+        assert_eq!(preprocess.failed_names(), ["broken.png"]);
+
+        assert_eq!(
+            failure_summary(&preprocess.failed_names()),
+            "Left out 1 image that could not be processed: broken.png"
+        );
+        let many: Vec<String> = (1..=5).map(|n| format!("{}.jpg", n)).collect();
+        assert!(failure_summary(&many).ends_with("1.jpg, 2.jpg, 3.jpg and 2 more"));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::persist;
+use crate::scan::ArtworkInfo;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const RATINGS_FILE: &str = "ratings.json";
+
+/// Highest rating, given with the 5 key.
+pub const MAX_STARS: u8 = 5;
+
+/// What an artwork nobody has rated counts as, for `--min-rating` and `--weighted`.
+pub const UNRATED: u8 = 3;
+
+/// Star ratings given with the 1–5 keys, stored as paths relative to the image folder.
+pub struct Ratings {
+    root: PathBuf,
+    entries: BTreeMap<String, u8>,
+}
+
+/// Reads the ratings in `path`. A missing file has none, a malformed one is
+/// reported and treated as empty.
+fn read(path: &Path) -> BTreeMap<String, u8> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|err| {
+            tracing::warn!("ignoring malformed {}: {}", path.display(), err);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+impl Ratings {
+    /// Reads `ratings.json` from `root`.
+    pub fn load(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            entries: read(&root.join(RATINGS_FILE)),
+        }
+    }
+
+    pub fn get(&self, image: &Path) -> Option<u8> {
+        self.entries.get(&persist::relative_key(&self.root, image)).copied()
+    }
+
+    /// The rating of `image`, [`UNRATED`] when it has none.
+    pub fn stars(&self, image: &Path) -> u8 {
+        self.get(image).unwrap_or(UNRATED)
+    }
+
+    /// How likely each artwork is to come up next in weighted playback.
+    pub fn weights(&self, artworks: &[ArtworkInfo]) -> Vec<u32> {
+        artworks.iter().map(|info| self.stars(&info.path) as u32).collect()
+    }
+
+    /// Rates `image` and saves it. The file is read again first, so ratings
+    /// another session saved in the meantime are kept rather than overwritten.
+    pub fn set(&mut self, image: &Path, stars: u8) -> io::Result<()> {
+        let path = self.root.join(RATINGS_FILE);
+        let mut entries = read(&path);
+        entries.insert(persist::relative_key(&self.root, image), stars.clamp(1, MAX_STARS));
+
+        let json = serde_json::to_vec_pretty(&entries)?;
+        persist::write_atomic(&path, &json)?;
+        self.entries = entries;
+        Ok(())
+    }
+}
+
+/// `★★★☆☆` for three stars.
+pub fn star_text(stars: u8) -> String {
+    let stars = stars.min(MAX_STARS) as usize;
+    "★".repeat(stars) + &"☆".repeat(MAX_STARS as usize - stars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratings_from_two_sessions_accumulate() {
+        let dir = tempfile::tempdir().unwrap();
+        let goya = dir.path().join("goya.jpg");
+        let monet = dir.path().join("monet.jpg");
+
+        let mut first = Ratings::load(dir.path());
+        let mut second = Ratings::load(dir.path());
+        first.set(&goya, 5).unwrap();
+        second.set(&monet, 2).unwrap();
+
+        let saved = Ratings::load(dir.path());
+        assert_eq!(saved.get(&goya), Some(5));
+        assert_eq!(saved.get(&monet), Some(2));
+        assert_eq!(saved.stars(&dir.path().join("klimt.jpg")), UNRATED);
+        assert_eq!(star_text(2), "★★☆☆☆");
+    }
+}
@@ -0,0 +1,205 @@
+//! Playlist entries that are `http://` or `https://` URLs, for a library on a
+//! NAS or any web server. Each is downloaded into the cache folder's `remote`
+//! directory just before it is processed, on the loader's thread, and asked
+//! for again every time around with `If-None-Match` and `If-Modified-Since`,
+//! so a file that didn't change isn't downloaded twice. Its sidecar is the
+//! `.json` URL beside it, as with local files.
+//!
+//! When the server can't be reached the copy from last time is shown; an
+//! entry that was never downloaded is skipped until the next time around.
+
+use crate::pipeline::cache;
+use crate::scan::{self, ArtworkInfo};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(20);
+/// Larger downloads are refused, no painting needs this much.
+const MAX_BYTES: u64 = 512 * 1024 * 1024;
+const USER_AGENT: &str = concat!("art-slideshow/", env!("CARGO_PKG_VERSION"));
+
+pub fn is_url(entry: &str) -> bool {
+    entry.starts_with("http://") || entry.starts_with("https://")
+}
+
+/// Where `url` is downloaded to under `dir`: a folder of its own named after
+/// the URL, holding the file under its own name so the title falls back to
+/// it and the sidecar sits beside it.
+pub fn cache_path(dir: &Path, url: &str) -> PathBuf {
+    let without_query = url.split(['?', '#']).next().unwrap_or(url);
+    let name: String = without_query
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    dir.join(format!("{:016x}", cache::stable_hash(url.as_bytes()))).join(if name.is_empty() {
+        "image".to_string()
+    } else {
+        name
+    })
+}
+
+/// The sidecar's URL, `.json` in place of the image's extension.
+fn sidecar_url(url: &str) -> String {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let (base, rest) = url.split_at(end);
+    let name_start = base.rfind('/').map_or(0, |slash| slash + 1);
+    let stem = match base[name_start..].rfind('.') {
+        Some(dot) => &base[..name_start + dot],
+        None => base,
+    };
+    format!("{}.json{}", stem, rest)
+}
+
+/// What the server said about the copy on disk, sent back to ask whether it changed.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+fn validators_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".http");
+    path.with_file_name(name)
+}
+
+fn agent() -> &'static ureq::Agent {
+    static AGENT: OnceLock<ureq::Agent> = OnceLock::new();
+    AGENT.get_or_init(|| ureq::AgentBuilder::new().timeout(TIMEOUT).user_agent(USER_AGENT).build())
+}
+
+#[derive(Debug, PartialEq)]
+enum Downloaded {
+    Changed,
+    Unchanged,
+    /// 404 or 410.
+    Gone,
+}
+
+/// Downloads `url` into `path`, unless the copy there is still current.
+fn download(url: &str, path: &Path) -> Result<Downloaded, String> {
+    let validators: Validators = if path.is_file() {
+        fs::read_to_string(validators_path(path))
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    } else {
+        Validators::default()
+    };
+    let mut request = agent().get(url);
+    if let Some(etag) = &validators.etag {
+        request = request.set("If-None-Match", etag);
+    }
+    if let Some(modified) = &validators.last_modified {
+        request = request.set("If-Modified-Since", modified);
+    }
+    let response = match request.call() {
+        Ok(response) if response.status() == 304 => return Ok(Downloaded::Unchanged),
+        Ok(response) => response,
+        Err(ureq::Error::Status(404 | 410, _)) => return Ok(Downloaded::Gone),
+        Err(err) => return Err(err.to_string()),
+    };
+    let validators = Validators {
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+    };
+    let mut body = Vec::new();
+    response.into_reader().take(MAX_BYTES + 1).read_to_end(&mut body).map_err(|err| err.to_string())?;
+    if body.len() as u64 > MAX_BYTES {
+        return Err(format!("larger than {} MiB", MAX_BYTES / 1024 / 1024));
+    }
+
+    let write = |path: &Path, contents: &[u8]| -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        crate::persist::write_atomic(path, contents)
+    };
+    write(path, &body).map_err(|err| format!("cannot write {}: {}", path.display(), err))?;
+    // without them the next time around downloads it all again, nothing worse
+    if let Ok(text) = serde_json::to_string(&validators) {
+        let _ = write(&validators_path(path), text.as_bytes());
+    }
+    Ok(Downloaded::Changed)
+}
+
+/// `info` with its file on disk: downloaded or checked again if it is a URL
+/// entry, with its sidecar as it is now. Local files are left as they are.
+pub fn fetch(info: &ArtworkInfo) -> Result<ArtworkInfo, String> {
+    let Some(url) = &info.remote else {
+        return Ok(info.clone());
+    };
+    match download(url, &info.path) {
+        Ok(Downloaded::Changed | Downloaded::Unchanged) => {}
+        Ok(Downloaded::Gone) => return Err(format!("{} is gone", url)),
+        Err(err) if info.path.is_file() => {
+            tracing::warn!("cannot check {} again, showing the copy from last time: {}", url, err);
+            return Ok(info.clone());
+        }
+        Err(err) => return Err(format!("cannot download {}: {}", url, err)),
+    }
+
+    let sidecar = info.path.with_extension("json");
+    match download(&sidecar_url(url), &sidecar) {
+        Ok(Downloaded::Gone) => {
+            let _ = fs::remove_file(&sidecar);
+            let _ = fs::remove_file(validators_path(&sidecar));
+        }
+        Ok(_) => {}
+        Err(err) => tracing::debug!("cannot download the sidecar of {}: {}", url, err),
+    }
+    Ok(ArtworkInfo { metadata: scan::read_metadata(&info.path), ..info.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn downloads_are_asked_for_again_only_if_changed() {
+        assert_eq!(sidecar_url("http://nas/art/monet.jpg?size=full"), "http://nas/art/monet.json?size=full");
+        let dir = tempfile::tempdir().unwrap();
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/art/water%20lilies.jpg", server.server_addr().to_ip().unwrap());
+        let path = cache_path(dir.path(), &url);
+        assert_eq!(path.file_name().unwrap(), "water_20lilies.jpg");
+        let (asked, conditions) = mpsc::channel();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let etag =
+                    request.headers().iter().find(|h| h.field.equiv("If-None-Match")).map(|h| h.value.to_string());
+                let _ = asked.send((request.url().to_string(), etag.clone()));
+                let response = match (request.url().ends_with(".jpg"), etag) {
+                    (true, None) => tiny_http::Response::from_data(b"pixels".to_vec())
+                        .with_header(tiny_http::Header::from_bytes("ETag", "\"v1\"").unwrap()),
+                    (true, Some(_)) => tiny_http::Response::from_data(Vec::new()).with_status_code(304),
+                    (false, _) => tiny_http::Response::from_data(
+                        br#"{"title":"Water Lilies","artist":"Monet","year":"1906"}"#.to_vec(),
+                    ),
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        let info = ArtworkInfo { path: path.clone(), metadata: scan::read_metadata(&path), remote: Some(url) };
+        let fetched = fetch(&info).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"pixels");
+        assert_eq!(fetched.metadata.title, "Water Lilies");
+        let fetched = fetch(&info).unwrap();
+        assert_eq!(fetched.metadata.artist, "Monet");
+
+        let asked: Vec<_> = conditions.try_iter().collect();
+        assert_eq!(asked[0].1, None);
+        assert_eq!(asked[1].0, "/art/water%20lilies.json");
+        assert_eq!(asked[2].1.as_deref(), Some("\"v1\""));
+    }
+}
@@ -0,0 +1,362 @@
+//! `art-slideshow render`: the slides composited into numbered PNG files
+//! without a window, for promotional stills or checking layouts in CI.
+//!
+//! The artwork goes through the same pipeline, and is placed by the same
+//! layout code as on screen, so a frame matches what the slideshow shows. One
+//! point is one pixel. Text is drawn with ab_glyph in the caption font, egui's
+//! own fonts filling in for glyphs it lacks. The clock, QR codes and toasts
+//! change while the show runs and are left out.
+
+use crate::app;
+use crate::config::Config;
+use crate::fonts;
+use crate::layout::{self, Fit};
+use crate::overlay::{self, CAPTION_FONT_SIZES, CaptionFrame, CaptionLook, CaptionPalette, SHADOW_OFFSETS};
+use crate::pipeline::{self, Background, BlurSize, ProcessedImage};
+use ab_glyph::{Font, FontArc, Glyph, GlyphId, PxScale, ScaleFont, point};
+use eframe::egui;
+use image::{RgbaImage, imageops};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Frame size without `--size`.
+pub const DEFAULT_SIZE: (u32, u32) = (1920, 1080);
+
+/// A frame size written `1920x1080`.
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let size: BlurSize = s.parse()?;
+    if size.width == 0 || size.height == 0 {
+        return Err(format!("expected a size like 1920x1080, got {:?}", s));
+    }
+    Ok((size.width, size.height))
+}
+
+/// The caption font and egui's fonts behind it, the way the screen falls back.
+pub struct CaptionFonts(Vec<FontArc>);
+
+impl CaptionFonts {
+    pub fn load(path: Option<&Path>) -> Self {
+        let mut loaded = Vec::new();
+        if let Some(path) = path {
+            match fonts::load(path).and_then(|bytes| FontArc::try_from_vec(bytes).map_err(|err| err.to_string())) {
+                Ok(font) => loaded.push(font),
+                Err(err) => tracing::warn!("cannot use font {}, keeping the default: {}", path.display(), err),
+            }
+        }
+        let defaults = egui::FontDefinitions::default();
+        let family = defaults.families.get(&egui::FontFamily::Proportional).cloned().unwrap_or_default();
+        for name in family {
+            if let Some(data) = defaults.font_data.get(&name) {
+                if let Ok(font) = FontArc::try_from_vec(data.font.to_vec()) {
+                    loaded.push(font);
+                }
+            }
+        }
+        Self(loaded)
+    }
+
+    /// The first font that has `c`, and its glyph, the first font's blank one if none does.
+    fn glyph(&self, c: char) -> (usize, GlyphId) {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, font)| (index, font.glyph_id(c)))
+            .find(|(_, id)| id.0 != 0)
+            .unwrap_or((0, GlyphId(0)))
+    }
+
+    /// The scale that sets font `index` at `size` points the way egui does.
+    fn scale(&self, index: usize, size: f32) -> PxScale {
+        let font = &self.0[index];
+        PxScale::from(size * font.height_unscaled() / font.units_per_em().unwrap_or(1000.0))
+    }
+}
+
+/// A line of text laid out at its origin, wrapped onto as many rows as it needs.
+struct Text {
+    glyphs: Vec<(usize, Glyph)>,
+    size: egui::Vec2,
+}
+
+impl Text {
+    /// Lays `text` out at `size` points, wrapping at spaces to stay within `wrap_width`.
+    fn layout(fonts: &CaptionFonts, text: &str, size: f32, wrap_width: f32) -> Self {
+        let Some(first) = fonts.0.first() else {
+            return Self { glyphs: Vec::new(), size: egui::Vec2::ZERO };
+        };
+        let metrics = first.as_scaled(fonts.scale(0, size));
+        let row_height = metrics.ascent() - metrics.descent() + metrics.line_gap();
+
+        let chars: Vec<(usize, GlyphId, f32, bool)> = text
+            .chars()
+            .map(|c| {
+                let (index, id) = fonts.glyph(c);
+                let advance = fonts.0[index].as_scaled(fonts.scale(index, size)).h_advance(id);
+                (index, id, advance, c.is_whitespace())
+            })
+            .collect();
+
+        // breaks at the last space before the row gets too wide, or mid-word when it has none
+        let mut rows = Vec::new();
+        let (mut row_start, mut x, mut last_space) = (0, 0.0, None);
+        for (k, &(_, _, advance, space)) in chars.iter().enumerate() {
+            if x + advance > wrap_width && k > row_start && !space {
+                let (end, next) = match last_space {
+                    Some(at) if at > row_start => (at, at + 1),
+                    _ => (k, k),
+                };
+                rows.push(row_start..end);
+                row_start = next;
+                x = chars[next..k].iter().map(|c| c.2).sum();
+                last_space = None;
+            }
+            if space {
+                last_space = Some(k);
+            }
+            x += advance;
+        }
+        rows.push(row_start..chars.len());
+
+        let mut glyphs = Vec::new();
+        let mut width: f32 = 0.0;
+        for (row, range) in rows.iter().enumerate() {
+            let baseline = row as f32 * row_height + metrics.ascent();
+            let mut x = 0.0;
+            let mut inked = 0.0;
+            for &(index, id, advance, space) in &chars[range.clone()] {
+                glyphs.push((index, id.with_scale_and_position(fonts.scale(index, size), point(x, baseline))));
+                x += advance;
+                if !space {
+                    inked = x;
+                }
+            }
+            width = width.max(inked);
+        }
+        Self { glyphs, size: egui::vec2(width, rows.len() as f32 * row_height) }
+    }
+
+    fn draw(&self, canvas: &mut RgbaImage, fonts: &CaptionFonts, origin: egui::Pos2, color: egui::Color32) {
+        for (index, glyph) in &self.glyphs {
+            let mut glyph = glyph.clone();
+            glyph.position = point(glyph.position.x + origin.x, glyph.position.y + origin.y);
+            let Some(outlined) = fonts.0[*index].outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|x, y, coverage| {
+                blend(canvas, bounds.min.x as i64 + x as i64, bounds.min.y as i64 + y as i64, color, coverage);
+            });
+        }
+    }
+}
+
+/// Paints `color` over the pixel at `x`, `y` with `coverage` of its opacity.
+fn blend(canvas: &mut RgbaImage, x: i64, y: i64, color: egui::Color32, coverage: f32) {
+    if x < 0 || y < 0 || x >= canvas.width() as i64 || y >= canvas.height() as i64 {
+        return;
+    }
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let alpha = a as f32 / 255.0 * coverage.clamp(0.0, 1.0);
+    let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+    for (channel, value) in pixel.0.iter_mut().zip([r, g, b]) {
+        *channel = (*channel as f32 * (1.0 - alpha) + value as f32 * alpha).round() as u8;
+    }
+}
+
+/// Fills `rect` with `color`, its corners rounded by `rounding`.
+fn fill_rect(canvas: &mut RgbaImage, rect: egui::Rect, rounding: f32, color: egui::Color32) {
+    let inner = rect.shrink(rounding);
+    let (x0, y0) = (rect.min.x.floor().max(0.0) as i64, rect.min.y.floor().max(0.0) as i64);
+    for y in y0..rect.max.y.ceil() as i64 {
+        for x in x0..rect.max.x.ceil() as i64 {
+            let center = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+            if !rect.contains(center) {
+                continue;
+            }
+            // how far into a rounded corner, measured from the corner of the rect inside it
+            let coverage = if rounding > 0.0 { rounding + 0.5 - (center - inner.clamp(center)).length() } else { 1.0 };
+            blend(canvas, x, y, color, coverage);
+        }
+    }
+}
+
+/// The slide `processed` composited on a frame of `size`, as the screen would show it.
+pub fn compose(processed: &ProcessedImage, size: (u32, u32), config: &Config, fonts: &CaptionFonts) -> RgbaImage {
+    let mut canvas = match &processed.background {
+        Background::Blurred(blurred) => imageops::resize(&blurred.to_rgba8(), size.0, size.1, imageops::Triangle),
+        Background::Color([r, g, b]) => RgbaImage::from_pixel(size.0, size.1, image::Rgba([*r, *g, *b, 255])),
+        Background::Black => RgbaImage::from_pixel(size.0, size.1, image::Rgba([0, 0, 0, 255])),
+    };
+    let screen_size = egui::vec2(size.0 as f32, size.1 as f32);
+    let display_size = egui::vec2(processed.display_size.0 as f32, processed.display_size.1 as f32);
+
+    let mat = config.mat;
+    let matted = mat.is_on() && processed.fit == Fit::Contain && processed.works.len() == 1;
+    let (image_rect, uv) = layout::place_artwork(
+        processed.fit,
+        display_size,
+        screen_size,
+        matted.then_some(&mat),
+        processed.pixel_art,
+        1.0,
+    );
+    if matted {
+        for (rect, color) in mat.rings(image_rect, screen_size) {
+            fill_rect(&mut canvas, rect, 0.0, color.into());
+        }
+    }
+
+    let source = &processed.main_image;
+    let (width, height) = (source.width() as f32, source.height() as f32);
+    let shown = source.crop_imm(
+        (uv.min.x * width) as u32,
+        (uv.min.y * height) as u32,
+        ((uv.width() * width) as u32).max(1),
+        ((uv.height() * height) as u32).max(1),
+    );
+    let filter = if processed.pixel_art { imageops::Nearest } else { imageops::CatmullRom };
+    let placed = imageops::resize(
+        &shown.to_rgba8(),
+        (image_rect.width().round() as u32).max(1),
+        (image_rect.height().round() as u32).max(1),
+        filter,
+    );
+    imageops::overlay(&mut canvas, &placed, image_rect.min.x.round() as i64, image_rect.min.y.round() as i64);
+
+    if let Some(metadata) = processed.works.first() {
+        draw_caption(&mut canvas, processed, metadata, image_rect, config, fonts);
+    }
+    canvas
+}
+
+fn draw_caption(
+    canvas: &mut RgbaImage,
+    processed: &ProcessedImage,
+    metadata: &crate::scan::ArtworkMetadata,
+    image_rect: egui::Rect,
+    config: &Config,
+    fonts: &CaptionFonts,
+) {
+    let screen_size = egui::vec2(canvas.width() as f32, canvas.height() as f32);
+    let scale = config.caption_scale();
+    let wrap_width = overlay::caption_wrap_width(screen_size, config.overlay);
+    let [title, year] = overlay::caption_text(metadata);
+    let lines = [
+        Text::layout(fonts, &title, CAPTION_FONT_SIZES[0] * scale, wrap_width),
+        Text::layout(fonts, &year, CAPTION_FONT_SIZES[1] * scale, wrap_width),
+    ];
+    let sizes = [lines[0].size, lines[1].size];
+    let frame = CaptionFrame::new(sizes, screen_size, scale, config.overlay);
+    let palette = CaptionPalette::choose(config.caption_style, processed.caption_sample, image_rect, frame.rect);
+
+    if config.scrim {
+        let (clear, edge) = overlay::scrim_span(frame.rect, screen_size, config.overlay.position.is_top());
+        for y in 0..canvas.height() {
+            let t = (y as f32 + 0.5 - clear) / (edge - clear);
+            if (0.0..=1.0).contains(&t) {
+                for x in 0..canvas.width() {
+                    blend(canvas, x as i64, y as i64, egui::Color32::BLACK, overlay::scrim_alpha(t));
+                }
+            }
+        }
+    }
+    if let Some(rounding) = frame.rounding() {
+        fill_rect(canvas, frame.rect, rounding, palette.backdrop);
+    }
+    for ((line, color), origin) in lines.iter().zip([palette.title, palette.year]).zip(frame.line_positions(sizes)) {
+        if frame.look == CaptionLook::Shadow {
+            for offset in SHADOW_OFFSETS {
+                line.draw(canvas, fonts, origin + offset, palette.backdrop.gamma_multiply(0.5));
+            }
+        }
+        line.draw(canvas, fonts, origin, color);
+    }
+}
+
+/// How a render went.
+pub struct Report {
+    pub written: usize,
+    /// The first file of each slide that failed, and why.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Writes every slide of the show `config` makes of `folder` into `out` as
+/// `slide-0001.png` and on, at `size`. A slide that fails is reported and
+/// skipped, its number left out.
+pub fn render_all(folder: PathBuf, config: &Config, out: &Path, size: (u32, u32)) -> io::Result<Report> {
+    fs::create_dir_all(out)?;
+    let collection = app::collect(folder, config).map_err(io::Error::other)?;
+    let mut options = config.processing_options();
+    options.canvas = size;
+    if config.blur_size.is_none() {
+        options.blur_for_screen(size);
+    }
+    let fonts = CaptionFonts::load(config.font.as_deref());
+
+    let mut report = Report { written: 0, failed: Vec::new() };
+    for (number, slide) in collection.artworks.chunks(options.layout.cells()).enumerate() {
+        let path = out.join(format!("slide-{:04}.png", number + 1));
+        let processed =
+            pipeline::catch_panic(|| app::process_slide(slide, &*collection.source, &options, pipeline::process_image));
+        match processed {
+            Ok(processed) => {
+                compose(&processed, size, config, &fonts).save(&path).map_err(io::Error::other)?;
+                tracing::info!("wrote {}", path.display());
+                report.written += 1;
+            }
+            Err(err) => {
+                tracing::error!("cannot render {}: {}", slide[0].path.display(), err);
+                report.failed.push((slide[0].path.clone(), err.to_string()));
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::CaptionSample;
+    use crate::scan::ArtworkMetadata;
+    use image::{DynamicImage, Rgba};
+
+    #[test]
+    fn a_frame_matches_the_screen_layout() {
+        let processed = ProcessedImage {
+            main_image: DynamicImage::ImageRgba8(RgbaImage::from_pixel(100, 100, Rgba([200, 30, 30, 255]))),
+            display_size: (100, 100),
+            source_size: (100, 100),
+            is_preview: false,
+            fit: Fit::Contain,
+            background: Background::Color([0, 0, 90]),
+            caption_sample: CaptionSample { background: 0.1, image: 0.3 },
+            works: vec![ArtworkMetadata {
+                artist: "Vincent van Gogh".to_string(),
+                year: "1889".to_string(),
+                ..ArtworkMetadata::unknown("The Starry Night")
+            }],
+            qr_code: None,
+            pixel_art: false,
+            detail_duration: None,
+        };
+        let fonts = CaptionFonts::load(None);
+        let frame = compose(&processed, (640, 360), &Config::default(), &fonts);
+
+        // contained in the middle, the background around it
+        assert_eq!(frame.get_pixel(320, 180).0, [200, 30, 30, 255]);
+        assert_eq!(frame.get_pixel(20, 20).0, [0, 0, 90, 255]);
+        // the caption's dark box, with light text in it, in the bottom left corner
+        let text = Text::layout(&fonts, "The Starry Night - Vincent van Gogh", 26.0, f32::INFINITY);
+        assert!(text.size.x > 200.0 && text.size.y > 20.0);
+        let wrapped = Text::layout(&fonts, "The Starry Night - Vincent van Gogh", 26.0, 150.0);
+        assert!(wrapped.size.x <= 150.0 && wrapped.size.y > text.size.y * 1.5);
+        let frame = &frame;
+        let caption = (20..260).flat_map(|x| (300..345).map(move |y| frame.get_pixel(x, y).0));
+        let light = caption.filter(|pixel| pixel[0] > 150 && pixel[2] > 150).count();
+        assert!(light > 50, "only {} light pixels under the caption", light);
+
+        assert_eq!(parse_size("1280x720"), Ok((1280, 720)));
+        assert!(parse_size("0x720").is_err());
+    }
+}
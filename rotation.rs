@@ -0,0 +1,158 @@
+//! Turning the whole composition for displays mounted on their side.
+//!
+//! egui can't rotate a panel, so a slide is laid out in the rotated
+//! ("logical") screen and its shapes are mapped onto the real one afterwards.
+
+use egui::epaint::{Mesh, RectShape, Shape};
+use egui::{LayerId, Pos2, Rect, Vec2, pos2, vec2};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::FRAC_PI_2;
+use std::str::FromStr;
+
+/// Clockwise rotation of the composition.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rotation {
+    #[default]
+    #[serde(rename = "0")]
+    None,
+    #[serde(rename = "90")]
+    Quarter,
+    #[serde(rename = "180")]
+    Half,
+    #[serde(rename = "270")]
+    ThreeQuarters,
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(Rotation::None),
+            "90" => Ok(Rotation::Quarter),
+            "180" => Ok(Rotation::Half),
+            "270" => Ok(Rotation::ThreeQuarters),
+            other => Err(format!("expected 0, 90, 180 or 270, got {:?}", other)),
+        }
+    }
+}
+
+impl Rotation {
+    fn quarter_turns(self) -> u8 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Quarter => 1,
+            Rotation::Half => 2,
+            Rotation::ThreeQuarters => 3,
+        }
+    }
+
+    /// Size of the screen the slide is laid out in: width and height swap for 90 and 270.
+    pub fn logical_size(self, screen: Vec2) -> Vec2 {
+        if self.quarter_turns() % 2 == 1 {
+            vec2(screen.y, screen.x)
+        } else {
+            screen
+        }
+    }
+
+    /// Where a logical point ends up on a screen of size `screen`.
+    pub fn map(self, p: Pos2, screen: Vec2) -> Pos2 {
+        match self {
+            Rotation::None => p,
+            Rotation::Quarter => pos2(screen.x - p.y, p.x),
+            Rotation::Half => pos2(screen.x - p.x, screen.y - p.y),
+            Rotation::ThreeQuarters => pos2(p.y, screen.y - p.x),
+        }
+    }
+
+    /// The logical point that ends up at `p` on a screen of size `screen`, the
+    /// way back from [`Rotation::map`] for where the pointer is.
+    pub fn unmap(self, p: Pos2, screen: Vec2) -> Pos2 {
+        let inverse = match self {
+            Rotation::Quarter => Rotation::ThreeQuarters,
+            Rotation::ThreeQuarters => Rotation::Quarter,
+            other => other,
+        };
+        inverse.map(p, self.logical_size(screen))
+    }
+
+    fn map_rect(self, rect: Rect, screen: Vec2) -> Rect {
+        Rect::from_two_pos(self.map(rect.min, screen), self.map(rect.max, screen))
+    }
+
+    fn map_shape(self, shape: &mut Shape, screen: Vec2) {
+        match shape {
+            Shape::Vec(shapes) => shapes.iter_mut().for_each(|shape| self.map_shape(shape, screen)),
+            Shape::Circle(circle) => circle.center = self.map(circle.center, screen),
+            Shape::LineSegment { points, .. } => points.iter_mut().for_each(|p| *p = self.map(*p, screen)),
+            Shape::Path(path) => path.points.iter_mut().for_each(|p| *p = self.map(*p, screen)),
+            Shape::Text(text) => {
+                // text turns around its top-left corner
+                text.pos = self.map(text.pos, screen);
+                text.angle += self.quarter_turns() as f32 * FRAC_PI_2;
+            }
+            Shape::Mesh(mesh) => mesh.vertices.iter_mut().for_each(|v| v.pos = self.map(v.pos, screen)),
+            // a textured rect can't rotate its texture, it becomes a mesh
+            Shape::Rect(rect) if rect.fill_texture_id != egui::TextureId::default() => {
+                let mut mesh = Mesh::with_texture(rect.fill_texture_id);
+                mesh.add_rect_with_uv(rect.rect, rect.uv, rect.fill);
+                mesh.vertices.iter_mut().for_each(|v| v.pos = self.map(v.pos, screen));
+                *shape = Shape::Mesh(mesh);
+            }
+            Shape::Rect(RectShape { rect, .. }) => *rect = self.map_rect(*rect, screen),
+            _ => {}
+        }
+    }
+
+    /// Moves everything painted on `layer` from shape `start` on into place on a
+    /// screen of size `screen`. No-op for [`Rotation::None`].
+    pub fn apply(self, ctx: &egui::Context, layer: LayerId, start: usize, screen: Vec2) {
+        if self == Rotation::None {
+            return;
+        }
+        let full = Rect::from_min_size(Pos2::ZERO, screen);
+        ctx.graphics_mut(|graphics| {
+            let list = graphics.entry(layer);
+            for idx in start..list.next_idx().0 {
+                list.mutate_shape(egui::layers::ShapeIdx(idx), |clipped| {
+                    clipped.clip_rect = full;
+                    self.map_shape(&mut clipped.shape, screen);
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_follow_the_rotation() {
+        let screen = vec2(1920.0, 1080.0);
+        let logical = Rotation::Quarter.logical_size(screen);
+        assert_eq!(logical, vec2(1080.0, 1920.0));
+
+        // the logical top-left lands top-right, the bottom-right bottom-left
+        assert_eq!(Rotation::Quarter.map(Pos2::ZERO, screen), pos2(1920.0, 0.0));
+        assert_eq!(Rotation::Quarter.map(logical.to_pos2(), screen), pos2(0.0, 1080.0));
+        assert_eq!(Rotation::Half.map(Pos2::ZERO, screen), pos2(1920.0, 1080.0));
+        assert_eq!(Rotation::ThreeQuarters.map(Pos2::ZERO, screen), pos2(0.0, 1080.0));
+        assert_eq!(Rotation::ThreeQuarters.map(logical.to_pos2(), screen), pos2(1920.0, 0.0));
+
+        let p = pos2(100.0, 200.0);
+        for rotation in [Rotation::None, Rotation::Quarter, Rotation::Half, Rotation::ThreeQuarters] {
+            assert_eq!(rotation.unmap(rotation.map(p, screen), screen), p);
+        }
+    }
+
+    #[test]
+    fn rects_stay_on_screen() {
+        let screen = vec2(1920.0, 1080.0);
+        for rotation in [Rotation::Quarter, Rotation::Half, Rotation::ThreeQuarters] {
+            let logical = Rect::from_min_size(Pos2::ZERO, rotation.logical_size(screen));
+            assert_eq!(rotation.map_rect(logical, screen), Rect::from_min_size(Pos2::ZERO, screen));
+        }
+    }
+}
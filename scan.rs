@@ -0,0 +1,524 @@
+use crate::favorites::FAVORITES_FILE;
+use crate::ratings::RATINGS_FILE;
+use crate::remote;
+use crate::trash::TRASH_DIR;
+use crate::layout::Fit;
+use crate::pipeline::color::ColorFilter;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArtworkMetadata {
+    pub title: String,
+    pub artist: String,
+    pub year: String,
+    /// Seconds this slide stays up, overriding the global slide duration.
+    #[serde(default)]
+    pub duration: Option<f32>,
+    /// Overrides `--fit` for this artwork.
+    #[serde(default)]
+    pub fit: Option<Fit>,
+    /// Page about the work, shown as a QR code for visitors to scan.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Drawn with hard pixel edges, at whole multiples of its size. Unset,
+    /// an image under 512 pixels both ways is taken for pixel art.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_art: Option<bool>,
+    /// Overrides `--filter` for this artwork.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ColorFilter>,
+}
+
+impl ArtworkMetadata {
+    /// What is known of an artwork titled `title` without a sidecar.
+    pub(crate) fn unknown(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            artist: "Unknown".to_string(),
+            year: "Unknown".to_string(),
+            duration: None,
+            fit: None,
+            url: None,
+            pixel_art: None,
+            filter: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArtworkInfo {
+    pub path: PathBuf,
+    pub metadata: ArtworkMetadata,
+    /// The URL a playlist entry is downloaded from, into `path`.
+    pub remote: Option<String>,
+}
+
+pub fn is_supported_image(path: &Path) -> bool {
+    match path.extension() {
+        Some(ext) => {
+            let ext = ext.to_string_lossy().to_lowercase();
+            // every arm is `true` with all the format features on
+            #[allow(clippy::match_like_matches_macro)]
+            match ext.as_str() {
+                "jpg" | "jpeg" | "png" | "bmp" | "gif" | "tif" | "tiff" | "exr" => true,
+                "svg" => cfg!(feature = "svg"),
+                "avif" => cfg!(feature = "avif"),
+                "jxl" => cfg!(feature = "jxl"),
+                "cr2" | "cr3" | "nef" | "arw" | "dng" => cfg!(feature = "raw"),
+                _ => false,
+            }
+        }
+        None => false,
+    }
+}
+
+/// Metadata for the image at `path`, read from the `.json` sidecar next to it.
+///
+/// Without a sidecar the title, artist and year embedded in the image are
+/// used, and whatever is missing falls back to the file name as the title and
+/// "Unknown". An unreadable or malformed sidecar is logged and falls back to
+/// "Unknown" everywhere.
+pub fn read_metadata(path: &Path) -> ArtworkMetadata {
+    try_read_metadata(path).unwrap_or_else(|err| {
+        tracing::warn!("bad sidecar {}: {}", path.with_extension("json").display(), err);
+        ArtworkMetadata::unknown("Unknown")
+    })
+}
+
+/// Like [`read_metadata`], but a sidecar that can't be read or parsed is an
+/// error, with the line and column for parse errors.
+pub fn try_read_metadata(path: &Path) -> Result<ArtworkMetadata, String> {
+    let json_path = path.with_extension("json");
+
+    if json_path.exists() {
+        parse_sidecar(&json_path)
+    } else {
+        let mut metadata = ArtworkMetadata::unknown(&path.file_stem().unwrap_or_default().to_string_lossy());
+        if let Some(embedded) = read_embedded(path) {
+            metadata.title = embedded.title.unwrap_or(metadata.title);
+            metadata.artist = embedded.artist.unwrap_or(metadata.artist);
+            metadata.year = embedded.year.unwrap_or(metadata.year);
+        }
+        Ok(metadata)
+    }
+}
+
+/// Fields found in an image's EXIF data.
+#[derive(Debug, Default, PartialEq)]
+struct Embedded {
+    title: Option<String>,
+    artist: Option<String>,
+    year: Option<String>,
+}
+
+/// The EXIF ImageDescription, Artist and DateTimeOriginal of the image at
+/// `path`, reduced to the year. Only the file's headers are parsed, and any
+/// failure just means there is nothing embedded.
+fn read_embedded(path: &Path) -> Option<Embedded> {
+    let file = fs::File::open(path).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut io::BufReader::new(file)).ok()?;
+    let text = |tag| match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Ascii(parts) => {
+            let text = String::from_utf8_lossy(parts.first()?).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        }
+        _ => None,
+    };
+
+    let embedded = Embedded {
+        title: text(exif::Tag::ImageDescription),
+        artist: text(exif::Tag::Artist),
+        year: text(exif::Tag::DateTimeOriginal).and_then(|date| year_of(&date)),
+    };
+    (embedded != Embedded::default()).then_some(embedded)
+}
+
+/// `1889` from an EXIF date like `1889:06:01 00:00:00`.
+fn year_of(date: &str) -> Option<String> {
+    let year = date.get(..4)?;
+    year.bytes().all(|byte| byte.is_ascii_digit()).then(|| year.to_string())
+}
+
+/// Reads one sidecar. Parse errors carry the line and column.
+fn parse_sidecar(json_path: &Path) -> Result<ArtworkMetadata, String> {
+    let content = fs::read_to_string(json_path).map_err(|err| err.to_string())?;
+    serde_json::from_str(&content).map_err(|err| err.to_string())
+}
+
+/// Something `--validate` found wrong with a folder.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    MissingSidecar(PathBuf),
+    BadSidecar { path: PathBuf, error: String },
+    /// A sidecar with no image next to it.
+    OrphanSidecar(PathBuf),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MissingSidecar(image) => write!(f, "{}: no sidecar", image.display()),
+            Problem::BadSidecar { path, error } => write!(f, "{}: {}", path.display(), error),
+            Problem::OrphanSidecar(path) => write!(f, "{}: no matching image", path.display()),
+        }
+    }
+}
+
+/// Checks every image and sidecar in `folder`, sorted by path.
+pub fn validate_folder(folder: &Path) -> io::Result<Vec<Problem>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(folder)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.sort();
+
+    let mut problems = Vec::new();
+    let mut claimed = BTreeSet::new();
+    for image in paths.iter().filter(|path| is_supported_image(path)) {
+        let json_path = image.with_extension("json");
+        if !json_path.exists() {
+            problems.push(Problem::MissingSidecar(image.clone()));
+        } else if let Err(error) = parse_sidecar(&json_path) {
+            problems.push(Problem::BadSidecar { path: json_path.clone(), error });
+        }
+        claimed.insert(json_path);
+    }
+
+    for path in &paths {
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let is_own = path.file_name().is_some_and(|name| name == FAVORITES_FILE || name == RATINGS_FILE);
+        if is_json && !is_own && !claimed.contains(path) {
+            problems.push(Problem::OrphanSidecar(path.clone()));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Lists every supported image in `folder` together with its metadata.
+pub fn scan_folder(folder: &Path) -> io::Result<Vec<ArtworkInfo>> {
+    let mut artworks = Vec::new();
+
+    for entry in fs::read_dir(folder)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                tracing::warn!("skipping unreadable entry in {}: {}", folder.display(), err);
+                continue;
+            }
+        };
+        if is_supported_image(&path) {
+            let metadata = read_metadata(&path);
+            artworks.push(ArtworkInfo { path, metadata, remote: None });
+        }
+    }
+
+    Ok(artworks)
+}
+
+/// The artworks of `folder` and of every folder below it, each folder's own
+/// before those of its subfolders, which follow each other by name. The
+/// trash is left out, and so are links to folders, which could lead back up.
+/// A subfolder that can't be read is skipped with a warning.
+pub fn scan_tree(folder: &Path) -> io::Result<Vec<ArtworkInfo>> {
+    let mut artworks = scan_folder(folder)?;
+    let mut subfolders: Vec<PathBuf> = fs::read_dir(folder)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()) && entry.file_name() != TRASH_DIR)
+        .map(|entry| entry.path())
+        .collect();
+    subfolders.sort();
+    for subfolder in subfolders {
+        match scan_tree(&subfolder) {
+            Ok(found) => artworks.extend(found),
+            Err(err) => tracing::warn!("skipping unreadable folder {}: {}", subfolder.display(), err),
+        }
+    }
+    Ok(artworks)
+}
+
+/// Reads the artworks listed in a playlist file, in its order: one image path
+/// per line, `#` starting a comment line, relative paths resolved against the
+/// playlist's directory. Entries may repeat, missing files are skipped.
+///
+/// An `http://` or `https://` entry is downloaded into `downloads` when it
+/// is shown, see [`remote`](crate::remote); until then it has the metadata of
+/// the copy from last time, if any.
+pub fn read_playlist(playlist: &Path, downloads: &Path) -> io::Result<Vec<ArtworkInfo>> {
+    let content = fs::read_to_string(playlist)?;
+    let base = playlist.parent().unwrap_or(Path::new(""));
+    let mut artworks = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if remote::is_url(line) {
+            let path = remote::cache_path(downloads, line);
+            if is_supported_image(&path) {
+                artworks.push(ArtworkInfo { metadata: read_metadata(&path), path, remote: Some(line.to_string()) });
+            } else {
+                tracing::warn!("{}:{}: skipping unsupported {}", playlist.display(), number + 1, line);
+            }
+            continue;
+        }
+        let path = base.join(line);
+        if !path.is_file() {
+            tracing::warn!("{}:{}: skipping missing {}", playlist.display(), number + 1, path.display());
+        } else if !is_supported_image(&path) {
+            tracing::warn!("{}:{}: skipping unsupported {}", playlist.display(), number + 1, path.display());
+        } else {
+            let metadata = read_metadata(&path);
+            artworks.push(ArtworkInfo { path, metadata, remote: None });
+        }
+    }
+
+    Ok(artworks)
+}
+
+/// Artworks from items dropped on the window: a folder is scanned, otherwise
+/// the dropped images are shown as they are and anything else is ignored.
+/// Returns the folder they come from along with them.
+pub fn scan_dropped(paths: &[PathBuf]) -> Result<(PathBuf, Vec<ArtworkInfo>), String> {
+    if let [folder] = paths {
+        if folder.is_dir() {
+            let artworks = scan_folder(folder).map_err(|err| format!("cannot read {}: {}", folder.display(), err))?;
+            if artworks.is_empty() {
+                return Err(format!("no images in {}", folder.display()));
+            }
+            return Ok((folder.clone(), artworks));
+        }
+    }
+
+    let artworks: Vec<ArtworkInfo> = paths
+        .iter()
+        .filter(|path| path.is_file() && is_supported_image(path))
+        .map(|path| ArtworkInfo { path: path.clone(), metadata: read_metadata(path), remote: None })
+        .collect();
+    match artworks.first() {
+        Some(first) => Ok((first.path.parent().map(PathBuf::from).unwrap_or_default(), artworks)),
+        None if paths.len() == 1 => Err(format!("{} is not an image", paths[0].display())),
+        None => Err("none of these are images".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_one(files: &[(&str, &str)]) -> Vec<ArtworkInfo> {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+        scan_folder(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn sidecar_present() {
+        let artworks = scan_one(&[
+            ("goya.jpg", ""),
+            ("goya.json", r#"{"title": "Portrait", "artist": "Goya", "year": "1823"}"#),
+        ]);
+
+        assert_eq!(artworks.len(), 1);
+        assert_eq!(artworks[0].metadata.title, "Portrait");
+        assert_eq!(artworks[0].metadata.artist, "Goya");
+        assert_eq!(artworks[0].metadata.year, "1823");
+    }
+
+    #[test]
+    fn sidecar_duration() {
+        let artworks = scan_one(&[
+            ("goya.jpg", ""),
+            ("goya.json", r#"{"title": "Portrait", "artist": "Goya", "year": "1823", "duration": 30}"#),
+        ]);
+
+        assert_eq!(artworks[0].metadata.duration, Some(30.0));
+    }
+
+    #[test]
+    fn sidecar_missing_uses_file_stem() {
+        let artworks = scan_one(&[("starry_night.PNG", "")]);
+
+        assert_eq!(artworks.len(), 1);
+        assert_eq!(artworks[0].metadata, ArtworkMetadata::unknown("starry_night"));
+    }
+
+    /// A JPEG holding nothing but an EXIF segment with `fields`.
+    fn jpeg_with_exif(fields: &[(exif::Tag, &str)]) -> Vec<u8> {
+        let fields: Vec<exif::Field> = fields
+            .iter()
+            .map(|(tag, text)| exif::Field {
+                tag: *tag,
+                ifd_num: exif::In::PRIMARY,
+                value: exif::Value::Ascii(vec![text.as_bytes().to_vec()]),
+            })
+            .collect();
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+
+        let segment = [b"Exif\0\0".as_slice(), tiff.get_ref()].concat();
+        let length = (segment.len() + 2) as u16;
+        [&[0xff, 0xd8, 0xff, 0xe1][..], &length.to_be_bytes(), &segment, &[0xff, 0xd9]].concat()
+    }
+
+    #[test]
+    fn embedded_metadata_without_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let tagged = jpeg_with_exif(&[
+            (exif::Tag::ImageDescription, "The Starry Night"),
+            (exif::Tag::Artist, "Vincent van Gogh"),
+            (exif::Tag::DateTimeOriginal, "1889:06:01 00:00:00"),
+        ]);
+        fs::write(dir.path().join("starry.jpg"), &tagged).unwrap();
+        fs::write(dir.path().join("partial.jpg"), jpeg_with_exif(&[(exif::Tag::Artist, "Goya")])).unwrap();
+        fs::write(dir.path().join("sidecar.jpg"), &tagged).unwrap();
+        let sidecar = r#"{"title": "Portrait", "artist": "Goya", "year": "1823"}"#;
+        fs::write(dir.path().join("sidecar.json"), sidecar).unwrap();
+
+        let starry = read_metadata(&dir.path().join("starry.jpg"));
+        assert_eq!(starry.title, "The Starry Night");
+        assert_eq!(starry.artist, "Vincent van Gogh");
+        assert_eq!(starry.year, "1889");
+        let partial = read_metadata(&dir.path().join("partial.jpg"));
+        assert_eq!((partial.title.as_str(), partial.year.as_str()), ("partial", "Unknown"));
+        assert_eq!(read_metadata(&dir.path().join("sidecar.jpg")).title, "Portrait");
+    }
+
+    #[test]
+    fn sidecar_malformed_falls_back_to_unknown() {
+        let artworks = scan_one(&[
+            ("goya.jpg", ""),
+            ("goya.json", r#"{"title": "Portrait", "artist": "Goya",}"#),
+        ]);
+
+        assert_eq!(artworks[0].metadata, ArtworkMetadata::unknown("Unknown"));
+        // reloading by hand says what is wrong instead
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("goya.jpg");
+        fs::write(&image, "").unwrap();
+        fs::write(image.with_extension("json"), r#"{"title": "Portrait",}"#).unwrap();
+        let error = try_read_metadata(&image).unwrap_err();
+        assert!(error.contains("line 1"), "{}", error);
+    }
+
+    #[test]
+    fn validate_reports_every_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            ("good.jpg", ""),
+            ("good.json", r#"{"title": "T", "artist": "A", "year": "1900"}"#),
+            ("bare.png", ""),
+            ("broken.jpg", ""),
+            ("broken.json", "{\n  \"title\": \"T\",\n}"),
+            ("orphan.json", "{}"),
+            (FAVORITES_FILE, "[]"),
+            (RATINGS_FILE, "{}"),
+        ];
+        for (name, content) in files {
+            fs::write(dir.path().join(name), content).unwrap();
+        }
+
+        let problems = validate_folder(dir.path()).unwrap();
+        assert_eq!(problems.len(), 3);
+        assert_eq!(problems[0], Problem::MissingSidecar(dir.path().join("bare.png")));
+        assert!(matches!(&problems[1], Problem::BadSidecar { error, .. } if error.contains("line 3 column 1")));
+        assert_eq!(problems[2], Problem::OrphanSidecar(dir.path().join("orphan.json")));
+    }
+
+    #[test]
+    fn playlist_keeps_its_order() {
+        let root = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("old")).unwrap();
+        fs::write(root.path().join("old/goya.jpg"), "").unwrap();
+        fs::write(root.path().join("old/goya.json"), r#"{"title": "Portrait", "artist": "Goya", "year": "1823"}"#)
+            .unwrap();
+        fs::write(other.path().join("klimt.png"), "").unwrap();
+
+        let playlist = root.path().join("show.txt");
+        let absolute = other.path().join("klimt.png");
+        let lines = format!(
+            "# opening\n{}\n\nold/goya.jpg\nmissing.jpg\n  old/goya.jpg  \nhttps://nas/art/monet.jpg\n",
+            absolute.display()
+        );
+        fs::write(&playlist, lines).unwrap();
+
+        let downloads = root.path().join("downloads");
+        let artworks = read_playlist(&playlist, &downloads).unwrap();
+        let paths: Vec<_> = artworks.iter().map(|info| info.path.clone()).collect();
+        let monet = remote::cache_path(&downloads, "https://nas/art/monet.jpg");
+        assert_eq!(paths, [absolute, root.path().join("old/goya.jpg"), root.path().join("old/goya.jpg"), monet]);
+        assert_eq!(artworks[3].remote.as_deref(), Some("https://nas/art/monet.jpg"));
+        assert_eq!(artworks[3].metadata.title, "monet");
+        assert_eq!(artworks[1].metadata.artist, "Goya");
+        assert_eq!(artworks[0].metadata, ArtworkMetadata::unknown("klimt"));
+    }
+
+    #[test]
+    fn dropped_folders_and_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.jpg", "b.png", "notes.txt"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let (folder, artworks) = scan_dropped(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!((folder.as_path(), artworks.len()), (dir.path(), 2));
+
+        let files = [dir.path().join("b.png"), dir.path().join("notes.txt")];
+        let (folder, artworks) = scan_dropped(&files).unwrap();
+        assert_eq!(folder, dir.path());
+        assert_eq!(artworks.len(), 1);
+
+        assert!(scan_dropped(&[dir.path().join("notes.txt")]).unwrap_err().contains("not an image"));
+        let empty = tempfile::tempdir().unwrap();
+        assert!(scan_dropped(&[empty.path().to_path_buf()]).is_err());
+    }
+
+    #[test]
+    fn non_images_are_skipped() {
+        let artworks = scan_one(&[("notes.txt", ""), ("orphan.json", "{}"), ("a.jpeg", "")]);
+
+        assert_eq!(artworks.len(), 1);
+        assert!(artworks[0].path.ends_with("a.jpeg"));
+    }
+
+    #[test]
+    fn optional_formats_only_with_their_features() {
+        assert_eq!(is_supported_image(Path::new("plate.svg")), cfg!(feature = "svg"));
+        assert_eq!(is_supported_image(Path::new("met-open-access.AVIF")), cfg!(feature = "avif"));
+        assert_eq!(is_supported_image(Path::new("export.jxl")), cfg!(feature = "jxl"));
+        assert_eq!(is_supported_image(Path::new("DSC_0042.NEF")), cfg!(feature = "raw"));
+    }
+
+    #[test]
+    fn trees_are_scanned_folder_by_folder_without_the_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        for folder in ["Impressionism", "Expressionism/Brücke", TRASH_DIR] {
+            fs::create_dir_all(dir.path().join(folder)).unwrap();
+        }
+        let shown =
+            ["cover.jpg", "Expressionism/munch.jpg", "Expressionism/Brücke/kirchner.jpg", "Impressionism/monet.jpg"];
+        for file in shown.iter().chain([&".slideshow-trash/deleted.jpg"]) {
+            fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        let found: Vec<PathBuf> = scan_tree(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|info| info.path.strip_prefix(dir.path()).unwrap().into())
+            .collect();
+        assert_eq!(found, shown.map(PathBuf::from));
+        assert_eq!(scan_folder(dir.path()).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,1263 @@
+use crate::scan::ArtworkInfo;
+use crate::transition;
+use rand::distributions::{Distribution, WeightedIndex};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Shortest time a slide stays on screen. Anything lower turns the
+/// auto-advance into a busy loop that outruns the preloader.
+pub const MIN_SLIDE_DURATION: Duration = Duration::from_secs(1);
+
+pub fn clamp_duration(duration: Duration) -> Duration {
+    if duration < MIN_SLIDE_DURATION {
+        tracing::warn!(
+            "slide duration {:?} is below the minimum, using {:?}",
+            duration, MIN_SLIDE_DURATION
+        );
+        MIN_SLIDE_DURATION
+    } else {
+        duration
+    }
+}
+
+/// Background image processing as seen by the scheduler.
+pub trait Processor {
+    /// Starts processing the slide at `index`, the artworks it shows. `urgent`
+    /// is set when the slide is wanted on screen right away, so a quick
+    /// preview beats waiting for full quality.
+    fn start(&mut self, index: usize, slide: &[ArtworkInfo], urgent: bool);
+    /// Returns the outcome of a finished load, if any.
+    fn poll(&mut self) -> Option<Loaded>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Loaded {
+    /// The slide at `index` is processed and waiting to be shown. `portrait`
+    /// says which of its entries, from the first on, turned out taller than
+    /// wide, for those that were measured.
+    Ready { index: usize, portrait: Vec<bool> },
+    /// Processing the artwork at this index failed, it will be skipped.
+    Failed(usize),
+    /// The artwork at this index couldn't be read this time, it is skipped
+    /// now but may work when it comes round again.
+    Unavailable(usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Tick {
+    Stay,
+    /// Nothing was on screen, the slide at `index` comes up now.
+    First { index: usize },
+    /// Show `index` now. `reuse_current` means the slide repeats the one on
+    /// screen and the existing textures can be kept.
+    Advance { index: usize, reuse_current: bool },
+    /// The slide at `index` was waited for too long, the one after it is loaded instead.
+    Skipped { index: usize },
+    /// The last round of the show is over, the slide on screen stays.
+    Ended,
+}
+
+/// Where the slide on screen is in its time, pauses aside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlideState {
+    /// Nothing on screen yet, since `since`: the slide at the current index
+    /// comes up as soon as it is processed. A show starts here, and goes
+    /// back here when the slide on screen is taken out.
+    Initial { since: Instant },
+    /// Up since `since`, with time left.
+    Showing { since: Instant },
+    /// Up since `since` and still coming in over the slide before, for the
+    /// scheduler's [`Scheduler::transition`].
+    Transitioning { since: Instant },
+    /// Up since `since` and due to change from `due`, but the next slide
+    /// isn't processed yet. `due` starts over when a late one is skipped.
+    WaitingForNext { since: Instant, due: Instant },
+}
+
+impl SlideState {
+    /// When the slide came up, or the first one started to be waited for.
+    pub fn since(self) -> Instant {
+        match self {
+            SlideState::Initial { since }
+            | SlideState::Showing { since }
+            | SlideState::Transitioning { since }
+            | SlideState::WaitingForNext { since, .. } => since,
+        }
+    }
+
+    /// The same state with any transition over.
+    fn settled(self) -> Self {
+        match self {
+            SlideState::Transitioning { since } => SlideState::Showing { since },
+            state => state,
+        }
+    }
+
+    /// The same state `by` later, for the time a pause took.
+    fn delayed(self, by: Duration) -> Self {
+        match self {
+            SlideState::Initial { since } => SlideState::Initial { since: since + by },
+            SlideState::Showing { since } => SlideState::Showing { since: since + by },
+            SlideState::Transitioning { since } => SlideState::Transitioning { since: since + by },
+            SlideState::WaitingForNext { since, due } => {
+                SlideState::WaitingForNext { since: since + by, due: due + by }
+            }
+        }
+    }
+}
+
+/// What the timer of the slide on screen goes by.
+#[derive(Debug, Clone, Copy)]
+struct Inputs {
+    /// How long the slide stays.
+    duration: Duration,
+    /// How long it takes to come in over the one before.
+    transition: Duration,
+    paused: bool,
+    /// The next slide is the one on screen again.
+    repeats: bool,
+    /// The next slide is processed.
+    next_ready: bool,
+    /// How long the next slide is waited for past its time, before it is skipped.
+    give_up_after: Option<Duration>,
+}
+
+/// What the timer calls for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    Stay,
+    /// Start the slide on screen over, as the next one.
+    Repeat,
+    Advance,
+    /// Stop waiting for the next slide, and wait for the one after it.
+    Skip,
+}
+
+/// Moves the slide on screen on in its time: once it had its time the next
+/// slide comes up if it is ready, or is waited for until `give_up_after`.
+/// Without a slide on screen the next one is the first, up once it is ready.
+/// A transition is over once it had its time.
+fn step(state: SlideState, inputs: Inputs, now: Instant) -> (SlideState, Step) {
+    if let SlideState::Initial { .. } = state {
+        let up = inputs.next_ready;
+        return if up { (SlideState::Showing { since: now }, Step::Advance) } else { (state, Step::Stay) };
+    }
+    let since = state.since();
+    if inputs.paused {
+        return (state, Step::Stay);
+    }
+    let state = if now.duration_since(since) >= inputs.transition { state.settled() } else { state };
+    if now.duration_since(since) < inputs.duration {
+        // a longer duration can take a waiting slide back
+        let state = if let SlideState::WaitingForNext { .. } = state { SlideState::Showing { since } } else { state };
+        return (state, Step::Stay);
+    }
+    if inputs.repeats {
+        return (SlideState::Showing { since: now }, Step::Repeat);
+    }
+    if inputs.next_ready {
+        return (SlideState::Showing { since: now }, Step::Advance);
+    }
+    match state {
+        SlideState::Initial { .. } | SlideState::Showing { .. } | SlideState::Transitioning { .. } => {
+            (SlideState::WaitingForNext { since, due: now }, Step::Stay)
+        }
+        SlideState::WaitingForNext { since, due }
+            if inputs.give_up_after.is_some_and(|after| now.duration_since(due) >= after) =>
+        {
+            (SlideState::WaitingForNext { since, due: now }, Step::Skip)
+        }
+        waiting => (waiting, Step::Stay),
+    }
+}
+
+/// Decides when to advance and which artwork to preload.
+///
+/// Repeated entries transition like any other slide, and a load is never
+/// requested for an index that is already in flight or already processed.
+/// A slide can show several entries, it is known by the index of its first.
+pub struct Scheduler {
+    pub current_index: usize,
+    pub slide_duration: Duration,
+    /// Slide durations the next slide is waited for past its time before it
+    /// is skipped, `None` waits however long it takes.
+    pub give_up_after: Option<f32>,
+    /// Rounds of the show played through in order before it stops, `None` for no end.
+    /// Weighted playback has no rounds and never ends.
+    pub loops: Option<u32>,
+    /// How long a new slide takes to come in over the one before, zero for a cut.
+    pub transition: Duration,
+    state: SlideState,
+    in_flight: Option<usize>,
+    ready: Option<usize>,
+    // set when a load failed, so the preloader moves on past the broken entry,
+    // or once per slide when the next one is drawn at random
+    next_index: Option<usize>,
+    // weighted playback: how likely each entry is to come next
+    weights: Option<Vec<u32>>,
+    // entries per slide
+    step: usize,
+    // two portrait entries in a row share a slide
+    pairing: bool,
+    // which entries are taller than wide, as the processor measured them
+    portrait: HashMap<PathBuf, bool>,
+    // effective duration of the slide on screen, resolved once per slide
+    current_duration: Option<Duration>,
+    // what the detail in the slide on screen asks for, in adaptive playback
+    detail_duration: Option<Duration>,
+    // manual navigation target, shown as soon as it is processed
+    jump: Option<usize>,
+    paused_at: Option<Instant>,
+    // times the show has come round to its start on its own, jumps don't count
+    rounds: u32,
+    // the last round is over, only a jump changes the slide
+    ended: bool,
+}
+
+impl Scheduler {
+    pub fn new(slide_duration: Duration, now: Instant) -> Self {
+        Self {
+            current_index: 0,
+            slide_duration: clamp_duration(slide_duration),
+            give_up_after: None,
+            loops: None,
+            transition: Duration::ZERO,
+            state: SlideState::Initial { since: now },
+            in_flight: None,
+            ready: None,
+            next_index: None,
+            weights: None,
+            step: 1,
+            pairing: false,
+            portrait: HashMap::new(),
+            current_duration: None,
+            detail_duration: None,
+            jump: None,
+            paused_at: None,
+            rounds: 0,
+            ended: false,
+        }
+    }
+
+    /// Shows `index` as soon as it is processed, regardless of the timer. A
+    /// transition under way jumps to its end, the next one starts from there.
+    pub fn go_to(&mut self, index: usize) {
+        self.jump = Some(index);
+        self.state = self.state.settled();
+    }
+
+    /// Whether a slide asked for is still being processed.
+    pub fn is_jumping(&self) -> bool {
+        self.jump.is_some()
+    }
+
+    pub fn go_next(&mut self, artworks: &[ArtworkInfo]) {
+        if !artworks.is_empty() {
+            let next = self.upcoming(artworks);
+            self.go_to(next);
+        }
+    }
+
+    /// Picks each next slide at random, entry `i` with probability proportional
+    /// to `weights[i]`, instead of going in order. `None` goes back to the order.
+    pub fn set_weights(&mut self, weights: Option<Vec<u32>>) {
+        self.weights = weights;
+    }
+
+    /// Shows `step` entries per slide, moving on by as many. A slide near the
+    /// end of the list shows whatever is left, the next one starts over.
+    pub fn set_step(&mut self, step: usize) {
+        self.step = step.max(1);
+    }
+
+    /// Puts two portrait entries in a row on one slide, side by side, when
+    /// slides show one entry each. Landscape ones still get a slide of their own.
+    pub fn set_pairing(&mut self, pairing: bool) {
+        self.pairing = pairing;
+    }
+
+    /// Remembers which of the entries from `index` on are taller than wide, as `portrait` says.
+    pub fn measured(&mut self, artworks: &[ArtworkInfo], index: usize, portrait: &[bool]) {
+        for (info, &portrait) in artworks.iter().skip(index).zip(portrait) {
+            self.portrait.insert(info.path.clone(), portrait);
+        }
+    }
+
+    /// Whether the entry at `index` shares its slide with the next one, `None`
+    /// while either could be a portrait that wasn't measured yet.
+    fn paired(&self, artworks: &[ArtworkInfo], index: usize) -> Option<bool> {
+        let Some(next) = artworks.get(index + 1).filter(|_| self.pairing && self.step == 1) else {
+            return Some(false);
+        };
+        let portrait = |info: &ArtworkInfo| self.portrait.get(&info.path).copied();
+        match (portrait(&artworks[index]), portrait(next)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// The entries on the slide starting at `index`. Two entries that might
+    /// be a portrait pair are asked for together, the processor settles it.
+    pub fn slide<'a>(&self, artworks: &'a [ArtworkInfo], index: usize) -> &'a [ArtworkInfo] {
+        let end = if self.pairing && self.step == 1 {
+            index + 1 + (self.paired(artworks, index) != Some(false)) as usize
+        } else {
+            index + self.step
+        };
+        &artworks[index..end.min(artworks.len())]
+    }
+
+    /// The slide after the current one: drawn once per slide in weighted playback, the next one otherwise.
+    fn upcoming(&mut self, artworks: &[ArtworkInfo]) -> usize {
+        let len = artworks.len();
+        if self.next_index.is_none() {
+            if let Some(weights) = self.weights.as_ref().filter(|weights| weights.len() == len) {
+                self.next_index = pick_weighted(weights, self.current_index);
+            }
+        }
+        let next = match self.next_index {
+            Some(next) => next,
+            None => self.current_index + self.slide(artworks, self.current_index).len(),
+        };
+        if next >= len { 0 } else { next }
+    }
+
+    pub fn go_prev(&mut self, artworks: &[ArtworkInfo]) {
+        let len = artworks.len();
+        if len > 0 {
+            let mut prev = match self.current_index {
+                0 => (len - 1) / self.step * self.step,
+                current => current.saturating_sub(self.step),
+            };
+            // back to the start of a pair that ends there
+            if prev > 0 && self.paired(artworks, prev - 1) == Some(true) {
+                prev -= 1;
+            }
+            self.go_to(prev);
+        }
+    }
+
+    /// Starts the slide's time `by` later, for something shown over it first.
+    pub fn delay(&mut self, by: Duration) {
+        self.state = self.state.delayed(by);
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(now);
+        }
+    }
+
+    /// Continues with whatever time the slide had left when it was paused.
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.state = self.state.delayed(now.duration_since(paused_at));
+        }
+    }
+
+    /// Changes the global duration, for the slide on screen too unless its sidecar sets its own.
+    pub fn set_slide_duration(&mut self, duration: Duration) {
+        self.slide_duration = clamp_duration(duration);
+        self.current_duration = None;
+    }
+
+    /// Has the slide on screen stay for `duration`, worked out from its detail,
+    /// instead of the global duration. A duration in its sidecar still wins.
+    pub fn set_detail_duration(&mut self, duration: Option<Duration>) {
+        self.detail_duration = duration.map(clamp_duration);
+        self.current_duration = None;
+    }
+
+    /// Forgets the preloaded slide so it gets processed again, e.g. with new options.
+    pub fn discard_preload(&mut self) {
+        self.in_flight = None;
+        self.ready = None;
+    }
+
+    /// Forgets entry `index`, just taken out of a list now `remaining` long.
+    /// Whatever was preloaded or asked for is dropped since the indices moved.
+    /// If it was on screen, the entry after it comes up in its place as soon as it is processed.
+    pub fn remove(&mut self, index: usize, remaining: usize, now: Instant) {
+        match index.cmp(&self.current_index) {
+            Ordering::Less => self.current_index -= 1,
+            Ordering::Equal => {
+                if self.current_index >= remaining {
+                    self.current_index = 0;
+                }
+                self.current_duration = None;
+                self.detail_duration = None;
+                self.state = SlideState::Initial { since: now };
+                if self.paused_at.is_some() {
+                    self.paused_at = Some(now);
+                }
+            }
+            Ordering::Greater => {}
+        }
+        if let Some(weights) = &mut self.weights {
+            if index < weights.len() {
+                weights.remove(index);
+            }
+        }
+        self.discard_preload();
+        self.next_index = None;
+        self.jump = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Whether the show played its last round, see [`Scheduler::loops`].
+    pub fn has_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Time until the current slide is due to change, frozen while paused.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        if self.ended {
+            return None;
+        }
+        let shown = self.shown_for(now);
+        self.current_duration.map(|duration| duration.saturating_sub(shown))
+    }
+
+    /// How long the current slide has been up, pauses aside.
+    pub fn shown_for(&self, now: Instant) -> Duration {
+        self.paused_at.unwrap_or(now).duration_since(self.state.since())
+    }
+
+    pub fn state(&self) -> SlideState {
+        self.state
+    }
+
+    /// How far the slide on screen is through coming in over the one before,
+    /// from 0 to 1, `None` once it is in. Frozen while paused.
+    pub fn transition_progress(&self, now: Instant) -> Option<f32> {
+        match self.state {
+            SlideState::Transitioning { .. } => Some(transition::progress(self.shown_for(now), self.transition)),
+            _ => None,
+        }
+    }
+
+    /// How long the next slide has been waited for past its time, `None`
+    /// unless it is and the show is running.
+    pub fn late_by(&self, now: Instant) -> Option<Duration> {
+        match self.state {
+            SlideState::WaitingForNext { due, .. } if !self.is_paused() => Some(now.duration_since(due)),
+            _ => None,
+        }
+    }
+
+    /// Time until the next slide is given up on, if it is being waited for.
+    pub fn until_give_up(&self, now: Instant) -> Option<Duration> {
+        let after = self.current_duration?.mul_f32(self.give_up_after?);
+        self.late_by(now).map(|late| after.saturating_sub(late))
+    }
+
+    /// How long the current slide stays up in all, once it is known.
+    pub fn current_duration(&self) -> Option<Duration> {
+        self.current_duration
+    }
+
+    fn advance_to(&mut self, artworks: &[ArtworkInfo], index: usize, now: Instant, reuse_current: bool) -> Tick {
+        self.next_index = None;
+        if !reuse_current {
+            self.detail_duration = None;
+        }
+        self.current_index = index;
+        self.current_duration = Some(self.current_slide_duration(artworks));
+        self.state = if reuse_current || self.transition.is_zero() {
+            SlideState::Showing { since: now }
+        } else {
+            SlideState::Transitioning { since: now }
+        };
+        if self.paused_at.is_some() {
+            self.paused_at = Some(now);
+        }
+        Tick::Advance { index, reuse_current }
+    }
+
+    /// How long `info` stays on screen: its sidecar duration if valid, else the global one.
+    pub fn duration_for(&self, info: &ArtworkInfo) -> Duration {
+        match info.metadata.duration {
+            None => self.slide_duration,
+            Some(secs) => match Duration::try_from_secs_f32(secs) {
+                Ok(duration) if !duration.is_zero() => clamp_duration(duration),
+                _ => {
+                    tracing::warn!(
+                        "invalid duration {} for {}, using {:?}",
+                        secs,
+                        info.path.display(),
+                        self.slide_duration
+                    );
+                    self.slide_duration
+                }
+            },
+        }
+    }
+
+    /// How long the slide on screen stays: its sidecar's duration, else the
+    /// one for its detail, else the global one.
+    fn current_slide_duration(&self, artworks: &[ArtworkInfo]) -> Duration {
+        let info = &artworks[self.current_index];
+        match (info.metadata.duration, self.detail_duration) {
+            (None, Some(detail)) => detail,
+            _ => self.duration_for(info),
+        }
+    }
+
+    /// Asks for the slide at the current index straight away and brings it
+    /// up once it is processed, there is nothing on screen to time.
+    fn first<P: Processor>(&mut self, artworks: &[ArtworkInfo], processor: &mut P, now: Instant) -> Tick {
+        let index = self.current_index;
+        if self.in_flight != Some(index) && self.ready != Some(index) {
+            processor.start(index, self.slide(artworks, index), true);
+            self.in_flight = Some(index);
+            self.ready = None;
+        }
+        let inputs = Inputs {
+            duration: Duration::ZERO,
+            transition: Duration::ZERO,
+            paused: self.paused_at.is_some(),
+            repeats: false,
+            next_ready: self.ready == Some(index),
+            give_up_after: None,
+        };
+        let (state, step) = step(self.state, inputs, now);
+        self.state = state;
+        if step != Step::Advance {
+            return Tick::Stay;
+        }
+        self.ready = None;
+        self.advance_to(artworks, index, now, false);
+        // there was nothing to come in over
+        self.state = state;
+        Tick::First { index }
+    }
+
+    pub fn tick<P: Processor>(&mut self, artworks: &[ArtworkInfo], processor: &mut P, now: Instant) -> Tick {
+        if artworks.is_empty() {
+            return Tick::Stay;
+        }
+
+        match processor.poll() {
+            Some(Loaded::Ready { index, portrait }) => {
+                self.measured(artworks, index, &portrait);
+                if self.in_flight == Some(index) {
+                    self.in_flight = None;
+                }
+                self.ready = Some(index);
+            }
+            Some(loaded @ (Loaded::Failed(index) | Loaded::Unavailable(index))) => {
+                if self.in_flight == Some(index) {
+                    self.in_flight = None;
+                }
+                if self.jump == Some(index) {
+                    self.jump = None;
+                }
+                match self.weights.as_mut().and_then(|weights| weights.get_mut(index)) {
+                    // a new pick is made on the spot, a broken file is never drawn again
+                    Some(weight) => {
+                        if matches!(loaded, Loaded::Failed(_)) {
+                            *weight = 0;
+                        }
+                        self.next_index = None;
+                    }
+                    None => self.next_index = Some(index + self.slide(artworks, index).len()),
+                }
+                // nothing is on screen yet, the show starts from the one after instead
+                if matches!(self.state, SlideState::Initial { .. }) && index == self.current_index {
+                    self.current_index = (index + self.slide(artworks, index).len()) % artworks.len();
+                    self.next_index = None;
+                }
+            }
+            None => {}
+        }
+
+        if let (Some(target), SlideState::Initial { .. }) = (self.jump, self.state) {
+            // nothing on screen to go from, the slide gone to is the first
+            self.jump = None;
+            self.current_index = target;
+        }
+        if let Some(target) = self.jump {
+            if target == self.current_index {
+                self.jump = None;
+                return self.advance_to(artworks, target, now, true);
+            }
+            if self.ready == Some(target) {
+                self.jump = None;
+                self.ready = None;
+                return self.advance_to(artworks, target, now, false);
+            }
+            if self.in_flight != Some(target) {
+                processor.start(target, self.slide(artworks, target), true);
+                self.in_flight = Some(target);
+                self.ready = None;
+            }
+            return Tick::Stay;
+        }
+        if let SlideState::Initial { .. } = self.state {
+            return self.first(artworks, processor, now);
+        }
+        if self.ended {
+            return Tick::Stay;
+        }
+
+        let next_index = self.upcoming(artworks);
+
+        // a single entry repeats the slide already on screen
+        if next_index != self.current_index && self.in_flight.is_none() && self.ready.is_none() {
+            processor.start(next_index, self.slide(artworks, next_index), false);
+            self.in_flight = Some(next_index);
+        }
+
+        let duration = match self.current_duration {
+            Some(duration) => duration,
+            None => *self.current_duration.insert(self.current_slide_duration(artworks)),
+        };
+        let inputs = Inputs {
+            duration,
+            transition: self.transition,
+            paused: self.paused_at.is_some(),
+            repeats: next_index == self.current_index,
+            next_ready: self.ready == Some(next_index),
+            give_up_after: self.give_up_after.map(|factor| duration.mul_f32(factor)),
+        };
+        // coming round to the start again is the end of a round
+        let wraps = self.weights.is_none() && next_index <= self.current_index;
+        let last_round = self.loops.is_some_and(|loops| self.rounds + 1 >= loops);
+        if wraps && last_round && !inputs.paused && now.duration_since(self.state.since()) >= duration {
+            self.rounds += 1;
+            self.ended = true;
+            self.state = SlideState::Showing { since: now };
+            return Tick::Ended;
+        }
+        let (state, step) = step(self.state, inputs, now);
+        self.state = state;
+        if wraps && matches!(step, Step::Advance | Step::Repeat) {
+            self.rounds += 1;
+        }
+        match step {
+            Step::Stay => Tick::Stay,
+            Step::Repeat => Tick::Advance { index: next_index, reuse_current: true },
+            Step::Advance => {
+                self.ready = None;
+                self.advance_to(artworks, next_index, now, false)
+            }
+            Step::Skip => {
+                // asked for straight away, so what was in flight comes back stale
+                self.ready = None;
+                self.next_index = match self.weights {
+                    Some(_) => None,
+                    None => Some(next_index + self.slide(artworks, next_index).len()),
+                };
+                let after = self.upcoming(artworks);
+                self.in_flight = (after != self.current_index).then(|| {
+                    processor.start(after, self.slide(artworks, after), false);
+                    after
+                });
+                Tick::Skipped { index: next_index }
+            }
+        }
+    }
+}
+
+/// Draws an entry by weight, never `current` unless nothing else can come up.
+fn pick_weighted(weights: &[u32], current: usize) -> Option<usize> {
+    let mut others = weights.to_vec();
+    if let Some(weight) = others.get_mut(current) {
+        *weight = 0;
+    }
+    WeightedIndex::new(&others).ok().map(|weighted| weighted.sample(&mut rand::thread_rng()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+
+    #[derive(Default)]
+    struct MockProcessor {
+        started: Vec<usize>,
+        urgent: Vec<usize>,
+        finished: Option<Loaded>,
+    }
+
+    impl MockProcessor {
+        fn finish_pending(&mut self) {
+            self.finished = self.started.last().map(|&index| Loaded::Ready { index, portrait: Vec::new() });
+        }
+
+        fn fail_pending(&mut self) {
+            self.finished = self.started.last().copied().map(Loaded::Failed);
+        }
+    }
+
+    impl Processor for MockProcessor {
+        fn start(&mut self, index: usize, _slide: &[ArtworkInfo], urgent: bool) {
+            self.started.push(index);
+            if urgent {
+                self.urgent.push(index);
+            }
+        }
+
+        fn poll(&mut self) -> Option<Loaded> {
+            self.finished.take()
+        }
+    }
+
+    fn artworks(paths: &[&str]) -> Vec<ArtworkInfo> {
+        paths
+            .iter()
+            .map(|path| ArtworkInfo {
+                path: PathBuf::from(path),
+                metadata: ArtworkMetadata::unknown(path),
+                remote: None,
+            })
+            .collect()
+    }
+
+    /// A scheduler with its first slide up since `now`.
+    fn showing(slide_duration: Duration, now: Instant) -> Scheduler {
+        let mut scheduler = Scheduler::new(slide_duration, now);
+        scheduler.state = SlideState::Showing { since: now };
+        scheduler
+    }
+
+    #[test]
+    fn the_first_slide_comes_up_once_processed() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = Scheduler::new(Duration::from_secs(10), start);
+
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start), Tick::Stay);
+        assert_eq!(processor.urgent, [0]);
+        // nothing on screen has a time to run out
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(30)), Tick::Stay);
+        assert_eq!(processor.started, [0]);
+
+        // one that fails makes way for the next
+        processor.fail_pending();
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!((scheduler.current_index, processor.urgent.as_slice()), (1, [0, 1].as_slice()));
+        // one gone to before anything is up is the first instead
+        scheduler.go_to(2);
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!(processor.urgent, [0, 1, 2]);
+
+        processor.finish_pending();
+        let now = start + Duration::from_secs(3);
+        assert_eq!(scheduler.tick(&artworks, &mut processor, now), Tick::First { index: 2 });
+        assert_eq!(scheduler.state(), SlideState::Showing { since: now });
+        assert_eq!(scheduler.remaining(now), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn zero_duration_is_clamped() {
+        let scheduler = showing(Duration::ZERO, Instant::now());
+        assert_eq!(scheduler.slide_duration, MIN_SLIDE_DURATION);
+    }
+
+    #[test]
+    fn zero_duration_does_not_busy_advance() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::ZERO, start);
+
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start), Tick::Stay);
+        processor.finish_pending();
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_millis(10)), Tick::Stay);
+    }
+
+    #[test]
+    fn in_flight_index_is_not_requested_twice() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        for step in 0..50 {
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(step));
+        }
+
+        assert_eq!(processor.started, vec![1]);
+        assert_eq!(scheduler.current_index, 0);
+    }
+
+    #[test]
+    fn ready_index_is_not_requested_again() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(1));
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(2));
+
+        assert_eq!(processor.started, vec![1]);
+    }
+
+    #[test]
+    fn repeated_entries_transition_normally() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "a.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)),
+            Tick::Advance { index: 1, reuse_current: false }
+        );
+        assert_eq!(scheduler.current_index, 1);
+    }
+
+    #[test]
+    fn single_entry_never_starts_the_preloader() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start), Tick::Stay);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)),
+            Tick::Advance { index: 0, reuse_current: true }
+        );
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(11)), Tick::Stay);
+
+        assert!(processor.started.is_empty());
+    }
+
+    #[test]
+    fn empty_list_does_nothing() {
+        let start = Instant::now();
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        for step in 0..3 {
+            assert_eq!(scheduler.tick(&[], &mut processor, start + Duration::from_secs(step * 10)), Tick::Stay);
+        }
+        assert!(processor.started.is_empty());
+    }
+
+    #[test]
+    fn preloader_starts_when_a_second_entry_appears() {
+        let start = Instant::now();
+        let mut artworks = artworks(&["a.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)),
+            Tick::Advance { index: 0, reuse_current: true }
+        );
+
+        artworks.extend(self::artworks(&["b.jpg"]));
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(11));
+        assert_eq!(processor.started, vec![1]);
+        processor.finish_pending();
+        // the repeat restarted the timer, so the new entry comes 10s after it
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(15)), Tick::Stay);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(20)),
+            Tick::Advance { index: 1, reuse_current: false }
+        );
+    }
+
+    #[test]
+    fn failed_load_is_skipped() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "broken.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.fail_pending();
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(1));
+        processor.finish_pending();
+
+        assert_eq!(processor.started, vec![1, 2]);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)),
+            Tick::Advance { index: 2, reuse_current: false }
+        );
+    }
+
+    #[test]
+    fn sidecar_duration_overrides_global() {
+        let start = Instant::now();
+        let mut artworks = artworks(&["a.jpg", "b.jpg"]);
+        artworks[0].metadata.duration = Some(30.0);
+        artworks[1].metadata.duration = Some(-5.0);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)), Tick::Stay);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(30)),
+            Tick::Advance { index: 1, reuse_current: false }
+        );
+
+        assert_eq!(scheduler.duration_for(&artworks[1]), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn detail_duration_gives_way_to_the_sidecar() {
+        let start = Instant::now();
+        let mut artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        artworks[1].metadata.duration = Some(8.0);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        scheduler.set_detail_duration(Some(Duration::from_secs(25)));
+        processor.finish_pending();
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(20)), Tick::Stay);
+        let shown = start + Duration::from_secs(25);
+        assert_eq!(scheduler.tick(&artworks, &mut processor, shown), Tick::Advance { index: 1, reuse_current: false });
+
+        // the next slide has a duration of its own
+        scheduler.set_detail_duration(Some(Duration::from_secs(25)));
+        assert_eq!(scheduler.current_duration(), None);
+        scheduler.tick(&artworks, &mut processor, shown);
+        assert_eq!(scheduler.current_duration(), Some(Duration::from_secs(8)));
+    }
+
+    #[test]
+    fn new_duration_applies_to_the_slide_on_screen() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        scheduler.tick(&artworks, &mut processor, start);
+        scheduler.set_slide_duration(Duration::from_secs(60));
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)), Tick::Stay);
+
+        // the preload is requested again after being discarded
+        scheduler.discard_preload();
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(20));
+        assert_eq!(processor.started, vec![1, 1]);
+    }
+
+    #[test]
+    fn manual_next_uses_the_preloaded_slide() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        scheduler.tick(&artworks, &mut processor, start);
+
+        scheduler.go_next(&artworks);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(1)),
+            Tick::Advance { index: 1, reuse_current: false }
+        );
+        assert_eq!(processor.started, vec![1]);
+    }
+
+    #[test]
+    fn grid_slides_step_through_the_list() {
+        let start = Instant::now();
+        let artworks = artworks(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.set_step(4);
+
+        let mut shown = vec![];
+        for slide in 1..=3 {
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10 * slide - 9));
+            processor.finish_pending();
+            if let Tick::Advance { index, .. } =
+                scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10 * slide))
+            {
+                shown.push(index);
+            }
+        }
+        // the last slide has only two left, then the list starts over
+        assert_eq!(shown, [4, 8, 0]);
+        assert_eq!(scheduler.slide(&artworks, 8).len(), 2);
+
+        scheduler.go_prev(&artworks);
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(31));
+        assert_eq!(processor.urgent, [8]);
+    }
+
+    #[test]
+    fn portrait_pairs_share_a_slide() {
+        let start = Instant::now();
+        let artworks = artworks(&["tall1", "tall2", "wide", "tall3", "tall4"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.set_pairing(true);
+        // nothing measured yet, any two could be a pair
+        assert_eq!(scheduler.slide(&artworks, 2).len(), 2);
+        scheduler.measured(&artworks, 0, &[true, true, false]);
+        scheduler.measured(&artworks, 3, &[true, true]);
+
+        assert_eq!(scheduler.slide(&artworks, 0).len(), 2);
+        assert_eq!(scheduler.slide(&artworks, 1).len(), 1);
+        // the last one has nothing to pair with
+        assert_eq!(scheduler.slide(&artworks, 4).len(), 1);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!(processor.started, [2]);
+        // back from the start, onto the last pair as a whole
+        scheduler.go_prev(&artworks);
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!(processor.urgent, [3]);
+
+        // what the processor measured comes with the slide
+        processor.finished = Some(Loaded::Ready { index: 3, portrait: vec![true, false] });
+        scheduler.tick(&artworks, &mut processor, start);
+        assert_eq!(scheduler.current_index, 3);
+        assert_eq!(scheduler.slide(&artworks, 3).len(), 1);
+    }
+
+    #[test]
+    fn removing_the_preloaded_entry_drops_its_preload() {
+        let start = Instant::now();
+        let mut artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(1));
+        artworks.remove(1);
+        scheduler.remove(1, artworks.len(), start + Duration::from_secs(2));
+
+        // c moved up into b's place and is processed anew, not taken for the b that was ready
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)), Tick::Stay);
+        assert_eq!(processor.started, [1, 1]);
+
+        // the last entry on screen goes, the show starts over
+        scheduler.current_index = 1;
+        artworks.remove(1);
+        scheduler.remove(1, artworks.len(), start + Duration::from_secs(11));
+        assert_eq!(scheduler.current_index, 0);
+        assert_eq!(scheduler.remaining(start + Duration::from_secs(11)), None);
+    }
+
+    #[test]
+    fn manual_prev_loads_and_waits() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        scheduler.go_prev(&artworks);
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start), Tick::Stay);
+        assert_eq!(processor.started, vec![1, 2]);
+        assert_eq!(processor.urgent, vec![2]);
+
+        processor.finish_pending();
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(1)),
+            Tick::Advance { index: 2, reuse_current: false }
+        );
+    }
+
+    #[test]
+    fn pause_freezes_the_remaining_time() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        scheduler.pause(start + Duration::from_secs(4));
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(60)), Tick::Stay);
+        assert_eq!(scheduler.remaining(start + Duration::from_secs(60)), Some(Duration::from_secs(6)));
+
+        scheduler.resume(start + Duration::from_secs(60));
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(65)), Tick::Stay);
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(66)),
+            Tick::Advance { index: 1, reuse_current: false }
+        );
+    }
+
+    #[test]
+    fn a_slide_past_its_time_waits_for_the_next() {
+        let start = Instant::now();
+        let seconds = |n| start + Duration::from_secs(n);
+        let inputs = Inputs {
+            duration: Duration::from_secs(10),
+            transition: Duration::ZERO,
+            paused: false,
+            repeats: false,
+            next_ready: false,
+            give_up_after: None,
+        };
+        let showing = SlideState::Showing { since: start };
+        assert_eq!(step(showing, inputs, seconds(5)), (showing, Step::Stay));
+
+        let waiting = SlideState::WaitingForNext { since: start, due: seconds(12) };
+        assert_eq!(step(showing, inputs, seconds(12)), (waiting, Step::Stay));
+        assert_eq!(step(waiting, inputs, seconds(15)), (waiting, Step::Stay));
+        let paused = Inputs { paused: true, next_ready: true, ..inputs };
+        assert_eq!(step(waiting, paused, seconds(15)), (waiting, Step::Stay));
+        let ready = Inputs { next_ready: true, ..inputs };
+        assert_eq!(step(waiting, ready, seconds(16)), (SlideState::Showing { since: seconds(16) }, Step::Advance));
+
+        assert_eq!(step(showing, Inputs { repeats: true, ..inputs }, seconds(10)).1, Step::Repeat);
+        // a pause puts it all off alike
+        let resumed = SlideState::WaitingForNext { since: seconds(3), due: seconds(15) };
+        assert_eq!(waiting.delayed(Duration::from_secs(3)), resumed);
+
+        let impatient = Inputs { give_up_after: Some(Duration::from_secs(30)), ..inputs };
+        assert_eq!(step(waiting, impatient, seconds(41)), (waiting, Step::Stay));
+        let again = SlideState::WaitingForNext { since: start, due: seconds(42) };
+        assert_eq!(step(waiting, impatient, seconds(42)), (again, Step::Skip));
+    }
+
+    #[test]
+    fn a_transition_ends_in_its_time_or_when_a_slide_is_asked_for() {
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let start = Instant::now();
+        let seconds = |n: f32| start + Duration::from_secs_f32(n);
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.transition = Duration::from_secs(2);
+        let mut processor = MockProcessor::default();
+
+        scheduler.tick(&artworks, &mut processor, start);
+        processor.finish_pending();
+        let advanced = scheduler.tick(&artworks, &mut processor, seconds(10.0));
+        assert_eq!(advanced, Tick::Advance { index: 1, reuse_current: false });
+        assert_eq!(scheduler.state(), SlideState::Transitioning { since: seconds(10.0) });
+        assert_eq!(scheduler.transition_progress(seconds(10.5)), Some(0.25));
+        scheduler.tick(&artworks, &mut processor, seconds(11.0));
+        assert_eq!(scheduler.transition_progress(seconds(11.0)), Some(0.5));
+        scheduler.tick(&artworks, &mut processor, seconds(12.0));
+        assert_eq!(scheduler.state(), SlideState::Showing { since: seconds(10.0) });
+        assert_eq!(scheduler.transition_progress(seconds(12.0)), None);
+
+        processor.finish_pending();
+        let advanced = scheduler.tick(&artworks, &mut processor, seconds(20.0));
+        assert_eq!(advanced, Tick::Advance { index: 2, reuse_current: false });
+        // next halfway through ends it at once rather than waiting for it
+        scheduler.go_next(&artworks);
+        assert_eq!(scheduler.state(), SlideState::Showing { since: seconds(20.0) });
+        scheduler.tick(&artworks, &mut processor, seconds(20.5));
+        assert_eq!(processor.urgent, [0]);
+        // one slide repeated in place has nothing to come in over
+        scheduler.go_to(2);
+        let repeated = scheduler.tick(&artworks, &mut processor, seconds(21.0));
+        assert_eq!(repeated, Tick::Advance { index: 2, reuse_current: true });
+        assert_eq!(scheduler.transition_progress(seconds(21.0)), None);
+    }
+
+    #[test]
+    fn a_slide_too_late_is_skipped_for_the_one_after() {
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let start = Instant::now();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.give_up_after = Some(3.0);
+        let mut processor = MockProcessor::default();
+
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start), Tick::Stay);
+        assert_eq!(processor.started, [1]);
+        assert_eq!(scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10)), Tick::Stay);
+        assert_eq!(scheduler.late_by(start + Duration::from_secs(25)), Some(Duration::from_secs(15)));
+        assert_eq!(scheduler.until_give_up(start + Duration::from_secs(25)), Some(Duration::from_secs(15)));
+        let tick = scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(40));
+        assert_eq!(tick, Tick::Skipped { index: 1 });
+        assert_eq!(processor.started, [1, 2]);
+
+        processor.finish_pending();
+        assert_eq!(
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(41)),
+            Tick::Advance { index: 2, reuse_current: false }
+        );
+        assert_eq!(scheduler.late_by(start + Duration::from_secs(41)), None);
+    }
+
+    #[test]
+    fn the_show_stops_after_its_rounds() {
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg"]);
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.loops = Some(2);
+        let mut processor = MockProcessor::default();
+
+        // the slide after, once it is processed
+        let mut advance = |scheduler: &mut Scheduler, now| match scheduler.tick(&artworks, &mut processor, now) {
+            Tick::Stay => {
+                processor.finish_pending();
+                scheduler.tick(&artworks, &mut processor, now)
+            }
+            tick => tick,
+        };
+        assert_eq!(advance(&mut scheduler, at(10)), Tick::Advance { index: 1, reuse_current: false });
+        // going back by hand halfway through doesn't start a round
+        scheduler.go_to(0);
+        assert_eq!(advance(&mut scheduler, at(11)), Tick::Advance { index: 0, reuse_current: false });
+        for (n, index) in [(1, 1), (2, 2), (3, 0), (4, 1), (5, 2)] {
+            assert_eq!(advance(&mut scheduler, at(11 + 10 * n)), Tick::Advance { index, reuse_current: false });
+        }
+        assert_eq!(advance(&mut scheduler, at(71)), Tick::Ended);
+        assert!(scheduler.has_ended());
+        assert_eq!(advance(&mut scheduler, at(200)), Tick::Stay);
+        assert_eq!(scheduler.remaining(at(200)), None);
+
+        // the slides can still be gone through by hand, they just don't move on
+        scheduler.go_to(0);
+        assert_eq!(advance(&mut scheduler, at(201)), Tick::Advance { index: 0, reuse_current: false });
+        assert_eq!(advance(&mut scheduler, at(300)), Tick::Stay);
+    }
+
+    #[test]
+    fn weighted_playback_follows_the_weights() {
+        let start = Instant::now();
+        let artworks = artworks(&["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+        let mut processor = MockProcessor::default();
+        let mut scheduler = showing(Duration::from_secs(10), start);
+        scheduler.set_weights(Some(vec![5, 0, 1, 0]));
+
+        // zero weights never come up, and neither does the slide on screen
+        let mut shown = [0u32; 4];
+        for n in 1..=200 {
+            scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(10 * (n - 1)));
+            processor.finish_pending();
+            let now = start + Duration::from_secs(10 * n);
+            let Tick::Advance { index, .. } = scheduler.tick(&artworks, &mut processor, now) else {
+                panic!("slide {} didn't advance", n);
+            };
+            shown[index] += 1;
+        }
+        assert_eq!(shown[1] + shown[3], 0);
+        assert_eq!(shown[0] + shown[2], 200);
+        // a and c can only alternate
+        assert!(shown[0].abs_diff(shown[2]) <= 1);
+
+        // a broken entry drops out
+        scheduler.set_weights(Some(vec![1, 1, 1, 1]));
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(2000));
+        let broken = *processor.started.last().unwrap();
+        processor.fail_pending();
+        scheduler.tick(&artworks, &mut processor, start + Duration::from_secs(2000));
+        assert_ne!(processor.started.last(), Some(&broken));
+    }
+}
@@ -0,0 +1,409 @@
+//! The show installed as a Windows screensaver: renamed to
+//! `art-slideshow.scr`, Windows starts it with `/s` to run, `/c` to
+//! configure and `/p HWND` to draw the small preview in the screensaver
+//! settings. Elsewhere none of this is looked at.
+//!
+//! The settings live in `%APPDATA%\art-slideshow\screensaver.json`, an ordinary
+//! config file with the folder to show as `"folder"`; anything else a config
+//! file takes can be added to it by hand.
+
+use crate::app;
+use crate::config::Config;
+use crate::persist;
+use crate::scheduler::MIN_SLIDE_DURATION;
+use crate::settings::MAX_PANEL_DURATION;
+use eframe::egui;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Longest side of the picture in the preview, which is a few hundred pixels at most.
+const PREVIEW_SIZE: u32 = 400;
+
+/// What Windows started the screensaver for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// `/s`: the show, fullscreen, until a key or the mouse is touched.
+    Run,
+    /// `/c`, or no arguments at all: the settings dialog.
+    Configure,
+    /// `/p HWND`: a preview drawn into that window.
+    Preview(usize),
+}
+
+/// The mode `args`, without the program name, ask for. A `.scr` started
+/// without any is being configured; `None` for the slideshow's own arguments.
+pub fn mode(args: &[String], as_scr: bool) -> Option<Mode> {
+    let Some(first) = args.first() else {
+        return as_scr.then_some(Mode::Configure);
+    };
+    // the window handle comes after a colon or as the next argument, `/c:1234` or `/p 1234`
+    let first = first.to_ascii_lowercase();
+    let (flag, handle) = match first.split_once(':') {
+        Some((flag, handle)) => (flag, Some(handle)),
+        None => (first.as_str(), args.get(1).map(String::as_str)),
+    };
+    match flag {
+        "/s" | "-s" => Some(Mode::Run),
+        "/c" | "-c" => Some(Mode::Configure),
+        "/p" | "-p" => handle.and_then(|handle| handle.trim().parse().ok()).map(Mode::Preview),
+        _ => None,
+    }
+}
+
+/// Lets go of the console window Windows opens for the program, which
+/// has no business next to a screensaver.
+#[cfg(windows)]
+pub fn detach_console() {
+    // SAFETY: no arguments, fails harmlessly without a console
+    unsafe { windows_sys::Win32::System::Console::FreeConsole() };
+}
+
+/// Whether the program file is a `.scr`, as installed screensavers are.
+pub fn launched_as_scr() -> bool {
+    std::env::current_exe().is_ok_and(|exe| exe.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("scr")))
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("art-slideshow").join("screensaver.json"))
+}
+
+/// Shown when no folder was picked yet.
+fn default_folder() -> PathBuf {
+    std::env::var_os("USERPROFILE").map(|home| PathBuf::from(home).join("Pictures")).unwrap_or_default()
+}
+
+/// What the settings dialog changes in the screensaver's config file.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScrSettings {
+    pub folder: Option<PathBuf>,
+    pub slide_duration: f32,
+}
+
+impl Default for ScrSettings {
+    fn default() -> Self {
+        Self { folder: None, slide_duration: Config::default().slide_duration }
+    }
+}
+
+impl ScrSettings {
+    /// Reads them from `path`, the defaults if there is no such file yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                serde_json::from_str(&text).map_err(|err| format!("invalid config {}: {}", path.display(), err))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("cannot read {}: {}", path.display(), err)),
+        }
+    }
+
+    /// Writes them into `path`, leaving the rest of the file as it was.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut config = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => serde_json::Value::Object(Default::default()),
+            Err(err) => return Err(err),
+        };
+        let fields = config.as_object_mut().ok_or_else(|| io::Error::other("the config file is not a JSON object"))?;
+        fields.insert("folder".to_string(), serde_json::to_value(&self.folder).map_err(io::Error::other)?);
+        fields.insert("slide_duration".to_string(), self.slide_duration.into());
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = serde_json::to_string_pretty(&config).map_err(io::Error::other)?;
+        persist::write_atomic(path, text.as_bytes())
+    }
+}
+
+/// The slideshow's arguments for `/s`, program name first: the folder and
+/// the config file, if it was written yet.
+pub fn run_args(path: Option<&Path>) -> Result<Vec<String>, String> {
+    let settings = path.map(ScrSettings::load).transpose()?.unwrap_or_default();
+    let folder = settings.folder.unwrap_or_else(default_folder);
+    let mut args =
+        vec!["art-slideshow".to_string(), folder.to_string_lossy().into_owned(), "--exit-on-input".to_string()];
+    if let Some(path) = path.filter(|path| path.is_file()) {
+        args.extend(["--config".to_string(), path.to_string_lossy().into_owned()]);
+    }
+    Ok(args)
+}
+
+/// `/c`: the settings dialog, a folder and a slide duration.
+struct ConfigDialog {
+    path: PathBuf,
+    folder: String,
+    slide_duration: f32,
+    error: Option<String>,
+}
+
+impl eframe::App for ConfigDialog {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.iter().find_map(|file| file.path.clone()));
+        if let Some(folder) = dropped.filter(|path| path.is_dir()) {
+            self.folder = folder.to_string_lossy().into_owned();
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Art Slideshow screensaver");
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Folder");
+                ui.add(egui::TextEdit::singleline(&mut self.folder).desired_width(f32::INFINITY));
+            });
+            ui.weak("Type the path or drop the folder on this window");
+            ui.add(
+                egui::Slider::new(&mut self.slide_duration, MIN_SLIDE_DURATION.as_secs_f32()..=MAX_PANEL_DURATION)
+                    .logarithmic(true)
+                    .suffix(" s")
+                    .text("Slide duration"),
+            );
+            if let Some(error) = &self.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("OK").clicked() {
+                    let folder = self.folder.trim();
+                    let settings = ScrSettings {
+                        folder: (!folder.is_empty()).then(|| PathBuf::from(folder)),
+                        slide_duration: self.slide_duration,
+                    };
+                    match settings.save(&self.path) {
+                        Ok(()) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                        Err(err) => self.error = Some(format!("cannot write {}: {}", self.path.display(), err)),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+    }
+}
+
+/// Runs the settings dialog until it is closed.
+pub fn configure(path: PathBuf) -> Result<(), String> {
+    let settings = ScrSettings::load(&path)?;
+    let dialog = ConfigDialog {
+        folder: settings.folder.unwrap_or_else(default_folder).to_string_lossy().into_owned(),
+        slide_duration: settings.slide_duration,
+        error: None,
+        path,
+    };
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([460.0, 180.0])
+            .with_resizable(false)
+            .with_title("Art Slideshow screensaver"),
+        ..Default::default()
+    };
+    eframe::run_native("Art Slideshow screensaver", options, Box::new(|_cc| Ok(Box::new(dialog))))
+        .map_err(|err| err.to_string())
+}
+
+/// The first artwork of the show, small enough for the preview.
+fn preview_picture(path: Option<&Path>) -> Option<image::RgbaImage> {
+    let args = run_args(path).ok()?;
+    let cli = crate::cli::parse(args.into_iter().skip(1)).ok()?;
+    let collection = app::collect(cli.folder, &cli.config).ok()?;
+    let first = collection.artworks.first()?;
+    match image::open(&first.path) {
+        Ok(image) => Some(image.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE).to_rgba8()),
+        Err(err) => {
+            tracing::warn!("cannot open {} for the preview: {}", first.path.display(), err);
+            None
+        }
+    }
+}
+
+/// `/p`: the first artwork, still, in the settings' preview window until it
+/// goes away. The show itself doesn't run in there.
+pub fn preview(parent: usize, path: Option<&Path>) -> Result<(), String> {
+    preview_window::show(parent, preview_picture(path))
+}
+
+#[cfg(windows)]
+mod preview_window {
+    use std::mem::zeroed;
+    use std::ptr::{null, null_mut};
+    use std::sync::OnceLock;
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows_sys::Win32::Graphics::Gdi::{
+        BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BLACK_BRUSH, BeginPaint, DIB_RGB_COLORS, EndPaint, FillRect,
+        GetStockObject, HALFTONE, PAINTSTRUCT, SRCCOPY, SetStretchBltMode, StretchDIBits,
+    };
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetClientRect, GetMessageW, MSG, PostQuitMessage,
+        RegisterClassW, TranslateMessage, WM_DESTROY, WM_PAINT, WNDCLASSW, WS_CHILD, WS_VISIBLE,
+    };
+
+    /// Width, height and BGRA rows from the top, as GDI draws them.
+    static PICTURE: OnceLock<(i32, i32, Vec<u8>)> = OnceLock::new();
+
+    unsafe extern "system" fn window_proc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        // SAFETY: called by Windows with a live window of the class registered in `show`
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    paint(window);
+                    0
+                }
+                // the settings closed the preview, or went to another screensaver
+                WM_DESTROY => {
+                    PostQuitMessage(0);
+                    0
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+
+    /// Letterboxes the picture in black.
+    unsafe fn paint(window: HWND) {
+        // SAFETY: `window` is live, the structs are plain data that may be zeroed
+        unsafe {
+            let mut paint: PAINTSTRUCT = zeroed();
+            let hdc = BeginPaint(window, &mut paint);
+            let mut client: RECT = zeroed();
+            GetClientRect(window, &mut client);
+            FillRect(hdc, &client, GetStockObject(BLACK_BRUSH));
+            if let Some((width, height, pixels)) = PICTURE.get() {
+                let (client_width, client_height) = (client.right - client.left, client.bottom - client.top);
+                let scale = (client_width as f32 / *width as f32).min(client_height as f32 / *height as f32);
+                let (shown_width, shown_height) = ((*width as f32 * scale) as i32, (*height as f32 * scale) as i32);
+                let mut info: BITMAPINFO = zeroed();
+                info.bmiHeader = BITMAPINFOHEADER {
+                    biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: *width,
+                    // negative for rows from the top
+                    biHeight: -*height,
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB,
+                    ..zeroed()
+                };
+                SetStretchBltMode(hdc, HALFTONE);
+                StretchDIBits(
+                    hdc,
+                    (client_width - shown_width) / 2,
+                    (client_height - shown_height) / 2,
+                    shown_width,
+                    shown_height,
+                    0,
+                    0,
+                    *width,
+                    *height,
+                    pixels.as_ptr().cast(),
+                    &info,
+                    DIB_RGB_COLORS,
+                    SRCCOPY,
+                );
+            }
+            EndPaint(window, &paint);
+        }
+    }
+
+    pub fn show(parent: usize, picture: Option<image::RgbaImage>) -> Result<(), String> {
+        if let Some(picture) = picture {
+            let (width, height) = picture.dimensions();
+            let mut pixels = picture.into_raw();
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            let _ = PICTURE.set((width as i32, height as i32, pixels));
+        }
+        let parent = parent as HWND;
+        let class: Vec<u16> = "ArtSlideshowPreview\0".encode_utf16().collect();
+        // SAFETY: `class` outlives the window, `parent` is only handed to Windows, which checks it
+        unsafe {
+            let instance = GetModuleHandleW(null());
+            let mut class_info: WNDCLASSW = zeroed();
+            class_info.lpfnWndProc = Some(window_proc);
+            class_info.hInstance = instance;
+            class_info.lpszClassName = class.as_ptr();
+            if RegisterClassW(&class_info) == 0 {
+                return Err("cannot register the preview window".to_string());
+            }
+            let mut client: RECT = zeroed();
+            if GetClientRect(parent, &mut client) == 0 {
+                return Err("no preview window to draw in".to_string());
+            }
+            let window = CreateWindowExW(
+                0,
+                class.as_ptr(),
+                null(),
+                WS_CHILD | WS_VISIBLE,
+                0,
+                0,
+                client.right,
+                client.bottom,
+                parent,
+                null_mut(),
+                instance,
+                null(),
+            );
+            if window.is_null() {
+                return Err("cannot create the preview window".to_string());
+            }
+            let mut message: MSG = zeroed();
+            while GetMessageW(&mut message, null_mut(), 0, 0) > 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(windows))]
+mod preview_window {
+    pub fn show(_parent: usize, _picture: Option<image::RgbaImage>) -> Result<(), String> {
+        Err("the screensaver preview is for Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn windows_screensaver_arguments() {
+        assert_eq!(mode(&args(&["/S"]), true), Some(Mode::Run));
+        assert_eq!(mode(&args(&["/c:4242"]), true), Some(Mode::Configure));
+        assert_eq!(mode(&args(&[]), true), Some(Mode::Configure));
+        assert_eq!(mode(&args(&[]), false), None);
+        assert_eq!(mode(&args(&["/p", "4242"]), true), Some(Mode::Preview(4242)));
+        assert_eq!(mode(&args(&["/p:4242"]), false), Some(Mode::Preview(4242)));
+        assert_eq!(mode(&args(&["/p"]), true), None);
+        assert_eq!(mode(&args(&["C:\\Art", "--shuffle"]), false), None);
+    }
+
+    #[test]
+    fn settings_keep_the_rest_of_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("art-slideshow").join("screensaver.json");
+        assert_eq!(ScrSettings::load(&path).unwrap(), ScrSettings::default());
+
+        let settings = ScrSettings { folder: Some(PathBuf::from("/art")), slide_duration: 20.0 };
+        settings.save(&path).unwrap();
+        let mut config: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        config["shuffle"] = "smart".into();
+        fs::write(&path, config.to_string()).unwrap();
+        settings.save(&path).unwrap();
+
+        assert_eq!(ScrSettings::load(&path).unwrap(), settings);
+        let config = Config::load(&path).unwrap();
+        assert_eq!(config.slide_duration, 20.0);
+        let args = run_args(Some(&path)).unwrap();
+        assert_eq!(args[1..4], ["/art", "--exit-on-input", "--config"]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Jumping to an artwork by title or artist, opened with `/`.
+//!
+//! Matching ignores case and accents, so "durer" finds "Dürer", and is loose:
+//! the letters typed only have to appear in order, though a run of them as
+//! typed ranks first.
+
+use crate::scan::ArtworkInfo;
+use eframe::egui;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Matches listed at most.
+const MAX_MATCHES: usize = 8;
+
+/// `text` in lowercase without its accents.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// How well the folded `query` matches the folded `text`, higher being better,
+/// `None` for no match at all.
+fn score(query: &str, text: &str) -> Option<u32> {
+    if query.is_empty() {
+        return None;
+    }
+    if let Some(position) = text.find(query) {
+        // the start of a word beats the middle of one, earlier beats later
+        let word_start = !text[..position].ends_with(char::is_alphanumeric);
+        let position = position.min(999) as u32;
+        return Some(if word_start { 3000 } else { 2000 } - position);
+    }
+
+    // the letters in order, each gap between them costing a little
+    let mut rest = text.chars();
+    let mut penalty = 0;
+    for wanted in query.chars() {
+        let skipped = rest.by_ref().position(|c| c == wanted)?;
+        penalty += skipped.min(20) as u32;
+    }
+    Some(1000u32.saturating_sub(penalty))
+}
+
+/// Indices of the artworks best matching `query`, best first.
+pub fn matches(query: &str, artworks: &[ArtworkInfo]) -> Vec<usize> {
+    let query = fold(query.trim());
+    let mut scored: Vec<(u32, usize)> = artworks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, info)| {
+            let text = fold(&format!("{} {}", info.metadata.title, info.metadata.artist));
+            score(&query, &text).map(|score| (score, index))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().take(MAX_MATCHES).map(|(_, index)| index).collect()
+}
+
+#[derive(Default)]
+pub struct Search {
+    open: bool,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+    // give the text field the focus on the next frame
+    focus: bool,
+}
+
+impl Search {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens the search with an empty query.
+    pub fn open(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.matches.clear();
+        self.selected = 0;
+        self.focus = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Draws the search box and its matches and handles their keys. Returns the
+    /// index to jump to once a match is clicked or picked with Enter, which
+    /// also closes the search.
+    pub fn show(&mut self, ctx: &egui::Context, artworks: &[ArtworkInfo]) -> Option<usize> {
+        if !self.open {
+            return None;
+        }
+
+        // taken before the text field sees them, it would move its cursor
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+        let mut picked = if enter {
+            self.matches.get(self.selected).copied()
+        } else {
+            None
+        };
+
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+        egui::Area::new(egui::Id::new("search"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .show(ctx, |ui| {
+                frame.show(ui, |ui| {
+                    ui.set_width(480.0);
+                    let field = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .hint_text("Title or artist")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if std::mem::take(&mut self.focus) {
+                        field.request_focus();
+                    }
+                    if field.changed() {
+                        self.matches = matches(&self.query, artworks);
+                        self.selected = 0;
+                    }
+
+                    for (row, &index) in self.matches.iter().enumerate() {
+                        let Some(info) = artworks.get(index) else {
+                            continue;
+                        };
+                        let text = format!("{} — {}", info.metadata.title, info.metadata.artist);
+                        if ui.selectable_label(row == self.selected, text).clicked() {
+                            picked = Some(index);
+                        }
+                    }
+                    if self.matches.is_empty() && !self.query.trim().is_empty() {
+                        ui.weak("No matches");
+                    }
+                });
+            });
+
+        if picked.is_some() {
+            self.close();
+        }
+        picked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+
+    #[test]
+    fn matching_ignores_accents_and_gaps() {
+        let info = |title: &str, artist: &str| ArtworkInfo {
+            path: PathBuf::from(format!("{}.jpg", title)),
+            metadata: ArtworkMetadata { artist: artist.to_string(), ..ArtworkMetadata::unknown(title) },
+            remote: None,
+        };
+        let artworks = [
+            info("The Starry Night", "Vincent van Gogh"),
+            info("Melencolia I", "Albrecht Dürer"),
+            info("Self-Portrait at 28", "Albrecht Dürer"),
+        ];
+
+        assert_eq!(fold("Dürer ÉTÉ"), "durer ete");
+        assert_eq!(matches("durer", &artworks), [1, 2]);
+        // a run as typed beats letters spread out
+        assert_eq!(matches("night", &artworks)[0], 0);
+        assert_eq!(matches("strngt", &artworks), [0]);
+        assert!(matches("xyz", &artworks).is_empty());
+    }
+}
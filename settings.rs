@@ -0,0 +1,180 @@
+//! The on-screen settings panel, toggled with S.
+//!
+//! Everything in it is reachable from the keyboard alone: Tab moves between
+//! controls, the arrow keys change sliders and Space or Enter picks an option.
+//! The panel is not turned with `--rotate`, it is meant for whoever sets the
+//! display up rather than for the audience.
+
+use crate::layout::Fit;
+use crate::overlay::OverlayMode;
+use crate::persist;
+use crate::pipeline::{BRIGHTNESS_RANGE, GAMMA_RANGE};
+use crate::scheduler::MIN_SLIDE_DURATION;
+use crate::shuffle::Shuffle;
+use crate::transition::Transition;
+use eframe::egui;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Longest slide duration the slider offers, longer ones can still be set in the config file.
+pub const MAX_PANEL_DURATION: f32 = 600.0;
+
+/// The settings the panel changes on the running slideshow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveSettings {
+    pub slide_duration: f32,
+    pub shuffle: Shuffle,
+    pub transition: Transition,
+    pub fit: Fit,
+    pub overlay_mode: OverlayMode,
+    pub brightness: f32,
+    pub gamma: f32,
+}
+
+impl LiveSettings {
+    /// Writes the settings kept in the config file into `path`, leaving the
+    /// rest of the file as it was. The overlay mode is remembered by the
+    /// window storage instead, like when it is changed with I.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut config = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => serde_json::Value::Object(Default::default()),
+            Err(err) => return Err(err),
+        };
+        let fields = config
+            .as_object_mut()
+            .ok_or_else(|| io::Error::other("the config file is not a JSON object"))?;
+        fields.insert("slide_duration".to_string(), self.slide_duration.into());
+        fields.insert("shuffle".to_string(), serde_json::to_value(self.shuffle).map_err(io::Error::other)?);
+        fields.insert("transition".to_string(), serde_json::to_value(self.transition).map_err(io::Error::other)?);
+        fields.insert("fit".to_string(), serde_json::to_value(self.fit).map_err(io::Error::other)?);
+        fields.insert("brightness".to_string(), self.brightness.into());
+        fields.insert("gamma".to_string(), self.gamma.into());
+
+        let text = serde_json::to_string_pretty(&config).map_err(io::Error::other)?;
+        persist::write_atomic(path, text.as_bytes())
+    }
+}
+
+#[derive(Default)]
+pub struct SettingsPanel {
+    open: bool,
+    // the first control takes keyboard focus on the frame the panel opens
+    focus_first: bool,
+}
+
+impl SettingsPanel {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.focus_first = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Draws the panel in the top right corner, editing `settings` in place.
+    pub fn show(&mut self, ctx: &egui::Context, settings: &mut LiveSettings) {
+        if !self.open {
+            return;
+        }
+        let focus_first = std::mem::take(&mut self.focus_first);
+        let frame = egui::Frame::window(&ctx.style()).fill(egui::Color32::from_black_alpha(210));
+
+        egui::Window::new("Settings")
+            .frame(frame)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-20.0, 20.0))
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                let durations = MIN_SLIDE_DURATION.as_secs_f32()..=MAX_PANEL_DURATION;
+                let duration = ui.add(
+                    egui::Slider::new(&mut settings.slide_duration, durations)
+                        .logarithmic(true)
+                        // a longer duration from the config file is kept until the slider is moved
+                        .clamping(egui::SliderClamping::Edits)
+                        .suffix(" s")
+                        .text("Slide duration"),
+                );
+                if focus_first {
+                    duration.request_focus();
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Shuffle");
+                    ui.selectable_value(&mut settings.shuffle, Shuffle::Off, "Off");
+                    ui.selectable_value(&mut settings.shuffle, Shuffle::Plain, "Plain");
+                    ui.selectable_value(&mut settings.shuffle, Shuffle::Smart, "Smart");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Transition");
+                    ui.selectable_value(&mut settings.transition, Transition::Cut, "Cut");
+                    ui.selectable_value(&mut settings.transition, Transition::Crossfade, "Crossfade");
+                    ui.selectable_value(&mut settings.transition, Transition::FadeBlack, "Fade to black");
+                    ui.selectable_value(&mut settings.transition, Transition::SlideLeft, "Slide left");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Fit");
+                    ui.selectable_value(&mut settings.fit, Fit::Contain, "Contain");
+                    ui.selectable_value(&mut settings.fit, Fit::Cover, "Cover");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Caption");
+                    ui.selectable_value(&mut settings.overlay_mode, OverlayMode::Always, "Always");
+                    ui.selectable_value(&mut settings.overlay_mode, OverlayMode::AutoHide, "Auto-hide");
+                    ui.selectable_value(&mut settings.overlay_mode, OverlayMode::Hidden, "Hidden");
+                });
+                // for calibrating a projector by eye against the artwork on screen
+                ui.add(
+                    egui::Slider::new(&mut settings.brightness, BRIGHTNESS_RANGE).fixed_decimals(2).text("Brightness"),
+                );
+                ui.add(egui::Slider::new(&mut settings.gamma, GAMMA_RANGE).fixed_decimals(2).text("Gamma"));
+
+                ui.separator();
+                ui.weak("Tab: next control · arrows: adjust · S or Esc: close");
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_other_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, r#"{"kiosk": true, "slide_duration": 10.0}"#).unwrap();
+
+        let settings = LiveSettings {
+            slide_duration: 45.0,
+            shuffle: Shuffle::Smart,
+            transition: Transition::FadeBlack,
+            fit: Fit::Cover,
+            overlay_mode: OverlayMode::Hidden,
+            brightness: 1.2,
+            gamma: 1.0,
+        };
+        settings.save(&path).unwrap();
+
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["kiosk"], true);
+        assert_eq!(saved["slide_duration"], 45.0);
+        assert_eq!(saved["shuffle"], "smart");
+        assert_eq!(saved["transition"], "fade-black");
+        assert_eq!(saved["fit"], "cover");
+        assert!((saved["brightness"].as_f64().unwrap() - 1.2).abs() < 1e-6);
+        assert!(saved.get("overlay_mode").is_none());
+
+        // a missing file is created
+        let fresh = dir.path().join("new.json");
+        settings.save(&fresh).unwrap();
+        assert!(fresh.exists());
+    }
+}
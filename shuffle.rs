@@ -0,0 +1,137 @@
+//! Random show order.
+
+use crate::scan::ArtworkInfo;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Shuffle {
+    /// Folder order.
+    #[default]
+    Off,
+    Plain,
+    /// Shuffled, then spread so one artist doesn't come up twice in a row.
+    Smart,
+}
+
+impl FromStr for Shuffle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Shuffle::Off),
+            "plain" => Ok(Shuffle::Plain),
+            "smart" => Ok(Shuffle::Smart),
+            other => Err(format!("expected off, plain or smart, got {:?}", other)),
+        }
+    }
+}
+
+impl Shuffle {
+    pub fn apply(self, artworks: &mut Vec<ArtworkInfo>) {
+        if self == Shuffle::Off {
+            return;
+        }
+        artworks.shuffle(&mut rand::thread_rng());
+        if self == Shuffle::Smart {
+            spread_artists(artworks);
+        }
+    }
+}
+
+/// Artist compared for spreading, `None` when the sidecar didn't name one.
+pub fn artist_key(info: &ArtworkInfo) -> Option<String> {
+    let artist = info.metadata.artist.trim().to_lowercase();
+    (!artist.is_empty() && artist != "unknown").then_some(artist)
+}
+
+/// Reorders `artworks` so the same artist (ignoring case) never shows twice in
+/// a row while someone else's work is left to put in between. A folder
+/// dominated by one artist still ends up with some of it back to back.
+fn spread_artists(artworks: &mut Vec<ArtworkInfo>) {
+    let mut artists: Vec<Option<String>> = artworks.iter().map(artist_key).collect();
+    let repeats = |a: &Option<String>, b: &Option<String>| a.is_some() && a == b;
+
+    for i in 1..artworks.len() {
+        if !repeats(&artists[i], &artists[i - 1]) {
+            continue;
+        }
+        // bring the nearest later work by someone else forward
+        if let Some(j) = (i + 1..artists.len()).find(|&j| !repeats(&artists[j], &artists[i - 1])) {
+            artworks.swap(i, j);
+            artists.swap(i, j);
+        } else if let Some(k) =
+            (0..i).find(|&k| !repeats(&artists[k], &artists[i]) && (k == 0 || !repeats(&artists[k - 1], &artists[i])))
+        {
+            // only this artist is left, slot it in earlier between two others
+            let info = artworks.remove(i);
+            artworks.insert(k, info);
+            let artist = artists.remove(i);
+            artists.insert(k, artist);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+
+    fn artworks(artists: &[&str]) -> Vec<ArtworkInfo> {
+        artists
+            .iter()
+            .enumerate()
+            .map(|(i, artist)| ArtworkInfo {
+                path: PathBuf::from(format!("{}.jpg", i)),
+                metadata: ArtworkMetadata { artist: artist.to_string(), ..ArtworkMetadata::unknown(&i.to_string()) },
+                remote: None,
+            })
+            .collect()
+    }
+
+    fn repeats(artworks: &[ArtworkInfo]) -> usize {
+        artworks
+            .windows(2)
+            .filter(|pair| artist_key(&pair[0]).is_some() && artist_key(&pair[0]) == artist_key(&pair[1]))
+            .count()
+    }
+
+    #[test]
+    fn spreads_a_dominant_artist() {
+        // 5 Van Goghs can be kept apart by 4 others, whatever the shuffle did
+        let mut skewed = artworks(&[
+            "Monet", "Klimt", "Van Gogh", "van gogh", "Van Gogh", "Vermeer", "Van Gogh", "Degas", "Van Gogh",
+        ]);
+        for _ in 0..50 {
+            skewed.shuffle(&mut rand::thread_rng());
+            spread_artists(&mut skewed);
+            let order: Vec<_> = skewed.iter().map(|a| a.metadata.artist.as_str()).collect();
+            assert_eq!(repeats(&skewed), 0, "{:?}", order);
+        }
+
+        // the worst case: everything at the end
+        let mut tail = artworks(&[
+            "Monet", "Klimt", "Vermeer", "Degas", "Van Gogh", "Van Gogh", "Van Gogh", "Van Gogh", "Van Gogh",
+        ]);
+        spread_artists(&mut tail);
+        assert_eq!(repeats(&tail), 0);
+    }
+
+    #[test]
+    fn a_single_artist_folder_keeps_its_works() {
+        let mut skewed = artworks(&["Monet", "Van Gogh", "Van Gogh", "Van Gogh", "Van Gogh", "Van Gogh", "Van Gogh"]);
+        spread_artists(&mut skewed);
+        // one other artist can break up the run only once
+        assert_eq!(repeats(&skewed), 4);
+        assert_eq!(skewed.len(), 7);
+
+        // works without a named artist are never moved
+        let mut unknown = artworks(&["Unknown", "Unknown", "Unknown"]);
+        spread_artists(&mut unknown);
+        let order: Vec<_> = unknown.iter().map(|a| a.metadata.title.as_str()).collect();
+        assert_eq!(order, ["0", "1", "2"]);
+    }
+}
@@ -0,0 +1,99 @@
+//! Unix signals as the simplest remote control, `pkill -USR1 art-slideshow`
+//! from a shell or a cron job: SIGUSR1 goes to the next slide, SIGUSR2
+//! pauses or resumes, SIGHUP reads the config file and the folders again.
+//!
+//! The handlers only note that a signal came, a thread of signal-hook's wakes
+//! the window up and the next frame does the rest. Elsewhere than Unix no
+//! signal ever comes.
+
+use eframe::egui;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    Next,
+    TogglePause,
+    Reload,
+}
+
+#[derive(Default)]
+struct Pending {
+    next: AtomicBool,
+    toggle_pause: AtomicBool,
+    reload: AtomicBool,
+}
+
+impl Pending {
+    fn flag(&self, signal: Signal) -> &AtomicBool {
+        match signal {
+            Signal::Next => &self.next,
+            Signal::TogglePause => &self.toggle_pause,
+            Signal::Reload => &self.reload,
+        }
+    }
+}
+
+/// The signals that came since the last frame. Several of the same kind
+/// count as one.
+#[derive(Default)]
+pub struct SignalFlags {
+    pending: Arc<Pending>,
+}
+
+impl SignalFlags {
+    /// Starts taking the signals, repainting `ctx` for each.
+    pub fn install(ctx: egui::Context) -> io::Result<Self> {
+        let flags = Self::default();
+        listen(Arc::clone(&flags.pending), ctx)?;
+        Ok(flags)
+    }
+
+    /// The signals that came since the last call, clearing them.
+    pub fn take(&self) -> Vec<Signal> {
+        [Signal::Reload, Signal::Next, Signal::TogglePause]
+            .into_iter()
+            .filter(|&signal| self.pending.flag(signal).swap(false, Ordering::Relaxed))
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn listen(pending: Arc<Pending>, ctx: egui::Context) -> io::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
+
+    let mut signals = signal_hook::iterator::Signals::new([SIGUSR1, SIGUSR2, SIGHUP])?;
+    std::thread::spawn(move || {
+        for number in signals.forever() {
+            let signal = match number {
+                SIGUSR1 => Signal::Next,
+                SIGUSR2 => Signal::TogglePause,
+                _ => Signal::Reload,
+            };
+            pending.flag(signal).store(true, Ordering::Relaxed);
+            ctx.request_repaint();
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_pending: Arc<Pending>, _ctx: egui::Context) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signals_are_taken_once() {
+        let flags = SignalFlags::default();
+        assert!(flags.take().is_empty());
+        flags.pending.flag(Signal::Next).store(true, Ordering::Relaxed);
+        flags.pending.flag(Signal::Reload).store(true, Ordering::Relaxed);
+        assert_eq!(flags.take(), [Signal::Reload, Signal::Next]);
+        assert!(flags.take().is_empty());
+    }
+}
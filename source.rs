@@ -0,0 +1,152 @@
+//! Where a show's images come from: a folder, a playlist, or anything else
+//! that can list artworks and hand over their bytes. The pipeline decodes
+//! what [`ImageSource::read`] returns, so a source doesn't need its images
+//! on the disk.
+
+use crate::ignore::{IGNORE_FILE, IgnoreList};
+use crate::scan::{self, ArtworkInfo};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub enum SourceError {
+    /// The folder, the playlist or an image could not be read.
+    Io(io::Error),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SourceError::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for SourceError {
+    fn from(err: io::Error) -> Self {
+        SourceError::Io(err)
+    }
+}
+
+pub trait ImageSource {
+    /// The artworks there are, in the order the source gives them.
+    fn list(&self) -> Result<Vec<ArtworkInfo>, SourceError>;
+
+    /// The encoded image of `info`, one of those listed.
+    fn read(&self, info: &ArtworkInfo) -> Result<Vec<u8>, SourceError>;
+}
+
+/// A source read from by the loader threads as well as the show.
+pub type SharedSource = Arc<dyn ImageSource + Send + Sync>;
+
+/// The images in a folder, without what its ignore file leaves out.
+pub struct FolderSource {
+    folder: PathBuf,
+    /// The folders below it are listed too, see [`scan::scan_tree`].
+    recursive: bool,
+}
+
+impl FolderSource {
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self { folder: folder.into(), recursive: false }
+    }
+
+    /// The images in `folder` and all the folders below it.
+    pub fn recursive(folder: impl Into<PathBuf>) -> Self {
+        Self { folder: folder.into(), recursive: true }
+    }
+}
+
+impl ImageSource for FolderSource {
+    fn list(&self) -> Result<Vec<ArtworkInfo>, SourceError> {
+        let mut artworks =
+            if self.recursive { scan::scan_tree(&self.folder)? } else { scan::scan_folder(&self.folder)? };
+        let ignore = IgnoreList::load(&self.folder);
+        if !ignore.is_empty() {
+            let found = artworks.len();
+            artworks.retain(|info| !ignore.is_ignored(&self.folder, &info.path));
+            tracing::info!(
+                "{} excludes {} of the images in {}",
+                IGNORE_FILE,
+                found - artworks.len(),
+                self.folder.display()
+            );
+        }
+        Ok(artworks)
+    }
+
+    fn read(&self, info: &ArtworkInfo) -> Result<Vec<u8>, SourceError> {
+        Ok(fs::read(&info.path)?)
+    }
+}
+
+/// The images a playlist file lists, see [`scan::read_playlist`]. Its URL
+/// entries are read from where they are downloaded to, under `downloads`.
+pub struct PlaylistSource {
+    playlist: PathBuf,
+    downloads: PathBuf,
+}
+
+impl PlaylistSource {
+    pub fn new(playlist: impl Into<PathBuf>, downloads: impl Into<PathBuf>) -> Self {
+        Self { playlist: playlist.into(), downloads: downloads.into() }
+    }
+}
+
+impl ImageSource for PlaylistSource {
+    fn list(&self) -> Result<Vec<ArtworkInfo>, SourceError> {
+        Ok(scan::read_playlist(&self.playlist, &self.downloads)?)
+    }
+
+    fn read(&self, info: &ArtworkInfo) -> Result<Vec<u8>, SourceError> {
+        Ok(fs::read(&info.path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_leave_out_what_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("kept.png"), b"kept").unwrap();
+        fs::write(dir.path().join("draft.png"), b"draft").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"not an image").unwrap();
+        fs::write(dir.path().join(IGNORE_FILE), "draft.png\n").unwrap();
+
+        let source = FolderSource::new(dir.path());
+        let artworks = source.list().unwrap();
+        assert_eq!(artworks.len(), 1);
+        assert_eq!(artworks[0].path, dir.path().join("kept.png"));
+        assert_eq!(source.read(&artworks[0]).unwrap(), b"kept");
+        assert!(FolderSource::new(dir.path().join("missing")).list().is_err());
+    }
+
+    #[test]
+    fn playlists_list_in_their_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("second.png"), b"second").unwrap();
+        fs::write(dir.path().join("first.png"), b"first").unwrap();
+        let playlist = dir.path().join("show.txt");
+        fs::write(&playlist, "first.png\nsecond.png\nfirst.png\n").unwrap();
+
+        let source = PlaylistSource::new(&playlist, dir.path().join("remote"));
+        let artworks = source.list().unwrap();
+        let names: Vec<_> = artworks.iter().map(|info| info.path.file_name().unwrap()).collect();
+        assert_eq!(names, ["first.png", "second.png", "first.png"]);
+        assert_eq!(source.read(&artworks[1]).unwrap(), b"second");
+        assert!(PlaylistSource::new(dir.path().join("missing.txt"), dir.path()).list().is_err());
+    }
+}
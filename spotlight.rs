@@ -0,0 +1,119 @@
+//! `--group-by-artist N`: the show in runs of up to N works by one artist,
+//! the artists taking turns in a random order, each run shuffled. The first
+//! slide of a run says whose it is.
+
+use crate::scan::ArtworkInfo;
+use crate::shuffle::artist_key;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+/// Reorders `artworks` into runs of up to `run` works by one artist. An artist
+/// with more comes back for another run once everyone else has had theirs;
+/// works without an artist are runs of their own. When they come `after`
+/// another work, the first run is by someone else where it can be.
+pub fn group_by_artist(artworks: &mut Vec<ArtworkInfo>, run: usize, after: Option<&ArtworkInfo>) {
+    let mut rng = rand::thread_rng();
+    let mut order = Vec::new();
+    let mut by_artist: HashMap<String, Vec<ArtworkInfo>> = HashMap::new();
+    let mut unattributed = Vec::new();
+    for info in std::mem::take(artworks) {
+        match artist_key(&info) {
+            Some(artist) => {
+                if !by_artist.contains_key(&artist) {
+                    order.push(artist.clone());
+                }
+                by_artist.entry(artist).or_default().push(info);
+            }
+            None => unattributed.push(vec![info]),
+        }
+    }
+
+    let mut groups: Vec<Vec<ArtworkInfo>> = order.iter().filter_map(|artist| by_artist.remove(artist)).collect();
+    groups.extend(unattributed);
+    groups.shuffle(&mut rng);
+    // a first run by the same artist would run on from the work before, its first slide not told apart
+    if let Some(before) = after.and_then(artist_key) {
+        if let Some(other) = groups.iter().position(|works| artist_key(&works[0]).as_ref() != Some(&before)) {
+            groups.swap(0, other);
+        }
+    }
+    for works in &mut groups {
+        works.shuffle(&mut rng);
+        // taken from the back a run at a time
+        works.reverse();
+    }
+
+    let run = run.max(1);
+    while !groups.is_empty() {
+        for works in &mut groups {
+            let at = works.len().saturating_sub(run);
+            artworks.extend(works.drain(at..).rev());
+        }
+        groups.retain(|works| !works.is_empty());
+    }
+}
+
+/// The artist whose run of up to `run` works starts at `index`, if one does.
+pub fn starts_run(artworks: &[ArtworkInfo], index: usize, run: usize) -> Option<&str> {
+    let info = artworks.get(index)?;
+    let artist = artist_key(info)?;
+    let before = artworks[..index].iter().rev().take_while(|other| artist_key(other).as_ref() == Some(&artist)).count();
+    (before % run.max(1) == 0).then_some(info.metadata.artist.trim())
+}
+
+pub fn line(artist: &str) -> String {
+    format!("Artist Spotlight: {}", artist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+    use std::path::PathBuf;
+
+    fn by(artist: &str, title: &str) -> ArtworkInfo {
+        ArtworkInfo {
+            path: PathBuf::from(title),
+            metadata: ArtworkMetadata {
+                artist: artist.to_string(),
+                year: "1900".to_string(),
+                ..ArtworkMetadata::unknown(title)
+            },
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn artists_take_turns_in_runs() {
+        let mut artworks: Vec<_> = (0..7).map(|i| by("Claude Monet", &format!("monet {}", i))).collect();
+        artworks.extend((0..2).map(|i| by("Degas", &format!("degas {}", i))));
+        artworks.push(by("Unknown", "anonymous"));
+        group_by_artist(&mut artworks, 3, None);
+        assert_eq!(artworks.len(), 10);
+
+        let artists: Vec<_> = artworks.iter().map(|info| info.metadata.artist.as_str()).collect();
+        let mut runs: Vec<(&str, usize)> = Vec::new();
+        for (i, artist) in artists.iter().enumerate() {
+            if starts_run(&artworks, i, 3).is_some() || artist == &"Unknown" {
+                runs.push((artist, 1));
+            } else {
+                runs.last_mut().unwrap().1 += 1;
+            }
+        }
+        let mut monet: Vec<_> = runs.iter().filter(|(artist, _)| *artist == "Claude Monet").map(|run| run.1).collect();
+        monet.sort();
+        assert_eq!(monet, [1, 3, 3]);
+        assert!(runs.contains(&("Degas", 2)) && runs.contains(&("Unknown", 1)));
+        // everyone has had a run before Monet's second
+        let second = runs.iter().enumerate().filter(|(_, run)| run.0 == "Claude Monet").nth(1).unwrap().0;
+        assert!(second >= 3);
+
+        let first = artists.iter().position(|artist| *artist == "Claude Monet").unwrap();
+        assert_eq!(starts_run(&artworks, first, 3).map(line), Some("Artist Spotlight: Claude Monet".to_string()));
+        assert_eq!(starts_run(&artworks, first + 1, 3), None);
+
+        let mut after_degas = vec![by("Degas", "degas 2"), by("Claude Monet", "monet 7")];
+        group_by_artist(&mut after_degas, 3, Some(&by("Degas", "degas 1")));
+        assert_eq!(after_degas[0].metadata.artist, "Claude Monet");
+    }
+}
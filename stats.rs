@@ -0,0 +1,242 @@
+//! Log of what was on screen and for how long, for reporting on an installation.
+//!
+//! Every time a slide leaves the screen a JSON line is appended to the stats
+//! file: when it appeared, its file, title and artist, and how long it was
+//! actually up, pauses and skips included. Lines are written by a thread of
+//! their own every [`FLUSH_EVERY`] and on shutdown, so a slow or full disk never
+//! holds up the slideshow.
+
+use crate::scan::ArtworkInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often buffered lines are written out.
+pub const FLUSH_EVERY: Duration = Duration::from_secs(30);
+
+/// Lines kept while the file can't be written, the oldest are dropped past this.
+const MAX_PENDING: usize = 10_000;
+
+/// One slide's time on screen, a line of the stats file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShowRecord {
+    /// Local time the slide appeared, RFC 3339.
+    pub shown_at: String,
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: String,
+    /// How long it was on screen.
+    pub seconds: f64,
+}
+
+/// The slide whose record is still open.
+struct Showing {
+    record: ShowRecord,
+    since: Instant,
+}
+
+pub struct StatsLog {
+    showing: Option<Showing>,
+    lines: Option<Sender<String>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl StatsLog {
+    /// Starts the thread appending to `path`. The file is only opened when
+    /// writing, so it may be created, rotated or deleted in between.
+    pub fn new(path: PathBuf) -> Self {
+        let (lines, receiver) = mpsc::channel::<String>();
+        let writer = thread::spawn(move || {
+            let mut pending = VecDeque::new();
+            let mut failing = false;
+            let mut last_flush = Instant::now();
+            loop {
+                let finished = match receiver.recv_timeout(FLUSH_EVERY.saturating_sub(last_flush.elapsed())) {
+                    Ok(line) => {
+                        if pending.len() == MAX_PENDING {
+                            pending.pop_front();
+                        }
+                        pending.push_back(line);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+                if finished || last_flush.elapsed() >= FLUSH_EVERY {
+                    flush(&path, &mut pending, &mut failing);
+                    last_flush = Instant::now();
+                }
+                if finished {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            showing: None,
+            lines: Some(lines),
+            writer: Some(writer),
+        }
+    }
+
+    /// `info` went on screen, ending the record of whatever was there before.
+    pub fn shown(&mut self, info: &ArtworkInfo) {
+        self.hidden();
+        self.showing = Some(Showing {
+            record: ShowRecord {
+                shown_at: chrono::Local::now().to_rfc3339(),
+                path: info.path.clone(),
+                title: info.metadata.title.clone(),
+                artist: info.metadata.artist.clone(),
+                seconds: 0.0,
+            },
+            since: Instant::now(),
+        });
+    }
+
+    /// The screen went dark, the slide on it is no longer being looked at.
+    pub fn hidden(&mut self) {
+        let Some(Showing { mut record, since }) = self.showing.take() else {
+            return;
+        };
+        record.seconds = since.elapsed().as_secs_f64();
+        match serde_json::to_string(&record) {
+            Ok(line) => {
+                if let Some(lines) = &self.lines {
+                    let _ = lines.send(line);
+                }
+            }
+            Err(err) => tracing::warn!("cannot record {}: {}", record.path.display(), err),
+        }
+    }
+
+    pub fn is_showing(&self) -> bool {
+        self.showing.is_some()
+    }
+}
+
+impl Drop for StatsLog {
+    /// Records the last slide and waits for everything to be written.
+    fn drop(&mut self) {
+        self.hidden();
+        self.lines = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// Appends `pending` to the file. On failure the lines are kept for the next
+/// try, and only the first failure in a row is reported.
+fn flush(path: &Path, pending: &mut VecDeque<String>, failing: &mut bool) {
+    if pending.is_empty() {
+        return;
+    }
+    let text: String = pending.iter().map(|line| format!("{}\n", line)).collect();
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()));
+    match result {
+        Ok(()) => {
+            pending.clear();
+            if std::mem::take(failing) {
+                tracing::info!("writing stats to {} again", path.display());
+            }
+        }
+        Err(err) => {
+            if !*failing {
+                tracing::warn!("cannot write stats to {}, will retry: {}", path.display(), err);
+            }
+            *failing = true;
+        }
+    }
+}
+
+/// How much screen time one artwork got over a whole log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtworkTotal {
+    pub path: PathBuf,
+    pub title: String,
+    pub artist: String,
+    pub times_shown: u32,
+    pub seconds: f64,
+}
+
+/// Adds up a stats file per artwork, longest total first. Also returns how
+/// many lines were skipped for not parsing.
+pub fn summarize(log: impl BufRead) -> io::Result<(Vec<ArtworkTotal>, usize)> {
+    let mut totals: HashMap<PathBuf, ArtworkTotal> = HashMap::new();
+    let mut skipped = 0;
+    for line in log.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(record) = serde_json::from_str::<ShowRecord>(&line) else {
+            skipped += 1;
+            continue;
+        };
+        let total = totals.entry(record.path.clone()).or_insert_with(|| ArtworkTotal {
+            path: record.path,
+            title: String::new(),
+            artist: String::new(),
+            times_shown: 0,
+            seconds: 0.0,
+        });
+        // the latest sidecar wins if it changed along the way
+        total.title = record.title;
+        total.artist = record.artist;
+        total.times_shown += 1;
+        total.seconds += record.seconds;
+    }
+
+    let mut totals: Vec<ArtworkTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| b.seconds.total_cmp(&a.seconds).then_with(|| a.path.cmp(&b.path)));
+    Ok((totals, skipped))
+}
+
+/// `1:02:03` for an hour, two minutes and three seconds.
+pub fn format_seconds(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::ArtworkMetadata;
+
+    #[test]
+    fn log_adds_up_per_artwork() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let info = |name: &str| ArtworkInfo {
+            path: PathBuf::from(name),
+            metadata: ArtworkMetadata { artist: "Goya".to_string(), ..ArtworkMetadata::unknown(name) },
+            remote: None,
+        };
+
+        let mut log = StatsLog::new(path.clone());
+        log.shown(&info("a.jpg"));
+        log.shown(&info("b.jpg"));
+        log.hidden();
+        log.shown(&info("a.jpg"));
+        drop(log);
+
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        text.push_str("not json\n");
+        let (totals, skipped) = summarize(text.as_bytes()).unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(totals.len(), 2);
+        let a = totals.iter().find(|total| total.path == Path::new("a.jpg")).unwrap();
+        assert_eq!(a.times_shown, 2);
+        assert_eq!(format_seconds(3723.4), "1:02:03");
+    }
+}
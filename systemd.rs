@@ -0,0 +1,111 @@
+//! Telling systemd how the show is doing, with the `systemd` feature: ready
+//! once the first slide is on screen, and still alive every so often after
+//! that, so a service with `WatchdogSec=` is killed and restarted when the
+//! render loop hangs. Started outside systemd, without `NOTIFY_SOCKET`, none
+//! of it does anything.
+
+use std::time::{Duration, Instant};
+
+/// What is told to systemd.
+#[cfg_attr(not(feature = "systemd"), allow(dead_code))]
+enum State {
+    Ready,
+    Watchdog,
+}
+
+/// How often to ping a watchdog that gives up after `watchdog_usec`: twice
+/// within it, as systemd asks.
+#[cfg_attr(not(feature = "systemd"), allow(dead_code))]
+fn ping_interval(watchdog_usec: u64) -> Option<Duration> {
+    (watchdog_usec > 0).then(|| Duration::from_micros(watchdog_usec) / 2)
+}
+
+#[derive(Default)]
+pub struct Notifier {
+    ready: bool,
+    // `None` without a watchdog
+    interval: Option<Duration>,
+    last_ping: Option<Instant>,
+}
+
+impl Notifier {
+    /// Picks up the watchdog systemd started the show with, if any.
+    pub fn from_env() -> Self {
+        Self { interval: watchdog_interval(), ..Self::default() }
+    }
+
+    /// Tells systemd the show is up, the first time only.
+    pub fn ready(&mut self) {
+        if !std::mem::replace(&mut self.ready, true) {
+            notify(State::Ready);
+        }
+    }
+
+    /// Tells the watchdog the show is still going if a ping is due. Returns
+    /// how long until the next one, for the frame to wake up in time.
+    pub fn ping(&mut self, now: Instant) -> Option<Duration> {
+        let interval = self.interval?;
+        let last = match self.last_ping {
+            Some(last) if now.duration_since(last) < interval => last,
+            _ => {
+                notify(State::Watchdog);
+                self.last_ping = Some(now);
+                now
+            }
+        };
+        Some(interval - now.duration_since(last))
+    }
+}
+
+#[cfg(feature = "systemd")]
+fn watchdog_interval() -> Option<Duration> {
+    let mut usec = 0;
+    // not enabled outside systemd, or when it was set for another process
+    if sd_notify::watchdog_enabled(false, &mut usec) { ping_interval(usec) } else { None }
+}
+
+#[cfg(feature = "systemd")]
+fn notify(state: State) {
+    use sd_notify::NotifyState;
+    // does nothing without NOTIFY_SOCKET
+    match state {
+        State::Ready => {
+            if let Err(err) = sd_notify::notify(false, &[NotifyState::Ready]) {
+                tracing::warn!("cannot tell systemd the show is ready: {}", err);
+            }
+        }
+        State::Watchdog => {
+            if let Err(err) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                tracing::debug!("cannot ping the systemd watchdog: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "systemd"))]
+fn watchdog_interval() -> Option<Duration> {
+    None
+}
+
+#[cfg(not(feature = "systemd"))]
+fn notify(_state: State) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pings_come_twice_per_watchdog_timeout() {
+        assert_eq!(ping_interval(0), None);
+        let interval = ping_interval(10_000_000).unwrap();
+        assert_eq!(interval, Duration::from_secs(5));
+
+        let start = Instant::now();
+        let mut notifier = Notifier { interval: Some(interval), ..Notifier::default() };
+        assert_eq!(notifier.ping(start), Some(interval));
+        assert_eq!(notifier.ping(start + Duration::from_secs(2)), Some(Duration::from_secs(3)));
+        assert_eq!(notifier.ping(start + interval), Some(interval));
+        assert_eq!(notifier.last_ping, Some(start + interval));
+        assert_eq!(Notifier::default().ping(start), None);
+    }
+}
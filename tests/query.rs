@@ -0,0 +1,70 @@
+//! Runs the `query` subcommand of the real binary against an in-process endpoint.
+
+use art_slideshow::ipc::{self, Snapshot};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+fn endpoint(dir: &tempfile::TempDir) -> String {
+    if cfg!(unix) {
+        dir.path().join("query.sock").to_string_lossy().into_owned()
+    } else {
+        "127.0.0.1:47699".to_string()
+    }
+}
+
+fn run(endpoint: &str, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_art-slideshow"))
+        .arg("query")
+        .args(args)
+        .args(["--socket", endpoint])
+        .output()
+        .unwrap();
+    (output.status.code().unwrap(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn queries_a_running_instance() {
+    let dir = tempfile::tempdir().unwrap();
+    let endpoint = endpoint(&dir);
+    let snapshot = Arc::new(Mutex::new(Snapshot {
+        index: 2,
+        total: 5,
+        path: Some(PathBuf::from("/art/goya.jpg")),
+        title: Some("Portrait of Don Ramón Satué".to_string()),
+        artist: Some("Francisco de Goya".to_string()),
+        year: Some("1823".to_string()),
+        collections: vec![PathBuf::from("/art")],
+        slides_shown: 7,
+        ..Snapshot::default()
+    }));
+    let _server = ipc::serve(&endpoint, snapshot).unwrap();
+
+    let (code, stdout) = run(&endpoint, &["current", "--json"]);
+    assert_eq!(code, 0);
+    let current: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(current["index"], 2);
+    assert_eq!(current["artist"], "Francisco de Goya");
+    assert_eq!(current["path"], "/art/goya.jpg");
+
+    let (code, stdout) = run(&endpoint, &["stats", "--json"]);
+    assert_eq!(code, 0);
+    let stats: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(stats["slides_shown"], 7);
+
+    let (code, stdout) = run(&endpoint, &["list-collections", "--json"]);
+    assert_eq!(code, 0);
+    let collections: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(collections["collections"][0], "/art");
+
+    let (code, stdout) = run(&endpoint, &["status"]);
+    assert_eq!(code, 0);
+    assert!(stdout.contains("total: 5"));
+}
+
+#[test]
+fn no_instance_has_its_own_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let (code, _) = run(&endpoint(&dir), &["status"]);
+    assert_eq!(code, 2);
+}
@@ -0,0 +1,105 @@
+//! `--transition`: how one slide gives way to the next. Anything but a cut keeps
+//! the outgoing slide's textures until the transition is over and draws both
+//! slides, each with its own blurred background and caption.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// Straight over, nothing of the slide before is kept.
+    #[default]
+    Cut,
+    /// The new slide fades in over the old one.
+    Crossfade,
+    /// The old slide fades out to black, then the new one in from black.
+    FadeBlack,
+    /// The new slide comes in from the right, pushing the old one off to the left.
+    SlideLeft,
+}
+
+impl FromStr for Transition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cut" => Ok(Transition::Cut),
+            "crossfade" => Ok(Transition::Crossfade),
+            "fade-black" => Ok(Transition::FadeBlack),
+            "slide-left" => Ok(Transition::SlideLeft),
+            other => Err(format!("expected cut, crossfade, fade-black or slide-left, got {:?}", other)),
+        }
+    }
+}
+
+/// How one of the two slides is drawn at a point of the transition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Look {
+    pub opacity: f32,
+    /// Sideways by this much of the screen's width, to the left when negative.
+    pub shift: f32,
+}
+
+impl Look {
+    pub const PLAIN: Look = Look { opacity: 1.0, shift: 0.0 };
+}
+
+/// How far through `duration` a transition begun `elapsed` ago is, from 0 to 1.
+pub fn progress(elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 1.0;
+    }
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).min(1.0)
+}
+
+impl Transition {
+    /// The outgoing and the incoming slide `progress` of the way through, drawn
+    /// in that order; `None` isn't drawn at all.
+    pub fn looks(self, progress: f32) -> [Option<Look>; 2] {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            Transition::Cut => [None, Some(Look::PLAIN)],
+            // the new one over the old one at full strength adds up to a mix of the two
+            Transition::Crossfade => [Some(Look::PLAIN), Some(Look { opacity: progress, shift: 0.0 })],
+            Transition::FadeBlack if progress < 0.5 => [Some(Look { opacity: 1.0 - progress * 2.0, shift: 0.0 }), None],
+            Transition::FadeBlack => [None, Some(Look { opacity: progress * 2.0 - 1.0, shift: 0.0 })],
+            Transition::SlideLeft => {
+                // eased in and out, so neither slide starts or stops with a jolt
+                let moved = progress * progress * (3.0 - 2.0 * progress);
+                [Some(Look { opacity: 1.0, shift: -moved }), Some(Look { opacity: 1.0, shift: 1.0 - moved })]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_transition_ends_on_the_new_slide_alone() {
+        for transition in [Transition::Cut, Transition::Crossfade, Transition::FadeBlack, Transition::SlideLeft] {
+            let [_, incoming] = transition.looks(1.0);
+            assert_eq!(incoming, Some(Look::PLAIN), "{:?}", transition);
+            assert_eq!(transition.looks(0.0)[0].map(|look| look.shift), (transition != Transition::Cut).then_some(0.0));
+        }
+        assert_eq!(Transition::Cut.looks(0.0), [None, Some(Look::PLAIN)]);
+
+        // black halfway through, one slide or the other on each side of it
+        let [outgoing, incoming] = Transition::FadeBlack.looks(0.25);
+        assert_eq!((outgoing.map(|look| look.opacity), incoming), (Some(0.5), None));
+        let [outgoing, incoming] = Transition::FadeBlack.looks(0.75);
+        assert_eq!((outgoing, incoming.map(|look| look.opacity)), (None, Some(0.5)));
+
+        let [outgoing, incoming] = Transition::SlideLeft.looks(0.5);
+        assert_eq!((outgoing.unwrap().shift, incoming.unwrap().shift), (-0.5, 0.5));
+
+        assert_eq!(progress(Duration::from_millis(250), Duration::from_secs(1)), 0.25);
+        assert_eq!(progress(Duration::from_secs(3), Duration::from_secs(1)), 1.0);
+        assert_eq!(progress(Duration::ZERO, Duration::ZERO), 1.0);
+        assert_eq!("fade-black".parse(), Ok(Transition::FadeBlack));
+        assert!("wipe".parse::<Transition>().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Taking images out of the show with Delete, when `--allow-delete` is on.
+//!
+//! Nothing is deleted for real: the image and its sidecar are moved into a
+//! `.slideshow-trash` folder next to it, to be emptied or rescued by hand.
+//! Scanning doesn't go into subfolders, so they never come back on their own.
+
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const TRASH_DIR: &str = ".slideshow-trash";
+
+/// `image` and its sidecar in `trash`, under the first name taken by neither
+/// of them: `name.jpg`, then `name-1.jpg`, `name-2.jpg` and so on.
+fn free_names(trash: &Path, image: &Path) -> (PathBuf, PathBuf) {
+    let stem = image.file_stem().unwrap_or_default();
+    let extension = image.extension();
+    (0..)
+        .map(|n| {
+            let mut name = OsString::from(stem);
+            if n > 0 {
+                name.push(format!("-{}", n));
+            }
+            // pushed rather than set, a stem like `st.jerome` has dots of its own
+            let with_extension = |extension: &OsStr| {
+                let mut file = name.clone();
+                file.push(".");
+                file.push(extension);
+                trash.join(file)
+            };
+            let image_target = extension.map_or_else(|| trash.join(&name), with_extension);
+            (image_target, with_extension(OsStr::new("json")))
+        })
+        .find(|(image_target, sidecar_target)| !image_target.exists() && !sidecar_target.exists())
+        .expect("some suffix is free")
+}
+
+/// Moves `image`, and its sidecar if it has one, into the trash folder beside
+/// it. Returns where the image went.
+pub fn move_to_trash(image: &Path) -> io::Result<PathBuf> {
+    let trash = image.parent().unwrap_or(Path::new(".")).join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    let (image_target, sidecar_target) = free_names(&trash, image);
+
+    fs::rename(image, &image_target)?;
+    let sidecar = image.with_extension("json");
+    if sidecar.exists() {
+        if let Err(err) = fs::rename(&sidecar, &sidecar_target) {
+            // the image is gone from the show either way
+            tracing::warn!("cannot move {} to the trash: {}", sidecar.display(), err);
+        }
+    }
+    Ok(image_target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trashed_names_never_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        let image = dir.path().join("melencolia.i.jpg");
+        for _ in 0..2 {
+            fs::write(&image, b"jpeg").unwrap();
+            fs::write(image.with_extension("json"), b"{}").unwrap();
+            move_to_trash(&image).unwrap();
+        }
+        // a sidecar left behind on its own still takes its name, and the stem keeps its dot
+        fs::write(dir.path().join(TRASH_DIR).join("melencolia.i-2.json"), b"{}").unwrap();
+        fs::write(&image, b"jpeg").unwrap();
+        let third = move_to_trash(&image).unwrap();
+
+        let trash = dir.path().join(TRASH_DIR);
+        assert!(!image.exists() && !image.with_extension("json").exists());
+        assert!(trash.join("melencolia.i.jpg").exists() && trash.join("melencolia.i.json").exists());
+        assert!(trash.join("melencolia.i-1.jpg").exists() && trash.join("melencolia.i-1.json").exists());
+        assert_eq!(third, trash.join("melencolia.i-3.jpg"));
+    }
+}
@@ -0,0 +1,142 @@
+//! `--error-webhook URL`: trouble is posted to a URL as it happens, a file
+//! that won't process, a slide up far longer than it should be, a panic, so a
+//! show out of sight doesn't sit on one painting for days unnoticed.
+//! `--heartbeat-webhook URL` is sent the slide on screen every hour instead,
+//! for a monitor that raises the alarm when the pings stop.
+//!
+//! Posting happens on a thread of its own, with a short timeout and a couple
+//! of retries; a post that still fails is logged and the show goes on.
+
+use chrono::SecondsFormat;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// A slide this many times past its time makes the show look stuck.
+pub const STALL_FACTOR: u32 = 3;
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Tries after the first.
+const RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const USER_AGENT: &str = concat!("art-slideshow/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// An image failed to process.
+    Error,
+    /// No slide change for [`STALL_FACTOR`] times the slide's duration.
+    Stall,
+    Panic,
+    Heartbeat,
+}
+
+/// What is posted, as JSON.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    pub event: EventKind,
+    pub path: Option<PathBuf>,
+    pub message: String,
+    pub hostname: String,
+    /// When it happened, in RFC 3339.
+    pub timestamp: String,
+}
+
+impl Event {
+    pub fn new(event: EventKind, path: Option<&Path>, message: impl Into<String>) -> Self {
+        Self {
+            event,
+            path: path.map(Path::to_path_buf),
+            message: message.into(),
+            hostname: gethostname::gethostname().to_string_lossy().into_owned(),
+            timestamp: chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(TIMEOUT).user_agent(USER_AGENT).build()
+}
+
+/// Posts `event` to `url`, trying again a couple of times before giving up.
+fn post(agent: &ureq::Agent, url: &str, event: &Event) -> Result<(), String> {
+    let body = serde_json::to_string(event).map_err(|err| err.to_string())?;
+    let mut retries = 0;
+    loop {
+        match agent.post(url).set("Content-Type", "application/json").send_string(&body) {
+            Ok(_) => return Ok(()),
+            Err(_) if retries < RETRIES => {
+                retries += 1;
+                thread::sleep(RETRY_DELAY);
+            }
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+}
+
+/// Posts the events it is sent to one URL, in order.
+pub struct Webhook {
+    events: Sender<Event>,
+}
+
+impl Webhook {
+    pub fn start(url: String) -> Self {
+        let (events, receiver) = mpsc::channel::<Event>();
+        thread::spawn(move || {
+            let agent = agent();
+            for event in receiver {
+                if let Err(err) = post(&agent, &url, &event) {
+                    tracing::warn!("cannot post the {:?} event to {}: {}", event.event, url, err);
+                }
+            }
+        });
+        Self { events }
+    }
+
+    /// Posts `event`, returning at once.
+    pub fn send(&self, event: Event) {
+        // the thread only stops once this is dropped
+        let _ = self.events.send(event);
+    }
+}
+
+/// Has every panic posted to `url` as it happens, after the usual message.
+/// It is posted from the panicking thread, which may not get another chance.
+pub fn report_panics(url: String) {
+    let agent = agent();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let event = Event::new(EventKind::Panic, None, info.to_string());
+        if let Err(err) = post(&agent, &url, &event) {
+            tracing::warn!("cannot post the panic to {}: {}", url, err);
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_posted_as_json() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let webhook = Webhook::start(url);
+        webhook.send(Event::new(EventKind::Error, Some(Path::new("/art/broken.jpg")), "cannot decode"));
+
+        let mut request = server.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).unwrap();
+        let _ = request.respond(tiny_http::Response::empty(204));
+        let posted: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(posted["event"], "error");
+        assert_eq!(posted["path"], "/art/broken.jpg");
+        assert_eq!(posted["message"], "cannot decode");
+        assert!(!posted["hostname"].as_str().unwrap().is_empty());
+        assert!(posted["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+}
@@ -0,0 +1,292 @@
+//! Sidecars for famous works from Wikidata, for `art-slideshow fetch`.
+//!
+//! An image without a sidecar is looked up by its file name
+//! (`the_starry_night.jpg` becomes "the starry night"). Only an artwork whose
+//! label matches the name exactly, and the only one that does, counts as a
+//! match; its title, creator and inception year are written next to the image
+//! as a normal sidecar. Answers are cached, so a second run only asks about
+//! new files, and requests are spaced out to go easy on the public API.
+
+use crate::persist;
+use crate::pipeline::cache;
+use crate::scan;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const API: &str = "https://www.wikidata.org/w/api.php";
+const USER_AGENT: &str = concat!("art-slideshow/", env!("CARGO_PKG_VERSION"), " (sidecar fetcher)");
+/// Shortest time between two requests.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const CACHE_FILE: &str = "wikidata.json";
+
+/// Classes an item must be an instance of to be taken for an artwork:
+/// painting, work of art, sculpture, drawing and print.
+const ARTWORK_CLASSES: [&str; 5] = ["Q3305213", "Q838948", "Q860861", "Q93184", "Q11060274"];
+
+/// What ends up in the sidecar.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Found {
+    pub title: String,
+    pub artist: String,
+    pub year: String,
+}
+
+/// The search text for a file name: `The_Starry-Night (1).jpg` is `the starry night 1`.
+pub fn search_text(stem: &str) -> String {
+    stem.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A title compared without case, punctuation or a leading article.
+fn comparable(title: &str) -> String {
+    let text = search_text(title);
+    ["the ", "a ", "an "]
+        .iter()
+        .find_map(|article| text.strip_prefix(article))
+        .map(str::to_string)
+        .unwrap_or(text)
+}
+
+/// Images in `folder` that have no sidecar yet, sorted.
+pub fn missing_sidecars(folder: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut images: Vec<PathBuf> = fs::read_dir(folder)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| scan::is_supported_image(path) && !path.with_extension("json").exists())
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// Writes `found` as the sidecar of `image`, never replacing one that exists.
+pub fn write_sidecar(image: &Path, found: &Found) -> io::Result<()> {
+    let path = image.with_extension("json");
+    if path.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a sidecar is already there"));
+    }
+    let json = serde_json::to_vec_pretty(found)?;
+    persist::write_atomic(&path, &json)
+}
+
+/// Ids of the search results whose label or alias is the title searched for.
+fn matching_ids(search: &Value, text: &str) -> Vec<String> {
+    let wanted = comparable(text);
+    search["search"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|result| {
+            let matched = result["match"]["text"].as_str().or(result["label"].as_str()).unwrap_or_default();
+            comparable(matched) == wanted
+        })
+        .filter_map(|result| result["id"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// The ids a claim points at, like the classes of P31 or the creators of P170.
+fn claim_ids<'a>(entity: &'a Value, property: &str) -> impl Iterator<Item = &'a str> {
+    entity["claims"][property]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|claim| claim["mainsnak"]["datavalue"]["value"]["id"].as_str())
+}
+
+/// `1889` from an inception like `+1889-06-00T00:00:00Z`.
+fn inception_year(entity: &Value) -> Option<String> {
+    let time = entity["claims"]["P571"][0]["mainsnak"]["datavalue"]["value"]["time"].as_str()?;
+    let year = time.strip_prefix('+')?.split('-').next()?;
+    let year = year.trim_start_matches('0');
+    (!year.is_empty() && year.bytes().all(|byte| byte.is_ascii_digit())).then(|| year.to_string())
+}
+
+fn english_label(entity: &Value) -> Option<&str> {
+    entity["labels"]["en"]["value"].as_str()
+}
+
+/// The one artwork with a creator among `entities`, with its creator's id.
+/// Several are as good as none, there is no telling which one is meant.
+fn single_artwork(entities: &Value) -> Option<(&Value, &str)> {
+    let mut artworks = entities["entities"].as_object()?.values().filter_map(|entity| {
+        let is_artwork = claim_ids(entity, "P31").any(|class| ARTWORK_CLASSES.contains(&class));
+        let creator = claim_ids(entity, "P170").next()?;
+        is_artwork.then_some((entity, creator))
+    });
+    let artwork = artworks.next()?;
+    artworks.next().is_none().then_some(artwork)
+}
+
+/// Looks file names up on Wikidata, caching the answers on disk.
+pub struct Fetcher {
+    agent: ureq::Agent,
+    cache_path: Option<PathBuf>,
+    // `None` for names known to have no confident match
+    cache: BTreeMap<String, Option<Found>>,
+    last_request: Option<Instant>,
+}
+
+impl Fetcher {
+    /// The cache lives in the platform's cache directory, there's none without one.
+    pub fn new() -> Self {
+        let cache_path = cache::default_dir().map(|dir| dir.join(CACHE_FILE));
+        let cache = cache_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(15))
+                .user_agent(USER_AGENT)
+                .build(),
+            cache_path,
+            cache,
+            last_request: None,
+        }
+    }
+
+    /// The artwork `stem` names, `None` without a confident match. Errors are
+    /// network or API trouble, and aren't cached so the next run asks again.
+    pub fn lookup(&mut self, stem: &str) -> Result<Option<Found>, String> {
+        let text = search_text(stem);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        if let Some(cached) = self.cache.get(&text) {
+            return Ok(cached.clone());
+        }
+
+        let found = self.ask(&text)?;
+        self.cache.insert(text, found.clone());
+        self.save_cache();
+        Ok(found)
+    }
+
+    fn ask(&mut self, text: &str) -> Result<Option<Found>, String> {
+        let search = self.request(&[
+            ("action", "wbsearchentities"),
+            ("search", text),
+            ("language", "en"),
+            ("type", "item"),
+            ("limit", "7"),
+        ])?;
+        let ids = matching_ids(&search, text);
+        if ids.is_empty() {
+            return Ok(None);
+        }
+
+        let entities = self.request(&[
+            ("action", "wbgetentities"),
+            ("ids", &ids.join("|")),
+            ("props", "labels|claims"),
+            ("languages", "en"),
+        ])?;
+        let Some((artwork, creator)) = single_artwork(&entities) else {
+            return Ok(None);
+        };
+        let creator_entity =
+            self.request(&[("action", "wbgetentities"), ("ids", creator), ("props", "labels"), ("languages", "en")])?;
+        let Some(artist) = english_label(&creator_entity["entities"][creator]) else {
+            return Ok(None);
+        };
+
+        Ok(english_label(artwork).map(|title| Found {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            year: inception_year(artwork).unwrap_or_else(|| "Unknown".to_string()),
+        }))
+    }
+
+    /// One API call, waiting first if the last one was too recent.
+    fn request(&mut self, params: &[(&str, &str)]) -> Result<Value, String> {
+        if let Some(last) = self.last_request {
+            thread::sleep(REQUEST_INTERVAL.saturating_sub(last.elapsed()));
+        }
+        self.last_request = Some(Instant::now());
+
+        let mut request = self.agent.get(API).query("format", "json");
+        for (name, value) in params {
+            request = request.query(name, value);
+        }
+        let body = request
+            .call()
+            .map_err(|err| err.to_string())?
+            .into_string()
+            .map_err(|err| err.to_string())?;
+        serde_json::from_str(&body).map_err(|err| format!("unexpected answer from Wikidata: {}", err))
+    }
+
+    fn save_cache(&self) {
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| serde_json::to_vec_pretty(&self.cache).map_err(io::Error::other))
+            .and_then(|json| persist::write_atomic(path, &json));
+        if let Err(err) = result {
+            tracing::warn!("cannot save {}: {}", path.display(), err);
+        }
+    }
+}
+
+impl Default for Fetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn names_match_labels_loosely() {
+        assert_eq!(search_text("the_starry-night (1)"), "the starry night 1");
+        assert_eq!(comparable("The Starry Night"), comparable("starry_night"));
+
+        let search = json!({"search": [
+            {"id": "Q45585", "label": "The Starry Night", "match": {"type": "label", "text": "The Starry Night"}},
+            {
+                "id": "Q1",
+                "label": "Starry Night Over the Rhône",
+                "match": {"type": "label", "text": "Starry Night Over the Rhône"},
+            },
+        ]});
+        assert_eq!(matching_ids(&search, "starry night"), ["Q45585"]);
+    }
+
+    #[test]
+    fn only_a_single_artwork_counts() {
+        let painting = |creator: &str| {
+            json!({
+                "labels": {"en": {"value": "The Starry Night"}},
+                "claims": {
+                    "P31": [{"mainsnak": {"datavalue": {"value": {"id": "Q3305213"}}}}],
+                    "P170": [{"mainsnak": {"datavalue": {"value": {"id": creator}}}}],
+                    "P571": [{"mainsnak": {"datavalue": {"value": {"time": "+1889-06-00T00:00:00Z"}}}}],
+                },
+            })
+        };
+        let song = json!({"claims": {"P31": [{"mainsnak": {"datavalue": {"value": {"id": "Q7366"}}}}]}});
+
+        let entities = json!({"entities": {"Q45585": painting("Q5582"), "Q2": song}});
+        let (artwork, creator) = single_artwork(&entities).unwrap();
+        assert_eq!(creator, "Q5582");
+        assert_eq!(inception_year(artwork).as_deref(), Some("1889"));
+
+        let two = json!({"entities": {"Q45585": painting("Q5582"), "Q3": painting("Q296")}});
+        assert!(single_artwork(&two).is_none());
+    }
+}
@@ -0,0 +1,51 @@
+//! The window the show runs in. With `--windowed` it comes back where it was
+//! left: eframe keeps its size and position, moved onto a display that still
+//! exists, and [`MAXIMIZED_KEY`] whether it was maximized. Fullscreen ignores
+//! all of that.
+
+use eframe::egui;
+
+pub const MAXIMIZED_KEY: &str = "window_maximized";
+
+/// eframe's options for a window that is `windowed`, or fullscreen.
+pub fn native_options(windowed: bool) -> eframe::NativeOptions {
+    let viewport = egui::ViewportBuilder::default().with_fullscreen(!windowed).with_title("Art Slideshow");
+    let fullscreen = viewport.clone();
+    eframe::NativeOptions {
+        viewport,
+        persist_window: windowed,
+        // eframe puts back what it saved over `viewport` whatever the mode, a window saved
+        // fullscreen by an earlier version included
+        window_builder: Some(Box::new(
+            move |restored| if windowed { restored.with_fullscreen(false) } else { fullscreen },
+        )),
+        ..Default::default()
+    }
+}
+
+/// Maximizes the window again if it was when a `windowed` show last quit.
+pub fn restore_maximized(cc: &eframe::CreationContext<'_>, windowed: bool) {
+    let maximized = cc.storage.and_then(|storage| eframe::get_value(storage, MAXIMIZED_KEY)).unwrap_or(false);
+    if windowed && maximized {
+        cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_window_comes_back_where_it_was() {
+        let restored = || egui::ViewportBuilder::default().with_position([2000.0, 100.0]).with_fullscreen(true);
+
+        let hook = native_options(true).window_builder.unwrap();
+        let window = hook(restored());
+        assert_eq!((window.position, window.fullscreen), (Some(egui::pos2(2000.0, 100.0)), Some(false)));
+
+        let options = native_options(false);
+        assert!(!options.persist_window);
+        let screen = options.window_builder.unwrap()(restored().with_fullscreen(false));
+        assert_eq!((screen.position, screen.fullscreen), (None, Some(true)));
+    }
+}